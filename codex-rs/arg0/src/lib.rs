@@ -53,6 +53,12 @@ where
     }
 
     let argv1 = args.next().unwrap_or_default();
+    #[cfg(unix)]
+    if argv1 == codex_core::CODEX_RUN_WITH_LIMITS_ARG1 {
+        let err = codex_core::exec_with_limits(&args.collect::<Vec<_>>());
+        eprintln!("Error: {err}");
+        std::process::exit(1);
+    }
     if argv1 == CODEX_APPLY_PATCH_ARG1 {
         let patch_arg = args.next().and_then(|s| s.to_str().map(str::to_owned));
         let exit_code = match patch_arg {
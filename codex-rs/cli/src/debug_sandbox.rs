@@ -3,7 +3,9 @@ use std::path::PathBuf;
 use codex_common::CliConfigOverrides;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config_types::LinuxSandbox;
 use codex_core::exec_env::create_env;
+use codex_core::landlock::spawn_command_under_linux_namespaces;
 use codex_core::landlock::spawn_command_under_linux_sandbox;
 use codex_core::seatbelt::spawn_command_under_seatbelt;
 use codex_core::spawn::StdioPolicy;
@@ -95,6 +97,7 @@ async fn run_command_under_sandbox(
                 sandbox_policy_cwd.as_path(),
                 stdio_policy,
                 env,
+                config.exec_limits,
             )
             .await?
         }
@@ -103,16 +106,34 @@ async fn run_command_under_sandbox(
             let codex_linux_sandbox_exe = config
                 .codex_linux_sandbox_exe
                 .expect("codex-linux-sandbox executable not found");
-            spawn_command_under_linux_sandbox(
-                codex_linux_sandbox_exe,
-                command,
-                cwd,
-                &config.sandbox_policy,
-                sandbox_policy_cwd.as_path(),
-                stdio_policy,
-                env,
-            )
-            .await?
+            match config.linux_sandbox {
+                LinuxSandbox::Landlock => {
+                    spawn_command_under_linux_sandbox(
+                        codex_linux_sandbox_exe,
+                        command,
+                        cwd,
+                        &config.sandbox_policy,
+                        sandbox_policy_cwd.as_path(),
+                        stdio_policy,
+                        env,
+                        config.exec_limits,
+                    )
+                    .await?
+                }
+                LinuxSandbox::Namespaces => {
+                    spawn_command_under_linux_namespaces(
+                        codex_linux_sandbox_exe,
+                        command,
+                        cwd,
+                        &config.sandbox_policy,
+                        sandbox_policy_cwd.as_path(),
+                        stdio_policy,
+                        env,
+                        config.exec_limits,
+                    )
+                    .await?
+                }
+            }
         }
     };
     let status = child.wait().await?;
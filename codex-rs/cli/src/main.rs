@@ -21,8 +21,12 @@ use std::path::PathBuf;
 use supports_color::Stream;
 
 mod mcp_cmd;
+mod policy_cmd;
+mod prompts_cmd;
 
 use crate::mcp_cmd::McpCli;
+use crate::policy_cmd::PolicyCli;
+use crate::prompts_cmd::PromptsCli;
 use crate::proto::ProtoCli;
 
 /// Codex CLI
@@ -65,6 +69,12 @@ enum Subcommand {
     /// [experimental] Run Codex as an MCP server and manage MCP servers.
     Mcp(McpCli),
 
+    /// List, lint and render custom prompts.
+    Prompts(PromptsCli),
+
+    /// Test exec policy files against their examples and recorded sessions.
+    Policy(PolicyCli),
+
     /// Run the Protocol stream via stdin/stdout
     #[clap(visible_alias = "p")]
     Proto(ProtoCli),
@@ -229,6 +239,20 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut mcp_cli.config_overrides, root_config_overrides.clone());
             mcp_cli.run(codex_linux_sandbox_exe).await?;
         }
+        Some(Subcommand::Prompts(mut prompts_cli)) => {
+            prepend_config_flags(
+                &mut prompts_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            prompts_cli.run().await?;
+        }
+        Some(Subcommand::Policy(mut policy_cli)) => {
+            prepend_config_flags(
+                &mut policy_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            policy_cli.run().await?;
+        }
         Some(Subcommand::Resume(ResumeCommand {
             session_id,
             last,
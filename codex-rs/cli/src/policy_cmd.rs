@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use codex_common::CliConfigOverrides;
+use codex_core::ExecPolicy;
+use codex_core::ReplayDecision;
+use codex_core::ReplayedCommand;
+use codex_core::SESSIONS_SUBDIR;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::replay_rollout;
+use codex_execpolicy::NegativeExamplePassedCheck;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::PositiveExampleFailedCheck;
+
+const ROLLOUT_FILE_EXTENSION: &str = "jsonl";
+
+/// Test exec policy files before rolling them out.
+///
+/// Subcommands:
+/// - `check` — run the examples embedded in policy files and replay recorded
+///   commands through them
+#[derive(Debug, clap::Parser)]
+pub struct PolicyCli {
+    #[clap(flatten)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: PolicySubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum PolicySubcommand {
+    /// Run the `should_match` and `should_not_match` examples of policy files
+    /// and report how recorded commands would be decided under them.
+    Check(CheckArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct CheckArgs {
+    /// Policy files to check. They are combined with the default policy, as
+    /// they would be when installed in a `policies` directory.
+    #[arg(required = true, value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Replay the shell commands of these rollout files, or of the rollout
+    /// files under these directories. Without a path, replay every recorded
+    /// session in `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "PATH", num_args = 0..)]
+    pub replay: Option<Vec<PathBuf>>,
+
+    /// Output the results as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// A failing embedded example.
+enum ExampleFailure {
+    Positive(PositiveExampleFailedCheck),
+    Negative(NegativeExamplePassedCheck),
+}
+
+impl ExampleFailure {
+    fn kind(&self) -> &'static str {
+        match self {
+            ExampleFailure::Positive(_) => "PositiveExampleFailedCheck",
+            ExampleFailure::Negative(_) => "NegativeExamplePassedCheck",
+        }
+    }
+
+    fn to_json(&self, file: &Path) -> serde_json::Value {
+        match self {
+            ExampleFailure::Positive(check) => serde_json::json!({
+                "type": self.kind(),
+                "location": example_location(file, check.location.as_deref()),
+                "program": check.program,
+                "args": check.args,
+                "error": check.error,
+            }),
+            ExampleFailure::Negative(check) => serde_json::json!({
+                "type": self.kind(),
+                "location": example_location(file, check.location.as_deref()),
+                "program": check.program,
+                "args": check.args,
+            }),
+        }
+    }
+
+    fn describe(&self, file: &Path) -> String {
+        match self {
+            ExampleFailure::Positive(check) => format!(
+                "{}: {}: `{}` should match but was rejected: {:?}",
+                example_location(file, check.location.as_deref()),
+                self.kind(),
+                display_command(&check.program, &check.args),
+                check.error,
+            ),
+            ExampleFailure::Negative(check) => format!(
+                "{}: {}: `{}` should not match but was accepted",
+                example_location(file, check.location.as_deref()),
+                self.kind(),
+                display_command(&check.program, &check.args),
+            ),
+        }
+    }
+}
+
+impl PolicyCli {
+    pub async fn run(self) -> Result<()> {
+        let PolicyCli {
+            config_overrides,
+            cmd,
+        } = self;
+        let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+            .context("failed to load configuration")?;
+
+        match cmd {
+            PolicySubcommand::Check(args) => run_check(&config, args).await,
+        }
+    }
+}
+
+async fn run_check(config: &Config, check_args: CheckArgs) -> Result<()> {
+    let CheckArgs {
+        files,
+        replay,
+        json,
+    } = check_args;
+
+    let mut policies = Vec::new();
+    let mut failures: Vec<(&Path, ExampleFailure)> = Vec::new();
+    for file in &files {
+        let policy = parse_policy_file(file)?;
+        failures.extend(
+            policy
+                .check_each_good_list_individually()
+                .into_iter()
+                .map(|check| (file.as_path(), ExampleFailure::Positive(check))),
+        );
+        failures.extend(
+            policy
+                .check_each_bad_list_individually()
+                .into_iter()
+                .map(|check| (file.as_path(), ExampleFailure::Negative(check))),
+        );
+        policies.push(policy);
+    }
+
+    let replayed = match replay {
+        Some(paths) => {
+            let paths = if paths.is_empty() {
+                vec![config.codex_home.join(SESSIONS_SUBDIR)]
+            } else {
+                paths
+            };
+            let exec_policy = ExecPolicy::with_policies(policies).map_err(|e| anyhow!(e))?;
+            Some(replay_rollouts(config, &exec_policy, &paths).await?)
+        }
+        None => None,
+    };
+
+    if json {
+        let mut output = serde_json::json!({
+            "examples": failures
+                .iter()
+                .map(|(file, failure)| failure.to_json(file))
+                .collect::<Vec<_>>(),
+        });
+        if let Some(replayed) = &replayed {
+            output["replay"] = replay_json(replayed);
+        }
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        for (file, failure) in &failures {
+            println!("{}", failure.describe(file));
+        }
+        if let Some(replayed) = &replayed {
+            print_replay(replayed);
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "{} embedded example(s) failed in {} policy file(s)",
+            failures.len(),
+            files.len()
+        );
+    }
+    if !json {
+        println!(
+            "Checked {} policy file(s), all embedded examples passed.",
+            files.len()
+        );
+    }
+    Ok(())
+}
+
+fn parse_policy_file(path: &Path) -> Result<Policy> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    PolicyParser::new(&path.to_string_lossy(), &source)
+        .parse()
+        .map_err(|err| anyhow!("failed to parse {}: {err}", path.display()))
+}
+
+/// `file:line` of the program an example belongs to, or the file when the
+/// definition's location is unknown.
+fn example_location(file: &Path, location: Option<&str>) -> String {
+    location.map_or_else(|| file.display().to_string(), str::to_string)
+}
+
+fn display_command(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Replay every rollout file at or under `paths`, in path order.
+async fn replay_rollouts(
+    config: &Config,
+    exec_policy: &ExecPolicy,
+    paths: &[PathBuf],
+) -> Result<Vec<ReplayedCommand>> {
+    let mut rollout_files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_rollout_files(path, &mut rollout_files)?;
+        } else {
+            rollout_files.push(path.clone());
+        }
+    }
+    rollout_files.sort();
+
+    let mut replayed = Vec::new();
+    for rollout_file in &rollout_files {
+        let commands = replay_rollout(rollout_file, exec_policy, &config.permissions)
+            .await
+            .with_context(|| format!("failed to replay {}", rollout_file.display()))?;
+        replayed.extend(commands);
+    }
+    Ok(replayed)
+}
+
+fn collect_rollout_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_rollout_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == ROLLOUT_FILE_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn decision_label(decision: &ReplayDecision) -> &'static str {
+    match decision {
+        ReplayDecision::AutoApprove => "auto-approved",
+        ReplayDecision::Prompt => "prompted",
+        ReplayDecision::Reject { .. } => "rejected",
+    }
+}
+
+/// Replayed commands grouped by command line and decision, with how often
+/// each occurred.
+fn group_replayed(replayed: &[ReplayedCommand]) -> BTreeMap<(&'static str, String), usize> {
+    let mut groups = BTreeMap::new();
+    for command in replayed {
+        let line = shlex::try_join(command.command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.command.join(" "));
+        let line = match &command.decision {
+            ReplayDecision::Reject { reason } => format!("{line} ({reason})"),
+            ReplayDecision::AutoApprove | ReplayDecision::Prompt => line,
+        };
+        *groups
+            .entry((decision_label(&command.decision), line))
+            .or_insert(0) += 1;
+    }
+    groups
+}
+
+fn decision_counts(replayed: &[ReplayedCommand]) -> [usize; 3] {
+    let mut counts = [0; 3];
+    for command in replayed {
+        let index = match command.decision {
+            ReplayDecision::AutoApprove => 0,
+            ReplayDecision::Prompt => 1,
+            ReplayDecision::Reject { .. } => 2,
+        };
+        counts[index] += 1;
+    }
+    counts
+}
+
+fn replay_json(replayed: &[ReplayedCommand]) -> serde_json::Value {
+    let [auto_approved, prompted, rejected] = decision_counts(replayed);
+    let commands: Vec<_> = replayed
+        .iter()
+        .map(|command| {
+            let reason = match &command.decision {
+                ReplayDecision::Reject { reason } => Some(reason),
+                ReplayDecision::AutoApprove | ReplayDecision::Prompt => None,
+            };
+            serde_json::json!({
+                "command": command.command,
+                "cwd": command.cwd,
+                "decision": decision_label(&command.decision),
+                "reason": reason,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "auto_approved": auto_approved,
+        "prompted": prompted,
+        "rejected": rejected,
+        "commands": commands,
+    })
+}
+
+fn print_replay(replayed: &[ReplayedCommand]) {
+    let groups = group_replayed(replayed);
+    let width = groups
+        .keys()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    for ((label, line), count) in &groups {
+        println!("{label:<width$}  {count:>5}  {line}");
+    }
+    let [auto_approved, prompted, rejected] = decision_counts(replayed);
+    println!(
+        "Replayed {} command(s): {auto_approved} auto-approved, {prompted} prompted, {rejected} rejected.",
+        replayed.len()
+    );
+}
@@ -0,0 +1,340 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::custom_prompts::default_prompts_dir;
+use codex_core::custom_prompts::discover_prompts_in_excluding;
+use codex_core::custom_prompts::discover_prompts_with_directories;
+use codex_core::custom_prompts::expand_prompt_file_includes;
+use codex_core::custom_prompts::expand_prompt_includes;
+use codex_core::custom_prompts::has_prompt_includes;
+use codex_core::custom_prompts::lint_prompt;
+use codex_core::custom_prompts::project_prompts_dir;
+use codex_core::template_processor::expand_prompt;
+use codex_protocol::custom_prompts::CustomPrompt;
+
+/// Inspect the custom prompt library.
+///
+/// Subcommands:
+/// - `list`   — list global and project prompts and which ones are shadowed
+/// - `lint`   — check prompts for invalid frontmatter and template problems
+/// - `render` — print the text a prompt expands to for the given arguments
+#[derive(Debug, clap::Parser)]
+pub struct PromptsCli {
+    #[clap(flatten)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: PromptsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum PromptsSubcommand {
+    /// List global and project prompts.
+    List(ListArgs),
+
+    /// Check prompts for invalid frontmatter, unknown template variables and
+    /// duplicate names.
+    Lint(LintArgs),
+
+    /// Print the text a prompt expands to.
+    Render(RenderArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ListArgs {
+    /// Output the prompts as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct LintArgs {
+    /// Output the problems as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct RenderArgs {
+    /// Name of the prompt, as typed after `/`.
+    pub name: String,
+
+    /// Arguments passed to the prompt (`value` or `name=value`). Put them
+    /// after `--` if one starts with `-`.
+    pub args: Vec<String>,
+
+    /// Run the prompt's `` !`command` `` inclusions. Without it they are
+    /// printed as they are.
+    #[arg(long)]
+    pub exec: bool,
+
+    /// Output the rendered text and turn overrides as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Where a prompt was discovered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptOrigin {
+    Global,
+    Project,
+}
+
+impl PromptOrigin {
+    fn as_str(self) -> &'static str {
+        match self {
+            PromptOrigin::Global => "global",
+            PromptOrigin::Project => "project",
+        }
+    }
+}
+
+struct LibraryEntry {
+    prompt: CustomPrompt,
+    origin: PromptOrigin,
+    /// Set when a project prompt with the same name takes precedence.
+    shadowed: bool,
+}
+
+impl PromptsCli {
+    pub async fn run(self) -> Result<()> {
+        let PromptsCli {
+            config_overrides,
+            cmd,
+        } = self;
+        let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+            .context("failed to load configuration")?;
+
+        match cmd {
+            PromptsSubcommand::List(args) => run_list(&config, args).await,
+            PromptsSubcommand::Lint(args) => run_lint(&config, args).await,
+            PromptsSubcommand::Render(args) => run_render(&config, args).await,
+        }
+    }
+}
+
+/// Discover global and project prompts, keeping shadowed global prompts so
+/// they can be reported. Entries are sorted by name, global before project.
+async fn discover_library(cwd: &Path) -> Vec<LibraryEntry> {
+    let mut entries: Vec<LibraryEntry> = Vec::new();
+    if let Some(global_dir) = default_prompts_dir() {
+        let prompts = discover_prompts_in_excluding(&global_dir, &HashSet::new()).await;
+        entries.extend(prompts.into_iter().map(|prompt| LibraryEntry {
+            prompt,
+            origin: PromptOrigin::Global,
+            shadowed: false,
+        }));
+    }
+    if let Some(project_dir) = project_prompts_dir(cwd) {
+        let prompts = discover_prompts_with_directories(&project_dir).await;
+        for prompt in prompts {
+            for entry in entries
+                .iter_mut()
+                .filter(|e| e.origin == PromptOrigin::Global && e.prompt.name == prompt.name)
+            {
+                entry.shadowed = true;
+            }
+            entries.push(LibraryEntry {
+                prompt,
+                origin: PromptOrigin::Project,
+                shadowed: false,
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.prompt.name.cmp(&b.prompt.name));
+    entries
+}
+
+async fn run_list(config: &Config, list_args: ListArgs) -> Result<()> {
+    let entries = discover_library(&config.cwd).await;
+
+    if list_args.json {
+        let json_entries: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "name": entry.prompt.name,
+                    "origin": entry.origin.as_str(),
+                    "path": entry.prompt.path,
+                    "description": entry.prompt.description,
+                    "argument_hint": entry.prompt.argument_hint,
+                    "shadowed": entry.shadowed,
+                })
+            })
+            .collect();
+        let output = serde_json::to_string_pretty(&json_entries)?;
+        println!("{output}");
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!(
+            "No custom prompts found. Add Markdown files to ~/.codex/prompts or .codex/prompts."
+        );
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 4]> = entries
+        .iter()
+        .map(|entry| {
+            let name = if entry.shadowed {
+                format!("{} (shadowed)", entry.prompt.name)
+            } else {
+                entry.prompt.name.clone()
+            };
+            [
+                name,
+                entry.origin.as_str().to_string(),
+                entry
+                    .prompt
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+                entry.prompt.path.display().to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = ["Name".len(), "Origin".len(), "Description".len()];
+    for row in &rows {
+        for (i, width) in widths.iter_mut().enumerate() {
+            *width = (*width).max(row[i].len());
+        }
+    }
+
+    println!(
+        "{:<name_w$}  {:<origin_w$}  {:<desc_w$}  Path",
+        "Name",
+        "Origin",
+        "Description",
+        name_w = widths[0],
+        origin_w = widths[1],
+        desc_w = widths[2],
+    );
+    for row in rows {
+        println!(
+            "{:<name_w$}  {:<origin_w$}  {:<desc_w$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            name_w = widths[0],
+            origin_w = widths[1],
+            desc_w = widths[2],
+        );
+    }
+
+    Ok(())
+}
+
+async fn run_lint(config: &Config, lint_args: LintArgs) -> Result<()> {
+    let entries = discover_library(&config.cwd).await;
+
+    let mut results: Vec<(&LibraryEntry, Vec<String>)> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let raw_content = std::fs::read_to_string(&entry.prompt.path)
+            .with_context(|| format!("failed to read {}", entry.prompt.path.display()))?;
+        let mut issues = lint_prompt(&entry.prompt, &raw_content);
+        // Shadowing across origins is intended; two files with the same name
+        // in one library are not, since only one of them can be used.
+        if let Some(other) = entries[..i]
+            .iter()
+            .find(|other| other.origin == entry.origin && other.prompt.name == entry.prompt.name)
+        {
+            issues.push(format!(
+                "duplicate prompt name `{}` (also defined in {})",
+                entry.prompt.name,
+                other.prompt.path.display()
+            ));
+        }
+        if !issues.is_empty() {
+            results.push((entry, issues));
+        }
+    }
+    let problem_count: usize = results.iter().map(|(_, issues)| issues.len()).sum();
+
+    if lint_args.json {
+        let json_entries: Vec<_> = results
+            .iter()
+            .map(|(entry, issues)| {
+                serde_json::json!({
+                    "name": entry.prompt.name,
+                    "origin": entry.origin.as_str(),
+                    "path": entry.prompt.path,
+                    "issues": issues,
+                })
+            })
+            .collect();
+        let output = serde_json::to_string_pretty(&json_entries)?;
+        println!("{output}");
+    } else {
+        for (entry, issues) in &results {
+            for issue in issues {
+                println!("{}: {issue}", entry.prompt.path.display());
+            }
+        }
+    }
+
+    if problem_count > 0 {
+        bail!(
+            "found {problem_count} problem(s) in {} prompt(s)",
+            results.len()
+        );
+    }
+    if !lint_args.json {
+        println!("Checked {} prompt(s), no problems found.", entries.len());
+    }
+    Ok(())
+}
+
+async fn run_render(config: &Config, render_args: RenderArgs) -> Result<()> {
+    let RenderArgs {
+        name,
+        args,
+        exec,
+        json,
+    } = render_args;
+    let name = name.strip_prefix('/').unwrap_or(&name);
+
+    let entries = discover_library(&config.cwd).await;
+    let Some(entry) = entries
+        .into_iter()
+        .find(|entry| entry.prompt.name == name && !entry.shadowed)
+    else {
+        bail!("No prompt named '{name}' found.");
+    };
+    let prompt = entry.prompt;
+
+    let mut text = expand_prompt(&prompt, &args).map_err(|err| anyhow!("/{name}: {err}"))?;
+    // `config` has no prompt overrides applied, so commands run under the
+    // session's sandbox and approval policy, as in the TUI.
+    if has_prompt_includes(&text) {
+        text = if exec {
+            expand_prompt_includes(&text, config).await
+        } else {
+            expand_prompt_file_includes(&text, config).await
+        }
+        .map_err(|err| anyhow!("/{name}: {err}"))?;
+    }
+
+    if json {
+        let output = serde_json::to_string_pretty(&serde_json::json!({
+            "name": prompt.name,
+            "path": prompt.path,
+            "text": text,
+            "turn_overrides": prompt.turn_overrides,
+        }))?;
+        println!("{output}");
+    } else {
+        println!("{text}");
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use predicates::str::contains;
+use pretty_assertions::assert_eq;
+use serde_json::Value as JsonValue;
+use tempfile::TempDir;
+
+fn codex_command(codex_home: &Path, cwd: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", codex_home).current_dir(cwd);
+    Ok(cmd)
+}
+
+#[test]
+fn check_reports_failing_examples_with_their_location() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let cwd = TempDir::new()?;
+    fs::write(
+        cwd.path().join("good.policy"),
+        r#"
+define_program(
+    program="make",
+    args=[ARG_OPAQUE_VALUE],
+    should_match=[["test"]],
+    should_not_match=[["test", "lint"]],
+)
+"#,
+    )?;
+    fs::write(
+        cwd.path().join("bad.policy"),
+        r#"
+define_program(
+    program="make",
+    args=[ARG_OPAQUE_VALUE],
+    should_match=[["test", "lint"]],
+    should_not_match=[["test"]],
+)
+"#,
+    )?;
+
+    codex_command(codex_home.path(), cwd.path())?
+        .args(["policy", "check", "good.policy"])
+        .assert()
+        .success()
+        .stdout(contains("all embedded examples passed"));
+
+    codex_command(codex_home.path(), cwd.path())?
+        .args(["policy", "check", "bad.policy"])
+        .assert()
+        .failure()
+        .stdout(contains(
+            "bad.policy:2: PositiveExampleFailedCheck: `make test lint` should match",
+        ))
+        .stdout(contains(
+            "bad.policy:2: NegativeExamplePassedCheck: `make test` should not match",
+        ))
+        .stderr(contains("2 embedded example(s) failed"));
+
+    Ok(())
+}
+
+#[test]
+fn check_replays_recorded_commands() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let cwd = TempDir::new()?;
+    fs::write(
+        cwd.path().join("make.policy"),
+        r#"
+define_program(
+    program="make",
+    args=[ARG_OPAQUE_VALUE],
+)
+"#,
+    )?;
+    let session_dir = codex_home.path().join("sessions/2025/01/01");
+    fs::create_dir_all(&session_dir)?;
+    let turn_context = serde_json::json!({
+        "timestamp": "2025-01-01T00:00:00.000Z",
+        "type": "turn_context",
+        "payload": {
+            "cwd": cwd.path(),
+            "approval_policy": "untrusted",
+            "sandbox_policy": { "mode": "read-only" },
+            "model": "gpt-5",
+            "summary": "auto",
+        },
+    });
+    let shell_call = |command: &[&str]| {
+        serde_json::json!({
+            "timestamp": "2025-01-01T00:00:01.000Z",
+            "type": "response_item",
+            "payload": {
+                "type": "function_call",
+                "name": "shell",
+                "arguments": serde_json::json!({ "command": command }).to_string(),
+                "call_id": "call",
+            },
+        })
+    };
+    let lines = [
+        turn_context,
+        shell_call(&["make", "test"]),
+        shell_call(&["cargo", "build"]),
+    ]
+    .map(|line| line.to_string());
+    fs::write(session_dir.join("rollout-test.jsonl"), lines.join("\n"))?;
+
+    let output = codex_command(codex_home.path(), cwd.path())?
+        .args(["policy", "check", "make.policy", "--json", "--replay"])
+        .output()?;
+    assert!(output.status.success(), "{output:?}");
+    let parsed: JsonValue = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(parsed["examples"], serde_json::json!([]));
+    assert_eq!(parsed["replay"]["auto_approved"], 1);
+    assert_eq!(parsed["replay"]["prompted"], 1);
+    assert_eq!(parsed["replay"]["rejected"], 0);
+    assert_eq!(parsed["replay"]["commands"][0]["command"][0], "make");
+    assert_eq!(parsed["replay"]["commands"][0]["decision"], "auto-approved");
+    assert_eq!(parsed["replay"]["commands"][1]["decision"], "prompted");
+
+    Ok(())
+}
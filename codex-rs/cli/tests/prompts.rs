@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use predicates::str::contains;
+use pretty_assertions::assert_eq;
+use serde_json::Value as JsonValue;
+use tempfile::TempDir;
+
+fn codex_command(codex_home: &Path, cwd: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", codex_home).current_dir(cwd);
+    Ok(cmd)
+}
+
+fn write_prompt(codex_home: &Path, name: &str, content: &str) -> Result<()> {
+    let dir = codex_home.join("prompts");
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(format!("{name}.md")), content)?;
+    Ok(())
+}
+
+#[test]
+fn list_and_render_global_prompts() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let cwd = TempDir::new()?;
+    write_prompt(
+        codex_home.path(),
+        "review",
+        "---\ndescription: Review a file\narguments:\n  - name: file\n    required: true\n---\nReview {{ file }}{% if focus %} for {{ focus }}{% endif %}.",
+    )?;
+
+    let output = codex_command(codex_home.path(), cwd.path())?
+        .args(["prompts", "list", "--json"])
+        .output()?;
+    assert!(output.status.success());
+    let parsed: JsonValue = serde_json::from_slice(&output.stdout)?;
+    let array = parsed.as_array().expect("expected array");
+    assert_eq!(array.len(), 1);
+    assert_eq!(array[0]["name"], "review");
+    assert_eq!(array[0]["origin"], "global");
+    assert_eq!(array[0]["description"], "Review a file");
+    assert_eq!(array[0]["shadowed"], false);
+
+    codex_command(codex_home.path(), cwd.path())?
+        .args(["prompts", "render", "review", "main.rs", "focus=tests"])
+        .assert()
+        .success()
+        .stdout("Review main.rs for tests.\n");
+
+    codex_command(codex_home.path(), cwd.path())?
+        .args(["prompts", "render", "review"])
+        .assert()
+        .failure()
+        .stderr(contains("missing required argument `file`"));
+
+    Ok(())
+}
+
+#[test]
+fn render_runs_commands_only_with_exec() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let cwd = TempDir::new()?;
+    let marker = cwd.path().join("ran");
+    write_prompt(
+        codex_home.path(),
+        "status",
+        &format!(
+            "---\ntemplate: jinja\n---\nStatus of {{{{ ARGUMENTS | join(\" \") }}}}: !`touch {}`",
+            marker.display()
+        ),
+    )?;
+
+    // `--json` after the prompt arguments is still an option.
+    let output = codex_command(codex_home.path(), cwd.path())?
+        .args(["prompts", "render", "status", "main.rs", "--json"])
+        .output()?;
+    assert!(output.status.success());
+    let parsed: JsonValue = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        parsed["text"],
+        format!("Status of main.rs: !`touch {}`", marker.display())
+    );
+    assert!(!marker.exists());
+
+    Ok(())
+}
+
+#[test]
+fn lint_reports_problems() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let cwd = TempDir::new()?;
+    write_prompt(codex_home.path(), "fine", "Fix $ARGUMENTS")?;
+
+    codex_command(codex_home.path(), cwd.path())?
+        .args(["prompts", "lint"])
+        .assert()
+        .success()
+        .stdout(contains("no problems found"));
+
+    write_prompt(
+        codex_home.path(),
+        "broken",
+        "---\napproval: sometimes\ntemplate: jinja\n---\nReview {{ file }}",
+    )?;
+
+    let output = codex_command(codex_home.path(), cwd.path())?
+        .args(["prompts", "lint", "--json"])
+        .output()?;
+    assert!(!output.status.success());
+    let parsed: JsonValue = serde_json::from_slice(&output.stdout)?;
+    let array = parsed.as_array().expect("expected array");
+    assert_eq!(array.len(), 1);
+    assert_eq!(array[0]["name"], "broken");
+    assert_eq!(
+        array[0]["issues"],
+        serde_json::json!([
+            "invalid `approval` value `sometimes`",
+            "template variable `file` is not declared in `arguments:`",
+        ])
+    );
+
+    Ok(())
+}
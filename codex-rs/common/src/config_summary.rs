@@ -26,6 +26,18 @@ pub fn create_config_summary_entries(config: &Config) -> Vec<(&'static str, Stri
             "reasoning summaries",
             config.model_reasoning_summary.to_string(),
         ));
+    } else if config.model_provider.wire_api == WireApi::AnthropicMessages
+        && config.model_family.supports_reasoning_summaries
+    {
+        // Effort maps onto the extended thinking budget; the Messages API has
+        // no equivalent of reasoning summaries.
+        entries.push((
+            "reasoning effort",
+            config
+                .model_reasoning_effort
+                .map(|effort| effort.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+        ));
     }
 
     entries
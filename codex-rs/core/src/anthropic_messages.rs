@@ -0,0 +1,744 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::create_tools_json_for_responses_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+
+/// `max_tokens` is mandatory for the Messages API; used when the model is not
+/// known to [`get_model_info`].
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 8_192;
+
+/// Smallest thinking budget accepted by the Messages API.
+const MIN_THINKING_BUDGET_TOKENS: u64 = 1_024;
+
+/// Implementation for the Anthropic Messages API.
+pub(crate) async fn stream_anthropic_messages(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for Anthropic Messages API".to_string(),
+        ));
+    }
+
+    let payload = build_messages_payload(prompt, model_family, effort)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(&None),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let req_builder = provider.create_request_builder(client, &None).await?;
+
+        let res = req_builder
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload)
+            .send()
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_anthropic_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                // 529 is Anthropic's "overloaded" status and is retryable.
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Builds the JSON body for `POST /v1/messages`.
+///
+/// Cache breakpoints are placed on the last tool definition and on the system
+/// prompt so that the stable instruction prefix (tools → system) is served
+/// from the prompt cache, and on the final message so that the conversation
+/// so far is cached for the next turn.
+fn build_messages_payload(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+) -> Result<Value> {
+    let full_instructions = prompt.get_full_instructions(model_family);
+    let system = json!([{
+        "type": "text",
+        "text": full_instructions,
+        "cache_control": {"type": "ephemeral"},
+    }]);
+
+    let mut messages = build_messages(&prompt.get_formatted_input());
+    if let Some(last_block) = messages
+        .last_mut()
+        .and_then(|m| m.get_mut("content"))
+        .and_then(Value::as_array_mut)
+        .and_then(|blocks| blocks.last_mut())
+        .and_then(Value::as_object_mut)
+    {
+        last_block.insert("cache_control".to_string(), json!({"type": "ephemeral"}));
+    }
+
+    let mut tools = create_tools_json_for_anthropic_messages_api(prompt)?;
+    if let Some(last_tool) = tools.last_mut().and_then(Value::as_object_mut) {
+        last_tool.insert("cache_control".to_string(), json!({"type": "ephemeral"}));
+    }
+
+    let max_tokens = get_model_info(model_family)
+        .map(|info| info.max_output_tokens)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+
+    let mut payload = serde_json::Map::new();
+    payload.insert("model".to_string(), json!(model_family.slug));
+    payload.insert("max_tokens".to_string(), json!(max_tokens));
+    payload.insert("system".to_string(), system);
+    payload.insert("messages".to_string(), Value::Array(messages));
+    payload.insert("stream".to_string(), Value::Bool(true));
+    if !tools.is_empty() {
+        payload.insert("tools".to_string(), Value::Array(tools));
+    }
+    if model_family.supports_reasoning_summaries
+        && let Some(budget_tokens) = thinking_budget_tokens(effort, max_tokens)
+    {
+        payload.insert(
+            "thinking".to_string(),
+            json!({"type": "enabled", "budget_tokens": budget_tokens}),
+        );
+    }
+
+    Ok(Value::Object(payload))
+}
+
+/// Maps the configured reasoning effort onto an extended thinking budget.
+/// The budget must stay strictly below `max_tokens`.
+fn thinking_budget_tokens(effort: Option<ReasoningEffortConfig>, max_tokens: u64) -> Option<u64> {
+    let budget = match effort? {
+        ReasoningEffortConfig::Minimal => return None,
+        ReasoningEffortConfig::Low => 4_096,
+        ReasoningEffortConfig::Medium => 16_000,
+        ReasoningEffortConfig::High => 32_000,
+    };
+    let budget = budget.min(max_tokens / 2);
+    (budget >= MIN_THINKING_BUDGET_TOKENS).then_some(budget)
+}
+
+/// Rewrites the Responses API tool JSON into the Messages API shape. Only
+/// function tools are supported; hosted tools (web search, local shell) and
+/// freeform tools have no Messages API equivalent and are dropped.
+fn create_tools_json_for_anthropic_messages_api(prompt: &Prompt) -> Result<Vec<Value>> {
+    let responses_api_tools_json = create_tools_json_for_responses_api(&prompt.tools)?;
+    let tools_json = responses_api_tools_json
+        .into_iter()
+        .filter(|tool| tool.get("type").and_then(Value::as_str) == Some("function"))
+        .map(|tool| {
+            json!({
+                "name": tool.get("name").cloned().unwrap_or(Value::Null),
+                "description": tool.get("description").cloned().unwrap_or(Value::Null),
+                "input_schema": tool.get("parameters").cloned().unwrap_or_else(|| json!({"type": "object"})),
+            })
+        })
+        .collect();
+    Ok(tools_json)
+}
+
+/// Converts conversation history into Messages API `messages`. The Messages
+/// API requires strictly alternating `user`/`assistant` turns, so consecutive
+/// items that map onto the same role are merged into one message with
+/// several content blocks.
+fn build_messages(input: &[ResponseItem]) -> Vec<Value> {
+    let mut messages: Vec<(&'static str, Vec<Value>)> = Vec::new();
+    let mut push_block = |role: &'static str, block: Value| match messages.last_mut() {
+        Some((last_role, blocks)) if *last_role == role => blocks.push(block),
+        _ => messages.push((role, vec![block])),
+    };
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for c in content {
+                    let block = match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if text.is_empty() {
+                                continue;
+                            }
+                            json!({"type": "text", "text": text})
+                        }
+                        ContentItem::InputImage { image_url } => image_block(image_url),
+                    };
+                    push_block(role, block);
+                }
+            }
+            ResponseItem::Reasoning {
+                summary,
+                content,
+                encrypted_content,
+                ..
+            } => {
+                // Thinking blocks can only be replayed together with the
+                // signature the API attached to them.
+                let Some(signature) = encrypted_content else {
+                    continue;
+                };
+                let mut thinking = String::new();
+                for ReasoningItemReasoningSummary::SummaryText { text } in summary {
+                    thinking.push_str(text);
+                }
+                for c in content.iter().flatten() {
+                    match c {
+                        ReasoningItemContent::ReasoningText { text }
+                        | ReasoningItemContent::Text { text } => thinking.push_str(text),
+                    }
+                }
+                let block = if thinking.is_empty() {
+                    json!({"type": "redacted_thinking", "data": signature})
+                } else {
+                    json!({"type": "thinking", "thinking": thinking, "signature": signature})
+                };
+                push_block("assistant", block);
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                push_block(
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": name, "input": input}),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let LocalShellAction::Exec(exec) = action;
+                let id = call_id.clone().or_else(|| id.clone()).unwrap_or_default();
+                push_block(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": id,
+                        "name": "shell",
+                        "input": {
+                            "command": exec.command,
+                            "workdir": exec.working_directory,
+                            "timeout_ms": exec.timeout_ms,
+                        },
+                    }),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                push_block(
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": name, "input": {"input": input}}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let mut block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": output.content,
+                });
+                if output.success == Some(false)
+                    && let Some(obj) = block.as_object_mut()
+                {
+                    obj.insert("is_error".to_string(), Value::Bool(true));
+                }
+                push_block("user", block);
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                push_block(
+                    "user",
+                    json!({"type": "tool_result", "tool_use_id": call_id, "content": output}),
+                );
+            }
+            ResponseItem::WebSearchCall { .. } | ResponseItem::Other => {
+                // Omit these items from the conversation history.
+                continue;
+            }
+        }
+    }
+
+    messages
+        .into_iter()
+        .map(|(role, content)| json!({"role": role, "content": content}))
+        .collect()
+}
+
+/// Images are attached either inline (for `data:` URLs produced by
+/// `InputItem::LocalImage`) or by reference.
+fn image_block(image_url: &str) -> Value {
+    if let Some(rest) = image_url.strip_prefix("data:")
+        && let Some((media_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data},
+        });
+    }
+    json!({"type": "image", "source": {"type": "url", "url": image_url}})
+}
+
+/// A content block that is being streamed by the server. Blocks are keyed by
+/// their `index` and turned into a [`ResponseItem`] on `content_block_stop`.
+enum ContentBlockState {
+    Text(String),
+    ToolUse {
+        id: String,
+        name: String,
+        partial_json: String,
+    },
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+}
+
+impl ContentBlockState {
+    fn from_start(block: &Value) -> Option<Self> {
+        let str_field = |key: &str| {
+            block
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        match block.get("type").and_then(Value::as_str)? {
+            "text" => Some(Self::Text(str_field("text"))),
+            "tool_use" => Some(Self::ToolUse {
+                id: str_field("id"),
+                name: str_field("name"),
+                partial_json: String::new(),
+            }),
+            "thinking" => Some(Self::Thinking {
+                thinking: str_field("thinking"),
+                signature: str_field("signature"),
+            }),
+            "redacted_thinking" => Some(Self::RedactedThinking(str_field("data"))),
+            _ => None,
+        }
+    }
+
+    fn into_response_item(self) -> Option<ResponseItem> {
+        match self {
+            Self::Text(text) if text.is_empty() => None,
+            Self::Text(text) => Some(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            Self::ToolUse {
+                id,
+                name,
+                partial_json,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if partial_json.trim().is_empty() {
+                    "{}".to_string()
+                } else {
+                    partial_json
+                },
+                call_id: id,
+            }),
+            // The signature rides along in `encrypted_content` so the
+            // thinking block can be replayed verbatim on the next request.
+            Self::Thinking {
+                thinking,
+                signature,
+            } => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText { text: thinking }],
+                content: None,
+                encrypted_content: Some(signature),
+            }),
+            Self::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some(data),
+            }),
+        }
+    }
+}
+
+/// SSE processor for the Messages streaming format. The output is mapped onto
+/// Codex's internal [`ResponseEvent`] so that the rest of the pipeline can
+/// stay agnostic of the underlying wire format.
+async fn process_anthropic_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut blocks: HashMap<u64, ContentBlockState> = HashMap::new();
+    let mut response_id = String::new();
+    let mut usage = AnthropicUsage::default();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let event: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("anthropic_messages received SSE event: {event:?}");
+
+        let index = event.get("index").and_then(Value::as_u64).unwrap_or(0);
+        match event
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+        {
+            "message_start" => {
+                if let Some(message) = event.get("message") {
+                    if let Some(id) = message.get("id").and_then(Value::as_str) {
+                        response_id = id.to_string();
+                    }
+                    if let Some(u) = message.get("usage") {
+                        usage.update(u);
+                    }
+                }
+                let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+            }
+            "content_block_start" => {
+                if let Some(state) = event
+                    .get("content_block")
+                    .and_then(ContentBlockState::from_start)
+                {
+                    blocks.insert(index, state);
+                }
+            }
+            "content_block_delta" => {
+                let Some(delta) = event.get("delta") else {
+                    continue;
+                };
+                let text_field = |key: &str| {
+                    delta
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let outgoing = match (
+                    blocks.get_mut(&index),
+                    delta.get("type").and_then(Value::as_str),
+                ) {
+                    (Some(ContentBlockState::Text(text)), Some("text_delta")) => {
+                        let fragment = text_field("text");
+                        text.push_str(&fragment);
+                        (!fragment.is_empty()).then_some(ResponseEvent::OutputTextDelta(fragment))
+                    }
+                    (
+                        Some(ContentBlockState::ToolUse { partial_json, .. }),
+                        Some("input_json_delta"),
+                    ) => {
+                        partial_json.push_str(&text_field("partial_json"));
+                        None
+                    }
+                    (
+                        Some(ContentBlockState::Thinking { thinking, .. }),
+                        Some("thinking_delta"),
+                    ) => {
+                        let fragment = text_field("thinking");
+                        thinking.push_str(&fragment);
+                        (!fragment.is_empty())
+                            .then_some(ResponseEvent::ReasoningSummaryDelta(fragment))
+                    }
+                    (
+                        Some(ContentBlockState::Thinking { signature, .. }),
+                        Some("signature_delta"),
+                    ) => {
+                        signature.push_str(&text_field("signature"));
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(ev) = outgoing {
+                    let _ = tx_event.send(Ok(ev)).await;
+                }
+            }
+            "content_block_stop" => {
+                if let Some(item) = blocks
+                    .remove(&index)
+                    .and_then(ContentBlockState::into_response_item)
+                {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            "message_delta" => {
+                if let Some(u) = event.get("usage") {
+                    usage.update(u);
+                }
+            }
+            "message_stop" => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(usage.into()),
+                    }))
+                    .await;
+                return;
+            }
+            "error" => {
+                let error = event.get("error");
+                let message = error
+                    .and_then(|e| e.get("message"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                let error_type = error
+                    .and_then(|e| e.get("type"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("error");
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        format!("{error_type}: {message}"),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            // `ping` and any event types added in the future.
+            _ => {}
+        }
+    }
+}
+
+/// Token accounting as reported by `message_start` and `message_delta`. The
+/// later event carries cumulative counts, so each field is overwritten rather
+/// than summed.
+#[derive(Default, Clone, Copy)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    cache_creation_input_tokens: u64,
+    cache_read_input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl AnthropicUsage {
+    fn update(&mut self, usage: &Value) {
+        let fields = [
+            ("input_tokens", &mut self.input_tokens),
+            (
+                "cache_creation_input_tokens",
+                &mut self.cache_creation_input_tokens,
+            ),
+            ("cache_read_input_tokens", &mut self.cache_read_input_tokens),
+            ("output_tokens", &mut self.output_tokens),
+        ];
+        for (key, slot) in fields {
+            if let Some(v) = usage.get(key).and_then(Value::as_u64) {
+                *slot = v;
+            }
+        }
+    }
+}
+
+impl From<AnthropicUsage> for TokenUsage {
+    fn from(usage: AnthropicUsage) -> Self {
+        // Anthropic reports cached and uncached input separately whereas
+        // `TokenUsage::input_tokens` includes the cached portion.
+        let input_tokens =
+            usage.input_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: usage.cache_read_input_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + usage.output_tokens,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::find_family_for_model;
+    use crate::openai_tools::OpenAiTool;
+    use crate::plan_tool::PLAN_TOOL;
+    use crate::tool_apply_patch::create_apply_patch_freeform_tool;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn tools_are_converted_and_last_one_is_cached() {
+        let Some(model_family) = find_family_for_model("claude-sonnet-4-5") else {
+            panic!("claude family should be known");
+        };
+        let prompt = Prompt {
+            tools: vec![
+                OpenAiTool::WebSearch {},
+                create_apply_patch_freeform_tool(),
+                PLAN_TOOL.clone(),
+            ],
+            ..Default::default()
+        };
+
+        let payload = match build_messages_payload(&prompt, &model_family, None) {
+            Ok(payload) => payload,
+            Err(e) => panic!("failed to build payload: {e}"),
+        };
+
+        let Some(tools) = payload["tools"].as_array() else {
+            panic!("expected tools array");
+        };
+        assert_eq!(tools.len(), 1, "only function tools are supported");
+        assert_eq!(tools[0]["name"], "update_plan");
+        assert_eq!(tools[0]["input_schema"]["type"], "object");
+        assert_eq!(tools[0]["cache_control"], json!({"type": "ephemeral"}));
+        assert_eq!(
+            payload["system"][0]["cache_control"],
+            json!({"type": "ephemeral"})
+        );
+    }
+
+    #[test]
+    fn thinking_budget_stays_below_max_tokens() {
+        assert_eq!(thinking_budget_tokens(None, 64_000), None);
+        assert_eq!(
+            thinking_budget_tokens(Some(ReasoningEffortConfig::Minimal), 64_000),
+            None
+        );
+        assert_eq!(
+            thinking_budget_tokens(Some(ReasoningEffortConfig::High), 64_000),
+            Some(32_000)
+        );
+        assert_eq!(
+            thinking_budget_tokens(Some(ReasoningEffortConfig::High), 8_192),
+            Some(4_096)
+        );
+        assert_eq!(
+            thinking_budget_tokens(Some(ReasoningEffortConfig::Low), 1_024),
+            None
+        );
+    }
+
+    #[test]
+    fn data_url_images_are_inlined() {
+        assert_eq!(
+            image_block("data:image/png;base64,AAAA"),
+            json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"},
+            })
+        );
+        assert_eq!(
+            image_block("https://example.com/a.png"),
+            json!({"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}})
+        );
+    }
+}
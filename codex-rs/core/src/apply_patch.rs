@@ -1,6 +1,7 @@
 use crate::codex::Session;
 use crate::codex::TurnContext;
 use crate::function_tool::FunctionCallError;
+use crate::permissions::PermissionVerdict;
 use crate::protocol::FileChange;
 use crate::protocol::ReviewDecision;
 use crate::safety::SafetyCheck;
@@ -39,6 +40,26 @@ pub(crate) async fn apply_patch(
     call_id: &str,
     action: ApplyPatchAction,
 ) -> InternalApplyPatchInvocation {
+    // `Edit(...)` permission rules are standing decisions by the user, so an
+    // allowed patch counts as explicitly approved.
+    match sess
+        .check_edit_permissions(&action, &turn_context.cwd)
+        .await
+    {
+        PermissionVerdict::Deny { rule } => {
+            return InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+                format!("patch rejected: denied by permission rule `{rule}`"),
+            )));
+        }
+        PermissionVerdict::Allow => {
+            return InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+                action,
+                user_explicitly_approved_this_action: true,
+            });
+        }
+        PermissionVerdict::Ask => {}
+    }
+
     match assess_patch_safety(
         &action,
         turn_context.approval_policy,
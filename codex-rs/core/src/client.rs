@@ -23,6 +23,7 @@ use tracing::debug;
 use tracing::trace;
 use tracing::warn;
 
+use crate::anthropic_messages::stream_anthropic_messages;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -114,12 +115,25 @@ impl ModelClient {
         })
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
-    /// specialised helpers are private to avoid accidental misuse.
+    /// Dispatches to the Responses, Chat or Anthropic Messages implementation
+    /// depending on the provider config.  Public callers always invoke
+    /// `stream()` – the specialised helpers are private to avoid accidental
+    /// misuse.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
+            // The Messages API already streams complete content blocks, so no
+            // aggregation adapter is needed.
+            WireApi::AnthropicMessages => {
+                stream_anthropic_messages(
+                    prompt,
+                    &self.config.model_family,
+                    self.effort,
+                    &self.client,
+                    &self.provider,
+                )
+                .await
+            }
             WireApi::Chat => {
                 // Create the raw streaming connection first.
                 let response_stream = stream_chat_completions(
@@ -404,6 +418,11 @@ impl ModelClient {
     pub fn get_auth_manager(&self) -> Option<Arc<AuthManager>> {
         self.auth_manager.clone()
     }
+
+    /// Returns the config the client was created with.
+    pub(crate) fn get_config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
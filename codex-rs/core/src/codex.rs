@@ -1,13 +1,12 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::io::BufWriter;
-use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::client_common::REVIEW_PROMPT;
@@ -18,6 +17,7 @@ use crate::user_notification::UserNotifier;
 use async_channel::Receiver;
 use async_channel::Sender;
 use codex_apply_patch::ApplyPatchAction;
+use codex_apply_patch::ApplyPatchFileChange;
 use codex_apply_patch::MaybeApplyPatchVerified;
 use codex_apply_patch::maybe_parse_apply_patch_verified;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -37,12 +37,15 @@ use serde_json;
 use serde_json::Value;
 use tokio::sync::Mutex;
 use tokio::sync::oneshot;
+use tokio::sync::watch;
 use tokio::task::AbortHandle;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing::trace;
 use tracing::warn;
+use uuid::Uuid;
+use wildmatch::WildMatchPattern;
 
 use crate::ModelProviderInfo;
 use crate::apply_patch;
@@ -54,6 +57,10 @@ use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::ExecLimits;
+use crate::config_types::HookConfig;
+use crate::config_types::HooksConfig;
+use crate::config_types::LinuxSandbox;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
 use crate::environment_context::EnvironmentContext;
@@ -67,21 +74,54 @@ use crate::exec::SandboxType;
 use crate::exec::StdoutStream;
 use crate::exec::StreamOutput;
 use crate::exec::process_exec_tool_call;
+use crate::exec::spawn_exec_child;
+use crate::exec_command::BG_LOGS_TOOL_NAME;
+use crate::exec_command::BG_START_TOOL_NAME;
+use crate::exec_command::BG_STOP_TOOL_NAME;
+use crate::exec_command::BgLogsParams;
+use crate::exec_command::BgStartParams;
+use crate::exec_command::BgStopParams;
 use crate::exec_command::EXEC_COMMAND_TOOL_NAME;
+use crate::exec_command::ExecCommandOutput;
 use crate::exec_command::ExecCommandParams;
+use crate::exec_command::ExecSessionId;
 use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
+use crate::exec_command::display_background_command;
 use crate::exec_env::create_env;
+use crate::exec_policy::ExecPolicy;
+use crate::hooks::DEFAULT_STOP_LOOP_LIMIT;
+use crate::hooks::HOOK_BLOCK_EXIT_CODE;
+use crate::hooks::HookDecision;
+use crate::hooks::HookEvent;
+use crate::hooks::HookOutput;
+use crate::hooks::HookResponse;
+use crate::hooks::enabled_hooks;
+use crate::hooks::hook_context_item;
+use crate::hooks::matches_tool_call;
+use crate::hooks::run_hook;
+use crate::hooks::truncate_hook_output;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::mcp_prompts::mcp_prompt_to_custom_prompt;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::model_family::find_family_for_model;
+use crate::network_proxy::NetworkProxy;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::ApplyPatchToolArgs;
+use crate::openai_tools::TASK_TOOL_NAME;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
+use crate::output_artifacts::OutputArtifacts;
+use crate::output_artifacts::READ_OUTPUT_TOOL_NAME;
+use crate::output_artifacts::ReadOutputArgs;
+use crate::output_artifacts::saved_output_note;
 use crate::parse_command::parse_command;
+use crate::permissions::PermissionRule;
+use crate::permissions::PermissionRules;
+use crate::permissions::PermissionVerdict;
+use crate::permissions::permissions_project_root;
 use crate::plan_tool::handle_update_plan;
 use crate::project_doc::get_user_instructions;
 use crate::protocol::AgentMessageDeltaEvent;
@@ -98,7 +138,11 @@ use crate::protocol::ExecApprovalRequestEvent;
 use crate::protocol::ExecCommandBeginEvent;
 use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
+use crate::protocol::GetMcpPromptResponseEvent;
+use crate::protocol::HookBeginEvent;
+use crate::protocol::HookEndEvent;
 use crate::protocol::InputItem;
+use crate::protocol::ListBackgroundProcessesResponseEvent;
 use crate::protocol::ListCustomPromptsResponseEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
@@ -121,7 +165,12 @@ use crate::rollout::RolloutRecorderParams;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::safety::tightened_approval_policy;
+use crate::safety::tightened_sandbox_policy;
 use crate::shell;
+use crate::spawn::StdioPolicy;
+use crate::subagents::discover_subagents_in;
+use crate::subagents::project_agents_dir;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
@@ -130,6 +179,7 @@ use crate::util::backoff;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::PromptTurnOverrides;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::LocalShellAction;
@@ -139,6 +189,7 @@ use codex_protocol::models::ShellToolCallParams;
 use codex_protocol::protocol::InitialHistory;
 
 pub mod compact;
+mod subagent;
 use self::compact::build_compacted_history;
 use self::compact::collect_user_messages;
 
@@ -259,12 +310,30 @@ impl Codex {
 #[derive(Default)]
 struct State {
     approved_commands: HashSet<Vec<String>>,
+    /// Calls the user approved when a `pre_tool_use` hook asked, so that the
+    /// exec path does not ask a second time.
+    hook_approved_calls: HashSet<String>,
+    /// Permission rules from the config plus those added during the session.
+    permissions: PermissionRules,
     current_task: Option<AgentTask>,
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
     pending_input: Vec<ResponseInputItem>,
     history: ConversationHistory,
     token_info: Option<TokenUsageInfo>,
     latest_rate_limits: Option<RateLimitSnapshot>,
+    /// Exit codes of the commands run for tool calls, by call id, kept until
+    /// the `post_tool_use` hooks of the call have run.
+    exec_exit_codes: HashMap<String, i32>,
+    /// `user_prompt_submit` hooks running for prompts submitted while a task
+    /// was running; aborted along with the task on interrupt.
+    prompt_hook_tasks: Vec<AbortHandle>,
+    /// Set when a hook replied with `"continue": false`; the running task
+    /// ends after the current turn and this message is shown to the user.
+    hook_stop_message: Option<String>,
+    /// Set when the model was last shown the environment context of a task
+    /// started with a prompt's overrides, so that the next task shows it the
+    /// session's context again.
+    environment_overridden: bool,
 }
 
 /// Context for an initialized model agent
@@ -276,8 +345,18 @@ pub(crate) struct Session {
 
     /// Manager for external MCP servers/tools.
     mcp_connection_manager: McpConnectionManager,
+    /// Exec policies used to auto-approve or forbid commands.
+    exec_policy: ExecPolicy,
+    /// Proxy for sandboxed commands restricted to a network allowlist,
+    /// started on first use.
+    network_proxy: Mutex<Option<Arc<NetworkProxy>>>,
     session_manager: ExecSessionManager,
     unified_exec_manager: UnifiedExecSessionManager,
+    /// Set once the `session_start` hooks have run, so that the first task
+    /// records their context ahead of its prompt.
+    session_start_hooks_done: watch::Sender<bool>,
+    /// Full output of truncated commands, read back with `read_output`.
+    output_artifacts: OutputArtifacts,
 
     notifier: UserNotifier,
 
@@ -286,6 +365,7 @@ pub(crate) struct Session {
     rollout: Mutex<Option<RolloutRecorder>>,
     state: Mutex<State>,
     codex_linux_sandbox_exe: Option<PathBuf>,
+    linux_sandbox: LinuxSandbox,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
     next_internal_sub_id: AtomicU64,
@@ -304,12 +384,27 @@ pub(crate) struct TurnContext {
     pub(crate) approval_policy: AskForApproval,
     pub(crate) sandbox_policy: SandboxPolicy,
     pub(crate) shell_environment_policy: ShellEnvironmentPolicy,
+    pub(crate) exec_limits: ExecLimits,
     pub(crate) tools_config: ToolsConfig,
     pub(crate) is_review_mode: bool,
+    /// Set for the turns of a subagent started through the `task` tool. Its
+    /// messages are not shown as agent messages; the final one is returned
+    /// to the parent agent instead.
+    pub(crate) is_subagent: bool,
     pub(crate) final_output_json_schema: Option<Value>,
+    /// Tools the model may call (`*` globs allowed). `None` allows every tool.
+    pub(crate) allowed_tools: Option<Vec<String>>,
 }
 
 impl TurnContext {
+    fn is_tool_allowed(&self, tool_name: &str) -> bool {
+        self.allowed_tools.as_ref().is_none_or(|allowed| {
+            allowed
+                .iter()
+                .any(|pattern| WildMatchPattern::<'*', '?'>::new(pattern).matches(tool_name))
+        })
+    }
+
     fn resolve_path(&self, path: Option<String>) -> PathBuf {
         path.as_ref()
             .map(PathBuf::from)
@@ -399,15 +494,29 @@ impl Session {
         // - spin up MCP connection manager
         // - perform default shell discovery
         // - load history metadata
+        // - discover the project's subagents
         let rollout_fut = RolloutRecorder::new(&config, rollout_params);
 
         let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone());
         let default_shell_fut = shell::default_user_shell();
         let history_meta_fut = crate::message_history::history_metadata(&config);
+        let agents_dir = project_agents_dir(&cwd);
+        let subagents_fut = discover_subagents_in(&agents_dir);
 
         // Join all independent futures.
-        let (rollout_recorder, mcp_res, default_shell, (history_log_id, history_entry_count)) =
-            tokio::join!(rollout_fut, mcp_fut, default_shell_fut, history_meta_fut);
+        let (
+            rollout_recorder,
+            mcp_res,
+            default_shell,
+            (history_log_id, history_entry_count),
+            subagents,
+        ) = tokio::join!(
+            rollout_fut,
+            mcp_fut,
+            default_shell_fut,
+            history_meta_fut,
+            subagents_fut
+        );
 
         let rollout_recorder = rollout_recorder.map_err(|e| {
             error!("failed to initialize rollout recorder: {e:#}");
@@ -417,6 +526,7 @@ impl Session {
         // Create the mutable state for the Session.
         let state = State {
             history: ConversationHistory::new(),
+            permissions: config.permissions.clone(),
             ..Default::default()
         };
 
@@ -434,6 +544,14 @@ impl Session {
             }
         };
 
+        let (exec_policy, exec_policy_errors) = ExecPolicy::load(&config.codex_home, &cwd);
+        for message in exec_policy_errors {
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            });
+        }
+
         // Surface individual client start-up failures to the user.
         if !failed_clients.is_empty() {
             for (server_name, err) in failed_clients {
@@ -456,36 +574,48 @@ impl Session {
             model_reasoning_summary,
             conversation_id,
         );
+        let mut tools_config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &config.model_family,
+            include_plan_tool: config.include_plan_tool,
+            include_apply_patch_tool: config.include_apply_patch_tool,
+            include_web_search_request: config.tools_web_search_request,
+            use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+            include_view_image_tool: config.include_view_image_tool,
+            experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+            include_background_process_tools: config.include_background_process_tools,
+            include_read_output_tool: config.include_read_output_tool,
+        });
+        tools_config.subagents = subagents;
         let turn_context = TurnContext {
             client,
-            tools_config: ToolsConfig::new(&ToolsConfigParams {
-                model_family: &config.model_family,
-                include_plan_tool: config.include_plan_tool,
-                include_apply_patch_tool: config.include_apply_patch_tool,
-                include_web_search_request: config.tools_web_search_request,
-                use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
-                include_view_image_tool: config.include_view_image_tool,
-                experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
-            }),
+            tools_config,
             user_instructions,
             base_instructions,
             approval_policy,
             sandbox_policy,
             shell_environment_policy: config.shell_environment_policy.clone(),
+            exec_limits: config.exec_limits,
             cwd,
             is_review_mode: false,
+            is_subagent: false,
             final_output_json_schema: None,
+            allowed_tools: None,
         };
         let sess = Arc::new(Session {
             conversation_id,
             tx_event: tx_event.clone(),
             mcp_connection_manager,
+            exec_policy,
+            network_proxy: Mutex::new(None),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            session_start_hooks_done: watch::Sender::new(false),
+            output_artifacts: OutputArtifacts::new(&config.codex_home, conversation_id),
             notifier: notify,
             state: Mutex::new(state),
             rollout: Mutex::new(Some(rollout_recorder)),
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
+            linux_sandbox: config.linux_sandbox,
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
             next_internal_sub_id: AtomicU64::new(0),
@@ -532,6 +662,7 @@ impl Session {
         {
             state.current_task.take();
         }
+        state.hook_approved_calls.clear();
     }
 
     fn next_internal_sub_id(&self) -> String {
@@ -665,6 +796,46 @@ impl Session {
         state.approved_commands.insert(cmd);
     }
 
+    async fn record_hook_approval(&self, call_id: &str) {
+        let mut state = self.state.lock().await;
+        state.hook_approved_calls.insert(call_id.to_string());
+    }
+
+    /// Whether the user already approved `call_id` when a `pre_tool_use` hook
+    /// asked. The approval is consumed.
+    async fn take_hook_approval(&self, call_id: &str) -> bool {
+        let mut state = self.state.lock().await;
+        state.hook_approved_calls.remove(call_id)
+    }
+
+    /// Exit code of the last command run for `call_id`, if a command ran.
+    async fn take_exec_exit_code(&self, call_id: &str) -> Option<i32> {
+        self.state.lock().await.exec_exit_codes.remove(call_id)
+    }
+
+    async fn add_permission_rule(&self, rule: PermissionRule) {
+        let mut state = self.state.lock().await;
+        state.permissions.add_allow(rule);
+    }
+
+    /// Check the paths written by `action`, including move destinations,
+    /// against the `Edit(...)` permission rules.
+    pub(crate) async fn check_edit_permissions(
+        &self,
+        action: &ApplyPatchAction,
+        cwd: &Path,
+    ) -> PermissionVerdict {
+        let paths = action.changes().iter().flat_map(|(path, change)| {
+            let move_path = match change {
+                ApplyPatchFileChange::Update { move_path, .. } => move_path.as_deref(),
+                ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => None,
+            };
+            std::iter::once(path.as_path()).chain(move_path)
+        });
+        let state = self.state.lock().await;
+        state.permissions.check_paths(paths, cwd)
+    }
+
     /// Records input items: always append to conversation history and
     /// persist these response items to rollout.
     async fn record_conversation_items(&self, items: &[ResponseItem]) {
@@ -864,7 +1035,7 @@ impl Session {
         // Send full stdout/stderr to clients; do not truncate.
         let stdout = stdout.text.clone();
         let stderr = stderr.text.clone();
-        let formatted_output = format_exec_output_str(output);
+        let formatted_output = format_exec_output_str(output, None);
         let aggregated_output: String = aggregated_output.text.clone();
 
         let msg = if is_apply_patch {
@@ -923,8 +1094,27 @@ impl Session {
         self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
             .await;
 
-        let result = process_exec_tool_call(
-            exec_args.params,
+        let mut params = exec_args.params;
+        let network_allowlist = exec_args.sandbox_policy.network_allowlist();
+        let proxy_client =
+            if exec_args.sandbox_type != SandboxType::None && !network_allowlist.is_empty() {
+                match self.network_proxy().await {
+                    Ok(network_proxy) => {
+                        let client = network_proxy.register(network_allowlist);
+                        client.apply_to_env(&mut params.env);
+                        Some(client)
+                    }
+                    Err(e) => {
+                        warn!("failed to start the network proxy: {e}");
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+        let mut result = process_exec_tool_call(
+            params,
             exec_args.sandbox_type,
             exec_args.sandbox_policy,
             exec_args.sandbox_cwd,
@@ -933,6 +1123,42 @@ impl Session {
         )
         .await;
 
+        let blocked = proxy_client
+            .map(|client| client.take_blocked())
+            .unwrap_or_default();
+        if !blocked.is_empty() {
+            let hosts = blocked
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!("blocked network access to {hosts} (not in network_allowlist)");
+            self.notify_background_event(&sub_id, message.clone()).await;
+            let note = format!("\n[codex] {message}\n");
+            match &mut result {
+                Ok(output) => append_to_exec_output(output, &note),
+                Err(CodexErr::Sandbox(
+                    SandboxErr::Denied { output, .. } | SandboxErr::Timeout { output },
+                )) => append_to_exec_output(output, &note),
+                Err(_) => {}
+            }
+        }
+
+        let exit_code = match &result {
+            Ok(output)
+            | Err(CodexErr::Sandbox(
+                SandboxErr::Denied { output, .. } | SandboxErr::Timeout { output },
+            )) => Some(output.exit_code),
+            Err(_) => None,
+        };
+        if let Some(exit_code) = exit_code {
+            self.state
+                .lock()
+                .await
+                .exec_exit_codes
+                .insert(call_id.clone(), exit_code);
+        }
+
         let output_stderr;
         let borrowed: &ExecToolCallOutput = match &result {
             Ok(output) => output,
@@ -961,6 +1187,19 @@ impl Session {
         result
     }
 
+    /// Returns the network proxy, started on first use.
+    async fn network_proxy(&self) -> std::io::Result<Arc<NetworkProxy>> {
+        let mut network_proxy = self.network_proxy.lock().await;
+        match network_proxy.as_ref() {
+            Some(proxy) => Ok(Arc::clone(proxy)),
+            None => {
+                let proxy = Arc::new(NetworkProxy::start().await?);
+                *network_proxy = Some(Arc::clone(&proxy));
+                Ok(proxy)
+            }
+        }
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
@@ -974,6 +1213,23 @@ impl Session {
         self.send_event(event).await;
     }
 
+    /// Saves the full output of a command that is truncated for the model
+    /// and returns its `read_output` handle, if that tool is offered.
+    async fn save_full_output(&self, turn_context: &TurnContext, output: &str) -> Option<String> {
+        if !turn_context.tools_config.read_output_tool
+            || !turn_context.is_tool_allowed(READ_OUTPUT_TOOL_NAME)
+        {
+            return None;
+        }
+        match self.output_artifacts.save(output).await {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                warn!("failed to save full command output: {e}");
+                None
+            }
+        }
+    }
+
     async fn notify_stream_error(&self, sub_id: &str, message: impl Into<String>) {
         let event = Event {
             id: sub_id.to_string(),
@@ -1032,6 +1288,10 @@ impl Session {
         let mut state = self.state.lock().await;
         state.pending_approvals.clear();
         state.pending_input.clear();
+        state.hook_approved_calls.clear();
+        for handle in state.prompt_hook_tasks.drain(..) {
+            handle.abort();
+        }
         if let Some(task) = state.current_task.take() {
             task.abort(TurnAbortReason::Interrupted);
         }
@@ -1041,6 +1301,9 @@ impl Session {
         if let Ok(mut state) = self.state.try_lock() {
             state.pending_approvals.clear();
             state.pending_input.clear();
+            for handle in state.prompt_hook_tasks.drain(..) {
+                handle.abort();
+            }
             if let Some(task) = state.current_task.take() {
                 task.abort(TurnAbortReason::Interrupted);
             }
@@ -1051,129 +1314,329 @@ impl Session {
         &self.notifier
     }
 
-    fn maybe_notify_stop_hooks(
+    /// Run the `stop` hooks once the agent has finished a turn. Returns the
+    /// name and reason of the first hook that blocked the stop, unless a hook
+    /// replied with `"continue": false`.
+    async fn run_stop_hooks(
         &self,
-        hooks_config: &crate::config_types::HooksConfig,
+        hooks_config: &HooksConfig,
         turn_context: &TurnContext,
-        notification: UserNotification,
-    ) {
-        for (hook_name, hook_config) in &hooks_config.stop {
-            if !hook_config.enabled {
+        sub_id: &str,
+        event: &HookEvent,
+    ) -> Option<(String, String)> {
+        let mut blocked = None;
+        for (hook_name, hook) in enabled_hooks(&hooks_config.stop) {
+            let Some(response) = self
+                .run_hook_for_reply(sub_id, hook_name, hook, event, &turn_context.cwd)
+                .await
+            else {
                 continue;
+            };
+            if !response.should_continue() {
+                return None;
+            }
+            if response.blocks() && blocked.is_none() {
+                blocked = Some((
+                    hook_name.to_string(),
+                    response.reason_or_default().to_string(),
+                ));
             }
-
-            self.execute_hook(hook_name, hook_config, &notification, turn_context);
         }
+        blocked
     }
 
-    fn execute_hook(
+    /// Run the `session_start` hooks and record what they print as context
+    /// for the model.
+    async fn run_session_start_hooks(
         &self,
-        _hook_name: &str,
-        hook_config: &crate::config_types::HookConfig,
-        notification: &UserNotification,
+        hooks_config: &HooksConfig,
         turn_context: &TurnContext,
     ) {
-        self.execute_hook_with_timeout(hook_config, notification, turn_context);
+        let hooks = enabled_hooks(&hooks_config.session_start);
+        if hooks.is_empty() {
+            return;
+        }
+        let event = HookEvent::SessionStart {
+            session_id: self.conversation_id.to_string(),
+            cwd: turn_context.cwd.to_string_lossy().to_string(),
+        };
+        let mut context = Vec::new();
+        for (hook_name, hook) in hooks {
+            let Some(response) = self
+                .run_hook_for_reply(
+                    INITIAL_SUBMIT_ID,
+                    hook_name,
+                    hook,
+                    &event,
+                    &turn_context.cwd,
+                )
+                .await
+            else {
+                continue;
+            };
+            if let Some(text) = response.additional_context {
+                context.push(hook_context_item("session_start", hook_name, &text).into());
+            }
+        }
+        if !context.is_empty() {
+            self.record_conversation_items(&context).await;
+        }
+    }
+
+    /// Wait for the `session_start` hooks to finish running.
+    async fn wait_for_session_start_hooks(&self) {
+        let mut done = self.session_start_hooks_done.subscribe();
+        // The sender lives as long as the session, so this cannot fail.
+        let _ = done.wait_for(|done| *done).await;
     }
 
-    fn execute_hook_with_timeout(
+    /// Run the `session_end` hooks, waiting for each to finish.
+    async fn run_session_end_hooks(
         &self,
-        hook_config: &crate::config_types::HookConfig,
-        notification: &UserNotification,
+        hooks_config: &HooksConfig,
         turn_context: &TurnContext,
+        sub_id: &str,
     ) {
-        let Ok(json) = serde_json::to_string(notification) else {
-            error!("failed to serialize hook notification");
-            return;
+        let event = HookEvent::SessionEnd {
+            session_id: self.conversation_id.to_string(),
+            cwd: turn_context.cwd.to_string_lossy().to_string(),
         };
-
-        let timeout = Duration::from_millis(hook_config.timeout_ms.unwrap_or(60000)); // Default 60s
-
-        let expanded_command = self.expand_hook_command(&hook_config.command, turn_context);
-        let expanded_args = self.expand_hook_args(&hook_config.args, turn_context);
-
-        let mut command = std::process::Command::new(expanded_command);
-        command.args(expanded_args);
-        command.stdin(std::process::Stdio::piped());
-        command.stdout(std::process::Stdio::null());
-        command.stderr(std::process::Stdio::null());
-
-        // Set environment variables
-        if let Some(env) = &hook_config.env {
-            for (key, value) in env {
-                let expanded_value = self.expand_hook_env_var(value, turn_context);
-                command.env(key, expanded_value);
-            }
+        for (hook_name, hook) in enabled_hooks(&hooks_config.session_end) {
+            self.run_hook_for_reply(sub_id, hook_name, hook, &event, &turn_context.cwd)
+                .await;
         }
+    }
 
-        // Set CODEX_PROJECT_DIR for path expansion
-        if let Some(git_root) = crate::git_info::get_git_repo_root(&turn_context.cwd) {
-            command.env("CODEX_PROJECT_DIR", git_root);
+    /// Run the `user_prompt_submit` hooks for a prompt. Returns the context
+    /// items the hooks contributed, or the message to show the user when a
+    /// hook blocked the prompt.
+    async fn run_user_prompt_submit_hooks(
+        &self,
+        hooks_config: &HooksConfig,
+        sub_id: &str,
+        cwd: &Path,
+        items: &[InputItem],
+    ) -> Result<Vec<ResponseInputItem>, String> {
+        let hooks = enabled_hooks(&hooks_config.user_prompt_submit);
+        if hooks.is_empty() {
+            return Ok(Vec::new());
         }
-
-        // Spawn with timeout handling
-        if let Ok(mut child) = command.spawn() {
-            if let Some(stdin) = child.stdin.take() {
-                let _ = Write::write_all(&mut BufWriter::new(stdin), json.as_bytes());
+        let prompt = items
+            .iter()
+            .filter_map(|item| match item {
+                InputItem::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let event = HookEvent::UserPromptSubmit {
+            session_id: self.conversation_id.to_string(),
+            cwd: cwd.to_string_lossy().to_string(),
+            prompt,
+        };
+        let mut context = Vec::new();
+        for (hook_name, hook) in hooks {
+            let Some(response) = self
+                .run_hook_for_reply(sub_id, hook_name, hook, &event, cwd)
+                .await
+            else {
+                continue;
+            };
+            if response.blocks() || !response.should_continue() {
+                return Err(format!(
+                    "prompt blocked by user_prompt_submit hook '{hook_name}': {}",
+                    response.reason_or_default()
+                ));
+            }
+            if let Some(text) = response.additional_context {
+                context.push(hook_context_item("user_prompt_submit", hook_name, &text));
             }
+        }
+        Ok(context)
+    }
 
-            // Fire-and-forget with timeout (don't wait)
-            std::thread::spawn(move || {
-                use std::time::Instant;
+    /// Run one hook for `event` and interpret its reply, emitting
+    /// `HookBegin`/`HookEnd` around the run.
+    ///
+    /// Hooks that cannot be run, time out or exit with a failure code other
+    /// than 2 yield `None`; `post_tool_use` hooks are the exception, their
+    /// output is used whatever their exit code. Replies that break the
+    /// protocol also yield `None`. Every such failure is reported to the
+    /// user, as is any `system_message` in the reply.
+    async fn run_hook_for_reply(
+        &self,
+        sub_id: &str,
+        hook_name: &str,
+        hook: &HookConfig,
+        event: &HookEvent,
+        cwd: &Path,
+    ) -> Option<HookResponse> {
+        let event_name = event.name();
+        let call_id = Uuid::new_v4().to_string();
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::HookBegin(HookBeginEvent {
+                call_id: call_id.clone(),
+                name: hook_name.to_string(),
+                event: event_name.to_string(),
+            }),
+        })
+        .await;
 
-                let start = Instant::now();
-                loop {
-                    match child.try_wait() {
-                        Ok(Some(_status)) => {
-                            // Child has exited
-                            break;
-                        }
-                        Ok(None) => {
-                            // Child is still running
-                            if start.elapsed() >= timeout {
-                                // Timeout reached, kill the child
-                                let _ = child.kill();
-                                let _ = child.wait();
-                                break;
-                            }
-                            std::thread::sleep(Duration::from_millis(100));
-                        }
-                        Err(_) => {
-                            // Error checking child status
-                            break;
-                        }
+        let start = Instant::now();
+        let result = run_hook(hook, event, cwd).await;
+        let mut end = HookEndEvent {
+            call_id,
+            name: hook_name.to_string(),
+            event: event_name.to_string(),
+            duration: start.elapsed(),
+            exit_code: None,
+            timed_out: false,
+            stdout: String::new(),
+            stderr: String::new(),
+            blocked_reason: None,
+        };
+        let reply = match result {
+            Ok(output) => {
+                end.exit_code = output.exit_code;
+                end.timed_out = output.timed_out;
+                end.stderr = truncate_hook_output(&output.stderr);
+                let reply = Self::interpret_hook_output(event, &output);
+                if let Ok(response) = &reply {
+                    if !response.suppress_output {
+                        end.stdout = truncate_hook_output(&output.stdout);
+                    }
+                    if response.blocks() {
+                        end.blocked_reason = Some(response.reason_or_default().to_string());
                     }
                 }
-            });
-        } else {
-            warn!("failed to spawn hook '{}'", hook_config.command);
+                reply
+            }
+            Err(e) => {
+                end.stderr = e.to_string();
+                Err(format!("failed to run: {e}"))
+            }
+        };
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::HookEnd(end),
+        })
+        .await;
+
+        match reply {
+            Ok(response) => {
+                if let Some(message) = &response.system_message {
+                    self.notify_background_event(
+                        sub_id,
+                        format!("{event_name} hook '{hook_name}': {message}"),
+                    )
+                    .await;
+                }
+                Some(response)
+            }
+            Err(e) => {
+                warn!("{event_name} hook '{hook_name}' {e}");
+                let ignored = if hook.fail_closed && matches!(event, HookEvent::PreToolUse { .. }) {
+                    "the tool call is blocked because the hook is fail_closed"
+                } else {
+                    "its reply is ignored"
+                };
+                self.notify_background_event(
+                    sub_id,
+                    format!("{event_name} hook '{hook_name}' {e}; {ignored}"),
+                )
+                .await;
+                None
+            }
         }
     }
 
-    fn expand_hook_command(&self, command: &str, turn_context: &TurnContext) -> String {
-        let git_root = crate::git_info::get_git_repo_root(&turn_context.cwd)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| turn_context.cwd.to_string_lossy().to_string());
+    /// Interpret the output of a hook run: the parsed reply, or why there is
+    /// none because the hook timed out, failed or broke the protocol.
+    fn interpret_hook_output(
+        event: &HookEvent,
+        output: &HookOutput,
+    ) -> Result<HookResponse, String> {
+        if output.timed_out {
+            return Err("timed out".to_string());
+        }
+        if !output.success()
+            && output.exit_code != Some(HOOK_BLOCK_EXIT_CODE)
+            && !matches!(event, HookEvent::PostToolUse { .. })
+        {
+            let mut failure = match output.exit_code {
+                Some(code) => format!("exited with code {code}"),
+                None => "was killed by a signal".to_string(),
+            };
+            let stderr = output.stderr.trim();
+            if !stderr.is_empty() {
+                failure.push_str(&format!(": {stderr}"));
+            }
+            return Err(failure);
+        }
+        HookResponse::parse(event, output)
+            .map_err(|e| format!("replied with an invalid response: {e}"))
+    }
 
-        command.replace("$CODEX_PROJECT_DIR", &git_root)
+    /// End the running task after its current turn, showing `message` to the
+    /// user. Used for hooks that reply with `"continue": false`.
+    async fn request_hook_stop(&self, message: String) {
+        self.state
+            .lock()
+            .await
+            .hook_stop_message
+            .get_or_insert(message);
     }
 
-    fn expand_hook_args(&self, args: &[String], turn_context: &TurnContext) -> Vec<String> {
-        let git_root = crate::git_info::get_git_repo_root(&turn_context.cwd)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| turn_context.cwd.to_string_lossy().to_string());
+    async fn take_hook_stop_message(&self) -> Option<String> {
+        self.state.lock().await.hook_stop_message.take()
+    }
 
-        args.iter()
-            .map(|arg| arg.replace("$CODEX_PROJECT_DIR", &git_root))
-            .collect()
+    /// Record whether the model is being shown an environment context that
+    /// only applies to one task. Returns the previous value.
+    async fn set_environment_overridden(&self, overridden: bool) -> bool {
+        std::mem::replace(
+            &mut self.state.lock().await.environment_overridden,
+            overridden,
+        )
     }
 
-    fn expand_hook_env_var(&self, value: &str, turn_context: &TurnContext) -> String {
-        let git_root = crate::git_info::get_git_repo_root(&turn_context.cwd)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| turn_context.cwd.to_string_lossy().to_string());
+    /// Queue hook `context` followed by `input` for the running task. Returns
+    /// the input back when no task is running.
+    async fn inject_input_with_context(
+        &self,
+        input: Vec<InputItem>,
+        context: &[ResponseInputItem],
+    ) -> Result<(), Vec<InputItem>> {
+        let mut state = self.state.lock().await;
+        if state.current_task.is_some() {
+            state.pending_input.extend(context.iter().cloned());
+            state.pending_input.push(input.into());
+            Ok(())
+        } else {
+            Err(input)
+        }
+    }
 
-        value.replace("$CODEX_PROJECT_DIR", &git_root)
+    /// Report a prompt rejected by a `user_prompt_submit` hook. When no task
+    /// is running the submission also completes, so that clients waiting for
+    /// the turn to end do not hang.
+    async fn reject_prompt(&self, sub_id: &str, message: String) {
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::Error(ErrorEvent { message }),
+        })
+        .await;
+        let task_running = self.state.lock().await.current_task.is_some();
+        if !task_running {
+            self.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                    last_agent_message: None,
+                }),
+            })
+            .await;
+        }
     }
 }
 
@@ -1214,19 +1677,41 @@ pub(crate) struct AgentTask {
 }
 
 impl AgentTask {
+    /// Start a task for a user prompt. `hook_context` is what the
+    /// `user_prompt_submit` hooks returned when they already ran for `input`;
+    /// otherwise the task runs them first, so that an interrupt stops them.
     fn spawn(
         sess: Arc<Session>,
         turn_context: Arc<TurnContext>,
         sub_id: String,
         input: Vec<InputItem>,
-        hooks_config: crate::config_types::HooksConfig,
+        hooks_config: HooksConfig,
+        hook_context: Option<Vec<ResponseInputItem>>,
     ) -> Self {
         let handle = {
             let sess = sess.clone();
             let sub_id = sub_id.clone();
             let tc = Arc::clone(&turn_context);
-            tokio::spawn(async move { run_task(sess, tc, sub_id, input, &hooks_config).await })
-                .abort_handle()
+            tokio::spawn(async move {
+                sess.wait_for_session_start_hooks().await;
+                let context = match hook_context {
+                    Some(context) => context,
+                    None => match sess
+                        .run_user_prompt_submit_hooks(&hooks_config, &sub_id, &tc.cwd, &input)
+                        .await
+                    {
+                        Ok(context) => context,
+                        Err(message) => {
+                            sess.remove_task(&sub_id).await;
+                            sess.reject_prompt(&sub_id, message).await;
+                            return;
+                        }
+                    },
+                };
+                record_hook_context(&sess, context).await;
+                run_task(sess, tc, sub_id, input, &hooks_config).await
+            })
+            .abort_handle()
         };
         Self {
             sess,
@@ -1241,7 +1726,7 @@ impl AgentTask {
         turn_context: Arc<TurnContext>,
         sub_id: String,
         input: Vec<InputItem>,
-        hooks_config: crate::config_types::HooksConfig,
+        hooks_config: HooksConfig,
     ) -> Self {
         let handle = {
             let sess = sess.clone();
@@ -1306,6 +1791,16 @@ async fn submission_loop(
 ) {
     // Wrap once to avoid cloning TurnContext for each task.
     let mut turn_context = Arc::new(turn_context);
+    {
+        let sess = sess.clone();
+        let turn_context = Arc::clone(&turn_context);
+        let hooks_config = config.hooks.clone();
+        tokio::spawn(async move {
+            sess.run_session_start_hooks(&hooks_config, &turn_context)
+                .await;
+            sess.session_start_hooks_done.send_replace(true);
+        });
+    }
     // To break out of this loop, send Op::Shutdown.
     while let Ok(sub) = rx_sub.recv().await {
         debug!(?sub, "Submission");
@@ -1363,7 +1858,7 @@ async fn submission_loop(
                     .unwrap_or(prev.sandbox_policy.clone());
                 let new_cwd = cwd.clone().unwrap_or_else(|| prev.cwd.clone());
 
-                let tools_config = ToolsConfig::new(&ToolsConfigParams {
+                let mut tools_config = ToolsConfig::new(&ToolsConfigParams {
                     model_family: &effective_family,
                     include_plan_tool: config.include_plan_tool,
                     include_apply_patch_tool: config.include_apply_patch_tool,
@@ -1371,7 +1866,10 @@ async fn submission_loop(
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
                     experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                    include_background_process_tools: config.include_background_process_tools,
+                    include_read_output_tool: config.include_read_output_tool,
                 });
+                tools_config.subagents = prev.tools_config.subagents.clone();
 
                 let new_turn_context = TurnContext {
                     client,
@@ -1381,9 +1879,12 @@ async fn submission_loop(
                     approval_policy: new_approval_policy,
                     sandbox_policy: new_sandbox_policy.clone(),
                     shell_environment_policy: prev.shell_environment_policy.clone(),
+                    exec_limits: prev.exec_limits,
                     cwd: new_cwd.clone(),
                     is_review_mode: false,
+                    is_subagent: false,
                     final_output_json_schema: None,
+                    allowed_tools: prev.allowed_tools.clone(),
                 };
 
                 // Install the new persistent context for subsequent tasks/turns.
@@ -1403,7 +1904,15 @@ async fn submission_loop(
             }
             Op::UserInput { items } => {
                 // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items).await {
+                if let Err(items) =
+                    queue_prompt(&sess, &turn_context, None, &config.hooks, &sub.id, items).await
+                {
+                    if sess.set_environment_overridden(false).await {
+                        sess.record_conversation_items(&[ResponseItem::from(
+                            EnvironmentContext::from(turn_context.as_ref()),
+                        )])
+                        .await;
+                    }
                     // no current task, spawn a new one
                     let task = AgentTask::spawn(
                         sess.clone(),
@@ -1411,6 +1920,7 @@ async fn submission_loop(
                         sub.id,
                         items,
                         config.hooks.clone(),
+                        None,
                     );
                     sess.set_task(task).await;
                 }
@@ -1424,69 +1934,94 @@ async fn submission_loop(
                 effort,
                 summary,
                 final_output_json_schema,
+                allowed_tools,
             } => {
-                // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items).await {
-                    // Derive a fresh TurnContext for this turn using the provided overrides.
-                    let provider = turn_context.client.get_provider();
-                    let auth_manager = turn_context.client.get_auth_manager();
-
-                    // Derive a model family for the requested model; fall back to the session's.
-                    let model_family = find_family_for_model(&model)
-                        .unwrap_or_else(|| config.model_family.clone());
-
-                    // Create a per‑turn Config clone with the requested model/family.
-                    let mut per_turn_config = (*config).clone();
-                    per_turn_config.model = model.clone();
-                    per_turn_config.model_family = model_family.clone();
-                    if let Some(model_info) = get_model_info(&model_family) {
-                        per_turn_config.model_context_window = Some(model_info.context_window);
-                    }
+                // Derive a fresh TurnContext for this turn using the provided overrides.
+                let provider = turn_context.client.get_provider();
+                let auth_manager = turn_context.client.get_auth_manager();
+
+                // Derive a model family for the requested model; fall back to the session's.
+                let model_family =
+                    find_family_for_model(&model).unwrap_or_else(|| config.model_family.clone());
+
+                // Create a per‑turn Config clone with the requested model/family.
+                let mut per_turn_config = (*config).clone();
+                per_turn_config.model = model.clone();
+                per_turn_config.model_family = model_family.clone();
+                if let Some(model_info) = get_model_info(&model_family) {
+                    per_turn_config.model_context_window = Some(model_info.context_window);
+                }
 
-                    // Build a new client with per‑turn reasoning settings.
-                    // Reuse the same provider and session id; auth defaults to env/API key.
-                    let client = ModelClient::new(
-                        Arc::new(per_turn_config),
-                        auth_manager,
-                        provider,
-                        effort,
-                        summary,
-                        sess.conversation_id,
-                    );
+                // Build a new client with per‑turn reasoning settings.
+                // Reuse the same provider and session id; auth defaults to env/API key.
+                let client = ModelClient::new(
+                    Arc::new(per_turn_config),
+                    auth_manager,
+                    provider,
+                    effort,
+                    summary,
+                    sess.conversation_id,
+                );
 
-                    let fresh_turn_context = TurnContext {
-                        client,
-                        tools_config: ToolsConfig::new(&ToolsConfigParams {
-                            model_family: &model_family,
-                            include_plan_tool: config.include_plan_tool,
-                            include_apply_patch_tool: config.include_apply_patch_tool,
-                            include_web_search_request: config.tools_web_search_request,
-                            use_streamable_shell_tool: config
-                                .use_experimental_streamable_shell_tool,
-                            include_view_image_tool: config.include_view_image_tool,
-                            experimental_unified_exec_tool: config
-                                .use_experimental_unified_exec_tool,
-                        }),
-                        user_instructions: turn_context.user_instructions.clone(),
-                        base_instructions: turn_context.base_instructions.clone(),
-                        approval_policy,
-                        sandbox_policy,
-                        shell_environment_policy: turn_context.shell_environment_policy.clone(),
-                        cwd,
-                        is_review_mode: false,
-                        final_output_json_schema,
-                    };
+                let mut tools_config = ToolsConfig::new(&ToolsConfigParams {
+                    model_family: &model_family,
+                    include_plan_tool: config.include_plan_tool,
+                    include_apply_patch_tool: config.include_apply_patch_tool,
+                    include_web_search_request: config.tools_web_search_request,
+                    use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+                    include_view_image_tool: config.include_view_image_tool,
+                    experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                    include_background_process_tools: config.include_background_process_tools,
+                    include_read_output_tool: config.include_read_output_tool,
+                });
+                tools_config.subagents = turn_context.tools_config.subagents.clone();
+                let fresh_turn_context = Arc::new(TurnContext {
+                    client,
+                    tools_config,
+                    user_instructions: turn_context.user_instructions.clone(),
+                    base_instructions: turn_context.base_instructions.clone(),
+                    approval_policy,
+                    sandbox_policy,
+                    shell_environment_policy: turn_context.shell_environment_policy.clone(),
+                    exec_limits: turn_context.exec_limits,
+                    cwd,
+                    is_review_mode: false,
+                    is_subagent: false,
+                    final_output_json_schema,
+                    allowed_tools,
+                });
+
+                // if the environment context has changed, record it in the conversation
+                // history once the turn starts
+                let previous_env_context = EnvironmentContext::from(turn_context.as_ref());
+                let new_env_context = EnvironmentContext::from(fresh_turn_context.as_ref());
+                let env_change = (!new_env_context.equals_except_shell(&previous_env_context))
+                    .then_some(new_env_context);
 
-                    // if the environment context has changed, record it in the conversation history
-                    let previous_env_context = EnvironmentContext::from(turn_context.as_ref());
-                    let new_env_context = EnvironmentContext::from(&fresh_turn_context);
-                    if !new_env_context.equals_except_shell(&previous_env_context) {
-                        sess.record_conversation_items(&[ResponseItem::from(new_env_context)])
+                // attempt to inject input into current task
+                if let Err(items) = queue_prompt(
+                    &sess,
+                    &fresh_turn_context,
+                    env_change.clone(),
+                    &config.hooks,
+                    &sub.id,
+                    items,
+                )
+                .await
+                {
+                    // A task started with a prompt's overrides may have shown
+                    // the model another environment; show it this one again.
+                    let overridden = sess.set_environment_overridden(false).await;
+                    let env_change = env_change.or_else(|| {
+                        overridden.then(|| EnvironmentContext::from(fresh_turn_context.as_ref()))
+                    });
+                    if let Some(env_change) = env_change {
+                        sess.record_conversation_items(&[ResponseItem::from(env_change)])
                             .await;
                     }
 
                     // Install the new persistent context for subsequent tasks/turns.
-                    turn_context = Arc::new(fresh_turn_context);
+                    turn_context = fresh_turn_context;
 
                     // no current task, spawn a new one with the per‑turn context
                     let task = AgentTask::spawn(
@@ -1495,8 +2030,44 @@ async fn submission_loop(
                         sub.id,
                         items,
                         config.hooks.clone(),
+                        None,
+                    );
+                    sess.set_task(task).await;
+                }
+            }
+            Op::PromptTurn { items, overrides } => {
+                if let Err(items) =
+                    queue_prompt(&sess, &turn_context, None, &config.hooks, &sub.id, items).await
+                {
+                    let (prompt_context, ignored) =
+                        prompt_turn_context(&sess, &config, &turn_context, &overrides);
+                    for message in ignored {
+                        sess.notify_background_event(&sub.id, message).await;
+                    }
+                    // The overrides are dropped with the task, so the next
+                    // task shows the model the session's context again.
+                    let prompt_env_context = EnvironmentContext::from(&prompt_context);
+                    let overridden = !prompt_env_context
+                        .equals_except_shell(&EnvironmentContext::from(turn_context.as_ref()));
+                    if sess.set_environment_overridden(overridden).await || overridden {
+                        sess.record_conversation_items(&[ResponseItem::from(prompt_env_context)])
+                            .await;
+                    }
+                    let task = AgentTask::spawn(
+                        sess.clone(),
+                        Arc::new(prompt_context),
+                        sub.id,
+                        items,
+                        config.hooks.clone(),
+                        None,
                     );
                     sess.set_task(task).await;
+                } else if overrides != PromptTurnOverrides::default() {
+                    sess.notify_background_event(
+                        &sub.id,
+                        "the prompt was added to the running task, so its frontmatter overrides are not applied",
+                    )
+                    .await;
                 }
             }
             Op::ExecApproval { id, decision } => match decision {
@@ -1511,6 +2082,30 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other).await,
             },
+            Op::AddPermissionRule { rule } => {
+                let result = match PermissionRule::parse(&rule) {
+                    Ok(parsed) => {
+                        sess.add_permission_rule(parsed).await;
+                        let project = permissions_project_root(&turn_context.cwd);
+                        crate::config_edit::persist_project_permission_rule(
+                            &config.codex_home,
+                            &project,
+                            &rule,
+                        )
+                        .await
+                        .map_err(|e| format!("failed to save permission rule `{rule}`: {e}"))
+                    }
+                    Err(message) => Err(message),
+                };
+                if let Err(message) = result {
+                    warn!("{message}");
+                    sess.send_event(Event {
+                        id: sub.id.clone(),
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    })
+                    .await;
+                }
+            }
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1571,7 +2166,7 @@ async fn submission_loop(
             Op::ListCustomPrompts => {
                 let sub_id = sub.id.clone();
 
-                let custom_prompts: Vec<CustomPrompt> =
+                let mut custom_prompts: Vec<CustomPrompt> =
                     if let Some(global_dir) = crate::custom_prompts::default_prompts_dir() {
                         crate::custom_prompts::discover_prompts_with_project_support(
                             &global_dir,
@@ -1588,6 +2183,15 @@ async fn submission_loop(
                         }
                     };
 
+                // MCP prompts are listed as `server:prompt`; a prompt file with
+                // the same name takes precedence.
+                for (server, prompt) in sess.mcp_connection_manager.list_all_prompts() {
+                    let prompt = mcp_prompt_to_custom_prompt(&server, prompt);
+                    if !custom_prompts.iter().any(|p| p.name == prompt.name) {
+                        custom_prompts.push(prompt);
+                    }
+                }
+
                 let event = Event {
                     id: sub_id,
                     msg: EventMsg::ListCustomPromptsResponse(ListCustomPromptsResponseEvent {
@@ -1596,14 +2200,70 @@ async fn submission_loop(
                 };
                 sess.send_event(event).await;
             }
-            Op::Compact => {
-                // Attempt to inject input into current task
-                if let Err(items) = sess
-                    .inject_input(vec![InputItem::Text {
-                        text: compact::SUMMARIZATION_PROMPT.to_string(),
-                    }])
-                    .await
-                {
+            Op::ListBackgroundProcesses => {
+                let processes = sess.session_manager.list_background_processes().await;
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::ListBackgroundProcessesResponse(
+                        ListBackgroundProcessesResponseEvent { processes },
+                    ),
+                };
+                sess.send_event(event).await;
+            }
+            Op::StopBackgroundProcess { id } => {
+                let sess_clone = sess.clone();
+                let sub_id = sub.id.clone();
+
+                // Stopping waits for the process to exit, so do not block the
+                // submission loop.
+                tokio::spawn(async move {
+                    let message = match sess_clone
+                        .session_manager
+                        .stop_background_process(ExecSessionId(id))
+                        .await
+                    {
+                        Ok(_) => format!("stopped background process {id}"),
+                        Err(e) => e,
+                    };
+                    sess_clone.notify_background_event(&sub_id, message).await;
+                });
+            }
+            Op::GetMcpPrompt {
+                server,
+                prompt,
+                arguments,
+            } => {
+                let sess_clone = sess.clone();
+                let sub_id = sub.id.clone();
+
+                // Servers can take a while to render a prompt, so answer from a
+                // separate task instead of blocking the submission loop.
+                tokio::spawn(async move {
+                    let arguments = (!arguments.is_empty()).then(|| serde_json::json!(arguments));
+                    let result = sess_clone
+                        .mcp_connection_manager
+                        .get_prompt(&server, &prompt, arguments)
+                        .await
+                        .map_err(|e| format!("{e:#}"));
+                    let event = Event {
+                        id: sub_id,
+                        msg: EventMsg::GetMcpPromptResponse(GetMcpPromptResponseEvent {
+                            server,
+                            prompt,
+                            result,
+                        }),
+                    };
+                    sess_clone.send_event(event).await;
+                });
+            }
+            Op::Compact => {
+                // Attempt to inject input into current task
+                if let Err(items) = sess
+                    .inject_input(vec![InputItem::Text {
+                        text: compact::SUMMARIZATION_PROMPT.to_string(),
+                    }])
+                    .await
+                {
                     compact::spawn_compact_task(
                         sess.clone(),
                         Arc::clone(&turn_context),
@@ -1615,6 +2275,9 @@ async fn submission_loop(
             }
             Op::Shutdown => {
                 info!("Shutting down Codex instance");
+                sess.session_manager.stop_all_background_processes().await;
+                sess.run_session_end_hooks(&config.hooks, &turn_context, &sub.id)
+                    .await;
 
                 // Gracefully flush and shutdown rollout recorder on session end so tests
                 // that inspect the rollout file do not race with the background writer.
@@ -1687,6 +2350,168 @@ async fn submission_loop(
     debug!("Agent loop exited");
 }
 
+/// Build the context of a task started by a custom prompt: `base` with the
+/// prompt's frontmatter overrides applied. Prompts may come from the
+/// repository, so sandbox and approval overrides that would loosen `base`
+/// are dropped; the returned messages report them.
+fn prompt_turn_context(
+    sess: &Session,
+    config: &Config,
+    base: &TurnContext,
+    overrides: &PromptTurnOverrides,
+) -> (TurnContext, Vec<String>) {
+    let mut ignored = Vec::new();
+    let sandbox_policy = match overrides.sandbox {
+        None => base.sandbox_policy.clone(),
+        Some(mode) => tightened_sandbox_policy(&base.sandbox_policy, mode).unwrap_or_else(|| {
+            ignored.push(format!(
+                "ignored the prompt's `sandbox: {mode}`, which is less restrictive than the session's sandbox"
+            ));
+            base.sandbox_policy.clone()
+        }),
+    };
+    let approval_policy = match overrides.approval {
+        None => base.approval_policy,
+        Some(approval) => tightened_approval_policy(base.approval_policy, approval)
+            .unwrap_or_else(|| {
+                ignored.push(format!(
+                    "ignored the prompt's `approval: {approval}`, which asks for approval less often than the session's `{}`",
+                    base.approval_policy
+                ));
+                base.approval_policy
+            }),
+    };
+
+    let model = overrides
+        .model
+        .clone()
+        .unwrap_or_else(|| base.client.get_model());
+    let model_family =
+        find_family_for_model(&model).unwrap_or_else(|| base.client.get_model_family());
+    let effort = overrides
+        .effort
+        .or_else(|| base.client.get_reasoning_effort());
+    let mut per_turn_config = config.clone();
+    per_turn_config.model = model;
+    per_turn_config.model_family = model_family.clone();
+    per_turn_config.model_reasoning_effort = effort;
+    if let Some(model_info) = get_model_info(&model_family) {
+        per_turn_config.model_context_window = Some(model_info.context_window);
+    }
+    let client = ModelClient::new(
+        Arc::new(per_turn_config),
+        base.client.get_auth_manager(),
+        base.client.get_provider(),
+        effort,
+        base.client.get_reasoning_summary(),
+        sess.conversation_id,
+    );
+
+    let mut tools_config = ToolsConfig::new(&ToolsConfigParams {
+        model_family: &model_family,
+        include_plan_tool: config.include_plan_tool,
+        include_apply_patch_tool: config.include_apply_patch_tool,
+        include_web_search_request: config.tools_web_search_request,
+        use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+        include_view_image_tool: config.include_view_image_tool,
+        experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        include_background_process_tools: config.include_background_process_tools,
+        include_read_output_tool: config.include_read_output_tool,
+    });
+    tools_config.subagents = base.tools_config.subagents.clone();
+
+    let turn_context = TurnContext {
+        client,
+        tools_config,
+        user_instructions: base.user_instructions.clone(),
+        base_instructions: base.base_instructions.clone(),
+        approval_policy,
+        sandbox_policy,
+        shell_environment_policy: base.shell_environment_policy.clone(),
+        exec_limits: base.exec_limits,
+        cwd: base.cwd.clone(),
+        is_review_mode: false,
+        is_subagent: false,
+        final_output_json_schema: None,
+        allowed_tools: overrides
+            .allowed_tools
+            .clone()
+            .or_else(|| base.allowed_tools.clone()),
+    };
+    (turn_context, ignored)
+}
+
+/// Queue a prompt for the running task. Its `user_prompt_submit` hooks run
+/// in a separate task, with `turn_context`, so that the submission loop stays
+/// responsive; if the running task has finished by the time they are done,
+/// the prompt starts a new task with `turn_context`, recording `env_change`
+/// first. Returns the prompt back when no task is running.
+async fn queue_prompt(
+    sess: &Arc<Session>,
+    turn_context: &Arc<TurnContext>,
+    env_change: Option<EnvironmentContext>,
+    hooks_config: &HooksConfig,
+    sub_id: &str,
+    items: Vec<InputItem>,
+) -> Result<(), Vec<InputItem>> {
+    if enabled_hooks(&hooks_config.user_prompt_submit).is_empty() {
+        return sess.inject_input_with_context(items, &[]).await;
+    }
+    let mut state = sess.state.lock().await;
+    if state.current_task.is_none() {
+        return Err(items);
+    }
+    let handle = {
+        let sess = Arc::clone(sess);
+        let turn_context = Arc::clone(turn_context);
+        let hooks_config = hooks_config.clone();
+        let sub_id = sub_id.to_string();
+        tokio::spawn(async move {
+            let context = match sess
+                .run_user_prompt_submit_hooks(&hooks_config, &sub_id, &turn_context.cwd, &items)
+                .await
+            {
+                Ok(context) => context,
+                Err(message) => {
+                    sess.reject_prompt(&sub_id, message).await;
+                    return;
+                }
+            };
+            if let Err(items) = sess.inject_input_with_context(items, &context).await {
+                if let Some(env_change) = env_change {
+                    sess.record_conversation_items(&[ResponseItem::from(env_change)])
+                        .await;
+                }
+                let task = AgentTask::spawn(
+                    sess.clone(),
+                    turn_context,
+                    sub_id,
+                    items,
+                    hooks_config,
+                    Some(context),
+                );
+                sess.set_task(task).await;
+            }
+        })
+        .abort_handle()
+    };
+    state
+        .prompt_hook_tasks
+        .retain(|handle| !handle.is_finished());
+    state.prompt_hook_tasks.push(handle);
+    Ok(())
+}
+
+/// Record context contributed by `user_prompt_submit` hooks ahead of a new
+/// task's input.
+async fn record_hook_context(sess: &Session, context: Vec<ResponseInputItem>) {
+    if context.is_empty() {
+        return;
+    }
+    let items: Vec<ResponseItem> = context.into_iter().map(ResponseItem::from).collect();
+    sess.record_conversation_items(&items).await;
+}
+
 /// Spawn a review thread using the given prompt.
 async fn spawn_review_thread(
     sess: Arc<Session>,
@@ -1706,6 +2531,8 @@ async fn spawn_review_thread(
         use_streamable_shell_tool: false,
         include_view_image_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        include_background_process_tools: false,
+        include_read_output_tool: false,
     });
 
     let base_instructions = REVIEW_PROMPT.to_string();
@@ -1742,9 +2569,12 @@ async fn spawn_review_thread(
         approval_policy: parent_turn_context.approval_policy,
         sandbox_policy: parent_turn_context.sandbox_policy.clone(),
         shell_environment_policy: parent_turn_context.shell_environment_policy.clone(),
+        exec_limits: parent_turn_context.exec_limits,
         cwd: parent_turn_context.cwd.clone(),
         is_review_mode: true,
+        is_subagent: false,
         final_output_json_schema: None,
+        allowed_tools: parent_turn_context.allowed_tools.clone(),
     };
 
     // Seed the child task with the review prompt as the initial user message.
@@ -1760,7 +2590,7 @@ async fn spawn_review_thread(
         tc.clone(),
         sub_id,
         input,
-        crate::config_types::HooksConfig::default(),
+        HooksConfig::default(),
     );
     sess.set_task(task).await;
 
@@ -1794,7 +2624,7 @@ async fn run_task(
     turn_context: Arc<TurnContext>,
     sub_id: String,
     input: Vec<InputItem>,
-    hooks_config: &crate::config_types::HooksConfig,
+    hooks_config: &HooksConfig,
 ) {
     if input.is_empty() {
         return;
@@ -1807,6 +2637,9 @@ async fn run_task(
     };
     sess.send_event(event).await;
 
+    // Drop a stop request left behind by a task that was interrupted.
+    sess.take_hook_stop_message().await;
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
     // For review threads, keep an isolated in-memory history so the
     // model sees a fresh conversation without the parent session's history.
@@ -1827,6 +2660,8 @@ async fn run_task(
     // many turns, from the perspective of the user, it is a single turn.
     let mut turn_diff_tracker = TurnDiffTracker::new();
     let mut auto_compact_recently_attempted = false;
+    // Extra turns started because a stop hook blocked the stop.
+    let mut stop_hook_turns: u32 = 0;
 
     loop {
         // Note that pending_input would be something like a message the user
@@ -1878,6 +2713,7 @@ async fn run_task(
             &mut turn_diff_tracker,
             sub_id.clone(),
             turn_input,
+            hooks_config,
         )
         .await
         {
@@ -1896,98 +2732,8 @@ async fn run_task(
                 let token_limit_reached = total_usage_tokens
                     .map(|tokens| (tokens as i64) >= limit)
                     .unwrap_or(false);
-                let mut items_to_record_in_conversation_history = Vec::<ResponseItem>::new();
-                let mut responses = Vec::<ResponseInputItem>::new();
-                for processed_response_item in processed_items {
-                    let ProcessedResponseItem { item, response } = processed_response_item;
-                    match (&item, &response) {
-                        (ResponseItem::Message { role, .. }, None) if role == "assistant" => {
-                            // If the model returned a message, we need to record it.
-                            items_to_record_in_conversation_history.push(item);
-                        }
-                        (
-                            ResponseItem::LocalShellCall { .. },
-                            Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
-                        ) => {
-                            items_to_record_in_conversation_history.push(item);
-                            items_to_record_in_conversation_history.push(
-                                ResponseItem::FunctionCallOutput {
-                                    call_id: call_id.clone(),
-                                    output: output.clone(),
-                                },
-                            );
-                        }
-                        (
-                            ResponseItem::FunctionCall { .. },
-                            Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
-                        ) => {
-                            items_to_record_in_conversation_history.push(item);
-                            items_to_record_in_conversation_history.push(
-                                ResponseItem::FunctionCallOutput {
-                                    call_id: call_id.clone(),
-                                    output: output.clone(),
-                                },
-                            );
-                        }
-                        (
-                            ResponseItem::CustomToolCall { .. },
-                            Some(ResponseInputItem::CustomToolCallOutput { call_id, output }),
-                        ) => {
-                            items_to_record_in_conversation_history.push(item);
-                            items_to_record_in_conversation_history.push(
-                                ResponseItem::CustomToolCallOutput {
-                                    call_id: call_id.clone(),
-                                    output: output.clone(),
-                                },
-                            );
-                        }
-                        (
-                            ResponseItem::FunctionCall { .. },
-                            Some(ResponseInputItem::McpToolCallOutput { call_id, result }),
-                        ) => {
-                            items_to_record_in_conversation_history.push(item);
-                            let output = match result {
-                                Ok(call_tool_result) => {
-                                    convert_call_tool_result_to_function_call_output_payload(
-                                        call_tool_result,
-                                    )
-                                }
-                                Err(err) => FunctionCallOutputPayload {
-                                    content: err.clone(),
-                                    success: Some(false),
-                                },
-                            };
-                            items_to_record_in_conversation_history.push(
-                                ResponseItem::FunctionCallOutput {
-                                    call_id: call_id.clone(),
-                                    output,
-                                },
-                            );
-                        }
-                        (
-                            ResponseItem::Reasoning {
-                                id,
-                                summary,
-                                content,
-                                encrypted_content,
-                            },
-                            None,
-                        ) => {
-                            items_to_record_in_conversation_history.push(ResponseItem::Reasoning {
-                                id: id.clone(),
-                                summary: summary.clone(),
-                                content: content.clone(),
-                                encrypted_content: encrypted_content.clone(),
-                            });
-                        }
-                        _ => {
-                            warn!("Unexpected response item: {item:?} with response: {response:?}");
-                        }
-                    };
-                    if let Some(response) = response {
-                        responses.push(response);
-                    }
-                }
+                let (items_to_record_in_conversation_history, responses) =
+                    split_processed_items(processed_items);
 
                 // Only attempt to take the lock if there is something to record.
                 if !items_to_record_in_conversation_history.is_empty() {
@@ -2000,6 +2746,11 @@ async fn run_task(
                     }
                 }
 
+                if let Some(message) = sess.take_hook_stop_message().await {
+                    sess.notify_background_event(&sub_id, message).await;
+                    break;
+                }
+
                 if token_limit_reached {
                     if auto_compact_recently_attempted {
                         let limit_str = limit.to_string();
@@ -2028,24 +2779,56 @@ async fn run_task(
                     last_agent_message = get_last_assistant_message_from_turn(
                         &items_to_record_in_conversation_history,
                     );
-                    sess.maybe_notify(UserNotification::AgentTurnComplete {
-                        turn_id: sub_id.clone(),
-                        input_messages: turn_input_messages.clone(),
-                        last_assistant_message: last_agent_message.clone(),
-                    });
-
-                    // Execute Stop hooks when agent turn completes
-                    sess.maybe_notify_stop_hooks(
-                        hooks_config,
-                        &turn_context,
-                        UserNotification::AgentTurnStopped {
+                    sess.notifier()
+                        .notify(&UserNotification::AgentTurnComplete {
                             turn_id: sub_id.clone(),
-                            session_id: sess.conversation_id.to_string(),
-                            cwd: turn_context.cwd.to_string_lossy().to_string(),
-                            input_messages: turn_input_messages,
+                            input_messages: turn_input_messages.clone(),
                             last_assistant_message: last_agent_message.clone(),
-                        },
-                    );
+                        });
+
+                    let event = HookEvent::Stop {
+                        turn_id: sub_id.clone(),
+                        session_id: sess.conversation_id.to_string(),
+                        cwd: turn_context.cwd.to_string_lossy().to_string(),
+                        input_messages: turn_input_messages,
+                        last_assistant_message: last_agent_message.clone(),
+                        stop_hook_active: stop_hook_turns > 0,
+                    };
+                    if let Some((hook_name, reason)) = sess
+                        .run_stop_hooks(hooks_config, &turn_context, &sub_id, &event)
+                        .await
+                    {
+                        let limit = hooks_config
+                            .stop_loop_limit
+                            .unwrap_or(DEFAULT_STOP_LOOP_LIMIT);
+                        if stop_hook_turns < limit {
+                            stop_hook_turns += 1;
+                            sess.notify_background_event(
+                                &sub_id,
+                                format!("stop hook '{hook_name}' kept the agent working: {reason}"),
+                            )
+                            .await;
+                            let context = hook_context_item(
+                                "stop",
+                                &hook_name,
+                                &format!("You are not done yet: {reason}"),
+                            );
+                            let context: ResponseItem = context.into();
+                            if is_review_mode {
+                                review_thread_history.push(context);
+                            } else {
+                                sess.record_conversation_items(&[context]).await;
+                            }
+                            continue;
+                        }
+                        sess.notify_background_event(
+                            &sub_id,
+                            format!(
+                                "stop hook '{hook_name}' blocked the stop again, but the limit of {limit} extra turns was reached: {reason}"
+                            ),
+                        )
+                        .await;
+                    }
                     break;
                 }
                 continue;
@@ -2089,6 +2872,96 @@ async fn run_task(
     sess.send_event(event).await;
 }
 
+/// Split the items of a finished turn into the items to record in the
+/// conversation history and the responses to send back to the model.
+fn split_processed_items(
+    processed_items: Vec<ProcessedResponseItem>,
+) -> (Vec<ResponseItem>, Vec<ResponseInputItem>) {
+    let mut items_to_record_in_conversation_history = Vec::<ResponseItem>::new();
+    let mut responses = Vec::<ResponseInputItem>::new();
+    for processed_response_item in processed_items {
+        let ProcessedResponseItem { item, response } = processed_response_item;
+        match (&item, &response) {
+            (ResponseItem::Message { role, .. }, None) if role == "assistant" => {
+                // If the model returned a message, we need to record it.
+                items_to_record_in_conversation_history.push(item);
+            }
+            (
+                ResponseItem::LocalShellCall { .. },
+                Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
+            ) => {
+                items_to_record_in_conversation_history.push(item);
+                items_to_record_in_conversation_history.push(ResponseItem::FunctionCallOutput {
+                    call_id: call_id.clone(),
+                    output: output.clone(),
+                });
+            }
+            (
+                ResponseItem::FunctionCall { .. },
+                Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
+            ) => {
+                items_to_record_in_conversation_history.push(item);
+                items_to_record_in_conversation_history.push(ResponseItem::FunctionCallOutput {
+                    call_id: call_id.clone(),
+                    output: output.clone(),
+                });
+            }
+            (
+                ResponseItem::CustomToolCall { .. },
+                Some(ResponseInputItem::CustomToolCallOutput { call_id, output }),
+            ) => {
+                items_to_record_in_conversation_history.push(item);
+                items_to_record_in_conversation_history.push(ResponseItem::CustomToolCallOutput {
+                    call_id: call_id.clone(),
+                    output: output.clone(),
+                });
+            }
+            (
+                ResponseItem::FunctionCall { .. },
+                Some(ResponseInputItem::McpToolCallOutput { call_id, result }),
+            ) => {
+                items_to_record_in_conversation_history.push(item);
+                let output = match result {
+                    Ok(call_tool_result) => {
+                        convert_call_tool_result_to_function_call_output_payload(call_tool_result)
+                    }
+                    Err(err) => FunctionCallOutputPayload {
+                        content: err.clone(),
+                        success: Some(false),
+                    },
+                };
+                items_to_record_in_conversation_history.push(ResponseItem::FunctionCallOutput {
+                    call_id: call_id.clone(),
+                    output,
+                });
+            }
+            (
+                ResponseItem::Reasoning {
+                    id,
+                    summary,
+                    content,
+                    encrypted_content,
+                },
+                None,
+            ) => {
+                items_to_record_in_conversation_history.push(ResponseItem::Reasoning {
+                    id: id.clone(),
+                    summary: summary.clone(),
+                    content: content.clone(),
+                    encrypted_content: encrypted_content.clone(),
+                });
+            }
+            _ => {
+                warn!("Unexpected response item: {item:?} with response: {response:?}");
+            }
+        };
+        if let Some(response) = response {
+            responses.push(response);
+        }
+    }
+    (items_to_record_in_conversation_history, responses)
+}
+
 /// Parse the review output; when not valid JSON, build a structured
 /// fallback that carries the plain text as the overall explanation.
 ///
@@ -2120,11 +2993,15 @@ async fn run_turn(
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: String,
     input: Vec<ResponseItem>,
+    hooks_config: &HooksConfig,
 ) -> CodexResult<TurnRunResult> {
     let tools = get_openai_tools(
         &turn_context.tools_config,
         Some(sess.mcp_connection_manager.list_all_tools()),
-    );
+    )
+    .into_iter()
+    .filter(|tool| turn_context.is_tool_allowed(tool.name()))
+    .collect();
 
     let prompt = Prompt {
         input,
@@ -2135,7 +3012,16 @@ async fn run_turn(
 
     let mut retries = 0;
     loop {
-        match try_run_turn(sess, turn_context, turn_diff_tracker, &sub_id, &prompt).await {
+        match try_run_turn(
+            sess,
+            turn_context,
+            turn_diff_tracker,
+            &sub_id,
+            &prompt,
+            hooks_config,
+        )
+        .await
+        {
             Ok(output) => return Ok(output),
             Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
@@ -2202,6 +3088,7 @@ async fn try_run_turn(
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: &str,
     prompt: &Prompt,
+    hooks_config: &HooksConfig,
 ) -> CodexResult<TurnRunResult> {
     // call_ids that are part of this response.
     let completed_call_ids = prompt
@@ -2303,6 +3190,7 @@ async fn try_run_turn(
                     turn_diff_tracker,
                     sub_id,
                     item.clone(),
+                    hooks_config,
                 )
                 .await?;
                 output.push(ProcessedResponseItem { item, response });
@@ -2348,14 +3236,15 @@ async fn try_run_turn(
             ResponseEvent::OutputTextDelta(delta) => {
                 // In review child threads, suppress assistant text deltas; the
                 // UI will show a selection popup from the final ReviewOutput.
-                if !turn_context.is_review_mode {
+                // Subagent messages are reported through SubagentEnd.
+                if !turn_context.is_review_mode && !turn_context.is_subagent {
                     let event = Event {
                         id: sub_id.to_string(),
                         msg: EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }),
                     };
                     sess.send_event(event).await;
                 } else {
-                    trace!("suppressing OutputTextDelta in review mode");
+                    trace!("suppressing OutputTextDelta in review or subagent thread");
                 }
             }
             ResponseEvent::ReasoningSummaryDelta(delta) => {
@@ -2393,6 +3282,7 @@ async fn handle_response_item(
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: &str,
     item: ResponseItem,
+    hooks_config: &HooksConfig,
 ) -> CodexResult<Option<ResponseInputItem>> {
     debug!(?item, "Output item");
     let output = match item {
@@ -2403,6 +3293,29 @@ async fn handle_response_item(
             ..
         } => {
             info!("FunctionCall: {name}({arguments})");
+            let arguments = match run_pre_tool_use_hooks(
+                sess,
+                turn_context,
+                hooks_config,
+                sub_id,
+                &call_id,
+                &name,
+                tool_input_from_arguments(&arguments),
+            )
+            .await
+            {
+                Ok(Some(updated_input)) => updated_input.to_string(),
+                Ok(None) => arguments,
+                Err(FunctionCallError::RespondToModel(msg)) => {
+                    return Ok(Some(ResponseInputItem::FunctionCallOutput {
+                        call_id,
+                        output: FunctionCallOutputPayload {
+                            content: msg,
+                            success: Some(false),
+                        },
+                    }));
+                }
+            };
             if let Some((server, tool_name)) = sess.mcp_connection_manager.parse_tool_name(&name) {
                 let resp = handle_mcp_tool_call(
                     sess,
@@ -2415,18 +3328,20 @@ async fn handle_response_item(
                 .await;
                 Some(resp)
             } else {
+                let tool_input = tool_input_from_arguments(&arguments);
                 let result = handle_function_call(
                     sess,
                     turn_context,
                     turn_diff_tracker,
                     sub_id.to_string(),
-                    name,
+                    name.clone(),
                     arguments,
                     call_id.clone(),
+                    hooks_config,
                 )
                 .await;
 
-                let output = match result {
+                let mut output = match result {
                     Ok(content) => FunctionCallOutputPayload {
                         content,
                         success: Some(true),
@@ -2436,6 +3351,24 @@ async fn handle_response_item(
                         success: Some(false),
                     },
                 };
+                let exit_code = sess.take_exec_exit_code(&call_id).await;
+                if matches!(name.as_str(), "container.exec" | "shell" | "apply_patch")
+                    && let Some(feedback) = run_post_tool_use_hooks(
+                        sess,
+                        turn_context,
+                        hooks_config,
+                        sub_id,
+                        &call_id,
+                        &name,
+                        tool_input,
+                        &output.content,
+                        output.success == Some(true),
+                        exit_code,
+                    )
+                    .await
+                {
+                    output.content.push_str(&feedback);
+                }
                 Some(ResponseInputItem::FunctionCallOutput { call_id, output })
             }
         }
@@ -2469,6 +3402,47 @@ async fn handle_response_item(
                 }
             };
 
+            let tool_input = serde_json::json!({
+                "command": params.command,
+                "workdir": params.workdir,
+                "timeout_ms": params.timeout_ms,
+            });
+            let hook_result = run_pre_tool_use_hooks(
+                sess,
+                turn_context,
+                hooks_config,
+                sub_id,
+                &effective_call_id,
+                "shell",
+                tool_input,
+            )
+            .await
+            .and_then(|updated_input| match updated_input {
+                Some(updated_input) => serde_json::from_value(updated_input).map_err(|e| {
+                    FunctionCallError::RespondToModel(format!(
+                        "pre_tool_use hook produced invalid shell arguments: {e}"
+                    ))
+                }),
+                None => Ok(params),
+            });
+            let params = match hook_result {
+                Ok(params) => params,
+                Err(FunctionCallError::RespondToModel(msg)) => {
+                    return Ok(Some(ResponseInputItem::FunctionCallOutput {
+                        call_id: effective_call_id,
+                        output: FunctionCallOutputPayload {
+                            content: msg,
+                            success: Some(false),
+                        },
+                    }));
+                }
+            };
+
+            let tool_input = serde_json::json!({
+                "command": params.command,
+                "workdir": params.workdir,
+                "timeout_ms": params.timeout_ms,
+            });
             let exec_params = to_exec_params(params, turn_context);
             {
                 let result = handle_container_exec_with_params(
@@ -2481,7 +3455,7 @@ async fn handle_response_item(
                 )
                 .await;
 
-                let output = match result {
+                let mut output = match result {
                     Ok(content) => FunctionCallOutputPayload {
                         content,
                         success: Some(true),
@@ -2491,6 +3465,23 @@ async fn handle_response_item(
                         success: Some(false),
                     },
                 };
+                let exit_code = sess.take_exec_exit_code(&effective_call_id).await;
+                if let Some(feedback) = run_post_tool_use_hooks(
+                    sess,
+                    turn_context,
+                    hooks_config,
+                    sub_id,
+                    &effective_call_id,
+                    "shell",
+                    tool_input,
+                    &output.content,
+                    output.success == Some(true),
+                    exit_code,
+                )
+                .await
+                {
+                    output.content.push_str(&feedback);
+                }
                 Some(ResponseInputItem::FunctionCallOutput {
                     call_id: effective_call_id,
                     output,
@@ -2504,21 +3495,68 @@ async fn handle_response_item(
             input,
             status: _,
         } => {
+            let hook_result = run_pre_tool_use_hooks(
+                sess,
+                turn_context,
+                hooks_config,
+                sub_id,
+                &call_id,
+                &name,
+                serde_json::json!({ "input": input }),
+            )
+            .await
+            .and_then(|updated_input| match updated_input {
+                Some(updated_input) => match updated_input.get("input") {
+                    Some(Value::String(input)) => Ok(input.clone()),
+                    _ => Err(FunctionCallError::RespondToModel(format!(
+                        "pre_tool_use hook produced invalid {name} arguments: expected a string `input`"
+                    ))),
+                },
+                None => Ok(input),
+            });
+            let input = match hook_result {
+                Ok(input) => input,
+                Err(FunctionCallError::RespondToModel(msg)) => {
+                    return Ok(Some(ResponseInputItem::CustomToolCallOutput {
+                        call_id,
+                        output: msg,
+                    }));
+                }
+            };
+            let tool_input = serde_json::json!({ "input": input });
             let result = handle_custom_tool_call(
                 sess,
                 turn_context,
                 turn_diff_tracker,
                 sub_id.to_string(),
-                name,
+                name.clone(),
                 input,
                 call_id.clone(),
             )
             .await;
 
-            let output = match result {
-                Ok(content) => content,
-                Err(FunctionCallError::RespondToModel(msg)) => msg,
+            let (mut output, success) = match result {
+                Ok(content) => (content, true),
+                Err(FunctionCallError::RespondToModel(msg)) => (msg, false),
             };
+            let exit_code = sess.take_exec_exit_code(&call_id).await;
+            if name == "apply_patch"
+                && let Some(feedback) = run_post_tool_use_hooks(
+                    sess,
+                    turn_context,
+                    hooks_config,
+                    sub_id,
+                    &call_id,
+                    &name,
+                    tool_input,
+                    &output,
+                    success,
+                    exit_code,
+                )
+                .await
+            {
+                output.push_str(&feedback);
+            }
             Some(ResponseInputItem::CustomToolCallOutput { call_id, output })
         }
         ResponseItem::FunctionCallOutput { .. } => {
@@ -2535,8 +3573,10 @@ async fn handle_response_item(
             // In review child threads, suppress assistant message events but
             // keep reasoning/web search.
             let msgs = match &item {
-                ResponseItem::Message { .. } if turn_context.is_review_mode => {
-                    trace!("suppressing assistant Message in review mode");
+                ResponseItem::Message { .. }
+                    if turn_context.is_review_mode || turn_context.is_subagent =>
+                {
+                    trace!("suppressing assistant Message in review or subagent thread");
                     Vec::new()
                 }
                 _ => map_response_item_to_event_messages(&item, sess.show_raw_agent_reasoning),
@@ -2555,11 +3595,258 @@ async fn handle_response_item(
     Ok(output)
 }
 
+/// Arguments of a function call as the JSON value handed to hooks. Arguments
+/// that are not valid JSON are passed as a string.
+fn tool_input_from_arguments(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.to_string()))
+}
+
+/// Run the enabled `pre_tool_use` hooks, in name order, for a tool call that
+/// is about to be dispatched. Calls to tools outside the turn's
+/// `allowed_tools` are rejected before any hook runs.
+///
+/// Returns the rewritten arguments when a hook replaced them, `Ok(None)` when
+/// the call should proceed unchanged, and an error carrying the message for
+/// the model when a hook (or the user, when a hook asks) rejected the call.
+/// Only hooks whose `matcher` selects the call run. Hooks that fail to run
+/// are reported to the user and otherwise ignored, unless they are
+/// `fail_closed`, in which case the call is blocked.
+async fn run_pre_tool_use_hooks(
+    sess: &Session,
+    turn_context: &TurnContext,
+    hooks_config: &HooksConfig,
+    sub_id: &str,
+    call_id: &str,
+    tool_name: &str,
+    tool_input: Value,
+) -> Result<Option<Value>, FunctionCallError> {
+    // Tools outside the turn's allow list are never offered, but the model
+    // may still try to call them.
+    if !turn_context.is_tool_allowed(tool_name) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{tool_name} is not allowed in this turn"
+        )));
+    }
+
+    let hooks = enabled_hooks(&hooks_config.pre_tool_use);
+    if hooks.is_empty() {
+        return Ok(None);
+    }
+
+    let mut tool_input = tool_input;
+    let mut updated = false;
+    let mut ask_reason: Option<Option<String>> = None;
+    for (hook_name, hook) in hooks {
+        if !hook_matches_tool_call(
+            sess,
+            sub_id,
+            "pre_tool_use",
+            hook_name,
+            hook,
+            tool_name,
+            &tool_input,
+        )
+        .await
+        {
+            continue;
+        }
+        let event = HookEvent::PreToolUse {
+            session_id: sess.conversation_id.to_string(),
+            call_id: call_id.to_string(),
+            cwd: turn_context.cwd.to_string_lossy().to_string(),
+            tool_name: tool_name.to_string(),
+            tool_input: tool_input.clone(),
+        };
+        let Some(response) = sess
+            .run_hook_for_reply(sub_id, hook_name, hook, &event, &turn_context.cwd)
+            .await
+        else {
+            if hook.fail_closed {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "{tool_name} call not run: pre_tool_use hook '{hook_name}' failed and is configured with fail_closed"
+                )));
+            }
+            continue;
+        };
+
+        if !response.should_continue() {
+            let reason = response.reason_or_default();
+            sess.request_hook_stop(format!(
+                "stopped by pre_tool_use hook '{hook_name}': {reason}"
+            ))
+            .await;
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{tool_name} call not run: pre_tool_use hook '{hook_name}' stopped the turn: {reason}"
+            )));
+        }
+        if let Some(updated_input) = response.updated_input.clone() {
+            tool_input = updated_input;
+            updated = true;
+        }
+        match response.decision {
+            Some(HookDecision::Block) => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "{tool_name} call blocked by pre_tool_use hook '{hook_name}': {}",
+                    response.reason_or_default()
+                )));
+            }
+            Some(HookDecision::Ask) => {
+                ask_reason.get_or_insert(response.reason);
+            }
+            Some(HookDecision::Allow) | None => {}
+        }
+    }
+
+    if let Some(reason) = ask_reason {
+        if matches!(turn_context.approval_policy, AskForApproval::Never) {
+            let reason = reason.unwrap_or_else(|| "approval required".to_string());
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{tool_name} call needs user approval ({reason}), but the approval policy is never"
+            )));
+        }
+        let command = match tool_input.get("command").and_then(Value::as_array) {
+            Some(command) if command.iter().all(Value::is_string) => command
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            _ => vec![tool_name.to_string(), tool_input.to_string()],
+        };
+        let decision = sess
+            .request_command_approval(
+                sub_id.to_string(),
+                call_id.to_string(),
+                command.clone(),
+                turn_context.cwd.clone(),
+                reason,
+            )
+            .await;
+        match decision {
+            ReviewDecision::Approved => sess.record_hook_approval(call_id).await,
+            ReviewDecision::ApprovedForSession => {
+                sess.add_approved_command(command).await;
+                sess.record_hook_approval(call_id).await;
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "{tool_name} call rejected by user"
+                )));
+            }
+        }
+    }
+
+    Ok(updated.then_some(tool_input))
+}
+
+/// Run the enabled `post_tool_use` hooks, in name order, for a finished
+/// `shell` or `apply_patch` call.
+///
+/// Returns the text to append to the tool output the model sees: each hook's
+/// `additional_context` (its raw stdout when it did not print a JSON object)
+/// and the `reason` of hooks that block. Only hooks whose `matcher` selects
+/// the call run; hooks that fail to run or time out are logged and skipped.
+#[allow(clippy::too_many_arguments)]
+async fn run_post_tool_use_hooks(
+    sess: &Session,
+    turn_context: &TurnContext,
+    hooks_config: &HooksConfig,
+    sub_id: &str,
+    call_id: &str,
+    tool_name: &str,
+    tool_input: Value,
+    tool_output: &str,
+    success: bool,
+    exit_code: Option<i32>,
+) -> Option<String> {
+    let hooks = enabled_hooks(&hooks_config.post_tool_use);
+    if hooks.is_empty() {
+        return None;
+    }
+
+    let mut matching = Vec::new();
+    for (hook_name, hook) in hooks {
+        if hook_matches_tool_call(
+            sess,
+            sub_id,
+            "post_tool_use",
+            hook_name,
+            hook,
+            tool_name,
+            &tool_input,
+        )
+        .await
+        {
+            matching.push((hook_name, hook));
+        }
+    }
+    let event = HookEvent::PostToolUse {
+        session_id: sess.conversation_id.to_string(),
+        call_id: call_id.to_string(),
+        cwd: turn_context.cwd.to_string_lossy().to_string(),
+        tool_name: tool_name.to_string(),
+        tool_input,
+        tool_output: tool_output.to_string(),
+        success,
+        exit_code,
+    };
+    let mut feedback = String::new();
+    for (hook_name, hook) in matching {
+        let Some(response) = sess
+            .run_hook_for_reply(sub_id, hook_name, hook, &event, &turn_context.cwd)
+            .await
+        else {
+            continue;
+        };
+        if let Some(context) = &response.additional_context {
+            feedback.push_str(&format!(
+                "\n\npost_tool_use hook '{hook_name}' output:\n{context}"
+            ));
+        }
+        if response.blocks() {
+            feedback.push_str(&format!(
+                "\n\npost_tool_use hook '{hook_name}' blocked: {}",
+                response.reason_or_default()
+            ));
+        }
+        if !response.should_continue() {
+            sess.request_hook_stop(format!(
+                "stopped by post_tool_use hook '{hook_name}': {}",
+                response.reason_or_default()
+            ))
+            .await;
+        }
+    }
+
+    (!feedback.is_empty()).then_some(feedback)
+}
+
+/// Whether `hook` should run for a call of `tool_name`. A matcher that cannot
+/// be evaluated is reported to the user and the hook is skipped.
+async fn hook_matches_tool_call(
+    sess: &Session,
+    sub_id: &str,
+    event_name: &str,
+    hook_name: &str,
+    hook: &HookConfig,
+    tool_name: &str,
+    tool_input: &Value,
+) -> bool {
+    match matches_tool_call(hook, tool_name, tool_input) {
+        Ok(matches) => matches,
+        Err(e) => {
+            sess.notify_background_event(sub_id, format!("{event_name} hook '{hook_name}': {e}"))
+                .await;
+            false
+        }
+    }
+}
+
 async fn handle_unified_exec_tool_call(
     sess: &Session,
     session_id: Option<String>,
     arguments: Vec<String>,
     timeout_ms: Option<u64>,
+    limits: ExecLimits,
 ) -> Result<String, FunctionCallError> {
     let parsed_session_id = if let Some(session_id) = session_id {
         match session_id.parse::<i32>() {
@@ -2578,6 +3865,7 @@ async fn handle_unified_exec_tool_call(
         session_id: parsed_session_id,
         input_chunks: &arguments,
         timeout_ms,
+        limits,
     };
 
     let value = sess
@@ -2605,6 +3893,7 @@ async fn handle_unified_exec_tool_call(
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_function_call(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2613,6 +3902,7 @@ async fn handle_function_call(
     name: String,
     arguments: String,
     call_id: String,
+    hooks_config: &HooksConfig,
 ) -> Result<String, FunctionCallError> {
     match name.as_str() {
         "container.exec" | "shell" => {
@@ -2643,7 +3933,14 @@ async fn handle_function_call(
                 ))
             })?;
 
-            handle_unified_exec_tool_call(sess, args.session_id, args.input, args.timeout_ms).await
+            handle_unified_exec_tool_call(
+                sess,
+                args.session_id,
+                args.input,
+                args.timeout_ms,
+                turn_context.exec_limits,
+            )
+            .await
         }
         "view_image" => {
             #[derive(serde::Deserialize)]
@@ -2679,6 +3976,7 @@ async fn handle_function_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                limits: ExecLimits::default(),
             };
             handle_container_exec_with_params(
                 exec_params,
@@ -2691,6 +3989,18 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        TASK_TOOL_NAME => {
+            subagent::handle_task_tool_call(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                &sub_id,
+                &call_id,
+                arguments,
+                hooks_config,
+            )
+            .await
+        }
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params: ExecCommandParams = serde_json::from_str(&arguments).map_err(|e| {
@@ -2700,13 +4010,46 @@ async fn handle_function_call(
             })?;
             let result = sess
                 .session_manager
-                .handle_exec_command_request(exec_params)
+                .handle_exec_command_request(exec_params, turn_context.exec_limits)
                 .await;
             match result {
-                Ok(output) => Ok(output.to_text_output()),
+                Ok(mut output) => {
+                    save_exec_command_full_output(sess, turn_context, &mut output).await;
+                    Ok(output.to_text_output())
+                }
                 Err(err) => Err(FunctionCallError::RespondToModel(err)),
             }
         }
+        BG_START_TOOL_NAME => {
+            let params = serde_json::from_str::<BgStartParams>(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            handle_bg_start(sess, turn_context, sub_id, call_id, params).await
+        }
+        BG_LOGS_TOOL_NAME => {
+            let params = serde_json::from_str::<BgLogsParams>(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            sess.session_manager
+                .background_process_logs(params)
+                .await
+                .map_err(FunctionCallError::RespondToModel)
+        }
+        BG_STOP_TOOL_NAME => {
+            let params = serde_json::from_str::<BgStopParams>(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            sess.session_manager
+                .stop_background_process(params.id)
+                .await
+                .map_err(FunctionCallError::RespondToModel)
+        }
         WRITE_STDIN_TOOL_NAME => {
             let write_stdin_params =
                 serde_json::from_str::<WriteStdinParams>(&arguments).map_err(|e| {
@@ -2715,14 +4058,26 @@ async fn handle_function_call(
                     ))
                 })?;
 
-            let result = sess
+            let mut result = sess
                 .session_manager
                 .handle_write_stdin_request(write_stdin_params)
                 .await
                 .map_err(FunctionCallError::RespondToModel)?;
+            save_exec_command_full_output(sess, turn_context, &mut result).await;
 
             Ok(result.to_text_output())
         }
+        READ_OUTPUT_TOOL_NAME => {
+            let args = serde_json::from_str::<ReadOutputArgs>(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            sess.output_artifacts
+                .read(args)
+                .await
+                .map_err(FunctionCallError::RespondToModel)
+        }
         _ => Err(FunctionCallError::RespondToModel(format!(
             "unsupported call: {name}"
         ))),
@@ -2748,6 +4103,7 @@ async fn handle_custom_tool_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                limits: ExecLimits::default(),
             };
 
             handle_container_exec_with_params(
@@ -2777,6 +4133,7 @@ fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> Ex
         env: create_env(&turn_context.shell_environment_policy),
         with_escalated_permissions: params.with_escalated_permissions,
         justification: params.justification,
+        limits: turn_context.exec_limits,
     }
 }
 
@@ -2836,8 +4193,16 @@ async fn handle_container_exec_with_params(
         )));
     }
 
+    // The user may already have approved this call when a `pre_tool_use`
+    // hook asked; do not ask again.
+    let approved_by_user = sess.take_hook_approval(&call_id).await;
+
     // check if this was a patch, and apply it if so
     let apply_patch_exec = match maybe_parse_apply_patch_verified(&params.command, &params.cwd) {
+        MaybeApplyPatchVerified::Body(action) if approved_by_user => Some(ApplyPatchExec {
+            action,
+            user_explicitly_approved_this_action: true,
+        }),
         MaybeApplyPatchVerified::Body(changes) => {
             match apply_patch::apply_patch(sess, turn_context, &sub_id, &call_id, changes).await {
                 InternalApplyPatchInvocation::Output(item) => return item,
@@ -2886,6 +4251,7 @@ async fn handle_container_exec_with_params(
                 env: HashMap::new(),
                 with_escalated_permissions: params.with_escalated_permissions,
                 justification: params.justification.clone(),
+                limits: params.limits,
             };
             let safety = if *user_explicitly_approved_this_action {
                 SafetyCheck::AutoApprove {
@@ -2911,6 +4277,9 @@ async fn handle_container_exec_with_params(
                     &params.command,
                     turn_context.approval_policy,
                     &turn_context.sandbox_policy,
+                    &sess.exec_policy,
+                    &state.permissions,
+                    &params.cwd,
                     &state.approved_commands,
                     params.with_escalated_permissions.unwrap_or(false),
                 )
@@ -2921,28 +4290,12 @@ async fn handle_container_exec_with_params(
     };
 
     let sandbox_type = match safety {
-        SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+        SafetyCheck::AutoApprove { sandbox_type } => {
+            sandbox_type.with_linux_sandbox(sess.linux_sandbox)
+        }
+        SafetyCheck::AskUser if approved_by_user => SandboxType::None,
         SafetyCheck::AskUser => {
-            let decision = sess
-                .request_command_approval(
-                    sub_id.clone(),
-                    call_id.clone(),
-                    params.command.clone(),
-                    params.cwd.clone(),
-                    params.justification.clone(),
-                )
-                .await;
-            match decision {
-                ReviewDecision::Approved => (),
-                ReviewDecision::ApprovedForSession => {
-                    sess.add_approved_command(params.command.clone()).await;
-                }
-                ReviewDecision::Denied | ReviewDecision::Abort => {
-                    return Err(FunctionCallError::RespondToModel(
-                        "exec command rejected by user".to_string(),
-                    ));
-                }
-            }
+            ask_user_to_approve_command(sess, &sub_id, &call_id, &params).await?;
             // No sandboxing is applied because the user has given
             // explicit approval. Often, we end up in this case because
             // the command cannot be run in a sandbox, such as
@@ -2999,7 +4352,7 @@ async fn handle_container_exec_with_params(
     match output_result {
         Ok(output) => {
             let ExecToolCallOutput { exit_code, .. } = &output;
-            let content = format_exec_output(&output);
+            let content = format_exec_output_for_model(sess, turn_context, &output).await;
             if *exit_code == 0 {
                 Ok(content)
             } else {
@@ -3024,6 +4377,127 @@ async fn handle_container_exec_with_params(
     }
 }
 
+/// Asks the user to approve running `params` outside the sandbox, and
+/// remembers the command if it was approved for the rest of the session.
+async fn ask_user_to_approve_command(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    params: &ExecParams,
+) -> Result<(), FunctionCallError> {
+    let decision = sess
+        .request_command_approval(
+            sub_id.to_string(),
+            call_id.to_string(),
+            params.command.clone(),
+            params.cwd.clone(),
+            params.justification.clone(),
+        )
+        .await;
+    match decision {
+        ReviewDecision::Approved => Ok(()),
+        ReviewDecision::ApprovedForSession => {
+            sess.add_approved_command(params.command.clone()).await;
+            Ok(())
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => Err(FunctionCallError::RespondToModel(
+            "exec command rejected by user".to_string(),
+        )),
+    }
+}
+
+/// Starts a background process for a `bg_start` call. The command is
+/// approved and sandboxed like a `shell` call, but keeps running after the
+/// call returns.
+async fn handle_bg_start(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    params: BgStartParams,
+) -> Result<String, FunctionCallError> {
+    let BgStartParams {
+        command,
+        workdir,
+        justification,
+        yield_time_ms,
+        max_output_tokens,
+    } = params;
+    let params = ExecParams {
+        command,
+        cwd: turn_context.resolve_path(workdir),
+        timeout_ms: None,
+        env: create_env(&turn_context.shell_environment_policy),
+        with_escalated_permissions: None,
+        justification,
+        limits: turn_context.exec_limits,
+    };
+
+    let safety = {
+        let state = sess.state.lock().await;
+        assess_command_safety(
+            &params.command,
+            turn_context.approval_policy,
+            &turn_context.sandbox_policy,
+            &sess.exec_policy,
+            &state.permissions,
+            &params.cwd,
+            &state.approved_commands,
+            false,
+        )
+    };
+    let sandbox_type = match safety {
+        SafetyCheck::AutoApprove { sandbox_type } => {
+            sandbox_type.with_linux_sandbox(sess.linux_sandbox)
+        }
+        SafetyCheck::AskUser => {
+            ask_user_to_approve_command(sess, &sub_id, &call_id, &params).await?;
+            SandboxType::None
+        }
+        SafetyCheck::Reject { reason } => {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "exec command rejected: {reason:?}"
+            )));
+        }
+    };
+
+    let mut params = maybe_translate_shell_command(params, sess, turn_context);
+    let network_allowlist = turn_context.sandbox_policy.network_allowlist();
+    if sandbox_type != SandboxType::None && !network_allowlist.is_empty() {
+        match sess.network_proxy(network_allowlist).await {
+            Ok(network_proxy) => network_proxy.apply_to_env(&mut params.env),
+            Err(e) => warn!("failed to start the network proxy: {e}"),
+        }
+    }
+
+    let command = params.command.clone();
+    let cwd = params.cwd.clone();
+    let child = spawn_exec_child(
+        params,
+        sandbox_type,
+        &turn_context.sandbox_policy,
+        &turn_context.cwd,
+        &sess.codex_linux_sandbox_exe,
+        StdioPolicy::RedirectForBackgroundProcess,
+    )
+    .await
+    .map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to start background process: {e}"))
+    })?;
+    sess.notify_background_event(
+        &sub_id,
+        format!(
+            "started background process: {} (use /ps to stop it)",
+            display_background_command(&command)
+        ),
+    )
+    .await;
+    Ok(sess
+        .session_manager
+        .start_background_process(child, command, cwd, yield_time_ms, max_output_tokens)
+        .await)
+}
+
 async fn handle_sandbox_error(
     turn_diff_tracker: &mut TurnDiffTracker,
     params: ExecParams,
@@ -3038,16 +4512,26 @@ async fn handle_sandbox_error(
     let cwd = exec_command_context.cwd.clone();
 
     if let SandboxErr::Timeout { output } = &error {
-        let content = format_exec_output(output);
+        let content = format_exec_output_for_model(sess, turn_context, output).await;
         return Err(FunctionCallError::RespondToModel(content));
     }
 
+    // A guess from the command's own output, only used to word the messages
+    // below; it must not change whether or how we retry.
+    let denial = match &error {
+        SandboxErr::Denied { denial, .. } => denial.clone(),
+        _ => None,
+    };
+
     // Early out if either the user never wants to be asked for approval, or
     // we're letting the model manage escalation requests. Otherwise, continue
     match turn_context.approval_policy {
         AskForApproval::Never | AskForApproval::OnRequest => {
+            let reason = denial
+                .map(|denial| format!(" ({denial})"))
+                .unwrap_or_default();
             return Err(FunctionCallError::RespondToModel(format!(
-                "failed in sandbox {sandbox_type:?} with execution error: {error:?}"
+                "failed in sandbox {sandbox_type:?}{reason} with execution error: {error:?}"
             )));
         }
         AskForApproval::UnlessTrusted | AskForApproval::OnFailure => (),
@@ -3071,7 +4555,10 @@ async fn handle_sandbox_error(
             call_id.clone(),
             params.command.clone(),
             cwd.clone(),
-            Some("command failed; retry without sandbox?".to_string()),
+            Some(match &denial {
+                Some(denial) => format!("{denial}; retry without sandbox?"),
+                None => "command failed; retry without sandbox?".to_string(),
+            }),
         )
         .await;
 
@@ -3114,7 +4601,8 @@ async fn handle_sandbox_error(
             match retry_output_result {
                 Ok(retry_output) => {
                     let ExecToolCallOutput { exit_code, .. } = &retry_output;
-                    let content = format_exec_output(&retry_output);
+                    let content =
+                        format_exec_output_for_model(sess, turn_context, &retry_output).await;
                     if *exit_code == 0 {
                         Ok(content)
                     } else {
@@ -3135,7 +4623,23 @@ async fn handle_sandbox_error(
     }
 }
 
-fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
+/// Appends a note from Codex to the stderr and aggregated output of a command.
+fn append_to_exec_output(output: &mut ExecToolCallOutput, note: &str) {
+    output.stderr.text.push_str(note);
+    output.aggregated_output.text.push_str(note);
+}
+
+/// Whether `output` is too long to show the model in full.
+fn exceeds_model_format_budget(output: &str) -> bool {
+    output.len() > MODEL_FORMAT_MAX_BYTES || output.lines().count() > MODEL_FORMAT_MAX_LINES
+}
+
+/// Formats the output of a command for the model. `full_output_handle` is the
+/// `read_output` handle of the full output, mentioned where lines are omitted.
+fn format_exec_output_str(
+    exec_output: &ExecToolCallOutput,
+    full_output_handle: Option<&str>,
+) -> String {
     let ExecToolCallOutput {
         aggregated_output, ..
     } = exec_output;
@@ -3154,10 +4658,10 @@ fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
         s = &prefixed_str;
     }
 
-    let total_lines = s.lines().count();
-    if s.len() <= MODEL_FORMAT_MAX_BYTES && total_lines <= MODEL_FORMAT_MAX_LINES {
+    if !exceeds_model_format_budget(s) {
         return s.to_string();
     }
+    let total_lines = s.lines().count();
 
     let lines: Vec<&str> = s.lines().collect();
     let head_take = MODEL_FORMAT_HEAD_LINES.min(lines.len());
@@ -3176,7 +4680,13 @@ fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
     } else {
         String::new()
     };
-    let marker = format!("\n[... omitted {omitted} of {total_lines} lines ...]\n\n");
+    let marker = match full_output_handle {
+        Some(handle) => format!(
+            "\n[... omitted {omitted} of {total_lines} lines; {} ...]\n\n",
+            saved_output_note(handle)
+        ),
+        None => format!("\n[... omitted {omitted} of {total_lines} lines ...]\n\n"),
+    };
 
     // Byte budgets for head/tail around the marker
     let mut head_budget = MODEL_FORMAT_HEAD_BYTES.min(MODEL_FORMAT_MAX_BYTES);
@@ -3247,8 +4757,43 @@ fn take_last_bytes_at_char_boundary(s: &str, maxb: usize) -> &str {
     &s[start..]
 }
 
+/// Formats `exec_output` for the model, first saving the full output for
+/// `read_output` if it is too long to show.
+async fn format_exec_output_for_model(
+    sess: &Session,
+    turn_context: &TurnContext,
+    exec_output: &ExecToolCallOutput,
+) -> String {
+    let aggregated_output = &exec_output.aggregated_output.text;
+    let full_output_handle = if exceeds_model_format_budget(aggregated_output) {
+        sess.save_full_output(turn_context, aggregated_output).await
+    } else {
+        None
+    };
+    format_exec_output(exec_output, full_output_handle.as_deref())
+}
+
+/// Saves the full output of an `exec_command` or `write_stdin` call whose
+/// output was truncated, so the model can read it with `read_output`.
+async fn save_exec_command_full_output(
+    sess: &Session,
+    turn_context: &TurnContext,
+    output: &mut ExecCommandOutput,
+) {
+    let handle = match output.full_output() {
+        Some(full_output) => sess.save_full_output(turn_context, full_output).await,
+        None => None,
+    };
+    if let Some(handle) = handle {
+        output.set_full_output_handle(handle);
+    }
+}
+
 /// Exec output is a pre-serialized JSON payload
-fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
+fn format_exec_output(
+    exec_output: &ExecToolCallOutput,
+    full_output_handle: Option<&str>,
+) -> String {
     let ExecToolCallOutput {
         exit_code,
         duration,
@@ -3270,7 +4815,7 @@ fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
     // round to 1 decimal place
     let duration_seconds = ((duration.as_secs_f32()) * 10.0).round() / 10.0;
 
-    let formatted_output = format_exec_output_str(exec_output);
+    let formatted_output = format_exec_output_str(exec_output, full_output_handle);
 
     let payload = ExecOutput {
         output: &formatted_output,
@@ -3495,7 +5040,7 @@ mod tests {
             timed_out: false,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
 
         // Expect elision marker with correct counts
         let omitted = 400 - MODEL_FORMAT_MAX_LINES; // 144
@@ -3538,7 +5083,7 @@ mod tests {
             timed_out: false,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
         assert!(out.len() <= MODEL_FORMAT_MAX_BYTES, "exceeds byte budget");
         assert!(out.contains("omitted"), "should contain elision marker");
 
@@ -3558,6 +5103,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn model_truncation_mentions_saved_full_output() {
+        let lines: Vec<String> = (1..=400).map(|i| format!("line{i}")).collect();
+        let exec = ExecToolCallOutput {
+            exit_code: 1,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(lines.join("\n")),
+            duration: StdDuration::from_secs(1),
+            timed_out: false,
+        };
+
+        let out = format_exec_output_str(&exec, Some("output-1"));
+
+        let omitted = 400 - MODEL_FORMAT_MAX_LINES;
+        let marker = format!(
+            "\n[... omitted {omitted} of 400 lines; full output saved as output-1, use read_output to page or search it ...]\n\n"
+        );
+        assert!(out.contains(&marker), "missing marker: {out}");
+        assert!(out.len() <= MODEL_FORMAT_MAX_BYTES, "exceeds byte budget");
+    }
+
     #[test]
     fn includes_timed_out_message() {
         let exec = ExecToolCallOutput {
@@ -3569,7 +5136,7 @@ mod tests {
             timed_out: true,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
 
         assert_eq!(
             out,
@@ -3664,6 +5231,8 @@ mod tests {
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+            include_background_process_tools: config.include_background_process_tools,
+            include_read_output_tool: config.include_read_output_tool,
         });
         let turn_context = TurnContext {
             client,
@@ -3673,16 +5242,23 @@ mod tests {
             approval_policy: config.approval_policy,
             sandbox_policy: config.sandbox_policy.clone(),
             shell_environment_policy: config.shell_environment_policy.clone(),
+            exec_limits: config.exec_limits,
             tools_config,
             is_review_mode: false,
+            is_subagent: false,
             final_output_json_schema: None,
+            allowed_tools: None,
         };
         let session = Session {
             conversation_id,
             tx_event,
             mcp_connection_manager: McpConnectionManager::default(),
+            exec_policy: ExecPolicy::default(),
+            network_proxy: Mutex::new(None),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            session_start_hooks_done: watch::Sender::new(true),
+            output_artifacts: OutputArtifacts::new(&config.codex_home, conversation_id),
             notifier: UserNotifier::default(),
             rollout: Mutex::new(None),
             state: Mutex::new(State {
@@ -3690,6 +5266,7 @@ mod tests {
                 ..Default::default()
             }),
             codex_linux_sandbox_exe: None,
+            linux_sandbox: config.linux_sandbox,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
             next_internal_sub_id: AtomicU64::new(0),
@@ -3830,6 +5407,7 @@ mod tests {
             env: HashMap::new(),
             with_escalated_permissions: Some(true),
             justification: Some("test".to_string()),
+            limits: ExecLimits::default(),
         };
 
         let params2 = ExecParams {
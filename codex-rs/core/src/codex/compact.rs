@@ -213,7 +213,9 @@ pub(crate) fn collect_user_messages(items: &[ResponseItem]) -> Vec<String> {
 pub fn is_session_prefix_message(text: &str) -> bool {
     matches!(
         InputMessageKind::from(("user", text)),
-        InputMessageKind::UserInstructions | InputMessageKind::EnvironmentContext
+        InputMessageKind::UserInstructions
+            | InputMessageKind::EnvironmentContext
+            | InputMessageKind::HookContext
     )
 }
 
@@ -0,0 +1,375 @@
+//! The `task` tool: runs a subagent from `.codex/agents` in a fresh thread
+//! and hands its final message back to the calling agent.
+//!
+//! Like review threads, a subagent thread keeps its own in-memory history.
+//! Its tool calls emit the usual events between `SubagentBegin` and
+//! `SubagentEnd`, and its conversation items are recorded in the rollout as
+//! `RolloutItem::SubagentItem` so they stay out of the resumed history.
+
+use std::sync::Arc;
+
+use super::Session;
+use super::TurnContext;
+use super::TurnRunResult;
+use super::get_last_assistant_message_from_turn;
+use super::run_turn;
+use super::split_processed_items;
+use crate::client::ModelClient;
+use crate::config_types::HooksConfig;
+use crate::function_tool::FunctionCallError;
+use crate::model_family::find_family_for_model;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::ToolsConfig;
+use crate::openai_tools::ToolsConfigParams;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::SubagentBeginEvent;
+use crate::protocol::SubagentEndEvent;
+use crate::safety::tightened_approval_policy;
+use crate::safety::tightened_sandbox_policy;
+use crate::subagents::SubagentDefinition;
+use crate::turn_diff_tracker::TurnDiffTracker;
+use codex_protocol::models::ResponseInputItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::SubagentRolloutItem;
+use serde::Deserialize;
+use wildmatch::WildMatchPattern;
+
+#[derive(Deserialize)]
+struct TaskToolArgs {
+    agent: String,
+    prompt: String,
+}
+
+/// Handle a `task` tool call: run the requested subagent to completion and
+/// return its final message as the tool output.
+pub(super) async fn handle_task_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: &str,
+    call_id: &str,
+    arguments: String,
+    hooks_config: &HooksConfig,
+) -> Result<String, FunctionCallError> {
+    let args: TaskToolArgs = serde_json::from_str(&arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let Some(agent) = turn_context
+        .tools_config
+        .subagents
+        .iter()
+        .find(|agent| agent.name == args.agent)
+    else {
+        let available = turn_context
+            .tools_config
+            .subagents
+            .iter()
+            .map(|agent| agent.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(FunctionCallError::RespondToModel(format!(
+            "unknown agent `{}`; available agents: {available}",
+            args.agent
+        )));
+    };
+
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::SubagentBegin(SubagentBeginEvent {
+            call_id: call_id.to_string(),
+            agent: agent.name.clone(),
+            prompt: args.prompt.clone(),
+        }),
+    })
+    .await;
+
+    let (child_context, ignored) = subagent_turn_context(sess, turn_context, agent);
+    for message in ignored {
+        sess.notify_background_event(sub_id, message).await;
+    }
+    let result = run_subagent(
+        sess,
+        &child_context,
+        turn_diff_tracker,
+        sub_id,
+        call_id,
+        &agent.name,
+        args.prompt,
+        hooks_config,
+    )
+    .await;
+
+    let (last_agent_message, error) = match &result {
+        Ok(message) => (message.clone(), None),
+        Err(e) => (None, Some(e.clone())),
+    };
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::SubagentEnd(SubagentEndEvent {
+            call_id: call_id.to_string(),
+            agent: agent.name.clone(),
+            last_agent_message: last_agent_message.clone(),
+            error,
+        }),
+    })
+    .await;
+
+    match result {
+        Ok(message) => {
+            Ok(message
+                .unwrap_or_else(|| format!("{} finished without a final message", agent.name)))
+        }
+        Err(e) => Err(FunctionCallError::RespondToModel(format!(
+            "{} failed: {e}",
+            agent.name
+        ))),
+    }
+}
+
+/// Build the turn context of a subagent from the parent's, applying the
+/// overrides from the agent's frontmatter. Agents are checked into the
+/// repository, so their `sandbox`, `approval` and `allowed-tools` can only be
+/// stricter than the parent's; looser values are ignored and returned as
+/// messages for the user.
+fn subagent_turn_context(
+    sess: &Session,
+    parent: &TurnContext,
+    agent: &SubagentDefinition,
+) -> (TurnContext, Vec<String>) {
+    let overrides = &agent.overrides;
+    let mut ignored = Vec::new();
+    let sandbox_policy = match overrides.sandbox {
+        None => parent.sandbox_policy.clone(),
+        Some(mode) => {
+            tightened_sandbox_policy(&parent.sandbox_policy, mode).unwrap_or_else(|| {
+                ignored.push(format!(
+                    "ignored `sandbox: {mode}` of agent {}, which is less restrictive than the session's sandbox",
+                    agent.name
+                ));
+                parent.sandbox_policy.clone()
+            })
+        }
+    };
+    let approval_policy = match overrides.approval {
+        None => parent.approval_policy,
+        Some(approval) => tightened_approval_policy(parent.approval_policy, approval)
+            .unwrap_or_else(|| {
+                ignored.push(format!(
+                    "ignored `approval: {approval}` of agent {}, which asks for approval less often than the session's `{}`",
+                    agent.name, parent.approval_policy
+                ));
+                parent.approval_policy
+            }),
+    };
+
+    let (allowed_tools, disallowed) = intersect_allowed_tools(
+        overrides.allowed_tools.as_deref(),
+        parent.allowed_tools.as_deref(),
+    );
+    for tool in disallowed {
+        ignored.push(format!(
+            "ignored `allowed-tools` entry `{tool}` of agent {}, which the session does not allow",
+            agent.name
+        ));
+    }
+
+    let model = overrides
+        .model
+        .clone()
+        .unwrap_or_else(|| parent.client.get_model());
+    let model_family =
+        find_family_for_model(&model).unwrap_or_else(|| parent.client.get_model_family());
+    let effort = overrides
+        .effort
+        .or_else(|| parent.client.get_reasoning_effort());
+
+    let mut config = (*parent.client.get_config()).clone();
+    config.model = model;
+    config.model_family = model_family.clone();
+    config.model_reasoning_effort = effort;
+    if let Some(model_info) = get_model_info(&model_family) {
+        config.model_context_window = Some(model_info.context_window);
+    }
+    let config = Arc::new(config);
+
+    let client = ModelClient::new(
+        config.clone(),
+        parent.client.get_auth_manager(),
+        parent.client.get_provider(),
+        effort,
+        parent.client.get_reasoning_summary(),
+        sess.conversation_id,
+    );
+    // Subagents do not get the plan tool (the plan belongs to the parent)
+    // nor the task tool, so they cannot delegate further.
+    let tools_config = ToolsConfig::new(&ToolsConfigParams {
+        model_family: &model_family,
+        include_plan_tool: false,
+        include_apply_patch_tool: config.include_apply_patch_tool,
+        include_web_search_request: config.tools_web_search_request,
+        use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+        include_view_image_tool: config.include_view_image_tool,
+        experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        include_background_process_tools: config.include_background_process_tools,
+        include_read_output_tool: config.include_read_output_tool,
+    });
+
+    let base_instructions = if agent.instructions.is_empty() {
+        parent.base_instructions.clone()
+    } else {
+        Some(agent.instructions.clone())
+    };
+
+    let turn_context = TurnContext {
+        client,
+        cwd: parent.cwd.clone(),
+        base_instructions,
+        user_instructions: parent.user_instructions.clone(),
+        approval_policy,
+        sandbox_policy,
+        shell_environment_policy: parent.shell_environment_policy.clone(),
+        exec_limits: parent.exec_limits,
+        tools_config,
+        is_review_mode: false,
+        is_subagent: true,
+        final_output_json_schema: None,
+        allowed_tools,
+    };
+    (turn_context, ignored)
+}
+
+/// Tools allowed by both the agent's `allowed-tools` and the parent's. An
+/// entry of either list is kept when the other list covers it, so `mcp__*`
+/// under a parent limited to `mcp__github__*` becomes `mcp__github__*`.
+/// Also returns the agent's entries that the parent allows none of.
+fn intersect_allowed_tools(
+    agent: Option<&[String]>,
+    parent: Option<&[String]>,
+) -> (Option<Vec<String>>, Vec<String>) {
+    let (agent, parent) = match (agent, parent) {
+        (Some(agent), Some(parent)) => (agent, parent),
+        (agent, parent) => return (agent.or(parent).map(<[String]>::to_vec), Vec::new()),
+    };
+    // A pattern that matches another pattern's text matches every tool name
+    // the other one does.
+    let covers = |patterns: &[String], entry: &str| {
+        patterns
+            .iter()
+            .any(|pattern| WildMatchPattern::<'*', '?'>::new(pattern).matches(entry))
+    };
+    let mut allowed = Vec::new();
+    let mut disallowed = Vec::new();
+    for entry in agent {
+        if covers(parent, entry) {
+            allowed.push(entry.clone());
+        } else if !parent
+            .iter()
+            .any(|p| covers(std::slice::from_ref(entry), p))
+        {
+            disallowed.push(entry.clone());
+        }
+    }
+    for entry in parent {
+        if covers(agent, entry) && !allowed.contains(entry) {
+            allowed.push(entry.clone());
+        }
+    }
+    (Some(allowed), disallowed)
+}
+
+/// Run turns in the subagent's own thread until it answers without calling
+/// a tool. Returns its final message.
+#[allow(clippy::too_many_arguments)]
+async fn run_subagent(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: &str,
+    call_id: &str,
+    agent: &str,
+    prompt: String,
+    hooks_config: &HooksConfig,
+) -> Result<Option<String>, String> {
+    let record = |items: Vec<ResponseItem>| {
+        let rollout_items: Vec<RolloutItem> = items
+            .into_iter()
+            .map(|item| {
+                RolloutItem::SubagentItem(SubagentRolloutItem {
+                    call_id: call_id.to_string(),
+                    agent: agent.to_string(),
+                    item,
+                })
+            })
+            .collect();
+        async move { sess.persist_rollout_items(&rollout_items).await }
+    };
+
+    let task: ResponseItem = ResponseInputItem::from(vec![InputItem::Text { text: prompt }]).into();
+    let mut history = sess.build_initial_context(turn_context);
+    history.push(task.clone());
+    record(vec![task]).await;
+
+    loop {
+        // Boxed because run_turn is what dispatched this tool call.
+        let TurnRunResult {
+            processed_items, ..
+        } = Box::pin(run_turn(
+            sess,
+            turn_context,
+            turn_diff_tracker,
+            sub_id.to_string(),
+            history.clone(),
+            hooks_config,
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let (items, responses) = split_processed_items(processed_items);
+        history.extend(items.iter().cloned());
+        let last_agent_message = get_last_assistant_message_from_turn(&items);
+        record(items).await;
+        if responses.is_empty() {
+            return Ok(last_agent_message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn tools(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| (*name).to_string()).collect()
+    }
+
+    #[test]
+    fn allowed_tools_are_intersected_with_the_parent() {
+        let parent = tools(&["shell", "mcp__github__*"]);
+        let agent = tools(&["shell", "apply_patch", "mcp__*"]);
+        assert_eq!(
+            intersect_allowed_tools(Some(&agent), Some(&parent)),
+            (
+                Some(tools(&["shell", "mcp__github__*"])),
+                tools(&["apply_patch"])
+            )
+        );
+
+        // Either side alone decides; an agent cannot widen an empty list.
+        assert_eq!(
+            intersect_allowed_tools(Some(&agent), None),
+            (Some(agent.clone()), Vec::new())
+        );
+        assert_eq!(
+            intersect_allowed_tools(None, Some(&parent)),
+            (Some(parent.clone()), Vec::new())
+        );
+        assert_eq!(
+            intersect_allowed_tools(Some(&agent), Some(&[])),
+            (Some(Vec::new()), agent)
+        );
+    }
+}
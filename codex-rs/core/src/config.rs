@@ -1,8 +1,11 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::ExecLimits;
 use crate::config_types::History;
 use crate::config_types::HooksConfig;
+use crate::config_types::LinuxSandbox;
 use crate::config_types::McpServerConfig;
 use crate::config_types::Notifications;
+use crate::config_types::PermissionsToml;
 use crate::config_types::ReasoningSummaryFormat;
 use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
@@ -16,8 +19,10 @@ use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
 use crate::openai_model_info::get_model_info;
+use crate::permissions::PermissionRules;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::default_read_deny_with_codex_home;
 use anyhow::Context;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
@@ -47,6 +52,10 @@ pub const GPT_5_CODEX_MEDIUM_MODEL: &str = "gpt-5-codex";
 /// the context window.
 pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
+/// File names looked up as project docs in each directory, in order.
+pub(crate) const DEFAULT_PROJECT_DOC_FILENAMES: &[&str] =
+    &["AGENTS.md", "CLAUDE.md", "CLAUDE.local.md"];
+
 pub(crate) const CONFIG_TOML_FILE: &str = "config.toml";
 
 /// Application configuration loaded from disk and merged with overrides.
@@ -82,6 +91,9 @@ pub struct Config {
 
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
+    /// Resource limits applied to each command the agent runs.
+    pub exec_limits: ExecLimits,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -137,6 +149,9 @@ pub struct Config {
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: usize,
 
+    /// File names that are picked up as project docs, in order.
+    pub project_doc_filenames: Vec<String>,
+
     /// Directory containing all Codex state (defaults to `~/.codex` but can be
     /// overridden by the `CODEX_HOME` environment variable).
     pub codex_home: PathBuf,
@@ -149,13 +164,17 @@ pub struct Config {
     pub file_opener: UriBasedFileOpener,
 
     /// Path to the `codex-linux-sandbox` executable. This must be set if
-    /// [`crate::exec::SandboxType::LinuxSeccomp`] is used. Note that this
+    /// [`crate::exec::SandboxType::LinuxSeccomp`] or
+    /// [`crate::exec::SandboxType::LinuxNamespaces`] is used. Note that this
     /// cannot be set in the config file: it must be set in code via
     /// [`ConfigOverrides`].
     ///
     /// When this program is invoked, arg0 will be set to `codex-linux-sandbox`.
     pub codex_linux_sandbox_exe: Option<PathBuf>,
 
+    /// Mechanism used to sandbox commands on Linux.
+    pub linux_sandbox: LinuxSandbox,
+
     /// Value to use for `reasoning.effort` when making a request using the
     /// Responses API.
     pub model_reasoning_effort: Option<ReasoningEffort>,
@@ -188,6 +207,14 @@ pub struct Config {
     /// Include the `view_image` tool that lets the agent attach a local image path to context.
     pub include_view_image_tool: bool,
 
+    /// Include the `bg_start`, `bg_logs` and `bg_stop` tools that let the
+    /// agent keep long-running processes such as dev servers in the background.
+    pub include_background_process_tools: bool,
+
+    /// Save the full output of commands whose output is truncated for the
+    /// model, and include the `read_output` tool to page or search it.
+    pub include_read_output_tool: bool,
+
     /// The active profile name used to derive this `Config` (if any).
     pub active_profile: Option<String>,
 
@@ -198,6 +225,10 @@ pub struct Config {
 
     /// Hook configuration for session lifecycle events.
     pub hooks: HooksConfig,
+
+    /// Allow and deny rules for commands and edits, from `[permissions]` and
+    /// the permissions of the current project.
+    pub permissions: PermissionRules,
 }
 
 impl Config {
@@ -628,12 +659,23 @@ pub struct ConfigToml {
     #[serde(default)]
     pub shell_environment_policy: ShellEnvironmentPolicyToml,
 
+    /// Resource limits applied to each command the agent runs.
+    #[serde(default)]
+    pub exec_limits: ExecLimits,
+
     /// Sandbox mode to use.
     pub sandbox_mode: Option<SandboxMode>,
 
     /// Sandbox configuration to apply if `sandbox` is `WorkspaceWrite`.
     pub sandbox_workspace_write: Option<SandboxWorkspaceWrite>,
 
+    /// Files and folders that sandboxed commands cannot read, replacing the
+    /// default list of credential stores.
+    pub sandbox_read_deny: Option<Vec<PathBuf>>,
+
+    /// Mechanism used to sandbox commands on Linux.
+    pub linux_sandbox: Option<LinuxSandbox>,
+
     /// Optional external command to spawn for end-user notifications.
     #[serde(default)]
     pub notify: Option<Vec<String>>,
@@ -652,6 +694,10 @@ pub struct ConfigToml {
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: Option<usize>,
 
+    /// File names that are picked up as project docs, in order. Defaults to
+    /// `AGENTS.md`, `CLAUDE.md` and `CLAUDE.local.md`.
+    pub project_doc_filenames: Option<Vec<String>>,
+
     /// Profile to use from the `profiles` map.
     pub profile: Option<String>,
 
@@ -711,6 +757,10 @@ pub struct ConfigToml {
     /// Hook configuration for session lifecycle events.
     #[serde(default)]
     pub hooks: Option<HooksConfig>,
+
+    /// Rules for commands and edits that are approved or rejected without
+    /// asking.
+    pub permissions: Option<PermissionsToml>,
 }
 
 impl From<ConfigToml> for UserSavedConfig {
@@ -739,6 +789,7 @@ impl From<ConfigToml> for UserSavedConfig {
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ProjectConfig {
     pub trust_level: Option<String>,
+    pub permissions: Option<PermissionsToml>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default, PartialEq)]
@@ -749,6 +800,16 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Enable the `bg_start`, `bg_logs` and `bg_stop` tools for long-running
+    /// background processes.
+    #[serde(default)]
+    pub background_processes: Option<bool>,
+
+    /// Save the full output of truncated commands under `CODEX_HOME` and
+    /// enable the `read_output` tool that reads it.
+    #[serde(default)]
+    pub read_output: Option<bool>,
 }
 
 impl From<ToolsToml> for Tools {
@@ -761,26 +822,41 @@ impl From<ToolsToml> for Tools {
 }
 
 impl ConfigToml {
-    /// Derive the effective sandbox policy from the configuration.
-    fn derive_sandbox_policy(&self, sandbox_mode_override: Option<SandboxMode>) -> SandboxPolicy {
+    /// Derive the effective sandbox policy from the configuration. The
+    /// default `read_deny` hides the login in `codex_home`.
+    fn derive_sandbox_policy(
+        &self,
+        sandbox_mode_override: Option<SandboxMode>,
+        codex_home: &Path,
+    ) -> SandboxPolicy {
         let resolved_sandbox_mode = sandbox_mode_override
             .or(self.sandbox_mode)
             .unwrap_or_default();
+        let read_deny = self
+            .sandbox_read_deny
+            .clone()
+            .unwrap_or_else(|| default_read_deny_with_codex_home(codex_home));
         match resolved_sandbox_mode {
-            SandboxMode::ReadOnly => SandboxPolicy::new_read_only_policy(),
+            SandboxMode::ReadOnly => SandboxPolicy::ReadOnly { read_deny },
             SandboxMode::WorkspaceWrite => match self.sandbox_workspace_write.as_ref() {
                 Some(SandboxWorkspaceWrite {
                     writable_roots,
                     network_access,
+                    network_allowlist,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
                 }) => SandboxPolicy::WorkspaceWrite {
                     writable_roots: writable_roots.clone(),
                     network_access: *network_access,
+                    network_allowlist: network_allowlist.clone(),
                     exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
                     exclude_slash_tmp: *exclude_slash_tmp,
+                    read_deny,
+                },
+                None => SandboxPolicy::WorkspaceWrite {
+                    read_deny,
+                    ..SandboxPolicy::new_workspace_write_policy()
                 },
-                None => SandboxPolicy::new_workspace_write_policy(),
             },
             SandboxMode::DangerFullAccess => SandboxPolicy::DangerFullAccess,
         }
@@ -899,7 +975,7 @@ impl Config {
             None => ConfigProfile::default(),
         };
 
-        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode);
+        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode, &codex_home);
 
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
@@ -944,6 +1020,13 @@ impl Config {
 
         let history = cfg.history.unwrap_or_default();
 
+        let permissions = PermissionRules::from_toml(
+            cfg.permissions
+                .iter()
+                .chain(project_permissions(cfg.projects.as_ref(), &resolved_cwd)),
+        )
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
         // Discover hooks with project-level support
         let hooks =
             discover_hooks_with_project_support(&cfg.hooks.unwrap_or_default(), &resolved_cwd);
@@ -956,6 +1039,18 @@ impl Config {
             .or(cfg.tools.as_ref().and_then(|t| t.view_image))
             .unwrap_or(true);
 
+        let include_background_process_tools = cfg
+            .tools
+            .as_ref()
+            .and_then(|t| t.background_processes)
+            .unwrap_or(false);
+
+        let include_read_output_tool = cfg
+            .tools
+            .as_ref()
+            .and_then(|t| t.read_output)
+            .unwrap_or(true);
+
         let model = model
             .or(config_profile.model)
             .or(cfg.model)
@@ -1018,16 +1113,24 @@ impl Config {
                 .unwrap_or_else(AskForApproval::default),
             sandbox_policy,
             shell_environment_policy,
+            exec_limits: cfg.exec_limits,
             notify: cfg.notify,
             user_instructions,
             base_instructions,
             mcp_servers: cfg.mcp_servers,
             model_providers,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
+            project_doc_filenames: cfg.project_doc_filenames.unwrap_or_else(|| {
+                DEFAULT_PROJECT_DOC_FILENAMES
+                    .iter()
+                    .map(|name| (*name).to_string())
+                    .collect()
+            }),
             codex_home,
             history,
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
             codex_linux_sandbox_exe,
+            linux_sandbox: cfg.linux_sandbox.unwrap_or_default(),
 
             hide_agent_reasoning: cfg.hide_agent_reasoning.unwrap_or(false),
             show_raw_agent_reasoning: cfg
@@ -1056,6 +1159,8 @@ impl Config {
                 .experimental_use_unified_exec_tool
                 .unwrap_or(false),
             include_view_image_tool,
+            include_background_process_tools,
+            include_read_output_tool,
             active_profile: active_profile_name,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
             tui_notifications: cfg
@@ -1064,6 +1169,7 @@ impl Config {
                 .map(|t| t.notifications.clone())
                 .unwrap_or_default(),
             hooks,
+            permissions,
         };
         Ok(config)
     }
@@ -1229,6 +1335,7 @@ persistence = "none"
 
     #[test]
     fn test_sandbox_config_parsing() {
+        let codex_home = Path::new("/home/user/.codex");
         let sandbox_full_access = r#"
 sandbox_mode = "danger-full-access"
 
@@ -1240,7 +1347,7 @@ network_access = false  # This should be ignored.
         let sandbox_mode_override = None;
         assert_eq!(
             SandboxPolicy::DangerFullAccess,
-            sandbox_full_access_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_full_access_cfg.derive_sandbox_policy(sandbox_mode_override, codex_home)
         );
 
         let sandbox_read_only = r#"
@@ -1254,8 +1361,10 @@ network_access = true  # This should be ignored.
             .expect("TOML deserialization should succeed");
         let sandbox_mode_override = None;
         assert_eq!(
-            SandboxPolicy::ReadOnly,
-            sandbox_read_only_cfg.derive_sandbox_policy(sandbox_mode_override)
+            SandboxPolicy::ReadOnly {
+                read_deny: default_read_deny_with_codex_home(codex_home),
+            },
+            sandbox_read_only_cfg.derive_sandbox_policy(sandbox_mode_override, codex_home)
         );
 
         let sandbox_workspace_write = r#"
@@ -1267,6 +1376,7 @@ writable_roots = [
 ]
 exclude_tmpdir_env_var = true
 exclude_slash_tmp = true
+network_allowlist = ["crates.io", "*.github.com"]
 "#;
 
         let sandbox_workspace_write_cfg = toml::from_str::<ConfigToml>(sandbox_workspace_write)
@@ -1276,10 +1386,64 @@ exclude_slash_tmp = true
             SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![PathBuf::from("/my/workspace")],
                 network_access: false,
+                network_allowlist: vec!["crates.io".to_string(), "*.github.com".to_string()],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                read_deny: default_read_deny_with_codex_home(codex_home),
+            },
+            sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override, codex_home)
+        );
+
+        let sandbox_read_deny = r#"
+sandbox_mode = "read-only"
+sandbox_read_deny = ["~/.secrets", ".env"]
+"#;
+
+        let sandbox_read_deny_cfg = toml::from_str::<ConfigToml>(sandbox_read_deny)
+            .expect("TOML deserialization should succeed");
+        let sandbox_mode_override = None;
+        assert_eq!(
+            SandboxPolicy::ReadOnly {
+                read_deny: vec![PathBuf::from("~/.secrets"), PathBuf::from(".env")],
             },
-            sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_read_deny_cfg.derive_sandbox_policy(sandbox_mode_override, codex_home)
+        );
+    }
+
+    #[test]
+    fn test_linux_sandbox_config_parsing() {
+        let cfg = toml::from_str::<ConfigToml>("").expect("TOML deserialization should succeed");
+        assert_eq!(None, cfg.linux_sandbox);
+
+        let cfg = toml::from_str::<ConfigToml>(r#"linux_sandbox = "namespaces""#)
+            .expect("TOML deserialization should succeed");
+        assert_eq!(Some(LinuxSandbox::Namespaces), cfg.linux_sandbox);
+
+        let cfg = toml::from_str::<ConfigToml>(r#"linux_sandbox = "landlock""#)
+            .expect("TOML deserialization should succeed");
+        assert_eq!(Some(LinuxSandbox::Landlock), cfg.linux_sandbox);
+    }
+
+    #[test]
+    fn test_exec_limits_config_parsing() {
+        let cfg = toml::from_str::<ConfigToml>("").expect("TOML deserialization should succeed");
+        assert_eq!(ExecLimits::default(), cfg.exec_limits);
+
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+[exec_limits]
+max_memory_mb = 2048
+max_output_bytes = 1048576
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        assert_eq!(
+            ExecLimits {
+                max_memory_mb: Some(2048),
+                max_output_bytes: Some(1_048_576),
+                ..Default::default()
+            },
+            cfg.exec_limits
         );
     }
 
@@ -1640,18 +1804,26 @@ model_verbosity = "high"
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
                 approval_policy: AskForApproval::Never,
-                sandbox_policy: SandboxPolicy::new_read_only_policy(),
+                sandbox_policy: SandboxPolicy::ReadOnly {
+                    read_deny: default_read_deny_with_codex_home(&fixture.codex_home()),
+                },
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
+                exec_limits: ExecLimits::default(),
                 user_instructions: None,
                 notify: None,
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
                 model_providers: fixture.model_provider_map.clone(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+                project_doc_filenames: DEFAULT_PROJECT_DOC_FILENAMES
+                    .iter()
+                    .map(|name| (*name).to_string())
+                    .collect(),
                 codex_home: fixture.codex_home(),
                 history: History::default(),
                 file_opener: UriBasedFileOpener::VsCode,
                 codex_linux_sandbox_exe: None,
+                linux_sandbox: LinuxSandbox::Landlock,
                 hide_agent_reasoning: false,
                 show_raw_agent_reasoning: false,
                 model_reasoning_effort: Some(ReasoningEffort::High),
@@ -1665,10 +1837,13 @@ model_verbosity = "high"
                 use_experimental_streamable_shell_tool: false,
                 use_experimental_unified_exec_tool: false,
                 include_view_image_tool: true,
+                include_background_process_tools: false,
+                include_read_output_tool: true,
                 active_profile: Some("o3".to_string()),
                 disable_paste_burst: false,
                 tui_notifications: Default::default(),
                 hooks: HooksConfig::default(),
+                permissions: PermissionRules::default(),
             },
             o3_profile_config
         );
@@ -1699,18 +1874,26 @@ model_verbosity = "high"
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
             approval_policy: AskForApproval::UnlessTrusted,
-            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            sandbox_policy: SandboxPolicy::ReadOnly {
+                read_deny: default_read_deny_with_codex_home(&fixture.codex_home()),
+            },
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            exec_limits: ExecLimits::default(),
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            project_doc_filenames: DEFAULT_PROJECT_DOC_FILENAMES
+                .iter()
+                .map(|name| (*name).to_string())
+                .collect(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
+            linux_sandbox: LinuxSandbox::Landlock,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
             model_reasoning_effort: None,
@@ -1724,10 +1907,13 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: true,
             active_profile: Some("gpt3".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
             hooks: HooksConfig::default(),
+            permissions: PermissionRules::default(),
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -1773,18 +1959,26 @@ model_verbosity = "high"
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
-            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            sandbox_policy: SandboxPolicy::ReadOnly {
+                read_deny: default_read_deny_with_codex_home(&fixture.codex_home()),
+            },
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            exec_limits: ExecLimits::default(),
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            project_doc_filenames: DEFAULT_PROJECT_DOC_FILENAMES
+                .iter()
+                .map(|name| (*name).to_string())
+                .collect(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
+            linux_sandbox: LinuxSandbox::Landlock,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
             model_reasoning_effort: None,
@@ -1798,10 +1992,13 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: true,
             active_profile: Some("zdr".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
             hooks: HooksConfig::default(),
+            permissions: PermissionRules::default(),
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
@@ -1833,18 +2030,26 @@ model_verbosity = "high"
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
-            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            sandbox_policy: SandboxPolicy::ReadOnly {
+                read_deny: default_read_deny_with_codex_home(&fixture.codex_home()),
+            },
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            exec_limits: ExecLimits::default(),
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            project_doc_filenames: DEFAULT_PROJECT_DOC_FILENAMES
+                .iter()
+                .map(|name| (*name).to_string())
+                .collect(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
+            linux_sandbox: LinuxSandbox::Landlock,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
             model_reasoning_effort: Some(ReasoningEffort::High),
@@ -1858,10 +2063,13 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: true,
             active_profile: Some("gpt5".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
             hooks: HooksConfig::default(),
+            permissions: PermissionRules::default(),
         };
 
         assert_eq!(expected_gpt5_profile_config, gpt5_profile_config);
@@ -2023,11 +2231,37 @@ fn discover_hooks_with_project_support(
             && let Some(project_hooks) = project_config.hooks
         {
             // Merge project hooks with global ones
-            for (name, config) in project_hooks.stop {
-                hooks.stop.insert(name, config);
+            hooks.stop.extend(project_hooks.stop);
+            hooks.pre_tool_use.extend(project_hooks.pre_tool_use);
+            hooks.post_tool_use.extend(project_hooks.post_tool_use);
+            hooks
+                .user_prompt_submit
+                .extend(project_hooks.user_prompt_submit);
+            hooks.session_start.extend(project_hooks.session_start);
+            hooks.session_end.extend(project_hooks.session_end);
+            if project_hooks.stop_loop_limit.is_some() {
+                hooks.stop_loop_limit = project_hooks.stop_loop_limit;
             }
         }
     }
 
     hooks
 }
+
+/// Permission rules of the project containing `resolved_cwd`, looked up the
+/// same way as [`ConfigToml::is_cwd_trusted`].
+fn project_permissions<'a>(
+    projects: Option<&'a HashMap<String, ProjectConfig>>,
+    resolved_cwd: &Path,
+) -> Option<&'a PermissionsToml> {
+    let projects = projects?;
+    let permissions_for = |path: &Path| {
+        projects
+            .get(path.to_string_lossy().as_ref())
+            .and_then(|p| p.permissions.as_ref())
+    };
+    permissions_for(resolved_cwd).or_else(|| {
+        resolve_root_git_project_for_trust(resolved_cwd)
+            .and_then(|root_project| permissions_for(&root_project))
+    })
+}
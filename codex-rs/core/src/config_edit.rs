@@ -1,5 +1,6 @@
 use crate::config::CONFIG_TOML_FILE;
 use anyhow::Result;
+use anyhow::anyhow;
 use std::path::Path;
 use tempfile::NamedTempFile;
 use toml_edit::DocumentMut;
@@ -186,6 +187,60 @@ async fn persist_overrides_with_behavior(
     Ok(())
 }
 
+/// Append `rule` to `allow` in `[projects."<project>".permissions]`, unless it
+/// is already there. Existing inline tables under `projects` are kept inline.
+pub async fn persist_project_permission_rule(
+    codex_home: &Path,
+    project: &Path,
+    rule: &str,
+) -> Result<()> {
+    let config_path = codex_home.join(CONFIG_TOML_FILE);
+    let mut doc = match tokio::fs::read_to_string(&config_path).await {
+        Ok(contents) => contents.parse::<DocumentMut>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            tokio::fs::create_dir_all(codex_home).await?;
+            DocumentMut::new()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let project_key = project.to_string_lossy();
+    let allowed = project_allowed_rules(&mut doc, &project_key).ok_or_else(|| {
+        anyhow!("`projects.\"{project_key}\".permissions.allow` in config.toml is not an array")
+    })?;
+    if allowed.iter().any(|value| value.as_str() == Some(rule)) {
+        return Ok(());
+    }
+    allowed.push(rule);
+
+    let tmp_file = NamedTempFile::new_in(codex_home)?;
+    tokio::fs::write(tmp_file.path(), doc.to_string()).await?;
+    tmp_file.persist(config_path)?;
+
+    Ok(())
+}
+
+/// The `projects.<project_key>.permissions.allow` array, creating the tables
+/// and the array as needed.
+fn project_allowed_rules<'a>(
+    doc: &'a mut DocumentMut,
+    project_key: &str,
+) -> Option<&'a mut toml_edit::Array> {
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for key in ["projects", project_key, "permissions"] {
+        if !table.contains_key(key) {
+            let mut new_table = toml_edit::Table::new();
+            new_table.set_implicit(true);
+            table.insert(key, toml_edit::Item::Table(new_table));
+        }
+        table = table.get_mut(key)?.as_table_like_mut()?;
+    }
+    if !table.contains_key("allow") {
+        table.insert("allow", toml_edit::value(toml_edit::Array::new()));
+    }
+    table.get_mut("allow")?.as_array_mut()
+}
+
 fn remove_toml_edit_segments(doc: &mut DocumentMut, segments: &[&str]) -> bool {
     use toml_edit::Item;
 
@@ -740,7 +795,64 @@ model_reasoning_effort = "high"
         assert!(!codex_home.join(CONFIG_TOML_FILE).exists());
     }
 
-    // Test helper moved to bottom per review guidance.
+    #[tokio::test]
+    async fn persist_project_permission_rule_appends_once() {
+        let tmpdir = tempdir().expect("tmp");
+        let codex_home = tmpdir.path();
+        let seed = r#"[projects."/work/app"]
+trust_level = "trusted"
+"#;
+        tokio::fs::write(codex_home.join(CONFIG_TOML_FILE), seed)
+            .await
+            .expect("seed write");
+
+        let project = Path::new("/work/app");
+        for rule in [
+            "Bash(cargo test:*)",
+            "Bash(git status)",
+            "Bash(cargo test:*)",
+        ] {
+            persist_project_permission_rule(codex_home, project, rule)
+                .await
+                .expect("persist");
+        }
+
+        let contents = read_config(codex_home).await;
+        let expected = r#"[projects."/work/app"]
+trust_level = "trusted"
+
+[projects."/work/app".permissions]
+allow = ["Bash(cargo test:*)", "Bash(git status)"]
+"#;
+        assert_eq!(contents, expected);
+    }
+
+    #[tokio::test]
+    async fn persist_project_permission_rule_keeps_inline_tables() {
+        let tmpdir = tempdir().expect("tmp");
+        let codex_home = tmpdir.path();
+        let seed = r#"projects = { "/work/app" = { trust_level = "trusted" } }
+"#;
+        tokio::fs::write(codex_home.join(CONFIG_TOML_FILE), seed)
+            .await
+            .expect("seed write");
+
+        persist_project_permission_rule(codex_home, Path::new("/work/app"), "Bash(make:*)")
+            .await
+            .expect("persist");
+
+        let contents = read_config(codex_home).await;
+        let parsed: toml::Value = toml::from_str(&contents).expect("valid toml");
+        assert_eq!(
+            parsed["projects"]["/work/app"]["permissions"]["allow"],
+            toml::Value::Array(vec![toml::Value::String("Bash(make:*)".to_string())])
+        );
+        assert_eq!(
+            parsed["projects"]["/work/app"]["trust_level"].as_str(),
+            Some("trusted")
+        );
+    }
+
     async fn read_config(codex_home: &Path) -> String {
         let p = codex_home.join(CONFIG_TOML_FILE);
         tokio::fs::read_to_string(p).await.unwrap_or_default()
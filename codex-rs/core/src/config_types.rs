@@ -118,6 +118,32 @@ pub struct HookConfig {
 
     #[serde(default = "default_hook_enabled")]
     pub enabled: bool,
+
+    /// Restricts `pre_tool_use` and `post_tool_use` hooks to some tool calls.
+    /// Ignored by other events.
+    #[serde(default)]
+    pub matcher: Option<HookMatcher>,
+
+    /// Block the tool call when a `pre_tool_use` hook fails to run, times out
+    /// or replies with an error, instead of letting the call run. Ignored by
+    /// other events.
+    #[serde(default)]
+    pub fail_closed: bool,
+}
+
+/// Selects the tool calls a hook runs for. When both fields are set, a call
+/// must match both.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct HookMatcher {
+    /// Regular expression matched against the whole tool name, e.g.
+    /// `"shell|apply_patch"`.
+    #[serde(default)]
+    pub tool: Option<String>,
+
+    /// Glob matched against the command line of shell calls and the paths
+    /// touched by `apply_patch`, e.g. `"*.rs"`.
+    #[serde(default)]
+    pub glob: Option<String>,
 }
 
 fn default_hook_enabled() -> bool {
@@ -126,8 +152,39 @@ fn default_hook_enabled() -> bool {
 
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct HooksConfig {
+    /// Run when the agent finishes a turn. These hooks may block the stop to
+    /// make the agent keep working.
     #[serde(default)]
     pub stop: HashMap<String, HookConfig>,
+
+    /// How many extra turns `stop` hooks may start within one task before the
+    /// task is allowed to end. Defaults to 5.
+    #[serde(default)]
+    pub stop_loop_limit: Option<u32>,
+
+    /// Run before a tool call is dispatched. These hooks may allow, deny or
+    /// rewrite the call, or ask the user to approve it.
+    #[serde(default)]
+    pub pre_tool_use: HashMap<String, HookConfig>,
+
+    /// Run after a `shell` or `apply_patch` call finishes. Their output is
+    /// appended to the tool output the model sees.
+    #[serde(default)]
+    pub post_tool_use: HashMap<String, HookConfig>,
+
+    /// Run when the user submits a prompt, before the turn starts. These
+    /// hooks may block the prompt or add context for the model.
+    #[serde(default)]
+    pub user_prompt_submit: HashMap<String, HookConfig>,
+
+    /// Run once the session is configured. Their output is recorded as
+    /// context for the model.
+    #[serde(default)]
+    pub session_start: HashMap<String, HookConfig>,
+
+    /// Run when the session shuts down.
+    #[serde(default)]
+    pub session_end: HashMap<String, HookConfig>,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
@@ -172,6 +229,43 @@ pub struct History {
     pub max_bytes: Option<usize>,
 }
 
+/// Resource limits applied to each command the agent runs, in addition to its
+/// timeout. Unset limits are not enforced.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExecLimits {
+    /// Maximum virtual memory (address space, not resident memory) of each
+    /// process of the command, in megabytes.
+    pub max_memory_mb: Option<u64>,
+
+    /// Maximum CPU time of each process of the command, in seconds.
+    pub max_cpu_seconds: Option<u64>,
+
+    /// Maximum number of processes. Like `ulimit -u`, this counts all the
+    /// processes of the current user, not only those of the command.
+    pub max_processes: Option<u64>,
+
+    /// Maximum number of files each process of the command can have open.
+    pub max_open_files: Option<u64>,
+
+    /// Maximum number of bytes the command can write to stdout and stderr
+    /// combined before it is killed.
+    pub max_output_bytes: Option<u64>,
+}
+
+/// Mechanism used to sandbox commands on Linux.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinuxSandbox {
+    /// Restrict filesystem writes with Landlock and network access with
+    /// seccomp.
+    #[default]
+    Landlock,
+    /// Additionally run commands in unprivileged user, mount, PID and network
+    /// namespaces with a private `/tmp`. Falls back to `Landlock` when user
+    /// namespaces are unavailable.
+    Namespaces,
+}
+
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum HistoryPersistence {
@@ -204,6 +298,20 @@ pub struct Tui {
     pub notifications: Notifications,
 }
 
+/// Permission rules from a `[permissions]` table, e.g.
+/// `allow = ["Bash(cargo test:*)"]`. See [`crate::permissions`] for the rule
+/// syntax.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PermissionsToml {
+    /// Rules for commands and edits that are approved without asking.
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// Rules for commands and edits that are always rejected.
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct SandboxWorkspaceWrite {
     #[serde(default)]
@@ -211,6 +319,8 @@ pub struct SandboxWorkspaceWrite {
     #[serde(default)]
     pub network_access: bool,
     #[serde(default)]
+    pub network_allowlist: Vec<String>,
+    #[serde(default)]
     pub exclude_tmpdir_env_var: bool,
     #[serde(default)]
     pub exclude_slash_tmp: bool,
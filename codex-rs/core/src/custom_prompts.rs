@@ -1,8 +1,27 @@
+use crate::config::Config;
+use crate::exec::ExecParams;
+use crate::exec::process_exec_tool_call;
+use crate::exec_env::create_env;
+use crate::exec_policy::ExecPolicy;
+use crate::safety::SafetyCheck;
+use crate::safety::assess_command_safety;
+use crate::template_processor::undeclared_prompt_variables;
+use crate::truncate::truncate_middle;
+use codex_protocol::config_types::ReasoningEffort;
+use codex_protocol::config_types::SandboxMode;
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::PromptTurnOverrides;
+use codex_protocol::custom_prompts::TemplateArg;
+use codex_protocol::custom_prompts::TemplateSyntax;
+use codex_protocol::protocol::AskForApproval;
+use regex_lite::Regex;
+use serde::de::DeserializeOwned;
 use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use tokio::fs;
+use tracing::warn;
 
 /// Return the default prompts directory: `$CODEX_HOME/prompts`.
 /// If `CODEX_HOME` cannot be resolved, returns `None`.
@@ -64,6 +83,11 @@ pub async fn discover_prompts_in_excluding(
             Err(_) => continue,
         };
         let (description, argument_hint, content) = parse_frontmatter(&raw_content);
+        let template_args = parse_template_args(&raw_content);
+        let turn_overrides = parse_turn_overrides(&path, &raw_content);
+        // Declared arguments are only useful if the body is rendered as a template.
+        let template_syntax = parse_template_syntax(&path, &raw_content)
+            .or_else(|| template_args.as_ref().map(|_| TemplateSyntax::Jinja));
         out.push(CustomPrompt {
             name,
             path,
@@ -71,8 +95,10 @@ pub async fn discover_prompts_in_excluding(
             category: None,
             argument_hint,
             description,
-            template_args: None,
-            template_syntax: None,
+            template_args,
+            template_syntax,
+            turn_overrides,
+            mcp_server: None,
         });
     }
     out.sort_by(|a, b| a.name.cmp(&b.name));
@@ -116,6 +142,12 @@ pub async fn discover_prompts_with_directories(base_dir: &Path) -> Vec<CustomPro
     prompts
 }
 
+/// Return the project prompts directory, `.codex/prompts` at the root of the
+/// git repository containing `cwd`. Returns `None` outside of a repository.
+pub fn project_prompts_dir(cwd: &Path) -> Option<PathBuf> {
+    crate::git_info::get_git_repo_root(cwd).map(|root| root.join(".codex/prompts"))
+}
+
 /// Discover prompts from both global and project-level directories
 pub async fn discover_prompts_with_project_support(
     global_dir: &Path,
@@ -133,8 +165,7 @@ pub async fn discover_prompts_with_project_support(
     }
 
     // 2. Project prompts (new behavior)
-    if let Some(git_root) = crate::git_info::get_git_repo_root(project_cwd) {
-        let project_prompt_dir = git_root.join(".codex/prompts");
+    if let Some(project_prompt_dir) = project_prompts_dir(project_cwd) {
         let project_prompts = discover_prompts_with_directories(&project_prompt_dir).await;
 
         // Project prompts override global ones (later sources take precedence)
@@ -146,16 +177,16 @@ pub async fn discover_prompts_with_project_support(
     prompts
 }
 
-/// Parse frontmatter from a markdown file content
-/// Returns (description, argument_hint, content_without_frontmatter)
-fn parse_frontmatter(content: &str) -> (Option<String>, Option<String>, String) {
+/// Split a markdown file into its frontmatter and body. Returns `None` when the
+/// file has no (complete) frontmatter block.
+pub(crate) fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
     // Check if content starts with frontmatter delimiter (handle both Unix and Windows line endings)
     let skip_bytes = if content.starts_with("---\n") {
         4
     } else if content.starts_with("---\r\n") {
         5
     } else {
-        return (None, None, content.to_string());
+        return None;
     };
 
     // Find the closing frontmatter delimiter (handle both line ending types)
@@ -165,58 +196,514 @@ fn parse_frontmatter(content: &str) -> (Option<String>, Option<String>, String)
     } else if content_after_start.contains("\r\n---\r\n") {
         ("\r\n---\r\n", 7)
     } else {
+        return None;
+    };
+
+    let end_pos = content_after_start.find(closing_delimiter)?;
+    let frontmatter = &content_after_start[..end_pos];
+    let body_content = &content_after_start[end_pos + delimiter_len..];
+
+    // Trim leading newline/CRLF from body content if present
+    let body_content = body_content
+        .strip_prefix("\r\n")
+        .or_else(|| body_content.strip_prefix('\n'))
+        .unwrap_or(body_content);
+    Some((frontmatter, body_content))
+}
+
+/// Strip matching single or double quotes around a frontmatter value.
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+/// Parse a top-level field value from frontmatter. Indented lines belong to
+/// nested blocks such as `arguments:` and are not considered.
+pub(crate) fn parse_field(frontmatter: &str, field_name: &str) -> Option<String> {
+    frontmatter
+        .lines()
+        .find(|line| line.starts_with(&format!("{field_name}:")))
+        .and_then(|line| {
+            let colon_pos = line.find(':')?;
+            let value = unquote(&line[colon_pos + 1..]);
+            if value.is_empty() {
+                None
+            } else {
+                Some(value.to_string())
+            }
+        })
+}
+
+/// Parse frontmatter from a markdown file content
+/// Returns (description, argument_hint, content_without_frontmatter)
+fn parse_frontmatter(content: &str) -> (Option<String>, Option<String>, String) {
+    let Some((frontmatter, body_content)) = split_frontmatter(content) else {
+        // No frontmatter (or no closing delimiter), treat as regular content
         return (None, None, content.to_string());
     };
 
-    if let Some(end_pos) = content_after_start.find(closing_delimiter) {
-        let frontmatter = &content_after_start[..end_pos];
-        let body_content = &content_after_start[end_pos + delimiter_len..];
-
-        // Trim leading newline/CRLF from body content if present
-        let body_content = body_content
-            .strip_prefix("\r\n")
-            .or_else(|| body_content.strip_prefix('\n'))
-            .unwrap_or(body_content);
-
-        // Helper function to parse a field value from frontmatter
-        let parse_field = |field_name: &str| -> Option<String> {
-            frontmatter
-                .lines()
-                .find(|line| line.trim_start().starts_with(&format!("{field_name}:")))
-                .and_then(|line| {
-                    let colon_pos = line.find(':')?;
-                    let value = line[colon_pos + 1..].trim();
-                    // Handle quoted values
-                    let value = if (value.starts_with('"') && value.ends_with('"'))
-                        || (value.starts_with('\'') && value.ends_with('\''))
-                    {
-                        &value[1..value.len() - 1]
-                    } else {
-                        value
-                    };
-                    if value.is_empty() {
-                        None
-                    } else {
-                        Some(value.to_string())
-                    }
-                })
+    // Parse description and argument-hint from frontmatter
+    let description = parse_field(frontmatter, "description");
+    let argument_hint = parse_field(frontmatter, "argument-hint");
+
+    // Return only the body content (frontmatter completely removed)
+    (description, argument_hint, body_content.to_string())
+}
+
+/// Parse the `template:` frontmatter key (`jinja` or `simple`) that opts a
+/// prompt into template rendering. Prompts without it are sent as written,
+/// so a `$1` or `{{` in a plain prompt is left alone.
+fn parse_template_syntax(path: &Path, content: &str) -> Option<TemplateSyntax> {
+    let (frontmatter, _) = split_frontmatter(content)?;
+    let value = parse_field(frontmatter, "template")?;
+    let syntax = parse_template_syntax_value(&value);
+    if syntax.is_none() {
+        warn!("ignoring invalid `template: {value}` in {}", path.display());
+    }
+    syntax
+}
+
+fn parse_template_syntax_value(value: &str) -> Option<TemplateSyntax> {
+    match value {
+        "jinja" => Some(TemplateSyntax::Jinja),
+        "simple" => Some(TemplateSyntax::Simple),
+        _ => None,
+    }
+}
+
+/// Parse the per-turn overrides (`model`, `reasoning-effort`, `sandbox`,
+/// `approval` and `allowed-tools`) from the frontmatter. Returns `None` when
+/// the prompt does not override anything. Unknown values are ignored with a
+/// warning.
+pub(crate) fn parse_turn_overrides(path: &Path, content: &str) -> Option<PromptTurnOverrides> {
+    let (frontmatter, _) = split_frontmatter(content)?;
+    let overrides = PromptTurnOverrides {
+        model: parse_field(frontmatter, "model"),
+        effort: parse_enum_field(path, frontmatter, "reasoning-effort"),
+        sandbox: parse_enum_field(path, frontmatter, "sandbox"),
+        approval: parse_enum_field(path, frontmatter, "approval"),
+        allowed_tools: parse_field(frontmatter, "allowed-tools")
+            .map(|value| split_tool_list(&value)),
+    };
+    (overrides != PromptTurnOverrides::default()).then_some(overrides)
+}
+
+/// Parse a field holding one of the kebab-case values of a config enum.
+fn parse_enum_field<T: DeserializeOwned>(
+    path: &Path,
+    frontmatter: &str,
+    field_name: &str,
+) -> Option<T> {
+    let value = parse_field(frontmatter, field_name)?;
+    match parse_enum_value(&value) {
+        Some(parsed) => Some(parsed),
+        None => {
+            warn!(
+                "ignoring invalid `{field_name}: {value}` in {}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+fn parse_enum_value<T: DeserializeOwned>(value: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+}
+
+/// Split an `allowed-tools` value such as `shell, apply_patch` or
+/// `[shell, "mcp__*"]` into tool names. Commas inside parentheses do not
+/// separate entries.
+fn split_tool_list(value: &str) -> Vec<String> {
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    let mut tools = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                tools.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    tools.push(current);
+    tools
+        .iter()
+        .map(|tool| unquote(tool).to_string())
+        .filter(|tool| !tool.is_empty())
+        .collect()
+}
+
+/// Parse the `arguments:` block of the frontmatter, if any:
+///
+/// ```text
+/// arguments:
+///   - name: file
+///     description: File to review
+///     required: true
+///   - name: depth
+///     default: shallow
+/// ```
+///
+/// `- file` is shorthand for an optional argument without a default.
+fn parse_template_args(content: &str) -> Option<Vec<TemplateArg>> {
+    let (frontmatter, _) = split_frontmatter(content)?;
+    let mut lines = frontmatter
+        .lines()
+        .skip_while(|line| line.trim_end() != "arguments:");
+    lines.next()?;
+
+    let mut args: Vec<TemplateArg> = Vec::new();
+    for line in lines {
+        // The block ends at the next top-level key.
+        if !line.starts_with([' ', '\t', '-']) {
+            break;
+        }
+        let line = line.trim();
+        let entry = match line.strip_prefix('-') {
+            Some(rest) => {
+                args.push(TemplateArg {
+                    name: String::new(),
+                    description: None,
+                    required: false,
+                    default_value: None,
+                });
+                rest.trim()
+            }
+            None => line,
+        };
+        let Some(arg) = args.last_mut() else {
+            continue;
+        };
+        let Some((key, value)) = entry.split_once(':') else {
+            if !entry.is_empty() {
+                arg.name = unquote(entry).to_string();
+            }
+            continue;
         };
+        let value = unquote(value);
+        match key.trim() {
+            "name" => arg.name = value.to_string(),
+            "description" => arg.description = Some(value.to_string()),
+            "required" => arg.required = matches!(value, "true" | "yes"),
+            "default" => arg.default_value = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    args.retain(|arg| !arg.name.is_empty());
+    Some(args)
+}
 
-        // Parse description and argument-hint from frontmatter
-        let description = parse_field("description");
-        let argument_hint = parse_field("argument-hint");
+/// Top-level frontmatter keys that prompt discovery understands.
+const FRONTMATTER_KEYS: &[&str] = &[
+    "description",
+    "argument-hint",
+    "arguments",
+    "template",
+    "model",
+    "reasoning-effort",
+    "sandbox",
+    "approval",
+    "allowed-tools",
+];
 
-        // Return only the body content (frontmatter completely removed)
-        (description, argument_hint, body_content.to_string())
-    } else {
-        // No closing delimiter found, treat as regular content
-        (None, None, content.to_string())
+/// Check a prompt file for problems that discovery silently tolerates: an
+/// unterminated frontmatter, unknown keys, invalid override values, malformed
+/// `arguments:` entries, template syntax errors and template variables that
+/// are not declared. `raw_content` is the file as read from disk.
+pub fn lint_prompt(prompt: &CustomPrompt, raw_content: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    match split_frontmatter(raw_content) {
+        Some((frontmatter, _)) => lint_frontmatter(frontmatter, &mut issues),
+        None if raw_content.starts_with("---\n") || raw_content.starts_with("---\r\n") => {
+            issues.push("frontmatter is not closed by a `---` line".to_string());
+        }
+        None => {}
+    }
+
+    let declared = prompt.template_args.as_deref().unwrap_or_default();
+    for (i, arg) in declared.iter().enumerate() {
+        if declared[..i].iter().any(|other| other.name == arg.name) {
+            issues.push(format!(
+                "argument `{}` is declared more than once",
+                arg.name
+            ));
+        }
+        if arg.required && arg.default_value.is_some() {
+            issues.push(format!(
+                "argument `{}` is required but also has a default",
+                arg.name
+            ));
+        }
     }
+
+    match undeclared_prompt_variables(prompt) {
+        Ok(undeclared) => issues.extend(
+            undeclared
+                .into_iter()
+                .map(|name| format!("template variable `{name}` is not declared in `arguments:`")),
+        ),
+        Err(err) => issues.push(format!("template error: {err}")),
+    }
+
+    issues
+}
+
+fn lint_frontmatter(frontmatter: &str, issues: &mut Vec<String>) {
+    for line in frontmatter.lines() {
+        // Nested blocks such as `arguments:` entries are indented.
+        if line.trim().is_empty() || line.starts_with([' ', '\t', '-', '#']) {
+            continue;
+        }
+        match line.split_once(':') {
+            Some((key, _)) if FRONTMATTER_KEYS.contains(&key.trim()) => {}
+            Some((key, _)) => issues.push(format!("unknown frontmatter key `{}`", key.trim())),
+            None => issues.push(format!("invalid frontmatter line `{line}`")),
+        }
+    }
+
+    check_enum_field::<ReasoningEffort>(frontmatter, "reasoning-effort", issues);
+    check_enum_field::<SandboxMode>(frontmatter, "sandbox", issues);
+    check_enum_field::<AskForApproval>(frontmatter, "approval", issues);
+    if let Some(value) = parse_field(frontmatter, "template")
+        && parse_template_syntax_value(&value).is_none()
+    {
+        issues.push(format!("invalid `template` value `{value}`"));
+    }
+}
+
+fn check_enum_field<T: DeserializeOwned>(
+    frontmatter: &str,
+    field_name: &str,
+    issues: &mut Vec<String>,
+) {
+    if let Some(value) = parse_field(frontmatter, field_name)
+        && parse_enum_value::<T>(&value).is_none()
+    {
+        issues.push(format!("invalid `{field_name}` value `{value}`"));
+    }
+}
+
+/// Maximum number of bytes a single `` !`command` `` or `@file` inclusion adds
+/// to a prompt. Longer output is truncated in the middle.
+pub const PROMPT_INCLUDE_MAX_BYTES: usize = 16 * 1024;
+
+/// Commands embedded in prompts are expected to be quick (`git diff`, `ls`).
+const PROMPT_COMMAND_TIMEOUT_MS: u64 = 10_000;
+
+fn prompt_include_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+
+    #[expect(clippy::unwrap_used)]
+    RE.get_or_init(|| Regex::new(r"(?m)!`([^`\n]+)`|(^|\s)@([^\s`]+)").unwrap())
+}
+
+/// Whether a prompt body contains `` !`command` `` or `@path` inclusions that
+/// [`expand_prompt_includes`] would need to resolve.
+pub fn has_prompt_includes(content: &str) -> bool {
+    prompt_include_regex().is_match(content)
+}
+
+/// Replace `` !`command` `` spans with the command's output and `@path`
+/// tokens with the contents of the file, resolved against `config.cwd`.
+///
+/// `config` must carry the session's sandbox and approval policy, never the
+/// prompt's frontmatter overrides: prompts may come from the repository.
+/// Commands go through the same safety check as the model's shell calls:
+/// they run in the session's sandbox, and commands that would need approval
+/// under the session's approval policy are refused since there is nobody to
+/// ask while a prompt is being expanded. Expansion runs outside the session:
+/// the exec policies are loaded from disk once per expansion, and commands
+/// the user approved for the session are not consulted, so they are refused
+/// as well. Files
+/// must be inside `config.cwd` and not hidden by the sandbox's `read_deny`.
+/// `@` tokens that do not name a file (e.g. `@someone`) are left as they are.
+pub async fn expand_prompt_includes(content: &str, config: &Config) -> Result<String, String> {
+    expand_includes(content, config, true).await
+}
+
+/// Like [`expand_prompt_includes`], but only `@path` tokens are expanded;
+/// `` !`command` `` spans are left as they are.
+pub async fn expand_prompt_file_includes(content: &str, config: &Config) -> Result<String, String> {
+    expand_includes(content, config, false).await
+}
+
+async fn expand_includes(
+    content: &str,
+    config: &Config,
+    run_commands: bool,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut exec_policy = None;
+    for caps in prompt_include_regex().captures_iter(content) {
+        let Some(whole) = caps.get(0) else {
+            continue;
+        };
+        out.push_str(&content[last..whole.start()]);
+        last = whole.end();
+        if let Some(command) = caps.get(1) {
+            if run_commands {
+                let exec_policy = exec_policy
+                    .get_or_insert_with(|| ExecPolicy::load(&config.codex_home, &config.cwd).0);
+                out.push_str(&run_prompt_command(command.as_str(), config, exec_policy).await?);
+            } else {
+                out.push_str(whole.as_str());
+            }
+        } else if let (Some(prefix), Some(token)) = (caps.get(2), caps.get(3)) {
+            out.push_str(prefix.as_str());
+            match read_prompt_file(token.as_str(), config).await? {
+                Some(contents) => out.push_str(&contents),
+                None => out.push_str(&whole.as_str()[prefix.len()..]),
+            }
+        }
+    }
+    out.push_str(&content[last..]);
+    Ok(out)
+}
+
+async fn run_prompt_command(
+    command: &str,
+    config: &Config,
+    exec_policy: &ExecPolicy,
+) -> Result<String, String> {
+    let argv = vec!["bash".to_string(), "-lc".to_string(), command.to_string()];
+    let sandbox_type = match assess_command_safety(
+        &argv,
+        config.approval_policy,
+        &config.sandbox_policy,
+        exec_policy,
+        &config.permissions,
+        &config.cwd,
+        &HashSet::new(),
+        false,
+    ) {
+        SafetyCheck::AutoApprove { sandbox_type } => {
+            sandbox_type.with_linux_sandbox(config.linux_sandbox)
+        }
+        SafetyCheck::AskUser => {
+            return Err(format!(
+                "`{command}` needs approval under the current approval policy; run it yourself instead"
+            ));
+        }
+        SafetyCheck::Reject { reason } => {
+            return Err(format!("`{command}` was rejected: {reason}"));
+        }
+    };
+    let params = ExecParams {
+        command: argv,
+        cwd: config.cwd.clone(),
+        timeout_ms: Some(PROMPT_COMMAND_TIMEOUT_MS),
+        env: create_env(&config.shell_environment_policy),
+        with_escalated_permissions: None,
+        justification: None,
+        limits: config.exec_limits,
+    };
+    let output = process_exec_tool_call(
+        params,
+        sandbox_type,
+        &config.sandbox_policy,
+        &config.cwd,
+        &config.codex_linux_sandbox_exe,
+        None,
+    )
+    .await
+    .map_err(|e| format!("`{command}` failed: {e}"))?;
+    if output.timed_out {
+        return Err(format!("`{command}` timed out"));
+    }
+    if output.exit_code != 0 {
+        let (stderr, _) = truncate_middle(output.stderr.text.trim(), PROMPT_INCLUDE_MAX_BYTES);
+        return Err(format!(
+            "`{command}` exited with code {}: {stderr}",
+            output.exit_code
+        ));
+    }
+    let stdout = output.stdout.text.trim_end_matches('\n');
+    Ok(truncate_middle(stdout, PROMPT_INCLUDE_MAX_BYTES).0)
+}
+
+/// Read the file named by an `@` token. Trailing punctuation is ignored so
+/// that `see @src/lib.rs.` works. Returns `None` if no such file exists.
+async fn read_prompt_file(token: &str, config: &Config) -> Result<Option<String>, String> {
+    let candidates = [
+        token,
+        token.trim_end_matches(['.', ',', ';', ':', ')', '!', '?']),
+    ];
+    for candidate in candidates {
+        let path = config.cwd.join(candidate);
+        if !fs::metadata(&path).await.is_ok_and(|m| m.is_file()) {
+            continue;
+        }
+        check_prompt_file_readable(&path, config).await?;
+        let bytes = fs::read(&path)
+            .await
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        let contents = String::from_utf8_lossy(&bytes);
+        let (contents, _) =
+            truncate_middle(contents.trim_end_matches('\n'), PROMPT_INCLUDE_MAX_BYTES);
+        // Keep the punctuation that followed the path.
+        return Ok(Some(format!("{contents}{}", &token[candidate.len()..])));
+    }
+    Ok(None)
+}
+
+/// Refuse files outside the workspace (after resolving `..` and symlinks) and
+/// files hidden by the sandbox's `read_deny`.
+async fn check_prompt_file_readable(path: &Path, config: &Config) -> Result<(), String> {
+    let resolved = fs::canonicalize(path)
+        .await
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let workspace = fs::canonicalize(&config.cwd)
+        .await
+        .unwrap_or_else(|_| config.cwd.clone());
+    if !resolved.starts_with(&workspace) {
+        return Err(format!(
+            "{} is outside the workspace; prompts can only include files under {}",
+            path.display(),
+            workspace.display()
+        ));
+    }
+    for denied in config
+        .sandbox_policy
+        .get_read_deny_paths_with_cwd(&config.cwd)
+    {
+        let denied = fs::canonicalize(&denied).await.unwrap_or(denied);
+        if resolved.starts_with(&denied) {
+            return Err(format!(
+                "{} is hidden from commands by `read_deny` and cannot be included",
+                path.display()
+            ));
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use codex_protocol::config_types::ReasoningEffort;
+    use codex_protocol::config_types::SandboxMode;
+    use codex_protocol::protocol::AskForApproval;
+    use codex_protocol::protocol::SandboxPolicy;
     use std::fs;
     use tempfile::tempdir;
 
@@ -359,4 +846,249 @@ You are tasked with analyzing implementation plans for potential blocking issues
             "You are tasked with analyzing implementation plans.\r\n\r\n**Plan input provided:** $1"
         );
     }
+
+    #[test]
+    fn parses_template_args_from_frontmatter() {
+        let content = "---\ndescription: Review a file\narguments:\n  - name: file\n    description: \"File to review\"\n    required: true\n  - name: depth\n    default: shallow\n  - focus\nargument-hint: <file> [depth=...]\n---\nReview {{ file }}";
+        let args = parse_template_args(content).expect("arguments block");
+        let summary: Vec<_> = args
+            .iter()
+            .map(|arg| {
+                (
+                    arg.name.as_str(),
+                    arg.description.as_deref(),
+                    arg.required,
+                    arg.default_value.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("file", Some("File to review"), true, None),
+                ("depth", None, false, Some("shallow")),
+                ("focus", None, false, None),
+            ]
+        );
+        // Nested keys do not leak into the top-level fields.
+        let (description, argument_hint, _) = parse_frontmatter(content);
+        assert_eq!(description.as_deref(), Some("Review a file"));
+        assert_eq!(argument_hint.as_deref(), Some("<file> [depth=...]"));
+
+        assert!(parse_template_args("---\ndescription: x\n---\nbody").is_none());
+    }
+
+    #[tokio::test]
+    async fn discovered_prompts_carry_template_metadata() {
+        let tmp = tempdir().expect("create TempDir");
+        let dir = tmp.path();
+        fs::write(
+            dir.join("review.md"),
+            "---\narguments:\n  - name: file\n    required: true\n---\nReview it",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("fix.md"),
+            "---\ntemplate: jinja\n---\nFix $ARGUMENTS",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("greet.md"),
+            "---\ntemplate: simple\n---\nHello {0}",
+        )
+        .unwrap();
+        // Without the opt-in, placeholder-like text is not a template.
+        fs::write(dir.join("plain.md"), "Costs $1 per {{ item }}, see {0}").unwrap();
+        fs::write(dir.join("typo.md"), "---\ntemplate: askama\n---\n$1").unwrap();
+        let found = discover_prompts_in(dir).await;
+        let syntax: Vec<_> = found
+            .iter()
+            .map(|p| (p.name.as_str(), p.template_syntax))
+            .collect();
+        assert_eq!(
+            syntax,
+            vec![
+                ("fix", Some(TemplateSyntax::Jinja)),
+                ("greet", Some(TemplateSyntax::Simple)),
+                ("plain", None),
+                ("review", Some(TemplateSyntax::Jinja)),
+                ("typo", None),
+            ]
+        );
+        assert_eq!(found[3].template_args.as_ref().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn parses_turn_overrides_from_frontmatter() {
+        let content = "---\ndescription: Security review\nmodel: gpt-5-codex\nreasoning-effort: high\nsandbox: read-only\napproval: never\nallowed-tools: shell, Bash(git:*, grep:*), \"mcp__*\"\n---\nReview";
+        let overrides = parse_turn_overrides(Path::new("review.md"), content).expect("overrides");
+        assert_eq!(
+            overrides,
+            PromptTurnOverrides {
+                model: Some("gpt-5-codex".to_string()),
+                effort: Some(ReasoningEffort::High),
+                sandbox: Some(SandboxMode::ReadOnly),
+                approval: Some(AskForApproval::Never),
+                allowed_tools: Some(vec![
+                    "shell".to_string(),
+                    "Bash(git:*, grep:*)".to_string(),
+                    "mcp__*".to_string(),
+                ]),
+            }
+        );
+
+        // Invalid values are dropped, and prompts without overrides get none.
+        let content = "---\nreasoning-effort: extreme\nsandbox: [read-only]\n---\nReview";
+        assert_eq!(parse_turn_overrides(Path::new("review.md"), content), None);
+        assert_eq!(
+            parse_turn_overrides(Path::new("review.md"), "---\ndescription: x\n---\nbody"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn lint_reports_frontmatter_and_template_problems() {
+        let tmp = tempdir().expect("create TempDir");
+        let raw = "---\ndescription: Review\nsandbox: read-write\ncolor: blue\narguments:\n  - name: file\n    required: true\n    default: main.rs\n  - name: file\n---\nReview {{ file }} for {{ focus }}";
+        fs::write(tmp.path().join("review.md"), raw).unwrap();
+        let prompt = discover_prompts_in(tmp.path()).await.remove(0);
+        assert_eq!(
+            lint_prompt(&prompt, raw),
+            vec![
+                "unknown frontmatter key `color`".to_string(),
+                "invalid `sandbox` value `read-write`".to_string(),
+                "argument `file` is required but also has a default".to_string(),
+                "argument `file` is declared more than once".to_string(),
+                "template variable `focus` is not declared in `arguments:`".to_string(),
+            ]
+        );
+
+        let raw = "---\ndescription: Unclosed\nReview";
+        fs::write(tmp.path().join("review.md"), raw).unwrap();
+        let prompt = discover_prompts_in(tmp.path()).await.remove(0);
+        assert_eq!(
+            lint_prompt(&prompt, raw),
+            vec!["frontmatter is not closed by a `---` line".to_string()]
+        );
+
+        let raw = "---\ntemplate: jinja2\n---\nReview";
+        fs::write(tmp.path().join("review.md"), raw).unwrap();
+        let prompt = discover_prompts_in(tmp.path()).await.remove(0);
+        assert_eq!(
+            lint_prompt(&prompt, raw),
+            vec!["invalid `template` value `jinja2`".to_string()]
+        );
+
+        let raw = "---\ntemplate: jinja\n---\nReview {% if %}";
+        fs::write(tmp.path().join("review.md"), raw).unwrap();
+        let prompt = discover_prompts_in(tmp.path()).await.remove(0);
+        let issues = lint_prompt(&prompt, raw);
+        assert!(issues[0].starts_with("template error:"), "{issues:?}");
+
+        let raw = "---\ndescription: Fine\ntemplate: jinja\n---\nFix $ARGUMENTS";
+        fs::write(tmp.path().join("review.md"), raw).unwrap();
+        let prompt = discover_prompts_in(tmp.path()).await.remove(0);
+        assert!(lint_prompt(&prompt, raw).is_empty());
+    }
+
+    fn include_test_config(cwd: &Path, codex_home: &Path) -> Config {
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.to_path_buf(),
+        )
+        .expect("defaults for test should always succeed");
+        config.cwd = cwd.to_path_buf();
+        config.approval_policy = AskForApproval::Never;
+        config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+        config
+    }
+
+    #[tokio::test]
+    async fn expands_commands_and_files() {
+        let cwd = tempdir().expect("create TempDir");
+        let codex_home = tempdir().expect("create TempDir");
+        fs::write(cwd.path().join("notes.md"), "remember the milk\n").unwrap();
+        let config = include_test_config(cwd.path(), codex_home.path());
+
+        let content = "Status: !`echo hello && echo world`\nNotes: @notes.md.\nPing @someone\nmail me@example.com";
+        assert!(has_prompt_includes(content));
+        let expanded = expand_prompt_includes(content, &config).await.unwrap();
+        assert_eq!(
+            expanded,
+            "Status: hello\nworld\nNotes: remember the milk.\nPing @someone\nmail me@example.com"
+        );
+        assert!(!has_prompt_includes("no includes, just me@example.com"));
+    }
+
+    #[tokio::test]
+    async fn failing_or_unapproved_commands_are_errors() {
+        let cwd = tempdir().expect("create TempDir");
+        let codex_home = tempdir().expect("create TempDir");
+        let mut config = include_test_config(cwd.path(), codex_home.path());
+
+        let err = expand_prompt_includes("!`echo oops >&2; exit 3`", &config)
+            .await
+            .unwrap_err();
+        assert!(
+            err.starts_with("`echo oops >&2; exit 3` exited with code 3: ")
+                && err.ends_with("oops"),
+            "{err}"
+        );
+
+        config.approval_policy = AskForApproval::UnlessTrusted;
+        let err = expand_prompt_includes("!`touch file`", &config)
+            .await
+            .unwrap_err();
+        assert!(err.contains("needs approval"), "{err}");
+        assert!(!cwd.path().join("file").exists());
+    }
+
+    #[tokio::test]
+    async fn files_outside_the_workspace_or_denied_are_not_included() {
+        let outside = tempdir().expect("create TempDir");
+        let root = tempdir().expect("create TempDir");
+        let codex_home = tempdir().expect("create TempDir");
+        let cwd = root.path().join("repo");
+        fs::create_dir(&cwd).unwrap();
+        fs::write(root.path().join("secret.txt"), "parent secret").unwrap();
+        fs::write(outside.path().join("id_rsa"), "private key").unwrap();
+        fs::create_dir(cwd.join(".env.d")).unwrap();
+        fs::write(cwd.join(".env.d/token"), "token").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(outside.path().join("id_rsa"), cwd.join("key")).unwrap();
+        let mut config = include_test_config(&cwd, codex_home.path());
+        config.sandbox_policy = SandboxPolicy::ReadOnly {
+            read_deny: vec![PathBuf::from(".env.d")],
+        };
+
+        let absolute = format!("@{}", outside.path().join("id_rsa").display());
+        let mut tokens = vec![absolute.as_str(), "@../secret.txt", "@.env.d/token"];
+        if cfg!(unix) {
+            tokens.push("@key");
+        }
+        for token in tokens {
+            let err = expand_prompt_includes(token, &config).await.unwrap_err();
+            assert!(
+                err.contains("is outside the workspace") || err.contains("by `read_deny`"),
+                "{token}: {err}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn large_includes_are_truncated() {
+        let cwd = tempdir().expect("create TempDir");
+        let codex_home = tempdir().expect("create TempDir");
+        fs::write(
+            cwd.path().join("big.txt"),
+            "x".repeat(PROMPT_INCLUDE_MAX_BYTES * 2),
+        )
+        .unwrap();
+        let config = include_test_config(cwd.path(), codex_home.path());
+
+        let expanded = expand_prompt_includes("@big.txt", &config).await.unwrap();
+        assert!(expanded.len() < PROMPT_INCLUDE_MAX_BYTES + 64);
+        assert!(expanded.contains("tokens truncated"));
+    }
 }
@@ -6,6 +6,9 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitStatus;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -14,16 +17,22 @@ use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
+use tokio::sync::Notify;
 
+use crate::config_types::ExecLimits;
+use crate::config_types::LinuxSandbox;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
+use crate::exec_limits::detect_exceeded_limit;
+use crate::landlock::spawn_command_under_linux_namespaces;
 use crate::landlock::spawn_command_under_linux_sandbox;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecCommandOutputDeltaEvent;
 use crate::protocol::ExecOutputStream;
 use crate::protocol::SandboxPolicy;
+use crate::sandbox_denial::detect_sandbox_denial;
 use crate::seatbelt::spawn_command_under_seatbelt;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
@@ -53,6 +62,7 @@ pub struct ExecParams {
     pub env: HashMap<String, String>,
     pub with_escalated_permissions: Option<bool>,
     pub justification: Option<String>,
+    pub limits: ExecLimits,
 }
 
 impl ExecParams {
@@ -70,6 +80,22 @@ pub enum SandboxType {
 
     /// Only available on Linux.
     LinuxSeccomp,
+
+    /// Only available on Linux. Runs the command in unprivileged user, mount,
+    /// PID and network namespaces on top of the Landlock+seccomp sandbox, and
+    /// falls back to the latter when user namespaces are unavailable.
+    LinuxNamespaces,
+}
+
+impl SandboxType {
+    /// Applies the configured Linux sandbox mechanism to the sandbox type
+    /// picked for the current platform.
+    pub fn with_linux_sandbox(self, linux_sandbox: LinuxSandbox) -> Self {
+        match (self, linux_sandbox) {
+            (SandboxType::LinuxSeccomp, LinuxSandbox::Namespaces) => SandboxType::LinuxNamespaces,
+            (sandbox_type, _) => sandbox_type,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -90,78 +116,68 @@ pub async fn process_exec_tool_call(
     let start = Instant::now();
 
     let timeout_duration = params.timeout_duration();
+    let command_cwd = params.cwd.clone();
+    let limits = params.limits;
 
-    let raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr> = match sandbox_type
+    let raw_output_result = match spawn_exec_child(
+        params,
+        sandbox_type,
+        sandbox_policy,
+        sandbox_cwd,
+        codex_linux_sandbox_exe,
+        StdioPolicy::RedirectForShellTool,
+    )
+    .await
     {
-        SandboxType::None => exec(params, sandbox_policy, stdout_stream.clone()).await,
-        SandboxType::MacosSeatbelt => {
-            let ExecParams {
-                command,
-                cwd: command_cwd,
-                env,
-                ..
-            } = params;
-            let child = spawn_command_under_seatbelt(
-                command,
-                command_cwd,
-                sandbox_policy,
-                sandbox_cwd,
-                StdioPolicy::RedirectForShellTool,
-                env,
-            )
-            .await?;
-            consume_truncated_output(child, timeout_duration, stdout_stream.clone()).await
-        }
-        SandboxType::LinuxSeccomp => {
-            let ExecParams {
-                command,
-                cwd: command_cwd,
-                env,
-                ..
-            } = params;
-
-            let codex_linux_sandbox_exe = codex_linux_sandbox_exe
-                .as_ref()
-                .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
-            let child = spawn_command_under_linux_sandbox(
-                codex_linux_sandbox_exe,
-                command,
-                command_cwd,
-                sandbox_policy,
-                sandbox_cwd,
-                StdioPolicy::RedirectForShellTool,
-                env,
-            )
-            .await?;
-
-            consume_truncated_output(child, timeout_duration, stdout_stream).await
-        }
+        Ok(child) => consume_truncated_output(child, timeout_duration, stdout_stream, limits).await,
+        Err(err) => Err(err),
     };
     let duration = start.elapsed();
     match raw_output_result {
         Ok(raw_output) => {
-            #[allow(unused_mut)]
-            let mut timed_out = raw_output.timed_out;
-
             #[cfg(target_family = "unix")]
+            let signal = raw_output.exit_status.signal();
+            #[cfg(not(target_family = "unix"))]
+            let signal: Option<i32> = None;
+            let timed_out = raw_output.timed_out || signal == Some(TIMEOUT_CODE);
+
+            let stdout = raw_output.stdout.from_utf8_lossy();
+            let mut stderr = raw_output.stderr.from_utf8_lossy();
+            let mut aggregated_output = raw_output.aggregated_output.from_utf8_lossy();
+
+            let exceeded_limit = if timed_out {
+                None
+            } else {
+                detect_exceeded_limit(
+                    &limits,
+                    signal,
+                    raw_output.exit_status.code().unwrap_or(-1),
+                    raw_output.output_limit_exceeded,
+                    &aggregated_output.text,
+                )
+            };
+
+            if let Some(signal) = signal
+                && !timed_out
+                && exceeded_limit.is_none()
             {
-                if let Some(signal) = raw_output.exit_status.signal() {
-                    if signal == TIMEOUT_CODE {
-                        timed_out = true;
-                    } else {
-                        return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
-                    }
-                }
+                return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
             }
 
             let mut exit_code = raw_output.exit_status.code().unwrap_or(-1);
             if timed_out {
                 exit_code = EXEC_TIMEOUT_EXIT_CODE;
+            } else if let Some(signal) = signal {
+                exit_code = EXIT_CODE_SIGNAL_BASE + signal;
+            }
+
+            // Tell the model why the command was cut short.
+            if let Some(exceeded_limit) = exceeded_limit {
+                let note = format!("\n[codex] {exceeded_limit}\n");
+                stderr.text.push_str(&note);
+                aggregated_output.text.push_str(&note);
             }
 
-            let stdout = raw_output.stdout.from_utf8_lossy();
-            let stderr = raw_output.stderr.from_utf8_lossy();
-            let aggregated_output = raw_output.aggregated_output.from_utf8_lossy();
             let exec_output = ExecToolCallOutput {
                 exit_code,
                 stdout,
@@ -177,9 +193,19 @@ pub async fn process_exec_tool_call(
                 }));
             }
 
-            if exit_code != 0 && is_likely_sandbox_denied(sandbox_type, exit_code) {
+            if exit_code != 0
+                && exceeded_limit.is_none()
+                && is_likely_sandbox_denied(sandbox_type, exit_code)
+            {
+                let denial = detect_sandbox_denial(
+                    &exec_output.aggregated_output.text,
+                    sandbox_policy,
+                    sandbox_cwd,
+                    &command_cwd,
+                );
                 return Err(CodexErr::Sandbox(SandboxErr::Denied {
                     output: Box::new(exec_output),
+                    denial,
                 }));
             }
 
@@ -224,6 +250,9 @@ struct RawExecToolCallOutput {
     pub stderr: StreamOutput<Vec<u8>>,
     pub aggregated_output: StreamOutput<Vec<u8>>,
     pub timed_out: bool,
+    /// Whether the command was killed for exceeding
+    /// `exec_limits.max_output_bytes`.
+    pub output_limit_exceeded: bool,
 }
 
 impl StreamOutput<String> {
@@ -259,42 +288,101 @@ pub struct ExecToolCallOutput {
     pub timed_out: bool,
 }
 
-async fn exec(
+/// Spawns the command in `params` under `sandbox_type`, with stdout and stderr
+/// piped and stdin closed. Used for `shell` tool calls and, with
+/// [`StdioPolicy::RedirectForBackgroundProcess`], for background processes
+/// started with `bg_start`.
+pub(crate) async fn spawn_exec_child(
     params: ExecParams,
+    sandbox_type: SandboxType,
     sandbox_policy: &SandboxPolicy,
-    stdout_stream: Option<StdoutStream>,
-) -> Result<RawExecToolCallOutput> {
-    let timeout = params.timeout_duration();
+    sandbox_cwd: &Path,
+    codex_linux_sandbox_exe: &Option<PathBuf>,
+    stdio_policy: StdioPolicy,
+) -> Result<Child> {
     let ExecParams {
-        command, cwd, env, ..
+        command,
+        cwd: command_cwd,
+        env,
+        limits,
+        ..
     } = params;
 
-    let (program, args) = command.split_first().ok_or_else(|| {
-        CodexErr::Io(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "command args are empty",
-        ))
-    })?;
-    let arg0 = None;
-    let child = spawn_child_async(
-        PathBuf::from(program),
-        args.into(),
-        arg0,
-        cwd,
-        sandbox_policy,
-        StdioPolicy::RedirectForShellTool,
-        env,
-    )
-    .await?;
-    consume_truncated_output(child, timeout, stdout_stream).await
+    let child = match sandbox_type {
+        SandboxType::None => {
+            let (program, args) = command.split_first().ok_or_else(|| {
+                CodexErr::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "command args are empty",
+                ))
+            })?;
+            let arg0 = None;
+            spawn_child_async(
+                PathBuf::from(program),
+                args.into(),
+                arg0,
+                command_cwd,
+                sandbox_policy,
+                stdio_policy,
+                env,
+                limits,
+            )
+            .await?
+        }
+        SandboxType::MacosSeatbelt => {
+            spawn_command_under_seatbelt(
+                command,
+                command_cwd,
+                sandbox_policy,
+                sandbox_cwd,
+                stdio_policy,
+                env,
+                limits,
+            )
+            .await?
+        }
+        SandboxType::LinuxSeccomp | SandboxType::LinuxNamespaces => {
+            let codex_linux_sandbox_exe = codex_linux_sandbox_exe
+                .as_ref()
+                .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+            if sandbox_type == SandboxType::LinuxNamespaces {
+                spawn_command_under_linux_namespaces(
+                    codex_linux_sandbox_exe,
+                    command,
+                    command_cwd,
+                    sandbox_policy,
+                    sandbox_cwd,
+                    stdio_policy,
+                    env,
+                    limits,
+                )
+                .await?
+            } else {
+                spawn_command_under_linux_sandbox(
+                    codex_linux_sandbox_exe,
+                    command,
+                    command_cwd,
+                    sandbox_policy,
+                    sandbox_cwd,
+                    stdio_policy,
+                    env,
+                    limits,
+                )
+                .await?
+            }
+        }
+    };
+    Ok(child)
 }
 
 /// Consumes the output of a child process, truncating it so it is suitable for
-/// use as the output of a `shell` tool call. Also enforces specified timeout.
+/// use as the output of a `shell` tool call. Also enforces specified timeout
+/// and `exec_limits.max_output_bytes`.
 async fn consume_truncated_output(
     mut child: Child,
     timeout: Duration,
     stdout_stream: Option<StdoutStream>,
+    limits: ExecLimits,
 ) -> Result<RawExecToolCallOutput> {
     // Both stdout and stderr were configured with `Stdio::piped()`
     // above, therefore `take()` should normally return `Some`.  If it doesn't
@@ -312,18 +400,21 @@ async fn consume_truncated_output(
     })?;
 
     let (agg_tx, agg_rx) = async_channel::unbounded::<Vec<u8>>();
+    let output_budget = limits.max_output_bytes.map(OutputBudget::new);
 
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
         stdout_stream.clone(),
         false,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
     let stderr_handle = tokio::spawn(read_capped(
         BufReader::new(stderr_reader),
         stdout_stream.clone(),
         true,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
 
     let (exit_status, timed_out) = tokio::select! {
@@ -345,6 +436,10 @@ async fn consume_truncated_output(
             child.start_kill()?;
             (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
         }
+        _ = OutputBudget::exceeded(output_budget.as_ref()) => {
+            child.start_kill()?;
+            (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
+        }
     };
 
     let stdout = stdout_handle.await??;
@@ -367,26 +462,80 @@ async fn consume_truncated_output(
         stderr,
         aggregated_output,
         timed_out,
+        output_limit_exceeded: output_budget.is_some_and(|budget| budget.is_exceeded()),
     })
 }
 
+/// Number of output bytes a command may still write, shared by the readers of
+/// its stdout and stderr.
+#[derive(Clone)]
+struct OutputBudget {
+    max_bytes: u64,
+    used: Arc<AtomicU64>,
+    exceeded: Arc<Notify>,
+}
+
+impl OutputBudget {
+    fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            used: Arc::new(AtomicU64::new(0)),
+            exceeded: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Records `len` more bytes of output and returns how many of them fit
+    /// in the budget.
+    fn consume(&self, len: usize) -> usize {
+        let used = self.used.fetch_add(len as u64, Ordering::Relaxed);
+        let remaining = self.max_bytes.saturating_sub(used);
+        if remaining < len as u64 {
+            self.exceeded.notify_one();
+            remaining as usize
+        } else {
+            len
+        }
+    }
+
+    fn is_exceeded(&self) -> bool {
+        self.used.load(Ordering::Relaxed) > self.max_bytes
+    }
+
+    /// Resolves once `budget` is exceeded; never resolves without a budget.
+    async fn exceeded(budget: Option<&Self>) {
+        match budget {
+            Some(budget) => budget.exceeded.notified().await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
 async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     mut reader: R,
     stream: Option<StdoutStream>,
     is_stderr: bool,
     aggregate_tx: Option<Sender<Vec<u8>>>,
+    output_budget: Option<OutputBudget>,
 ) -> io::Result<StreamOutput<Vec<u8>>> {
     let mut buf = Vec::with_capacity(AGGREGATE_BUFFER_INITIAL_CAPACITY);
     let mut tmp = [0u8; READ_CHUNK_SIZE];
     let mut emitted_deltas: usize = 0;
 
-    // No caps: append all bytes
+    // No caps unless `exec_limits.max_output_bytes` is set: append all bytes
 
     loop {
         let n = reader.read(&mut tmp).await?;
         if n == 0 {
             break;
         }
+        let n = match &output_budget {
+            Some(budget) => budget.consume(n),
+            None => n,
+        };
+        if n == 0 {
+            // Over budget: the command is being killed, drop the rest.
+            continue;
+        }
 
         if let Some(stream) = &stream
             && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL
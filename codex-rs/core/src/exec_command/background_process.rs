@@ -0,0 +1,420 @@
+//! Long-running processes, such as dev servers and file watchers, started with
+//! the `bg_start` tool. Their output is kept in a [`ProcessLog`] that the model
+//! reads with `bg_logs`, and they run until stopped with `bg_stop` or until
+//! the session shuts down.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::MutexGuard;
+
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::process::Child;
+use tokio::sync::oneshot;
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use tokio::time::Instant;
+use tokio::time::timeout;
+
+use crate::protocol::BackgroundProcessInfo;
+
+/// Only the most recent output of a background process is kept.
+pub(crate) const MAX_LOG_BYTES: usize = 1024 * 1024;
+
+/// Time a process gets to exit after `SIGTERM` before it is killed.
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Time to wait for the rest of the output once the process has exited.
+/// Processes it started may keep the pipes open after it exits.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(100);
+
+const READ_CHUNK_SIZE: usize = 8192;
+
+/// Output of a background process, with stdout and stderr interleaved in the
+/// order they were read. Offsets count bytes from the start of the output,
+/// including bytes that were discarded to stay under the size limit.
+#[derive(Debug)]
+pub(crate) struct ProcessLog {
+    /// Offset of the first byte of `data`.
+    start_offset: u64,
+    data: Vec<u8>,
+    max_bytes: usize,
+}
+
+impl ProcessLog {
+    pub(crate) fn new(max_bytes: usize) -> Self {
+        Self {
+            start_offset: 0,
+            data: Vec::new(),
+            max_bytes,
+        }
+    }
+
+    pub(crate) fn append(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+        if self.data.len() > self.max_bytes {
+            let excess = self.data.len() - self.max_bytes;
+            self.data.drain(..excess);
+            self.start_offset += excess as u64;
+        }
+    }
+
+    /// Offset of the first byte still in the log.
+    pub(crate) fn start_offset(&self) -> u64 {
+        self.start_offset
+    }
+
+    /// Offset just past the last byte written so far.
+    pub(crate) fn end_offset(&self) -> u64 {
+        self.start_offset + self.data.len() as u64
+    }
+
+    /// Output written at or after `offset`, with the offset it actually starts
+    /// at, which is later than `offset` if that part was discarded.
+    pub(crate) fn since(&self, offset: u64) -> (u64, &[u8]) {
+        let start = offset.clamp(self.start_offset, self.end_offset());
+        let index = (start - self.start_offset) as usize;
+        (start, &self.data[index..])
+    }
+
+    /// The last `lines` lines of output, with the offset they start at.
+    pub(crate) fn tail(&self, lines: usize) -> (u64, &[u8]) {
+        // A trailing newline ends the last line rather than starting a new one.
+        let content = self.data.strip_suffix(b"\n").unwrap_or(&self.data);
+        let index = if lines == 0 {
+            self.data.len()
+        } else {
+            content
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(_, byte)| **byte == b'\n')
+                .nth(lines - 1)
+                .map_or(0, |(index, _)| index + 1)
+        };
+        (self.start_offset + index as u64, &self.data[index..])
+    }
+}
+
+/// A process started with `bg_start`. Dropping it kills the process and
+/// everything it started.
+#[derive(Debug)]
+pub(crate) struct BackgroundProcess {
+    command: Vec<String>,
+    cwd: PathBuf,
+    started_at: Instant,
+    log: Arc<StdMutex<ProcessLog>>,
+    /// Exit code once the process has exited (`128 + signal` if a signal
+    /// killed it).
+    exit_code: watch::Receiver<Option<i32>>,
+    stop_tx: StdMutex<Option<oneshot::Sender<()>>>,
+    /// Task that owns the child: waits for it to exit and stops it on request.
+    wait_handle: JoinHandle<()>,
+    /// Process group the child leads, if it was spawned with
+    /// `process_group(0)`.
+    process_group: Option<u32>,
+    reader_aborts: Vec<AbortHandle>,
+}
+
+impl BackgroundProcess {
+    /// Starts collecting the output of `child`, which must have been spawned
+    /// with stdout and stderr piped, and in a process group of its own
+    /// (`process_group(0)`), so the processes it starts can be stopped too.
+    pub(crate) fn new(mut child: Child, command: Vec<String>, cwd: PathBuf) -> Self {
+        let process_group = if cfg!(unix) { child.id() } else { None };
+        let log = Arc::new(StdMutex::new(ProcessLog::new(MAX_LOG_BYTES)));
+        let mut reader_handles = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            reader_handles.push(tokio::spawn(read_into_log(stdout, Arc::clone(&log))));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            reader_handles.push(tokio::spawn(read_into_log(stderr, Arc::clone(&log))));
+        }
+        let reader_aborts = reader_handles
+            .iter()
+            .map(JoinHandle::abort_handle)
+            .collect();
+
+        let (exit_tx, exit_code) = watch::channel(None);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let wait_handle = tokio::spawn(async move {
+            let status = tokio::select! {
+                status = child.wait() => status,
+                _ = stop_rx => stop_process_group(&mut child, process_group).await,
+            };
+            let _ = timeout(
+                OUTPUT_DRAIN_TIMEOUT,
+                futures::future::join_all(reader_handles),
+            )
+            .await;
+            let code = match status {
+                Ok(status) => exit_code_of(status),
+                Err(_) => -1,
+            };
+            let _ = exit_tx.send(Some(code));
+        });
+
+        Self {
+            command,
+            cwd,
+            started_at: Instant::now(),
+            log,
+            exit_code,
+            stop_tx: StdMutex::new(Some(stop_tx)),
+            wait_handle,
+            process_group,
+            reader_aborts,
+        }
+    }
+
+    pub(crate) fn log(&self) -> MutexGuard<'_, ProcessLog> {
+        self.log
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub(crate) fn exit_code(&self) -> Option<i32> {
+        *self.exit_code.borrow()
+    }
+
+    /// Waits up to `max_wait` for the process to exit and returns its exit
+    /// code if it did.
+    pub(crate) async fn wait_for_exit(&self, max_wait: Duration) -> Option<i32> {
+        let mut exit_code = self.exit_code.clone();
+        match timeout(max_wait, exit_code.wait_for(Option::is_some)).await {
+            Ok(Ok(code)) => *code,
+            _ => self.exit_code(),
+        }
+    }
+
+    /// Stops the process and everything it started, `SIGTERM` first and
+    /// `SIGKILL` after a grace period, and returns its exit code.
+    pub(crate) async fn stop(&self) -> Option<i32> {
+        let stop_tx = self
+            .stop_tx
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        if let Some(stop_tx) = stop_tx {
+            let _ = stop_tx.send(());
+        }
+        self.wait_for_exit(STOP_GRACE_PERIOD + Duration::from_secs(1))
+            .await
+    }
+
+    pub(crate) fn display_command(&self) -> String {
+        display_background_command(&self.command)
+    }
+
+    /// One-line status such as `running for 42s` or `exited with code 1`.
+    pub(crate) fn status(&self) -> String {
+        match self.exit_code() {
+            Some(code) => format!("exited with code {code}"),
+            None => format!("running for {}s", self.started_at.elapsed().as_secs()),
+        }
+    }
+
+    pub(crate) fn to_info(&self, id: u32) -> BackgroundProcessInfo {
+        BackgroundProcessInfo {
+            id,
+            command: self.display_command(),
+            cwd: self.cwd.clone(),
+            exit_code: self.exit_code(),
+            running_for_secs: self.started_at.elapsed().as_secs(),
+            output_bytes: self.log().end_offset(),
+        }
+    }
+}
+
+impl Drop for BackgroundProcess {
+    fn drop(&mut self) {
+        // The child is spawned with `kill_on_drop`, so aborting the task that
+        // owns it kills the process, but not the processes it started. Kill
+        // the group only while the child has not been reaped, as its id may
+        // be reused afterwards.
+        if self.exit_code().is_none()
+            && let Some(process_group) = self.process_group
+        {
+            kill_process_group(process_group, KillSignal::Kill);
+        }
+        self.wait_handle.abort();
+        for reader in &self.reader_aborts {
+            reader.abort();
+        }
+    }
+}
+
+/// The command for display: the script of a `bash -lc` invocation, or the
+/// shell-quoted command.
+pub(crate) fn display_background_command(command: &[String]) -> String {
+    match command {
+        [shell, flag, script] if (flag == "-lc" || flag == "-c") && shell.ends_with("sh") => {
+            script.clone()
+        }
+        command => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    }
+}
+
+async fn read_into_log<R: AsyncRead + Unpin>(mut reader: R, log: Arc<StdMutex<ProcessLog>>) {
+    let mut buf = [0u8; READ_CHUNK_SIZE];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => log
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .append(&buf[..n]),
+        }
+    }
+}
+
+/// Sends `SIGTERM` to the process group `child` leads, and `SIGKILL` to what
+/// is left of it once `child` has exited or the grace period is over.
+async fn stop_process_group(
+    child: &mut Child,
+    process_group: Option<u32>,
+) -> std::io::Result<std::process::ExitStatus> {
+    let Some(process_group) = process_group else {
+        child.start_kill()?;
+        return child.wait().await;
+    };
+    kill_process_group(process_group, KillSignal::Term);
+    let status = match timeout(STOP_GRACE_PERIOD, child.wait()).await {
+        Ok(status) => status,
+        Err(_) => {
+            kill_process_group(process_group, KillSignal::Kill);
+            child.wait().await
+        }
+    };
+    // Processes that ignored `SIGTERM` keep the group, and so its id, alive
+    // after the child is reaped.
+    kill_process_group(process_group, KillSignal::Kill);
+    status
+}
+
+#[derive(Clone, Copy)]
+enum KillSignal {
+    Term,
+    Kill,
+}
+
+#[cfg(unix)]
+fn kill_process_group(process_group: u32, signal: KillSignal) {
+    let signal = match signal {
+        KillSignal::Term => libc::SIGTERM,
+        KillSignal::Kill => libc::SIGKILL,
+    };
+    // SAFETY: `killpg` has no memory safety requirements.
+    unsafe {
+        libc::killpg(process_group as libc::pid_t, signal);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_process_group: u32, _signal: KillSignal) {}
+
+fn exit_code_of(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(-1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn process_log_keeps_offsets_when_discarding_output() {
+        let mut log = ProcessLog::new(8);
+        log.append(b"0123");
+        log.append(b"456789ab");
+
+        assert_eq!(4, log.start_offset());
+        assert_eq!(12, log.end_offset());
+        assert_eq!((4, b"456789ab".as_slice()), log.since(0));
+        assert_eq!((10, b"ab".as_slice()), log.since(10));
+        assert_eq!((12, b"".as_slice()), log.since(20));
+    }
+
+    #[test]
+    fn process_log_tail_returns_the_last_lines() {
+        let mut log = ProcessLog::new(MAX_LOG_BYTES);
+        log.append(b"one\ntwo\nthree\n");
+
+        assert_eq!((8, b"three\n".as_slice()), log.tail(1));
+        assert_eq!((4, b"two\nthree\n".as_slice()), log.tail(2));
+        assert_eq!((0, b"one\ntwo\nthree\n".as_slice()), log.tail(10));
+        assert_eq!((14, b"".as_slice()), log.tail(0));
+    }
+
+    #[cfg(unix)]
+    fn spawn_background_process(script: &str) -> BackgroundProcess {
+        let command = vec!["bash".to_string(), "-c".to_string(), script.to_string()];
+        let child = tokio::process::Command::new(&command[0])
+            .args(&command[1..])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        BackgroundProcess::new(child, command, PathBuf::from("/"))
+    }
+
+    /// Whether `pid` is gone, or only a zombie waiting for its new parent to
+    /// reap it.
+    #[cfg(target_os = "linux")]
+    fn is_dead(pid: &str) -> bool {
+        match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+            Ok(stat) => stat
+                .rsplit_once(')')
+                .is_some_and(|(_, rest)| rest.trim_start().starts_with('Z')),
+            Err(_) => true,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn background_process_collects_output_until_stopped() {
+        let process = spawn_background_process("echo started; exec sleep 30");
+
+        assert_eq!(
+            None,
+            process.wait_for_exit(Duration::from_millis(500)).await
+        );
+        assert_eq!(b"started\n".as_slice(), process.log().since(0).1);
+        assert_eq!("echo started; exec sleep 30", process.display_command());
+
+        assert_eq!(Some(128 + libc::SIGTERM), process.stop().await);
+        assert_eq!("exited with code 143", process.status());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn stopping_a_background_process_stops_what_it_started() {
+        // The grandchild ignores `SIGTERM`, so it also needs the `SIGKILL`.
+        let process = spawn_background_process("(trap '' TERM; exec sleep 30) & echo $!; wait");
+        let _ = process.wait_for_exit(Duration::from_millis(500)).await;
+        let output = String::from_utf8_lossy(process.log().since(0).1).to_string();
+        let grandchild = output.trim().to_string();
+        assert!(!is_dead(&grandchild), "grandchild {grandchild} not running");
+
+        assert_eq!(Some(128 + libc::SIGTERM), process.stop().await);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !is_dead(&grandchild) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(is_dead(&grandchild), "grandchild {grandchild} survived");
+    }
+}
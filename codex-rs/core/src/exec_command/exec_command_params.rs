@@ -55,3 +55,53 @@ fn write_stdin_default_yield_time_ms() -> u64 {
 fn write_stdin_default_max_output_tokens() -> u64 {
     10_000
 }
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BgStartParams {
+    pub(crate) command: Vec<String>,
+
+    #[serde(default)]
+    pub(crate) workdir: Option<String>,
+
+    #[serde(default)]
+    pub(crate) justification: Option<String>,
+
+    #[serde(default = "bg_start_default_yield_time_ms")]
+    pub(crate) yield_time_ms: u64,
+
+    #[serde(default = "bg_default_max_output_tokens")]
+    pub(crate) max_output_tokens: u64,
+}
+
+fn bg_start_default_yield_time_ms() -> u64 {
+    2_000
+}
+
+fn bg_default_max_output_tokens() -> u64 {
+    10_000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BgLogsParams {
+    pub(crate) id: SessionId,
+
+    /// Return the output written at or after this byte offset instead of the
+    /// last `tail_lines` lines.
+    #[serde(default)]
+    pub(crate) since_offset: Option<u64>,
+
+    #[serde(default = "bg_logs_default_tail_lines")]
+    pub(crate) tail_lines: usize,
+
+    #[serde(default = "bg_default_max_output_tokens")]
+    pub(crate) max_output_tokens: u64,
+}
+
+fn bg_logs_default_tail_lines() -> usize {
+    100
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BgStopParams {
+    pub(crate) id: SessionId,
+}
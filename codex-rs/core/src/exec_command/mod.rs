@@ -1,14 +1,27 @@
+mod background_process;
 mod exec_command_params;
 mod exec_command_session;
 mod responses_api;
 mod session_id;
 mod session_manager;
 
+pub(crate) use background_process::display_background_command;
+pub use exec_command_params::BgLogsParams;
+pub use exec_command_params::BgStartParams;
+pub use exec_command_params::BgStopParams;
 pub use exec_command_params::ExecCommandParams;
 pub use exec_command_params::WriteStdinParams;
 pub(crate) use exec_command_session::ExecCommandSession;
+pub use responses_api::BG_LOGS_TOOL_NAME;
+pub use responses_api::BG_START_TOOL_NAME;
+pub use responses_api::BG_STOP_TOOL_NAME;
 pub use responses_api::EXEC_COMMAND_TOOL_NAME;
 pub use responses_api::WRITE_STDIN_TOOL_NAME;
+pub use responses_api::create_bg_logs_tool_for_responses_api;
+pub use responses_api::create_bg_start_tool_for_responses_api;
+pub use responses_api::create_bg_stop_tool_for_responses_api;
 pub use responses_api::create_exec_command_tool_for_responses_api;
 pub use responses_api::create_write_stdin_tool_for_responses_api;
+pub(crate) use session_id::SessionId as ExecSessionId;
+pub(crate) use session_manager::ExecCommandOutput;
 pub use session_manager::SessionManager as ExecSessionManager;
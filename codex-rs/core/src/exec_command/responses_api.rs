@@ -5,6 +5,9 @@ use crate::openai_tools::ResponsesApiTool;
 
 pub const EXEC_COMMAND_TOOL_NAME: &str = "exec_command";
 pub const WRITE_STDIN_TOOL_NAME: &str = "write_stdin";
+pub const BG_START_TOOL_NAME: &str = "bg_start";
+pub const BG_LOGS_TOOL_NAME: &str = "bg_logs";
+pub const BG_STOP_TOOL_NAME: &str = "bg_stop";
 
 pub fn create_exec_command_tool_for_responses_api() -> ResponsesApiTool {
     let mut properties = BTreeMap::<String, JsonSchema>::new();
@@ -96,3 +99,125 @@ Can write control characters (\u0003 for Ctrl-C), or an empty string to just pol
         },
     }
 }
+
+pub fn create_bg_start_tool_for_responses_api() -> ResponsesApiTool {
+    let mut properties = BTreeMap::<String, JsonSchema>::new();
+    properties.insert(
+        "command".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some("The command to run in the background.".to_string()),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to run the command in.".to_string()),
+        },
+    );
+    properties.insert(
+        "justification".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Why the process is needed, shown to the user if they are asked to approve it."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "yield_time_ms".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "How long to wait for initial output before returning. Defaults to 2000."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "max_output_tokens".to_string(),
+        JsonSchema::Number {
+            description: Some("The maximum number of tokens to output.".to_string()),
+        },
+    );
+
+    ResponsesApiTool {
+        name: BG_START_TOOL_NAME.to_owned(),
+        description: r#"Start a long-running process, such as a dev server or a file watcher, and keep it running in the background while you continue working.
+Returns the process id and the output written within yield_time_ms. Read later output with bg_logs and stop the process with bg_stop when you no longer need it."#
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["command".to_string()]),
+            additional_properties: Some(false),
+        },
+    }
+}
+
+pub fn create_bg_logs_tool_for_responses_api() -> ResponsesApiTool {
+    let mut properties = BTreeMap::<String, JsonSchema>::new();
+    properties.insert(
+        "id".to_string(),
+        JsonSchema::Number {
+            description: Some("The id of the background process.".to_string()),
+        },
+    );
+    properties.insert(
+        "since_offset".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Only return output written at or after this byte offset, as reported by a previous bg_start or bg_logs call."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "tail_lines".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Without since_offset, the number of most recent lines to return. Defaults to 100."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "max_output_tokens".to_string(),
+        JsonSchema::Number {
+            description: Some("The maximum number of tokens to output.".to_string()),
+        },
+    );
+
+    ResponsesApiTool {
+        name: BG_LOGS_TOOL_NAME.to_owned(),
+        description: "Read the combined stdout and stderr of a background process started with bg_start, and whether it is still running."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["id".to_string()]),
+            additional_properties: Some(false),
+        },
+    }
+}
+
+pub fn create_bg_stop_tool_for_responses_api() -> ResponsesApiTool {
+    let mut properties = BTreeMap::<String, JsonSchema>::new();
+    properties.insert(
+        "id".to_string(),
+        JsonSchema::Number {
+            description: Some("The id of the background process.".to_string()),
+        },
+    );
+
+    ResponsesApiTool {
+        name: BG_STOP_TOOL_NAME.to_owned(),
+        description: "Stop a background process started with bg_start and return its last output."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["id".to_string()]),
+            additional_properties: Some(false),
+        },
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::io::Read;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
 use std::sync::atomic::AtomicBool;
@@ -9,6 +10,7 @@ use std::sync::atomic::AtomicU32;
 use portable_pty::CommandBuilder;
 use portable_pty::PtySize;
 use portable_pty::native_pty_system;
+use tokio::process::Child;
 use tokio::sync::Mutex;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
@@ -16,16 +18,28 @@ use tokio::time::Duration;
 use tokio::time::Instant;
 use tokio::time::timeout;
 
+use crate::config_types::ExecLimits;
+use crate::exec_command::background_process::BackgroundProcess;
+use crate::exec_command::background_process::MAX_LOG_BYTES;
+use crate::exec_command::exec_command_params::BgLogsParams;
 use crate::exec_command::exec_command_params::ExecCommandParams;
 use crate::exec_command::exec_command_params::WriteStdinParams;
 use crate::exec_command::exec_command_session::ExecCommandSession;
 use crate::exec_command::session_id::SessionId;
+use crate::exec_limits::command_with_limits;
+use crate::output_artifacts::saved_output_note;
+use crate::protocol::BackgroundProcessInfo;
 use crate::truncate::truncate_middle;
 
+/// Lines of output returned when a background process is stopped.
+const BG_STOP_TAIL_LINES: usize = 50;
+
 #[derive(Debug, Default)]
 pub struct SessionManager {
     next_session_id: AtomicU32,
     sessions: Mutex<HashMap<SessionId, ExecCommandSession>>,
+    /// Processes started with `bg_start`. They share ids with `sessions`.
+    background_processes: Mutex<HashMap<SessionId, BackgroundProcess>>,
 }
 
 #[derive(Debug)]
@@ -34,6 +48,10 @@ pub struct ExecCommandOutput {
     exit_status: ExitStatus,
     original_token_count: Option<u64>,
     output: String,
+    /// The output before truncation, if it was truncated.
+    full_output: Option<String>,
+    /// `read_output` handle of the saved full output.
+    full_output_handle: Option<String>,
 }
 
 impl ExecCommandOutput {
@@ -45,11 +63,15 @@ impl ExecCommandOutput {
                 format!("Process running with session ID {}", session_id.0)
             }
         };
-        let truncation_status = match self.original_token_count {
-            Some(tokens) => {
+        let truncation_status = match (self.original_token_count, &self.full_output_handle) {
+            (Some(tokens), Some(handle)) => format!(
+                "\nWarning: truncated output (original token count: {tokens}); {}",
+                saved_output_note(handle)
+            ),
+            (Some(tokens), None) => {
                 format!("\nWarning: truncated output (original token count: {tokens})")
             }
-            None => "".to_string(),
+            (None, _) => "".to_string(),
         };
         format!(
             r#"Wall time: {wall_time_secs:.3} seconds
@@ -59,6 +81,14 @@ Output:
             output = self.output
         )
     }
+
+    pub(crate) fn full_output(&self) -> Option<&str> {
+        self.full_output.as_deref()
+    }
+
+    pub(crate) fn set_full_output_handle(&mut self, handle: String) {
+        self.full_output_handle = Some(handle);
+    }
 }
 
 #[derive(Debug)]
@@ -72,6 +102,7 @@ impl SessionManager {
     pub async fn handle_exec_command_request(
         &self,
         params: ExecCommandParams,
+        limits: ExecLimits,
     ) -> Result<ExecCommandOutput, String> {
         // Allocate a session id.
         let session_id = SessionId(
@@ -79,14 +110,15 @@ impl SessionManager {
                 .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
         );
 
-        let (session, mut output_rx, mut exit_rx) = create_exec_command_session(params.clone())
-            .await
-            .map_err(|err| {
-                format!(
-                    "failed to create exec command session for session id {}: {err}",
-                    session_id.0
-                )
-            })?;
+        let (session, mut output_rx, mut exit_rx) =
+            create_exec_command_session(params.clone(), limits)
+                .await
+                .map_err(|err| {
+                    format!(
+                        "failed to create exec command session for session id {}: {err}",
+                        session_id.0
+                    )
+                })?;
 
         // Insert into session map.
         self.sessions.lock().await.insert(session_id, session);
@@ -152,12 +184,14 @@ impl SessionManager {
         };
 
         // If output exceeds cap, truncate the middle and record original token estimate.
-        let (output, original_token_count) = truncate_middle(&output, cap_bytes);
+        let (truncated_output, original_token_count) = truncate_middle(&output, cap_bytes);
         Ok(ExecCommandOutput {
             wall_time: Instant::now().duration_since(start_time),
             exit_status,
             original_token_count,
-            output,
+            output: truncated_output,
+            full_output: original_token_count.map(|_| output),
+            full_output_handle: None,
         })
     }
 
@@ -216,19 +250,156 @@ impl SessionManager {
         let output = String::from_utf8_lossy(&collected).to_string();
         let cap_bytes_u64 = max_output_tokens.saturating_mul(4);
         let cap_bytes: usize = cap_bytes_u64.min(usize::MAX as u64) as usize;
-        let (output, original_token_count) = truncate_middle(&output, cap_bytes);
+        let (truncated_output, original_token_count) = truncate_middle(&output, cap_bytes);
         Ok(ExecCommandOutput {
             wall_time: Instant::now().duration_since(start_time),
             exit_status: ExitStatus::Ongoing(session_id),
             original_token_count,
-            output,
+            output: truncated_output,
+            full_output: original_token_count.map(|_| output),
+            full_output_handle: None,
         })
     }
 }
 
+impl SessionManager {
+    /// Tracks `child`, spawned for a `bg_start` call, as a background process
+    /// and returns its output within `yield_time_ms` for the model.
+    pub(crate) async fn start_background_process(
+        &self,
+        child: Child,
+        command: Vec<String>,
+        cwd: PathBuf,
+        yield_time_ms: u64,
+        max_output_tokens: u64,
+    ) -> String {
+        let id = SessionId(
+            self.next_session_id
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
+        );
+        let process = BackgroundProcess::new(child, command, cwd);
+        process
+            .wait_for_exit(Duration::from_millis(yield_time_ms))
+            .await;
+        let output = background_output_text(id, &process, Some(0), 0, max_output_tokens);
+        self.background_processes.lock().await.insert(id, process);
+        format!("Started background process {}.\n{output}", id.0)
+    }
+
+    /// Returns the output of a background process for a `bg_logs` call.
+    pub(crate) async fn background_process_logs(
+        &self,
+        params: BgLogsParams,
+    ) -> Result<String, String> {
+        let BgLogsParams {
+            id,
+            since_offset,
+            tail_lines,
+            max_output_tokens,
+        } = params;
+        let processes = self.background_processes.lock().await;
+        let process = processes
+            .get(&id)
+            .ok_or_else(|| format!("unknown background process id {}", id.0))?;
+        Ok(background_output_text(
+            id,
+            process,
+            since_offset,
+            tail_lines,
+            max_output_tokens,
+        ))
+    }
+
+    /// Stops a background process and stops tracking it. Returns its last
+    /// output.
+    pub(crate) async fn stop_background_process(&self, id: SessionId) -> Result<String, String> {
+        let process = self
+            .background_processes
+            .lock()
+            .await
+            .remove(&id)
+            .ok_or_else(|| format!("unknown background process id {}", id.0))?;
+        process.stop().await;
+        Ok(background_output_text(
+            id,
+            &process,
+            None,
+            BG_STOP_TAIL_LINES,
+            u64::MAX,
+        ))
+    }
+
+    pub(crate) async fn list_background_processes(&self) -> Vec<BackgroundProcessInfo> {
+        let processes = self.background_processes.lock().await;
+        let mut infos: Vec<BackgroundProcessInfo> = processes
+            .iter()
+            .map(|(id, process)| process.to_info(id.0))
+            .collect();
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+
+    /// Stops every background process, e.g. when the session shuts down.
+    pub(crate) async fn stop_all_background_processes(&self) {
+        let processes: Vec<BackgroundProcess> = self
+            .background_processes
+            .lock()
+            .await
+            .drain()
+            .map(|(_, process)| process)
+            .collect();
+        futures::future::join_all(processes.iter().map(BackgroundProcess::stop)).await;
+    }
+}
+
+/// Formats the output of a background process for the model: the output
+/// written since `since_offset`, or the last `tail_lines` lines without it.
+fn background_output_text(
+    id: SessionId,
+    process: &BackgroundProcess,
+    since_offset: Option<u64>,
+    tail_lines: usize,
+    max_output_tokens: u64,
+) -> String {
+    let log = process.log();
+    let (start, output) = match since_offset {
+        Some(offset) => log.since(offset),
+        None => log.tail(tail_lines),
+    };
+    let end = log.end_offset();
+    let cap_bytes = max_output_tokens.saturating_mul(4).min(usize::MAX as u64) as usize;
+    let (output, original_token_count) =
+        truncate_middle(&String::from_utf8_lossy(output), cap_bytes);
+
+    let mut text = format!(
+        "Process {} ({}): {}\n",
+        id.0,
+        process.display_command(),
+        process.status()
+    );
+    if let Some(offset) = since_offset
+        && offset < log.start_offset()
+    {
+        text.push_str(&format!(
+            "Note: output before byte {} was discarded; only the last {MAX_LOG_BYTES} bytes are kept\n",
+            log.start_offset(),
+        ));
+    }
+    if let Some(tokens) = original_token_count {
+        text.push_str(&format!(
+            "Warning: truncated output (original token count: {tokens})\n"
+        ));
+    }
+    text.push_str(&format!(
+        "Output (bytes {start}-{end}; pass since_offset={end} to bg_logs for newer output):\n{output}"
+    ));
+    text
+}
+
 /// Spawn PTY and child process per spawn_exec_command_session logic.
 async fn create_exec_command_session(
     params: ExecCommandParams,
+    limits: ExecLimits,
 ) -> anyhow::Result<(
     ExecCommandSession,
     tokio::sync::broadcast::Receiver<Vec<u8>>,
@@ -254,10 +425,10 @@ async fn create_exec_command_session(
     })?;
 
     // Spawn a shell into the pty
-    let mut command_builder = CommandBuilder::new(shell);
     let shell_mode_opt = if login { "-lc" } else { "-c" };
-    command_builder.arg(shell_mode_opt);
-    command_builder.arg(cmd);
+    let command = command_with_limits(vec![shell, shell_mode_opt.to_string(), cmd], &limits)?;
+    let mut command_builder = CommandBuilder::new(&command[0]);
+    command_builder.args(&command[1..]);
 
     let mut child = pair.slave.spawn_command(command_builder)?;
     // Obtain a killer that can signal the process independently of `.wait()`.
@@ -380,7 +551,7 @@ PY"#
             login: false,
         };
         let initial_output = match session_manager
-            .handle_exec_command_request(params.clone())
+            .handle_exec_command_request(params.clone(), ExecLimits::default())
             .await
         {
             Ok(v) => v,
@@ -478,6 +649,8 @@ PY"#
             exit_status: ExitStatus::Exited(0),
             original_token_count: None,
             output: "hello".to_string(),
+            full_output: None,
+            full_output_handle: None,
         };
         let text = out.to_text_output();
         let expected = r#"Wall time: 1.234 seconds
@@ -494,12 +667,34 @@ hello"#;
             exit_status: ExitStatus::Ongoing(SessionId(42)),
             original_token_count: Some(1000),
             output: "abc".to_string(),
+            full_output: Some("abcdef".to_string()),
+            full_output_handle: None,
         };
         let text = out.to_text_output();
         let expected = r#"Wall time: 0.500 seconds
 Process running with session ID 42
 Warning: truncated output (original token count: 1000)
 Output:
+abc"#;
+        assert_eq!(expected, text);
+    }
+
+    #[test]
+    fn to_text_output_points_to_saved_full_output() {
+        let mut out = ExecCommandOutput {
+            wall_time: Duration::from_millis(500),
+            exit_status: ExitStatus::Exited(1),
+            original_token_count: Some(1000),
+            output: "abc".to_string(),
+            full_output: Some("abcdef".to_string()),
+            full_output_handle: None,
+        };
+        out.set_full_output_handle("output-3".to_string());
+        let text = out.to_text_output();
+        let expected = r#"Wall time: 0.500 seconds
+Process exited with code 1
+Warning: truncated output (original token count: 1000); full output saved as output-3, use read_output to page or search it
+Output:
 abc"#;
         assert_eq!(expected, text);
     }
@@ -0,0 +1,456 @@
+//! Enforcement of [`ExecLimits`] for the commands the agent runs.
+//!
+//! Memory, CPU time, process and open file limits are applied with
+//! `setrlimit(2)` in the child before it execs, so they are inherited by the
+//! sandbox helpers and everything the command spawns. Commands that run in a
+//! PTY (`exec_command` and unified exec sessions) are spawned by
+//! `portable_pty`, which has no `pre_exec` hook, so they are started through
+//! the codex executable with [`CODEX_RUN_WITH_LIMITS_ARG1`] instead, see
+//! [`command_with_limits`]. The output limit is enforced while reading the
+//! command's output, see [`crate::exec::process_exec_tool_call`].
+//!
+//! These are rlimits, with their usual caveats:
+//!
+//! - `RLIMIT_AS` limits the address space of each process, not the memory it
+//!   uses. Runtimes that reserve large ranges up front (the JVM, Go, programs
+//!   built with AddressSanitizer) fail to start under a limit far below what
+//!   they would actually use.
+//! - `RLIMIT_NPROC` is checked against all the processes of the user's UID,
+//!   including those outside the command, so a command can hit it when the
+//!   user already runs many processes.
+//!
+//! The kernel does not say which limit made a command fail, so
+//! [`detect_exceeded_limit`] infers it from the exit status and from the error
+//! messages commands print when an allocation, `fork(2)` or `open(2)` fails.
+
+#[cfg(unix)]
+use std::ffi::OsString;
+use std::fmt;
+
+use crate::config_types::ExecLimits;
+
+/// When `argv[1]` of the codex executable is this, it applies the limits
+/// that follow (as `name=value` arguments) to itself and execs the command
+/// after `--`, see [`exec_with_limits`].
+pub const CODEX_RUN_WITH_LIMITS_ARG1: &str = "--codex-run-with-limits";
+
+#[cfg(unix)]
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+/// Messages (lowercased) printed when a process runs out of memory.
+const OUT_OF_MEMORY_MESSAGES: &[&str] = &[
+    "cannot allocate memory",
+    "out of memory",
+    "memoryerror",
+    "memory allocation of",
+    "failed to allocate",
+    "std::bad_alloc",
+];
+
+/// Message (lowercased) for `EAGAIN`, which `fork(2)` returns when the process
+/// limit is reached.
+const PROCESS_LIMIT_MESSAGE: &str = "resource temporarily unavailable";
+
+/// Message (lowercased) for `EMFILE`.
+const OPEN_FILE_LIMIT_MESSAGE: &str = "too many open files";
+
+/// A limit from [`ExecLimits`] that a command exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExecLimitExceeded {
+    Memory { max_memory_mb: u64 },
+    CpuTime { max_cpu_seconds: u64 },
+    Processes { max_processes: u64 },
+    OpenFiles { max_open_files: u64 },
+    Output { max_output_bytes: u64 },
+}
+
+impl fmt::Display for ExecLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecLimitExceeded::Memory { max_memory_mb } => write!(
+                f,
+                "command ran out of memory: each process is limited to {max_memory_mb} MB of address space (exec_limits.max_memory_mb)"
+            ),
+            ExecLimitExceeded::CpuTime { max_cpu_seconds } => write!(
+                f,
+                "command was killed after using {max_cpu_seconds} seconds of CPU time (exec_limits.max_cpu_seconds)"
+            ),
+            ExecLimitExceeded::Processes { max_processes } => write!(
+                f,
+                "command could not start more processes: your user is limited to {max_processes} processes in total (exec_limits.max_processes)"
+            ),
+            ExecLimitExceeded::OpenFiles { max_open_files } => write!(
+                f,
+                "command could not open more files: each process is limited to {max_open_files} (exec_limits.max_open_files)"
+            ),
+            ExecLimitExceeded::Output { max_output_bytes } => write!(
+                f,
+                "command was killed after writing more than {max_output_bytes} bytes of output (exec_limits.max_output_bytes)"
+            ),
+        }
+    }
+}
+
+/// Applies the rlimits in `limits` to the current process. Meant to be called
+/// from a `pre_exec` hook, so it only uses async-signal-safe functions.
+#[cfg(unix)]
+pub(crate) fn set_resource_limits(limits: &ExecLimits) -> std::io::Result<()> {
+    if let Some(max_memory_mb) = limits.max_memory_mb {
+        let max_bytes = max_memory_mb.saturating_mul(BYTES_PER_MB);
+        set_rlimit(libc::RLIMIT_AS, max_bytes, max_bytes)?;
+    }
+    if let Some(max_cpu_seconds) = limits.max_cpu_seconds {
+        // The soft limit sends SIGXCPU, which we recognize as this limit being
+        // exceeded. The hard limit sends SIGKILL to processes that ignore it.
+        set_rlimit(
+            libc::RLIMIT_CPU,
+            max_cpu_seconds,
+            max_cpu_seconds.saturating_add(1),
+        )?;
+    }
+    if let Some(max_processes) = limits.max_processes {
+        set_rlimit(libc::RLIMIT_NPROC, max_processes, max_processes)?;
+    }
+    if let Some(max_open_files) = limits.max_open_files {
+        set_rlimit(libc::RLIMIT_NOFILE, max_open_files, max_open_files)?;
+    }
+    Ok(())
+}
+
+/// Returns `command` wrapped so that it runs with the rlimits in `limits`,
+/// for commands spawned without a `pre_exec` hook. `command` is returned as is
+/// when no rlimit is set.
+pub(crate) fn command_with_limits(
+    command: Vec<String>,
+    limits: &ExecLimits,
+) -> std::io::Result<Vec<String>> {
+    let limit_args = limit_args(limits);
+    if cfg!(not(unix)) || limit_args.is_empty() {
+        return Ok(command);
+    }
+    let codex = std::env::current_exe()?;
+    let mut wrapped = vec![
+        codex.to_string_lossy().to_string(),
+        CODEX_RUN_WITH_LIMITS_ARG1.to_string(),
+    ];
+    wrapped.extend(limit_args);
+    wrapped.push("--".to_string());
+    wrapped.extend(command);
+    Ok(wrapped)
+}
+
+/// Applies the limits in `args`, the arguments after
+/// [`CODEX_RUN_WITH_LIMITS_ARG1`], and execs the command after `--`. Only
+/// returns on failure.
+#[cfg(unix)]
+pub fn exec_with_limits(args: &[OsString]) -> std::io::Error {
+    use std::os::unix::process::CommandExt;
+
+    let Some(separator) = args.iter().position(|arg| arg == "--") else {
+        return invalid_limit_args("missing `--` before the command");
+    };
+    let limits = match parse_limit_args(&args[..separator]) {
+        Ok(limits) => limits,
+        Err(err) => return err,
+    };
+    let Some((program, command_args)) = args[separator + 1..].split_first() else {
+        return invalid_limit_args("missing command after `--`");
+    };
+    if let Err(err) = set_resource_limits(&limits) {
+        return err;
+    }
+    std::process::Command::new(program)
+        .args(command_args)
+        .exec()
+}
+
+/// The rlimits in `limits` as `name=value` arguments, named like the
+/// `exec_limits` config keys.
+fn limit_args(limits: &ExecLimits) -> Vec<String> {
+    [
+        ("max_memory_mb", limits.max_memory_mb),
+        ("max_cpu_seconds", limits.max_cpu_seconds),
+        ("max_processes", limits.max_processes),
+        ("max_open_files", limits.max_open_files),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|value| format!("{name}={value}")))
+    .collect()
+}
+
+#[cfg(unix)]
+fn parse_limit_args(args: &[OsString]) -> std::io::Result<ExecLimits> {
+    let mut limits = ExecLimits::default();
+    for arg in args {
+        let invalid = || invalid_limit_args(&format!("invalid limit {}", arg.to_string_lossy()));
+        let (name, value) = arg
+            .to_str()
+            .and_then(|arg| arg.split_once('='))
+            .ok_or_else(invalid)?;
+        let value = value.parse::<u64>().map_err(|_| invalid())?;
+        let limit = match name {
+            "max_memory_mb" => &mut limits.max_memory_mb,
+            "max_cpu_seconds" => &mut limits.max_cpu_seconds,
+            "max_processes" => &mut limits.max_processes,
+            "max_open_files" => &mut limits.max_open_files,
+            _ => return Err(invalid()),
+        };
+        *limit = Some(value);
+    }
+    Ok(limits)
+}
+
+#[cfg(unix)]
+fn invalid_limit_args(message: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{CODEX_RUN_WITH_LIMITS_ARG1}: {message}"),
+    )
+}
+
+/// Lowers the soft and hard limits of `resource`. Limits above the current
+/// hard limit cannot be raised by an unprivileged process, so they are capped
+/// to it.
+#[cfg(unix)]
+fn set_rlimit(resource: RlimitResource, soft: u64, hard: u64) -> std::io::Result<()> {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `current` is a valid, writable `rlimit`.
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let hard = hard.min(current.rlim_max);
+    let limit = libc::rlimit {
+        rlim_cur: soft.min(hard),
+        rlim_max: hard,
+    };
+    // SAFETY: `limit` is a valid `rlimit`.
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Returns the limit that most likely made a command fail. `signal` is the
+/// signal that killed the command, if any, and `output` its combined output.
+pub(crate) fn detect_exceeded_limit(
+    limits: &ExecLimits,
+    signal: Option<i32>,
+    exit_code: i32,
+    output_limit_exceeded: bool,
+    output: &str,
+) -> Option<ExecLimitExceeded> {
+    if output_limit_exceeded && let Some(max_output_bytes) = limits.max_output_bytes {
+        return Some(ExecLimitExceeded::Output { max_output_bytes });
+    }
+    if exit_code == 0 && signal.is_none() {
+        return None;
+    }
+
+    #[cfg(unix)]
+    if let Some(max_cpu_seconds) = limits.max_cpu_seconds
+        && (signal == Some(libc::SIGXCPU) || exit_code == 128 + libc::SIGXCPU)
+    {
+        return Some(ExecLimitExceeded::CpuTime { max_cpu_seconds });
+    }
+
+    let output = output.to_lowercase();
+    if let Some(max_memory_mb) = limits.max_memory_mb
+        && OUT_OF_MEMORY_MESSAGES
+            .iter()
+            .any(|message| output.contains(message))
+    {
+        return Some(ExecLimitExceeded::Memory { max_memory_mb });
+    }
+    if let Some(max_processes) = limits.max_processes
+        && output.contains(PROCESS_LIMIT_MESSAGE)
+    {
+        return Some(ExecLimitExceeded::Processes { max_processes });
+    }
+    if let Some(max_open_files) = limits.max_open_files
+        && output.contains(OPEN_FILE_LIMIT_MESSAGE)
+    {
+        return Some(ExecLimitExceeded::OpenFiles { max_open_files });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detects_the_output_limit_first() {
+        let limits = ExecLimits {
+            max_output_bytes: Some(1024),
+            max_memory_mb: Some(512),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some(ExecLimitExceeded::Output {
+                max_output_bytes: 1024
+            }),
+            detect_exceeded_limit(&limits, Some(9), -1, true, "out of memory")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn detects_the_cpu_limit_from_sigxcpu() {
+        let limits = ExecLimits {
+            max_cpu_seconds: Some(10),
+            ..Default::default()
+        };
+        let cpu_time = Some(ExecLimitExceeded::CpuTime {
+            max_cpu_seconds: 10,
+        });
+
+        assert_eq!(
+            cpu_time,
+            detect_exceeded_limit(&limits, Some(libc::SIGXCPU), -1, false, "")
+        );
+        // A shell reports the signal that killed its child as 128 + signal.
+        assert_eq!(
+            cpu_time,
+            detect_exceeded_limit(&limits, None, 128 + libc::SIGXCPU, false, "")
+        );
+        assert_eq!(
+            None,
+            detect_exceeded_limit(&ExecLimits::default(), Some(libc::SIGXCPU), -1, false, "")
+        );
+    }
+
+    #[test]
+    fn detects_limits_from_error_messages() {
+        let limits = ExecLimits {
+            max_memory_mb: Some(512),
+            max_processes: Some(64),
+            max_open_files: Some(256),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some(ExecLimitExceeded::Memory { max_memory_mb: 512 }),
+            detect_exceeded_limit(
+                &limits,
+                None,
+                1,
+                false,
+                "Traceback (most recent call last):\nMemoryError"
+            )
+        );
+        assert_eq!(
+            Some(ExecLimitExceeded::Processes { max_processes: 64 }),
+            detect_exceeded_limit(
+                &limits,
+                None,
+                254,
+                false,
+                "bash: fork: retry: Resource temporarily unavailable"
+            )
+        );
+        assert_eq!(
+            Some(ExecLimitExceeded::OpenFiles {
+                max_open_files: 256
+            }),
+            detect_exceeded_limit(
+                &limits,
+                None,
+                1,
+                false,
+                "error: Too many open files (os error 24)"
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn limit_args_round_trip() {
+        let limits = ExecLimits {
+            max_memory_mb: Some(512),
+            max_processes: Some(64),
+            max_output_bytes: Some(1024),
+            ..Default::default()
+        };
+        let args = limit_args(&limits);
+        assert_eq!(vec!["max_memory_mb=512", "max_processes=64"], args);
+
+        let parsed = parse_limit_args(&args.into_iter().map(OsString::from).collect::<Vec<_>>())
+            .expect("parse limit args");
+        assert_eq!(
+            ExecLimits {
+                max_output_bytes: None,
+                ..limits
+            },
+            parsed
+        );
+        assert!(parse_limit_args(&[OsString::from("max_memory_mb=lots")]).is_err());
+        assert!(parse_limit_args(&[OsString::from("max_output_bytes=1")]).is_err());
+    }
+
+    #[test]
+    fn commands_without_rlimits_are_not_wrapped() {
+        let command = vec!["bash".to_string(), "-i".to_string()];
+        let limits = ExecLimits {
+            max_output_bytes: Some(1024),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            command.clone(),
+            command_with_limits(command, &limits).expect("command")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn commands_with_rlimits_run_through_codex() {
+        let limits = ExecLimits {
+            max_open_files: Some(256),
+            ..Default::default()
+        };
+        let wrapped = command_with_limits(vec!["bash".to_string()], &limits).expect("command");
+
+        assert_eq!(
+            vec![
+                CODEX_RUN_WITH_LIMITS_ARG1,
+                "max_open_files=256",
+                "--",
+                "bash"
+            ],
+            wrapped[1..]
+        );
+    }
+
+    #[test]
+    fn ignores_successful_commands_and_unset_limits() {
+        let limits = ExecLimits {
+            max_memory_mb: Some(512),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            None,
+            detect_exceeded_limit(&limits, None, 0, false, "out of memory")
+        );
+        assert_eq!(
+            None,
+            detect_exceeded_limit(
+                &ExecLimits::default(),
+                None,
+                1,
+                false,
+                "Cannot allocate memory"
+            )
+        );
+    }
+}
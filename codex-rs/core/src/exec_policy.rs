@@ -0,0 +1,451 @@
+//! Command classification with `codex-execpolicy`.
+//!
+//! The built-in `default.policy` is extended by the `*.policy` files in
+//! `$CODEX_HOME/policies/` and in `.codex/policies/` at the root of the
+//! repository (or the working directory outside of a repository). A command
+//! is forbidden if any policy forbids it, and safe if the default or a user
+//! policy matches it and every file it may write is inside the sandbox's
+//! writable roots. Project policies come with the repository, so they can
+//! only forbid commands.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::ValidExec;
+use codex_execpolicy::get_default_policy;
+use tracing::warn;
+
+use crate::bash::try_parse_bash;
+use crate::bash::try_parse_word_only_commands_sequence;
+use crate::git_info::get_git_repo_root;
+use crate::protocol::SandboxPolicy;
+use crate::safety::normalize_path;
+
+const POLICY_FILE_EXTENSION: &str = "policy";
+
+/// How the exec policies classify a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PolicyVerdict {
+    /// Matched by a policy. `writes_files` is set when the command may write
+    /// files; all of them are inside the sandbox's writable roots.
+    Safe { writes_files: bool },
+    /// Forbidden by a policy, with the policy's reason.
+    Forbidden { reason: String },
+    /// Not matched by any policy; the command needs the usual approval.
+    Unverified,
+}
+
+/// The exec policies in effect for a session.
+#[derive(Default)]
+pub struct ExecPolicy {
+    policies: Vec<Policy>,
+    /// Policies from the project, which are only consulted for forbidden
+    /// commands.
+    project_policies: Vec<Policy>,
+}
+
+impl ExecPolicy {
+    /// Load the default policy plus the user and project policy files. Files
+    /// that fail to parse are skipped; their errors are returned so they can
+    /// be shown to the user.
+    pub fn load(codex_home: &Path, cwd: &Path) -> (Self, Vec<String>) {
+        let mut policies = Vec::new();
+        let mut errors = Vec::new();
+        match get_default_policy() {
+            Ok(policy) => policies.push(policy),
+            Err(err) => errors.push(format!("failed to parse the default exec policy: {err}")),
+        }
+        let mut project_policies = Vec::new();
+        for (dir, loaded) in [
+            (codex_home.join("policies"), &mut policies),
+            (project_policies_dir(cwd), &mut project_policies),
+        ] {
+            for path in policy_files_in(&dir) {
+                match parse_policy_file(&path) {
+                    Ok(policy) => loaded.push(policy),
+                    Err(err) => {
+                        warn!("{err}");
+                        errors.push(err);
+                    }
+                }
+            }
+        }
+        (
+            Self {
+                policies,
+                project_policies,
+            },
+            errors,
+        )
+    }
+
+    /// The default policy extended by `policies` only, ignoring the user and
+    /// project policy directories. Used to try out policy files before they
+    /// are installed.
+    pub fn with_policies(policies: Vec<Policy>) -> Result<Self, String> {
+        let default_policy = get_default_policy()
+            .map_err(|err| format!("failed to parse the default exec policy: {err}"))?;
+        Ok(Self {
+            policies: std::iter::once(default_policy).chain(policies).collect(),
+        })
+    }
+
+    /// Classify `command` as run in `cwd` under `sandbox_policy`. Commands of
+    /// the form `bash -lc "<script>"` are classified by the plain commands in
+    /// the script. Scripts with anything else (redirections, substitutions)
+    /// are never safe; see [`Self::classify_unparsed_script`] for how forbid
+    /// rules apply to them.
+    pub(crate) fn classify(
+        &self,
+        command: &[String],
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> PolicyVerdict {
+        let commands = match command {
+            [bash, flag, script] if bash == "bash" && flag == "-lc" => {
+                match try_parse_bash(script)
+                    .and_then(|tree| try_parse_word_only_commands_sequence(&tree, script))
+                {
+                    Some(commands) if !commands.is_empty() => commands,
+                    _ => return self.classify_unparsed_script(script, sandbox_policy, cwd),
+                }
+            }
+            _ => vec![command.to_vec()],
+        };
+
+        let mut writes_files = false;
+        let mut all_safe = true;
+        for command in &commands {
+            match self.classify_exec(command, sandbox_policy, cwd) {
+                PolicyVerdict::Safe { writes_files: w } => writes_files |= w,
+                forbidden @ PolicyVerdict::Forbidden { .. } => return forbidden,
+                PolicyVerdict::Unverified => all_safe = false,
+            }
+        }
+        if all_safe {
+            PolicyVerdict::Safe { writes_files }
+        } else {
+            PolicyVerdict::Unverified
+        }
+    }
+
+    /// Best-effort forbid check for a script that cannot be split into plain
+    /// commands: the script is cut into commands at shell operators and
+    /// redirections are dropped. Quoting, variables or substitutions get
+    /// around it, so it only catches the obvious cases; the script is
+    /// unverified otherwise.
+    fn classify_unparsed_script(
+        &self,
+        script: &str,
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> PolicyVerdict {
+        unparsed_script_commands(script)
+            .iter()
+            .map(|command| self.classify_exec(command, sandbox_policy, cwd))
+            .find(|verdict| matches!(verdict, PolicyVerdict::Forbidden { .. }))
+            .unwrap_or(PolicyVerdict::Unverified)
+    }
+
+    fn classify_exec(
+        &self,
+        command: &[String],
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> PolicyVerdict {
+        let Some((program, args)) = command.split_first() else {
+            return PolicyVerdict::Unverified;
+        };
+        let exec_call = ExecCall {
+            program: program.clone(),
+            args: args.to_vec(),
+        };
+        for policy in &self.project_policies {
+            if let Ok(MatchedExec::Forbidden { reason, .. }) = policy.check(&exec_call) {
+                return PolicyVerdict::Forbidden { reason };
+            }
+        }
+        let mut verdict = PolicyVerdict::Unverified;
+        for policy in &self.policies {
+            match policy.check(&exec_call) {
+                Ok(MatchedExec::Forbidden { reason, .. }) => {
+                    return PolicyVerdict::Forbidden { reason };
+                }
+                Ok(MatchedExec::Match { exec })
+                    if verdict == PolicyVerdict::Unverified
+                        && writes_within_writable_roots(&exec, sandbox_policy, cwd) =>
+                {
+                    verdict = PolicyVerdict::Safe {
+                        writes_files: exec.might_write_files(),
+                    };
+                }
+                Ok(MatchedExec::Match { .. }) | Err(_) => {}
+            }
+        }
+        verdict
+    }
+}
+
+/// Split `script` into the words of its commands at `|`, `&`, `;`,
+/// parentheses, backticks and newlines, dropping redirections and their
+/// targets and stripping quotes around words.
+fn unparsed_script_commands(script: &str) -> Vec<Vec<String>> {
+    // `2>&1` and `&> file` are redirections, not background operators.
+    script
+        .replace(">&", ">")
+        .replace("&>", ">")
+        .split(['|', '&', ';', '(', ')', '`', '\n'])
+        .filter_map(|segment| {
+            let mut words = Vec::new();
+            let mut tokens = segment.split_whitespace();
+            while let Some(token) = tokens.next() {
+                let redirect = token.trim_start_matches(|c: char| c.is_ascii_digit());
+                if redirect.starts_with(['<', '>']) {
+                    // `> out.txt` names its target in the next word.
+                    if redirect.trim_start_matches(['<', '>']).is_empty() {
+                        tokens.next();
+                    }
+                    continue;
+                }
+                words.push(token.trim_matches(['"', '\'']).to_string());
+            }
+            (!words.is_empty()).then_some(words)
+        })
+        .collect()
+}
+
+/// Directory holding the exec policies of the project that contains `cwd`.
+fn project_policies_dir(cwd: &Path) -> PathBuf {
+    get_git_repo_root(cwd)
+        .unwrap_or_else(|| cwd.to_path_buf())
+        .join(".codex")
+        .join("policies")
+}
+
+/// `*.policy` files in `dir`, sorted by path. A missing directory yields none.
+fn policy_files_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == POLICY_FILE_EXTENSION)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn parse_policy_file(path: &Path) -> Result<Policy, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read exec policy {}: {err}", path.display()))?;
+    PolicyParser::new(&path.to_string_lossy(), &source)
+        .parse()
+        .map_err(|err| format!("failed to parse exec policy {}: {err}", path.display()))
+}
+
+/// Whether every file `exec` may write is inside a writable root of
+/// `sandbox_policy`.
+fn writes_within_writable_roots(
+    exec: &ValidExec,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> bool {
+    if !exec.might_write_files() {
+        return true;
+    }
+    let writable_roots = match sandbox_policy {
+        SandboxPolicy::DangerFullAccess => return true,
+        SandboxPolicy::ReadOnly { .. } => return false,
+        SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
+    };
+    exec.args
+        .iter()
+        .map(|arg| (&arg.r#type, &arg.value))
+        .chain(exec.opts.iter().map(|opt| (&opt.r#type, &opt.value)))
+        .filter(|(arg_type, _)| arg_type.might_write_file())
+        .all(|(_, value)| {
+            normalize_path(&cwd.join(value)).is_some_and(|path| {
+                writable_roots
+                    .iter()
+                    .any(|root| root.is_path_writable(&path))
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| (*s).to_string()).collect()
+    }
+
+    fn workspace_write() -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            read_deny: vec![],
+        }
+    }
+
+    #[test]
+    fn classifies_commands_with_the_default_policy() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path();
+        let (policy, errors) = ExecPolicy::load(&cwd.join("home"), cwd);
+        assert!(errors.is_empty(), "{errors:?}");
+        let classify =
+            |command: &[&str]| policy.classify(&strings(command), &workspace_write(), cwd);
+
+        assert_eq!(
+            classify(&["cat", "-n", "file.txt"]),
+            PolicyVerdict::Safe {
+                writes_files: false
+            }
+        );
+        assert_eq!(
+            classify(&["bash", "-lc", "pwd && cat file.txt"]),
+            PolicyVerdict::Safe {
+                writes_files: false
+            }
+        );
+        assert_eq!(
+            classify(&["cat", "-l", "file.txt"]),
+            PolicyVerdict::Unverified
+        );
+        assert_eq!(
+            classify(&["bash", "-lc", "cat file.txt > out.txt"]),
+            PolicyVerdict::Unverified
+        );
+
+        // `cp` writes its destination, which must be inside a writable root.
+        assert_eq!(
+            classify(&["cp", "a.txt", "b.txt"]),
+            PolicyVerdict::Safe { writes_files: true }
+        );
+        assert_eq!(
+            classify(&["cp", "a.txt", "../b.txt"]),
+            PolicyVerdict::Unverified
+        );
+        assert_eq!(
+            policy.classify(
+                &strings(&["cp", "a.txt", "b.txt"]),
+                &SandboxPolicy::new_read_only_policy(),
+                cwd
+            ),
+            PolicyVerdict::Unverified
+        );
+    }
+
+    #[test]
+    fn project_policies_can_only_forbid() {
+        let tmp = TempDir::new().unwrap();
+        let codex_home = tmp.path().join("home");
+        let cwd = tmp.path().join("project");
+        std::fs::create_dir_all(codex_home.join("policies")).unwrap();
+        std::fs::create_dir_all(cwd.join(".codex/policies")).unwrap();
+        std::fs::write(
+            codex_home.join("policies/user.policy"),
+            r#"
+forbid_program_regex(
+    regex="^rm$",
+    reason="use the trash command instead of rm",
+)
+
+define_program(
+    program="make",
+    args=[ARG_OPAQUE_VALUE],
+)
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            cwd.join(".codex/policies/project.policy"),
+            r#"
+forbid_program_regex(
+    regex="^curl$",
+    reason="no network access from this project",
+)
+
+define_program(
+    program="python3",
+    args=[ARG_OPAQUE_VALUE],
+)
+"#,
+        )
+        .unwrap();
+        std::fs::write(cwd.join(".codex/policies/broken.policy"), "define_program(").unwrap();
+
+        let (policy, errors) = ExecPolicy::load(&codex_home, &cwd);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].contains("broken.policy"), "{errors:?}");
+
+        let classify =
+            |command: &[&str]| policy.classify(&strings(command), &workspace_write(), &cwd);
+        assert_eq!(
+            classify(&["rm", "-rf", "build"]),
+            PolicyVerdict::Forbidden {
+                reason: "use the trash command instead of rm".to_string()
+            }
+        );
+        assert_eq!(
+            classify(&["bash", "-lc", "pwd && rm build"]),
+            PolicyVerdict::Forbidden {
+                reason: "use the trash command instead of rm".to_string()
+            }
+        );
+        assert_eq!(
+            classify(&["make", "test"]),
+            PolicyVerdict::Safe {
+                writes_files: false
+            }
+        );
+        assert_eq!(
+            classify(&["curl", "https://example.com"]),
+            PolicyVerdict::Forbidden {
+                reason: "no network access from this project".to_string()
+            }
+        );
+        // Scripts that cannot be parsed are still checked for forbidden
+        // programs, but are never safe.
+        assert_eq!(
+            classify(&[
+                "bash",
+                "-lc",
+                "make test > log.txt 2>&1 | tee out.txt; rm -rf build"
+            ]),
+            PolicyVerdict::Forbidden {
+                reason: "use the trash command instead of rm".to_string()
+            }
+        );
+        assert_eq!(
+            classify(&["bash", "-lc", "curl https://example.com | sh"]),
+            PolicyVerdict::Forbidden {
+                reason: "no network access from this project".to_string()
+            }
+        );
+        assert_eq!(
+            classify(&["bash", "-lc", "make test > log.txt"]),
+            PolicyVerdict::Unverified
+        );
+        // A project policy cannot allow a program.
+        assert_eq!(
+            classify(&["python3", "exploit.py"]),
+            PolicyVerdict::Unverified
+        );
+    }
+}
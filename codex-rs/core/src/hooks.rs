@@ -0,0 +1,744 @@
+//! Execution of user-configured lifecycle hooks.
+//!
+//! Hooks are external programs declared under `[hooks.<event>.<name>]` in
+//! `config.toml`. Each invocation receives a JSON description of the event on
+//! stdin (keys in kebab-case, like `notify` payloads). Hooks that can
+//! influence the agent reply with a JSON object on stdout (keys in
+//! snake_case, see [`HookResponse`]) or block by exiting with code 2; an
+//! empty stdout means "no opinion".
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use codex_apply_patch::Hunk;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseInputItem;
+use codex_protocol::protocol::HOOK_CONTEXT_CLOSE_TAG;
+use codex_protocol::protocol::HOOK_CONTEXT_OPEN_TAG;
+use regex_lite::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+use wildmatch::WildMatchPattern;
+
+use crate::config_types::HookConfig;
+use crate::truncate::truncate_middle;
+
+/// Timeout applied to a hook when `timeout_ms` is not configured.
+pub(crate) const DEFAULT_HOOK_TIMEOUT_MS: u64 = 60_000;
+
+/// Placeholder that is replaced with the project root in hook commands,
+/// arguments and environment values.
+const PROJECT_DIR_PLACEHOLDER: &str = "$CODEX_PROJECT_DIR";
+
+/// Payload written to a hook's stdin.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub(crate) enum HookEvent {
+    #[serde(rename_all = "kebab-case")]
+    PreToolUse {
+        session_id: String,
+        call_id: String,
+        cwd: String,
+        tool_name: String,
+        tool_input: Value,
+    },
+    #[serde(rename_all = "kebab-case")]
+    PostToolUse {
+        session_id: String,
+        call_id: String,
+        cwd: String,
+        tool_name: String,
+        tool_input: Value,
+        /// The output the model is about to receive for this call.
+        tool_output: String,
+        success: bool,
+        /// Exit code of the command, when the tool ran one.
+        exit_code: Option<i32>,
+    },
+    #[serde(rename_all = "kebab-case")]
+    UserPromptSubmit {
+        session_id: String,
+        cwd: String,
+        /// Text of the submitted prompt. Attached images are not included.
+        prompt: String,
+    },
+    #[serde(rename_all = "kebab-case")]
+    SessionStart { session_id: String, cwd: String },
+    #[serde(rename_all = "kebab-case")]
+    SessionEnd { session_id: String, cwd: String },
+    /// Keeps the `agent-turn-stopped` payload stop hooks have always
+    /// received.
+    #[serde(rename = "agent-turn-stopped", rename_all = "kebab-case")]
+    Stop {
+        turn_id: String,
+        session_id: String,
+        cwd: String,
+        input_messages: Vec<String>,
+        last_assistant_message: Option<String>,
+        /// Whether this turn was started because a stop hook blocked the
+        /// previous stop.
+        stop_hook_active: bool,
+    },
+}
+
+impl HookEvent {
+    /// Name of the event as written in `config.toml`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            HookEvent::PreToolUse { .. } => "pre_tool_use",
+            HookEvent::PostToolUse { .. } => "post_tool_use",
+            HookEvent::UserPromptSubmit { .. } => "user_prompt_submit",
+            HookEvent::SessionStart { .. } => "session_start",
+            HookEvent::SessionEnd { .. } => "session_end",
+            HookEvent::Stop { .. } => "stop",
+        }
+    }
+}
+
+/// What a hook process produced.
+#[derive(Debug)]
+pub(crate) struct HookOutput {
+    /// `None` when the hook was killed, either by a signal or because it ran
+    /// past its timeout.
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+    pub(crate) timed_out: bool,
+}
+
+impl HookOutput {
+    pub(crate) fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// How many extra turns `stop` hooks may start within one task when
+/// `stop_loop_limit` is not configured.
+pub(crate) const DEFAULT_STOP_LOOP_LIMIT: u32 = 5;
+
+/// Hook stdout and stderr carried by `HookEnd` events are truncated to this
+/// many bytes.
+const HOOK_EVENT_OUTPUT_MAX_BYTES: usize = 2 * 1024;
+
+/// Version of the reply protocol described by [`HookResponse`].
+pub(crate) const HOOK_PROTOCOL_VERSION: u32 = 1;
+
+/// Exit code with which a hook blocks what it was consulted about without
+/// printing a reply; its stderr becomes the reason.
+pub(crate) const HOOK_BLOCK_EXIT_CODE: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum HookDecision {
+    /// Let the action proceed without further checks from this hook.
+    Allow,
+    /// Ask the user to approve the tool call before running it.
+    Ask,
+    /// Stop the action; `reason` explains why.
+    #[serde(alias = "deny")]
+    Block,
+}
+
+/// Reply a hook prints on stdout.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub(crate) struct HookResponse {
+    /// Protocol version the reply follows; [`HOOK_PROTOCOL_VERSION`] when
+    /// omitted.
+    #[serde(default)]
+    pub(crate) version: Option<u32>,
+    #[serde(default)]
+    pub(crate) decision: Option<HookDecision>,
+    #[serde(default)]
+    pub(crate) reason: Option<String>,
+    /// `false` ends the current task once the hook has run.
+    #[serde(default, rename = "continue")]
+    pub(crate) continue_: Option<bool>,
+    /// Keep the hook's stdout out of what is shown to the user.
+    #[serde(default)]
+    pub(crate) suppress_output: bool,
+    /// Message shown to the user.
+    #[serde(default)]
+    pub(crate) system_message: Option<String>,
+    /// Replacement for the tool arguments. Later hooks and the tool itself
+    /// see this payload instead of the one produced by the model.
+    #[serde(default)]
+    pub(crate) updated_input: Option<Value>,
+    /// Text handed to the model.
+    #[serde(default)]
+    pub(crate) additional_context: Option<String>,
+}
+
+impl HookResponse {
+    /// Interpret what a hook printed for `event`.
+    ///
+    /// Exit code 2 blocks, with stderr as the reason. Otherwise an empty
+    /// stdout means "no opinion", a JSON object is a structured reply, and
+    /// other text is additional context for the events that accept it. The
+    /// error describes a reply that does not follow the protocol.
+    pub(crate) fn parse(event: &HookEvent, output: &HookOutput) -> Result<Self, String> {
+        let response = if output.exit_code == Some(HOOK_BLOCK_EXIT_CODE) {
+            let reason = output.stderr.trim();
+            Self {
+                decision: Some(HookDecision::Block),
+                reason: (!reason.is_empty()).then(|| reason.to_string()),
+                ..Default::default()
+            }
+        } else {
+            let stdout = output.stdout.trim();
+            if stdout.is_empty() {
+                return Ok(Self::default());
+            }
+            if stdout.starts_with('{') {
+                serde_json::from_str(stdout).map_err(|e| format!("invalid JSON reply: {e}"))?
+            } else if matches!(event, HookEvent::PreToolUse { .. }) {
+                return Err("expected a JSON object on stdout".to_string());
+            } else {
+                Self {
+                    additional_context: Some(stdout.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+        response.validate(event)?;
+        Ok(response)
+    }
+
+    fn validate(&self, event: &HookEvent) -> Result<(), String> {
+        let event_name = event.name();
+        if let Some(version) = self.version
+            && version != HOOK_PROTOCOL_VERSION
+        {
+            return Err(format!(
+                "unsupported protocol version {version} (expected {HOOK_PROTOCOL_VERSION})"
+            ));
+        }
+        let supported = match event {
+            HookEvent::PreToolUse { .. } => true,
+            HookEvent::PostToolUse { .. }
+            | HookEvent::UserPromptSubmit { .. }
+            | HookEvent::Stop { .. } => !matches!(self.decision, Some(HookDecision::Ask)),
+            HookEvent::SessionStart { .. } | HookEvent::SessionEnd { .. } => {
+                self.decision.is_none()
+            }
+        };
+        if !supported && let Some(decision) = self.decision {
+            let decision = match decision {
+                HookDecision::Allow => "allow",
+                HookDecision::Ask => "ask",
+                HookDecision::Block => "block",
+            };
+            return Err(format!(
+                "decision `{decision}` is not supported by {event_name} hooks"
+            ));
+        }
+        if self.updated_input.is_some() && !matches!(event, HookEvent::PreToolUse { .. }) {
+            return Err(format!(
+                "updated_input is not supported by {event_name} hooks"
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn blocks(&self) -> bool {
+        matches!(self.decision, Some(HookDecision::Block))
+    }
+
+    pub(crate) fn should_continue(&self) -> bool {
+        self.continue_.unwrap_or(true)
+    }
+
+    pub(crate) fn reason_or_default(&self) -> &str {
+        self.reason.as_deref().unwrap_or("no reason given")
+    }
+}
+
+/// Whether a tool hook's `matcher` selects the call of `tool_name` with
+/// `tool_input`. Hooks without a matcher run for every call. The error
+/// describes a matcher that cannot be evaluated.
+pub(crate) fn matches_tool_call(
+    hook: &HookConfig,
+    tool_name: &str,
+    tool_input: &Value,
+) -> Result<bool, String> {
+    let Some(matcher) = &hook.matcher else {
+        return Ok(true);
+    };
+    if let Some(tool) = &matcher.tool {
+        let regex = Regex::new(&format!("^(?:{tool})$"))
+            .map_err(|e| format!("invalid tool matcher `{tool}`: {e}"))?;
+        if !regex.is_match(tool_name) {
+            return Ok(false);
+        }
+    }
+    if let Some(glob) = &matcher.glob {
+        let pattern = WildMatchPattern::<'*', '?'>::new(glob);
+        return Ok(glob_targets(tool_input)
+            .iter()
+            .any(|target| pattern.matches(target)));
+    }
+    Ok(true)
+}
+
+/// Strings a matcher `glob` is tested against: the command line of shell
+/// calls (and the script of `sh -c` style invocations), and the paths a
+/// patch touches for `apply_patch` calls.
+fn glob_targets(tool_input: &Value) -> Vec<String> {
+    let mut targets = Vec::new();
+    if let Some(command) = tool_input.get("command").and_then(Value::as_array) {
+        let argv: Vec<&str> = command.iter().filter_map(Value::as_str).collect();
+        if let [_, flag, script] = argv.as_slice()
+            && matches!(*flag, "-c" | "-lc")
+        {
+            targets.push((*script).to_string());
+        }
+        targets.push(argv.join(" "));
+    }
+    if let Some(patch) = tool_input.get("input").and_then(Value::as_str)
+        && let Ok(args) = codex_apply_patch::parse_patch(patch)
+    {
+        for hunk in args.hunks {
+            match hunk {
+                Hunk::AddFile { path, .. } | Hunk::DeleteFile { path } => {
+                    targets.push(path.to_string_lossy().to_string());
+                }
+                Hunk::UpdateFile {
+                    path, move_path, ..
+                } => {
+                    targets.push(path.to_string_lossy().to_string());
+                    targets.extend(move_path.map(|path| path.to_string_lossy().to_string()));
+                }
+            }
+        }
+    }
+    targets
+}
+
+/// Conversation item that carries context contributed by the hook
+/// `hook_name` for the event `event_name` to the model.
+pub(crate) fn hook_context_item(
+    event_name: &str,
+    hook_name: &str,
+    context: &str,
+) -> ResponseInputItem {
+    ResponseInputItem::Message {
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: format!(
+                "{HOOK_CONTEXT_OPEN_TAG} source=\"{event_name}:{hook_name}\">\n{context}\n{HOOK_CONTEXT_CLOSE_TAG}"
+            ),
+        }],
+    }
+}
+
+/// Truncate hook stdout or stderr for a `HookEnd` event.
+pub(crate) fn truncate_hook_output(text: &str) -> String {
+    truncate_middle(text, HOOK_EVENT_OUTPUT_MAX_BYTES).0
+}
+
+/// The enabled hooks of one event, in name order.
+pub(crate) fn enabled_hooks(hooks: &HashMap<String, HookConfig>) -> Vec<(&str, &HookConfig)> {
+    let mut hooks: Vec<_> = hooks
+        .iter()
+        .filter(|(_, hook)| hook.enabled)
+        .map(|(name, hook)| (name.as_str(), hook))
+        .collect();
+    hooks.sort_by_key(|(name, _)| *name);
+    hooks
+}
+
+/// Directory substituted for `$CODEX_PROJECT_DIR`: the git root containing
+/// `cwd`, or `cwd` itself outside of a repository.
+pub(crate) fn project_dir(cwd: &Path) -> PathBuf {
+    crate::git_info::get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf())
+}
+
+/// Replace `$CODEX_PROJECT_DIR` in a hook command, argument or environment
+/// value.
+pub(crate) fn expand_project_dir(value: &str, project_dir: &Path) -> String {
+    value.replace(PROJECT_DIR_PLACEHOLDER, &project_dir.to_string_lossy())
+}
+
+/// Run `hook` to completion, feeding `event` on stdin. The hook is killed if
+/// it does not exit within its configured timeout.
+pub(crate) async fn run_hook(
+    hook: &HookConfig,
+    event: &HookEvent,
+    cwd: &Path,
+) -> std::io::Result<HookOutput> {
+    let payload = serde_json::to_vec(event)?;
+    let project_dir = project_dir(cwd);
+    let expand = |value: &str| expand_project_dir(value, &project_dir);
+
+    let mut command = Command::new(expand(&hook.command));
+    command
+        .args(hook.args.iter().map(|arg| expand(arg)))
+        .current_dir(cwd)
+        .env("CODEX_PROJECT_DIR", &project_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(env) = &hook.env {
+        for (key, value) in env {
+            command.env(key, expand(value));
+        }
+    }
+
+    let mut child = command.spawn()?;
+    let stdin = child.stdin.take();
+    let write_payload = async move {
+        if let Some(mut stdin) = stdin {
+            // A hook is free to ignore its input and exit early, which
+            // surfaces here as a broken pipe.
+            if let Err(e) = stdin.write_all(&payload).await
+                && e.kind() != std::io::ErrorKind::BrokenPipe
+            {
+                return Err(e);
+            }
+        }
+        // Dropping stdin closes it, so hooks reading to the end finish.
+        Ok(())
+    };
+    // The payload is written under the timeout too: a hook that never reads
+    // its input would otherwise block once the pipe buffer is full.
+    let run = async move {
+        let (written, output) = tokio::join!(write_payload, child.wait_with_output());
+        written?;
+        output
+    };
+
+    let timeout = Duration::from_millis(hook.timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS));
+    match tokio::time::timeout(timeout, run).await {
+        Ok(output) => {
+            let output = output?;
+            Ok(HookOutput {
+                exit_code: output.status.code(),
+                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                timed_out: false,
+            })
+        }
+        Err(_) => {
+            // Dropping the future drops the child, which kills it.
+            warn!("hook '{}' timed out after {timeout:?}", hook.command);
+            Ok(HookOutput {
+                exit_code: None,
+                stdout: String::new(),
+                stderr: String::new(),
+                timed_out: true,
+            })
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config_types::HookMatcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn sh(script: &str) -> HookConfig {
+        HookConfig {
+            command: "/bin/sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: None,
+            timeout_ms: None,
+            enabled: true,
+            matcher: None,
+            fail_closed: false,
+        }
+    }
+
+    fn pre_tool_use_event() -> HookEvent {
+        HookEvent::PreToolUse {
+            session_id: "session".to_string(),
+            call_id: "call-1".to_string(),
+            cwd: "/tmp".to_string(),
+            tool_name: "shell".to_string(),
+            tool_input: json!({"command": ["cargo", "publish"]}),
+        }
+    }
+
+    #[test]
+    fn pre_tool_use_event_serialization() {
+        let serialized = serde_json::to_value(pre_tool_use_event()).expect("serialize");
+        assert_eq!(
+            serialized,
+            json!({
+                "type": "pre-tool-use",
+                "session-id": "session",
+                "call-id": "call-1",
+                "cwd": "/tmp",
+                "tool-name": "shell",
+                "tool-input": {"command": ["cargo", "publish"]},
+            })
+        );
+    }
+
+    fn output(exit_code: i32, stdout: &str, stderr: &str) -> HookOutput {
+        HookOutput {
+            exit_code: Some(exit_code),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            timed_out: false,
+        }
+    }
+
+    fn session_start_event() -> HookEvent {
+        HookEvent::SessionStart {
+            session_id: "session".to_string(),
+            cwd: "/tmp".to_string(),
+        }
+    }
+
+    #[test]
+    fn stop_event_serialization() {
+        let event = HookEvent::Stop {
+            turn_id: "12345".to_string(),
+            session_id: "abc123".to_string(),
+            cwd: "/home/user/project".to_string(),
+            input_messages: vec!["Fix the authentication bug".to_string()],
+            last_assistant_message: Some("I've fixed the authentication issue.".to_string()),
+            stop_hook_active: false,
+        };
+        let serialized = serde_json::to_string(&event).expect("serialize");
+        assert_eq!(
+            serialized,
+            r#"{"type":"agent-turn-stopped","turn-id":"12345","session-id":"abc123","cwd":"/home/user/project","input-messages":["Fix the authentication bug"],"last-assistant-message":"I've fixed the authentication issue.","stop-hook-active":false}"#
+        );
+    }
+
+    #[test]
+    fn parses_structured_replies() {
+        let event = pre_tool_use_event();
+        assert_eq!(
+            HookResponse::parse(&event, &output(0, "  \n", "")),
+            Ok(HookResponse::default())
+        );
+        assert_eq!(
+            HookResponse::parse(
+                &event,
+                &output(
+                    0,
+                    r#"{"version":1,"decision":"deny","reason":"no publishing","continue":false,"suppress_output":true,"system_message":"publish blocked"}"#,
+                    ""
+                )
+            ),
+            Ok(HookResponse {
+                version: Some(1),
+                decision: Some(HookDecision::Block),
+                reason: Some("no publishing".to_string()),
+                continue_: Some(false),
+                suppress_output: true,
+                system_message: Some("publish blocked".to_string()),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            HookResponse::parse(
+                &event,
+                &output(0, r#"{"updated_input":{"command":["ls"]}}"#, "")
+            ),
+            Ok(HookResponse {
+                updated_input: Some(json!({"command": ["ls"]})),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn exit_code_two_blocks_with_stderr_as_reason() {
+        let response = HookResponse::parse(
+            &pre_tool_use_event(),
+            &output(HOOK_BLOCK_EXIT_CODE, "ignored", "no publishing\n"),
+        )
+        .expect("valid reply");
+        assert!(response.blocks());
+        assert_eq!(response.reason_or_default(), "no publishing");
+        assert!(response.should_continue());
+
+        assert_eq!(
+            HookResponse::parse(&session_start_event(), &output(2, "", "")),
+            Err("decision `block` is not supported by session_start hooks".to_string())
+        );
+    }
+
+    #[test]
+    fn plain_text_is_context_except_for_pre_tool_use() {
+        assert_eq!(
+            HookResponse::parse(&session_start_event(), &output(0, "on branch main\n", "")),
+            Ok(HookResponse {
+                additional_context: Some("on branch main".to_string()),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            HookResponse::parse(&pre_tool_use_event(), &output(0, "not json", "")),
+            Err("expected a JSON object on stdout".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_replies_that_break_the_protocol() {
+        let event = pre_tool_use_event();
+        let error = |stdout: &str| {
+            HookResponse::parse(&event, &output(0, stdout, "")).expect_err("invalid reply")
+        };
+        assert_eq!(
+            error(r#"{"version":2}"#),
+            "unsupported protocol version 2 (expected 1)"
+        );
+        assert!(
+            error(r#"{"decision":"maybe"}"#)
+                .starts_with("invalid JSON reply: unknown variant `maybe`")
+        );
+        assert!(error(r#"{"continue":"no"}"#).starts_with("invalid JSON reply: invalid type"));
+        assert!(error("{not json").starts_with("invalid JSON reply"));
+
+        assert_eq!(
+            HookResponse::parse(
+                &session_start_event(),
+                &output(0, r#"{"updated_input":{}}"#, "")
+            ),
+            Err("updated_input is not supported by session_start hooks".to_string())
+        );
+    }
+
+    #[test]
+    fn matches_tool_calls() {
+        let mut hook = sh("true");
+        let shell_input = json!({"command": ["bash", "-lc", "cargo publish --dry-run"]});
+        assert_eq!(matches_tool_call(&hook, "shell", &shell_input), Ok(true));
+
+        hook.matcher = Some(HookMatcher {
+            tool: Some("shell|apply_patch".to_string()),
+            glob: None,
+        });
+        assert_eq!(matches_tool_call(&hook, "shell", &shell_input), Ok(true));
+        assert_eq!(
+            matches_tool_call(&hook, "shell_v2", &shell_input),
+            Ok(false)
+        );
+        assert_eq!(
+            matches_tool_call(&hook, "read_file", &shell_input),
+            Ok(false)
+        );
+
+        hook.matcher = Some(HookMatcher {
+            tool: None,
+            glob: Some("cargo publish*".to_string()),
+        });
+        assert_eq!(matches_tool_call(&hook, "shell", &shell_input), Ok(true));
+        assert_eq!(
+            matches_tool_call(&hook, "shell", &json!({"command": ["cargo", "build"]})),
+            Ok(false)
+        );
+
+        hook.matcher = Some(HookMatcher {
+            tool: Some("apply_patch".to_string()),
+            glob: Some("*.rs".to_string()),
+        });
+        let patch = |path: &str| json!({"input": format!("*** Begin Patch\n*** Add File: {path}\n+fn main() {{}}\n*** End Patch")});
+        assert_eq!(
+            matches_tool_call(&hook, "apply_patch", &patch("src/main.rs")),
+            Ok(true)
+        );
+        assert_eq!(
+            matches_tool_call(&hook, "apply_patch", &patch("README.md")),
+            Ok(false)
+        );
+
+        hook.matcher = Some(HookMatcher {
+            tool: Some("(".to_string()),
+            glob: None,
+        });
+        assert!(matches_tool_call(&hook, "shell", &shell_input).is_err());
+    }
+
+    #[test]
+    fn enabled_hooks_are_sorted_and_filtered() {
+        let mut disabled = sh("true");
+        disabled.enabled = false;
+        let hooks = HashMap::from([
+            ("b".to_string(), sh("true")),
+            ("a".to_string(), sh("true")),
+            ("c".to_string(), disabled),
+        ]);
+        let names: Vec<&str> = enabled_hooks(&hooks).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn run_hook_passes_event_on_stdin() {
+        let cwd = tempfile::tempdir().expect("tempdir");
+        let output = run_hook(&sh("cat"), &pre_tool_use_event(), cwd.path())
+            .await
+            .expect("run hook");
+        assert!(output.success());
+        let echoed: Value = serde_json::from_str(&output.stdout).expect("json stdout");
+        assert_eq!(echoed["tool-name"], "shell");
+    }
+
+    #[tokio::test]
+    async fn run_hook_reports_exit_code_and_stderr() {
+        let cwd = tempfile::tempdir().expect("tempdir");
+        let output = run_hook(
+            &sh("echo oops >&2; exit 3"),
+            &pre_tool_use_event(),
+            cwd.path(),
+        )
+        .await
+        .expect("run hook");
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(output.stderr, "oops\n");
+        assert!(!output.timed_out);
+    }
+
+    #[tokio::test]
+    async fn run_hook_times_out_hooks_that_do_not_read_a_large_payload() {
+        let cwd = tempfile::tempdir().expect("tempdir");
+        let mut hook = sh("sleep 5");
+        hook.timeout_ms = Some(200);
+        let event = HookEvent::PreToolUse {
+            session_id: "session".to_string(),
+            call_id: "call-1".to_string(),
+            cwd: "/tmp".to_string(),
+            tool_name: "shell".to_string(),
+            // Larger than any pipe buffer.
+            tool_input: json!({"command": ["echo", "x".repeat(1024 * 1024)]}),
+        };
+        let output = run_hook(&hook, &event, cwd.path()).await.expect("run hook");
+        assert!(output.timed_out);
+    }
+
+    #[tokio::test]
+    async fn run_hook_kills_hooks_that_time_out() {
+        let cwd = tempfile::tempdir().expect("tempdir");
+        let mut hook = sh("sleep 5");
+        hook.timeout_ms = Some(100);
+        let output = run_hook(&hook, &pre_tool_use_event(), cwd.path())
+            .await
+            .expect("run hook");
+        assert!(output.timed_out);
+        assert_eq!(output.exit_code, None);
+    }
+
+    #[tokio::test]
+    async fn run_hook_expands_project_dir() {
+        let cwd = tempfile::tempdir().expect("tempdir");
+        let mut hook = sh("printf '%s' \"$HOOK_DIR\"");
+        hook.env = Some([("HOOK_DIR".to_string(), "$CODEX_PROJECT_DIR/x".to_string())].into());
+        let output = run_hook(&hook, &pre_tool_use_event(), cwd.path())
+            .await
+            .expect("run hook");
+        assert_eq!(output.stdout, format!("{}/x", cwd.path().display()));
+    }
+}
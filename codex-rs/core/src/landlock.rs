@@ -1,5 +1,7 @@
+use crate::config_types::ExecLimits;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::StdioPolicy;
+use crate::spawn::network_proxy_port;
 use crate::spawn::spawn_child_async;
 use std::collections::HashMap;
 use std::path::Path;
@@ -21,29 +23,99 @@ pub async fn spawn_command_under_linux_sandbox<P>(
     sandbox_policy_cwd: &Path,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    limits: ExecLimits,
 ) -> std::io::Result<Child>
 where
     P: AsRef<Path>,
 {
-    let args = create_linux_sandbox_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    spawn_linux_sandbox_helper(
+        codex_linux_sandbox_exe.as_ref(),
+        command,
+        command_cwd,
+        sandbox_policy,
+        sandbox_policy_cwd,
+        stdio_policy,
+        env,
+        limits,
+        false,
+    )
+    .await
+}
+
+/// Like [`spawn_command_under_linux_sandbox`], but asks the helper to also run
+/// the command in fresh user, mount, PID and (unless network access is
+/// allowed) network namespaces, with a private `/tmp` and `/proc`. The helper
+/// falls back to Landlock alone when user namespaces are unavailable.
+pub async fn spawn_command_under_linux_namespaces<P>(
+    codex_linux_sandbox_exe: P,
+    command: Vec<String>,
+    command_cwd: PathBuf,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
+    limits: ExecLimits,
+) -> std::io::Result<Child>
+where
+    P: AsRef<Path>,
+{
+    spawn_linux_sandbox_helper(
+        codex_linux_sandbox_exe.as_ref(),
+        command,
+        command_cwd,
+        sandbox_policy,
+        sandbox_policy_cwd,
+        stdio_policy,
+        env,
+        limits,
+        true,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn spawn_linux_sandbox_helper(
+    codex_linux_sandbox_exe: &Path,
+    command: Vec<String>,
+    command_cwd: PathBuf,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
+    limits: ExecLimits,
+    use_namespaces: bool,
+) -> std::io::Result<Child> {
+    let args = create_linux_sandbox_command_args(
+        command,
+        sandbox_policy,
+        sandbox_policy_cwd,
+        network_proxy_port(&env),
+        use_namespaces,
+    );
     let arg0 = Some("codex-linux-sandbox");
     spawn_child_async(
-        codex_linux_sandbox_exe.as_ref().to_path_buf(),
+        codex_linux_sandbox_exe.to_path_buf(),
         args,
         arg0,
         command_cwd,
         sandbox_policy,
         stdio_policy,
         env,
+        limits,
     )
     .await
 }
 
 /// Converts the sandbox policy into the CLI invocation for `codex-linux-sandbox`.
+/// `network_proxy_port` is the loopback port of the network proxy, the only
+/// port commands may connect to when network access is restricted to an
+/// allowlist. `use_namespaces` selects the namespace-based sandbox.
 fn create_linux_sandbox_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
+    network_proxy_port: Option<u16>,
+    use_namespaces: bool,
 ) -> Vec<String> {
     #[expect(clippy::expect_used)]
     let sandbox_policy_cwd = sandbox_policy_cwd
@@ -55,13 +127,20 @@ fn create_linux_sandbox_command_args(
     let sandbox_policy_json =
         serde_json::to_string(sandbox_policy).expect("Failed to serialize SandboxPolicy to JSON");
 
-    let mut linux_cmd: Vec<String> = vec![
+    let mut linux_cmd: Vec<String> = Vec::new();
+    if use_namespaces {
+        linux_cmd.push("--namespaces".to_string());
+    }
+    if let Some(port) = network_proxy_port {
+        linux_cmd.push(format!("--network-proxy-port={port}"));
+    }
+    linux_cmd.extend([
         sandbox_policy_cwd,
         sandbox_policy_json,
         // Separator so that command arguments starting with `-` are not parsed as
         // options of the helper itself.
         "--".to_string(),
-    ];
+    ]);
 
     // Append the original tool command.
     linux_cmd.extend(command);
@@ -5,6 +5,7 @@
 // the TUI or the tracing stack).
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+mod anthropic_messages;
 mod apply_patch;
 pub mod auth;
 pub mod bash;
@@ -26,16 +27,22 @@ pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+mod exec_limits;
+mod exec_policy;
 mod flags;
 pub mod git_info;
+mod hooks;
 pub mod internal_storage;
 mod is_safe_command;
 pub mod landlock;
 mod mcp_connection_manager;
+pub mod mcp_prompts;
 mod mcp_tool_call;
 mod message_history;
 mod model_provider_info;
+mod network_proxy;
 pub mod parse_command;
+pub mod permissions;
 pub mod template_processor;
 mod truncate;
 mod unified_exec;
@@ -58,13 +65,16 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+mod output_artifacts;
 pub mod plan_tool;
 pub mod project_doc;
 mod rollout;
 pub(crate) mod safety;
+pub mod sandbox_denial;
 pub mod seatbelt;
 pub mod shell;
 pub mod spawn;
+mod subagents;
 pub mod terminal;
 mod tool_apply_patch;
 pub mod turn_diff_tracker;
@@ -76,11 +86,18 @@ pub use rollout::find_conversation_path_by_id_str;
 pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
+pub use rollout::replay::ReplayDecision;
+pub use rollout::replay::ReplayedCommand;
+pub use rollout::replay::replay_rollout;
 mod function_tool;
 mod user_notification;
 pub mod util;
 
 pub use apply_patch::CODEX_APPLY_PATCH_ARG1;
+pub use exec_limits::CODEX_RUN_WITH_LIMITS_ARG1;
+#[cfg(unix)]
+pub use exec_limits::exec_with_limits;
+pub use exec_policy::ExecPolicy;
 pub use safety::get_platform_sandbox;
 // Re-export the protocol types from the standalone `codex-protocol` crate so existing
 // `codex_core::protocol::...` references continue to work across the workspace.
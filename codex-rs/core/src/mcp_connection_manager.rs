@@ -4,7 +4,9 @@
 //! configured server (keyed by the *server name*). It offers convenience
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
-//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key. Prompts offered by
+//! servers that advertise the `prompts` capability are listed at startup as
+//! well so they can be surfaced as slash commands.
 
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -17,7 +19,10 @@ use anyhow::Result;
 use anyhow::anyhow;
 use codex_mcp_client::McpClient;
 use mcp_types::ClientCapabilities;
+use mcp_types::GetPromptResult;
 use mcp_types::Implementation;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::Prompt;
 use mcp_types::Tool;
 
 use serde_json::json;
@@ -89,6 +94,8 @@ struct ManagedClient {
     client: Arc<McpClient>,
     startup_timeout: Duration,
     tool_timeout: Option<Duration>,
+    /// Whether the server advertised the `prompts` capability.
+    supports_prompts: bool,
 }
 
 /// A thin wrapper around a set of running [`McpClient`] instances.
@@ -102,6 +109,9 @@ pub(crate) struct McpConnectionManager {
 
     /// Fully qualified tool name -> tool instance.
     tools: HashMap<String, ToolInfo>,
+
+    /// Server name -> prompts offered by that server.
+    prompts: HashMap<String, Vec<Prompt>>,
 }
 
 impl McpConnectionManager {
@@ -183,7 +193,10 @@ impl McpConnectionManager {
                             .await;
                         (
                             (server_name, tool_timeout),
-                            init_result.map(|_| (client, startup_timeout)),
+                            init_result.map(|result| {
+                                let supports_prompts = result.capabilities.prompts.is_some();
+                                (client, startup_timeout, supports_prompts)
+                            }),
                         )
                     }
                     Err(e) => ((server_name, tool_timeout), Err(e.into())),
@@ -203,13 +216,14 @@ impl McpConnectionManager {
             };
 
             match client_res {
-                Ok((client, startup_timeout)) => {
+                Ok((client, startup_timeout, supports_prompts)) => {
                     clients.insert(
                         server_name,
                         ManagedClient {
                             client: Arc::new(client),
                             startup_timeout,
                             tool_timeout: Some(tool_timeout),
+                            supports_prompts,
                         },
                     );
                 }
@@ -228,8 +242,16 @@ impl McpConnectionManager {
         };
 
         let tools = qualify_tools(all_tools);
+        let prompts = list_all_prompts(&clients).await;
 
-        Ok((Self { clients, tools }, errors))
+        Ok((
+            Self {
+                clients,
+                tools,
+                prompts,
+            },
+            errors,
+        ))
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
@@ -261,6 +283,44 @@ impl McpConnectionManager {
             .with_context(|| format!("tool call failed for `{server}/{tool}`"))
     }
 
+    /// Returns the prompts of every server as `(server name, prompt)` pairs,
+    /// sorted by server and prompt name.
+    pub fn list_all_prompts(&self) -> Vec<(String, Prompt)> {
+        let mut prompts: Vec<(String, Prompt)> = self
+            .prompts
+            .iter()
+            .flat_map(|(server, prompts)| {
+                prompts
+                    .iter()
+                    .map(|prompt| (server.clone(), prompt.clone()))
+            })
+            .collect();
+        prompts.sort_by(|(a_server, a), (b_server, b)| {
+            a_server.cmp(b_server).then_with(|| a.name.cmp(&b.name))
+        });
+        prompts
+    }
+
+    /// Fetch the messages of `prompt` from `server` via `prompts/get`.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        prompt: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<GetPromptResult> {
+        let managed = self
+            .clients
+            .get(server)
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        let client = managed.client.clone();
+        let timeout = managed.tool_timeout;
+
+        client
+            .get_prompt(prompt.to_string(), arguments, timeout)
+            .await
+            .with_context(|| format!("prompt request failed for `{server}/{prompt}`"))
+    }
+
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
         self.tools
             .get(tool_name)
@@ -322,6 +382,62 @@ async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<
     Ok(aggregated)
 }
 
+/// Query every server that supports prompts for the prompts it offers,
+/// following pagination cursors. Servers that fail to answer are skipped.
+async fn list_all_prompts(
+    clients: &HashMap<String, ManagedClient>,
+) -> HashMap<String, Vec<Prompt>> {
+    let mut join_set = JoinSet::new();
+
+    for (server_name, managed_client) in clients {
+        if !managed_client.supports_prompts {
+            continue;
+        }
+        let server_name_cloned = server_name.clone();
+        let client_clone = managed_client.client.clone();
+        let startup_timeout = managed_client.startup_timeout;
+        join_set.spawn(async move {
+            let res = list_server_prompts(&client_clone, startup_timeout).await;
+            (server_name_cloned, res)
+        });
+    }
+
+    let mut aggregated = HashMap::with_capacity(join_set.len());
+    while let Some(join_res) = join_set.join_next().await {
+        let (server_name, list_result) = match join_res {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Task panic when listing prompts for MCP server: {e:#}");
+                continue;
+            }
+        };
+        match list_result {
+            Ok(prompts) => {
+                aggregated.insert(server_name, prompts);
+            }
+            Err(e) => warn!("Failed to list prompts for MCP server '{server_name}': {e:#}"),
+        }
+    }
+
+    aggregated
+}
+
+async fn list_server_prompts(client: &McpClient, timeout: Duration) -> Result<Vec<Prompt>> {
+    let mut prompts = Vec::new();
+    let mut cursor = None;
+    loop {
+        let params = cursor.map(|cursor| ListPromptsRequestParams {
+            cursor: Some(cursor),
+        });
+        let result = client.list_prompts(params, Some(timeout)).await?;
+        prompts.extend(result.prompts);
+        match result.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(prompts),
+        }
+    }
+}
+
 fn is_valid_mcp_server_name(server_name: &str) -> bool {
     !server_name.is_empty()
         && server_name
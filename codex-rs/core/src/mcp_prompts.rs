@@ -0,0 +1,176 @@
+//! Conversion between MCP prompts and the custom prompts shown in the slash
+//! popup.
+//!
+//! A prompt `name` offered by server `server` is listed as `server:name`. Its
+//! declared arguments become the prompt's template arguments so the composer
+//! can bind what the user typed after the command; the messages returned by
+//! `prompts/get` are then flattened into the text of a single user message.
+
+use std::path::PathBuf;
+
+use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::TemplateArg;
+use mcp_types::ContentBlock;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::GetPromptResult;
+use mcp_types::Prompt;
+
+/// Separator between the server name and the prompt name in the slash command.
+pub const MCP_PROMPT_DELIMITER: char = ':';
+
+/// Describe `prompt` from `server` as a custom prompt. The body is left empty;
+/// it is fetched with `prompts/get` when the prompt is used.
+pub fn mcp_prompt_to_custom_prompt(server: &str, prompt: Prompt) -> CustomPrompt {
+    let Prompt {
+        arguments,
+        description,
+        name,
+        title,
+    } = prompt;
+    let template_args: Vec<TemplateArg> = arguments
+        .unwrap_or_default()
+        .into_iter()
+        .map(|arg| TemplateArg {
+            description: arg.description.or(arg.title),
+            required: arg.required.unwrap_or(false),
+            name: arg.name,
+            default_value: None,
+        })
+        .collect();
+    let argument_hint = (!template_args.is_empty()).then(|| {
+        template_args
+            .iter()
+            .map(|arg| {
+                if arg.required {
+                    format!("<{}>", arg.name)
+                } else {
+                    format!("[{}=...]", arg.name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+
+    CustomPrompt {
+        name: format!("{server}{MCP_PROMPT_DELIMITER}{name}"),
+        path: PathBuf::new(),
+        content: String::new(),
+        category: Some(server.to_string()),
+        argument_hint,
+        description: description.or(title),
+        template_args: (!template_args.is_empty()).then_some(template_args),
+        template_syntax: None,
+        turn_overrides: None,
+        mcp_server: Some(server.to_string()),
+    }
+}
+
+/// Name of the prompt on its MCP server, i.e. the slash command name without
+/// the `server:` prefix.
+pub fn mcp_prompt_name(prompt: &CustomPrompt) -> &str {
+    match &prompt.mcp_server {
+        Some(server) => prompt
+            .name
+            .strip_prefix(server.as_str())
+            .and_then(|name| name.strip_prefix(MCP_PROMPT_DELIMITER))
+            .unwrap_or(&prompt.name),
+        None => &prompt.name,
+    }
+}
+
+/// Flatten the messages of a `prompts/get` result into the text of a single
+/// user message. Text content and embedded text resources are included
+/// verbatim; binary content is replaced by a short placeholder.
+pub fn mcp_prompt_messages_to_text(result: &GetPromptResult) -> String {
+    result
+        .messages
+        .iter()
+        .map(|message| match &message.content {
+            ContentBlock::TextContent(text) => text.text.clone(),
+            ContentBlock::EmbeddedResource(resource) => match &resource.resource {
+                EmbeddedResourceResource::TextResourceContents(contents) => contents.text.clone(),
+                EmbeddedResourceResource::BlobResourceContents(contents) => {
+                    format!("[resource omitted: {}]", contents.uri)
+                }
+            },
+            ContentBlock::ResourceLink(link) => link.uri.clone(),
+            ContentBlock::ImageContent(image) => format!("[image omitted: {}]", image.mime_type),
+            ContentBlock::AudioContent(audio) => format!("[audio omitted: {}]", audio.mime_type),
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::PromptArgument;
+    use mcp_types::PromptMessage;
+    use mcp_types::Role;
+    use mcp_types::TextContent;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn mcp_prompt_becomes_namespaced_custom_prompt() {
+        let prompt = mcp_prompt_to_custom_prompt(
+            "github",
+            Prompt {
+                arguments: Some(vec![
+                    PromptArgument {
+                        description: Some("Pull request number".to_string()),
+                        name: "pr".to_string(),
+                        required: Some(true),
+                        title: None,
+                    },
+                    PromptArgument {
+                        description: None,
+                        name: "focus".to_string(),
+                        required: None,
+                        title: Some("Focus area".to_string()),
+                    },
+                ]),
+                description: Some("Review a pull request".to_string()),
+                name: "review".to_string(),
+                title: None,
+            },
+        );
+
+        assert_eq!(prompt.name, "github:review");
+        assert_eq!(mcp_prompt_name(&prompt), "review");
+        assert_eq!(prompt.mcp_server.as_deref(), Some("github"));
+        assert_eq!(prompt.argument_hint.as_deref(), Some("<pr> [focus=...]"));
+        assert_eq!(prompt.description.as_deref(), Some("Review a pull request"));
+        let args = prompt.template_args.unwrap_or_default();
+        assert_eq!(
+            args.iter()
+                .map(|arg| (arg.name.as_str(), arg.required, arg.description.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("pr", true, Some("Pull request number")),
+                ("focus", false, Some("Focus area")),
+            ]
+        );
+    }
+
+    #[test]
+    fn prompt_messages_are_joined_into_one_message() {
+        let text = |text: &str| PromptMessage {
+            content: ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text: text.to_string(),
+                r#type: "text".to_string(),
+            }),
+            role: Role::User,
+        };
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![text("Review PR #12."), text("Focus on tests.")],
+        };
+
+        assert_eq!(
+            mcp_prompt_messages_to_text(&result),
+            "Review PR #12.\n\nFocus on tests."
+        );
+    }
+}
@@ -112,6 +112,20 @@ pub fn find_family_for_model(slug: &str) -> Option<ModelFamily> {
             supports_reasoning_summaries: true,
             needs_special_apply_patch_instructions: true,
         )
+    } else if slug.starts_with("claude-opus-4")
+        || slug.starts_with("claude-sonnet-4")
+        || slug.starts_with("claude-haiku-4")
+        || slug.starts_with("claude-3-7-sonnet")
+    {
+        // Claude models with extended thinking. The Messages API has no
+        // freeform tool type, so apply_patch must be a function tool.
+        model_family!(
+            slug, "claude",
+            supports_reasoning_summaries: true,
+            apply_patch_tool_type: Some(ApplyPatchToolType::Function),
+        )
+    } else if slug.starts_with("claude-") {
+        model_family!(slug, "claude", apply_patch_tool_type: Some(ApplyPatchToolType::Function))
     } else {
         None
     }
@@ -21,13 +21,16 @@ const DEFAULT_REQUEST_MAX_RETRIES: u64 = 4;
 const MAX_STREAM_MAX_RETRIES: u64 = 100;
 /// Hard cap for user-configured `request_max_retries`.
 const MAX_REQUEST_MAX_RETRIES: u64 = 100;
+/// Value sent in the `anthropic-version` header for the Messages API.
+const ANTHROPIC_API_VERSION: &str = "2023-06-01";
 
 /// Wire protocol that the provider speaks. Most third-party services only
 /// implement the classic OpenAI Chat Completions JSON schema, whereas OpenAI
 /// itself (and a handful of others) additionally expose the more modern
-/// *Responses* API. The two protocols use different request/response shapes
-/// and *cannot* be auto-detected at runtime, therefore each provider entry
-/// must declare which one it expects.
+/// *Responses* API, and Anthropic exposes its own *Messages* API. The
+/// protocols use different request/response shapes and *cannot* be
+/// auto-detected at runtime, therefore each provider entry must declare which
+/// one it expects.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum WireApi {
@@ -37,6 +40,10 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// The Anthropic Messages API exposed at `/v1/messages`.
+    #[serde(rename = "anthropic_messages")]
+    AnthropicMessages,
 }
 
 /// Serializable representation of a provider definition.
@@ -94,6 +101,8 @@ impl ModelProviderInfo {
     ///   • provider-specific headers (static + env based)
     ///   • Bearer auth header when an API key is available.
     ///   • Auth token for OAuth.
+    ///   • `x-api-key` + `anthropic-version` headers for the Anthropic
+    ///     Messages API.
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
     /// one produced by [`ModelProviderInfo::api_key`].
@@ -118,7 +127,12 @@ impl ModelProviderInfo {
 
         let mut builder = client.post(url);
 
-        if let Some(auth) = effective_auth.as_ref() {
+        if self.wire_api == WireApi::AnthropicMessages {
+            builder = builder.header("anthropic-version", ANTHROPIC_API_VERSION);
+            if let Some(auth) = effective_auth.as_ref() {
+                builder = builder.header("x-api-key", auth.get_token().await?);
+            }
+        } else if let Some(auth) = effective_auth.as_ref() {
             builder = builder.bearer_auth(auth.get_token().await?);
         }
 
@@ -139,7 +153,9 @@ impl ModelProviderInfo {
     }
 
     pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
+        let default_base_url = if self.wire_api == WireApi::AnthropicMessages {
+            "https://api.anthropic.com/v1"
+        } else if matches!(
             auth,
             Some(CodexAuth {
                 mode: AuthMode::ChatGPT,
@@ -159,6 +175,7 @@ impl ModelProviderInfo {
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::AnthropicMessages => format!("{base_url}/messages{query_string}"),
         }
     }
 
@@ -447,6 +464,36 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
         assert_eq!(expected_provider, provider);
     }
 
+    #[test]
+    fn test_deserialize_anthropic_model_provider_toml() {
+        let anthropic_provider_toml = r#"
+name = "Anthropic"
+env_key = "ANTHROPIC_API_KEY"
+wire_api = "anthropic_messages"
+        "#;
+        let expected_provider = ModelProviderInfo {
+            name: "Anthropic".into(),
+            base_url: None,
+            env_key: Some("ANTHROPIC_API_KEY".into()),
+            env_key_instructions: None,
+            wire_api: WireApi::AnthropicMessages,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: None,
+            stream_max_retries: None,
+            stream_idle_timeout_ms: None,
+            requires_openai_auth: false,
+        };
+
+        let provider: ModelProviderInfo = toml::from_str(anthropic_provider_toml).unwrap();
+        assert_eq!(expected_provider, provider);
+        assert_eq!(
+            provider.get_full_url(&None),
+            "https://api.anthropic.com/v1/messages"
+        );
+    }
+
     #[test]
     fn detects_azure_responses_base_urls() {
         fn provider_for(base_url: &str) -> ModelProviderInfo {
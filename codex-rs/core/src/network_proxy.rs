@@ -0,0 +1,659 @@
+//! Local HTTP proxy that gives sandboxed commands access to an allowlist of
+//! domains.
+//!
+//! When a `workspace-write` sandbox has a `network_allowlist`, sandboxed
+//! commands cannot open sockets except to this proxy on the loopback
+//! interface, and find it through the usual `HTTP(S)_PROXY` variables. The
+//! proxy understands `CONNECT host:port` (used for HTTPS) and plain HTTP
+//! requests in absolute form, and only connects to allowlisted hosts. Other
+//! requests get a `403 Forbidden` response and are recorded so they can be
+//! reported once the command finishes.
+//!
+//! One proxy serves every command of a session. Each command gets its own
+//! credentials in the proxy URL, which tools send as `Proxy-Authorization`,
+//! so requests are checked against the allowlist of the command that made
+//! them and blocked requests are reported to that command only. Requests
+//! without known credentials get a `407 Proxy Authentication Required`
+//! response.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use base64::Engine;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::spawn::CODEX_SANDBOX_NETWORK_PROXY_ENV_VAR;
+
+/// Requests with a longer head are refused.
+const MAX_REQUEST_HEAD_BYTES: usize = 64 * 1024;
+
+/// User name in the proxy URL; the password identifies the command.
+const PROXY_USER: &str = "codex";
+
+/// Proxy environment variables understood by common tools (curl, git, cargo,
+/// npm, pip, ...).
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
+
+/// Variables that would make tools bypass the proxy, and thus fail, for some
+/// hosts.
+const NO_PROXY_ENV_VARS: &[&str] = &["NO_PROXY", "no_proxy"];
+
+/// A request the proxy refused to forward.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlockedRequest {
+    pub host: String,
+    pub port: u16,
+}
+
+impl std::fmt::Display for BlockedRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+#[derive(Default)]
+struct ProxyState {
+    /// The commands using the proxy, keyed by the base64 credentials they
+    /// send in `Proxy-Authorization`.
+    clients: Mutex<HashMap<String, Arc<ClientState>>>,
+}
+
+impl ProxyState {
+    fn client(&self, credentials: &str) -> Option<Arc<ClientState>> {
+        #[expect(clippy::unwrap_used)]
+        let clients = self.clients.lock().unwrap();
+        clients.get(credentials).cloned()
+    }
+}
+
+/// What the proxy knows about one command.
+struct ClientState {
+    allowlist: Vec<String>,
+    blocked: Mutex<Vec<BlockedRequest>>,
+}
+
+impl ClientState {
+    fn is_allowed(&self, host: &str) -> bool {
+        self.allowlist
+            .iter()
+            .any(|pattern| domain_matches(pattern, host))
+    }
+
+    fn record_blocked(&self, host: &str, port: u16) {
+        #[expect(clippy::unwrap_used)]
+        let mut blocked = self.blocked.lock().unwrap();
+        let request = BlockedRequest {
+            host: host.to_string(),
+            port,
+        };
+        if !blocked.contains(&request) {
+            blocked.push(request);
+        }
+    }
+}
+
+/// A running proxy. It stops when dropped.
+pub(crate) struct NetworkProxy {
+    addr: SocketAddr,
+    state: Arc<ProxyState>,
+    accept_task: JoinHandle<()>,
+}
+
+impl NetworkProxy {
+    /// Starts the proxy on an ephemeral loopback port.
+    pub(crate) async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(ProxyState::default());
+        let accept_task = tokio::spawn({
+            let state = Arc::clone(&state);
+            async move {
+                loop {
+                    let (client, _) = match listener.accept().await {
+                        Ok(connection) => connection,
+                        Err(e) => {
+                            debug!("network proxy failed to accept a connection: {e}");
+                            continue;
+                        }
+                    };
+                    let state = Arc::clone(&state);
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(client, &state).await {
+                            debug!("network proxy connection failed: {e}");
+                        }
+                    });
+                }
+            }
+        });
+        Ok(Self {
+            addr,
+            state,
+            accept_task,
+        })
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Registers a command that may connect to the hosts in `allowlist`. The
+    /// command can use the proxy until the returned client is dropped.
+    pub(crate) fn register(&self, allowlist: &[String]) -> ProxyClient {
+        let password = Uuid::new_v4().simple().to_string();
+        let credentials =
+            base64::engine::general_purpose::STANDARD.encode(format!("{PROXY_USER}:{password}"));
+        let state = Arc::new(ClientState {
+            allowlist: allowlist.to_vec(),
+            blocked: Mutex::new(Vec::new()),
+        });
+        #[expect(clippy::unwrap_used)]
+        let mut clients = self.state.clients.lock().unwrap();
+        clients.insert(credentials.clone(), Arc::clone(&state));
+        ProxyClient {
+            addr: self.addr,
+            password,
+            credentials,
+            state,
+            proxy: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl Drop for NetworkProxy {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+/// A command's registration with the proxy. It is removed when dropped.
+pub(crate) struct ProxyClient {
+    addr: SocketAddr,
+    password: String,
+    credentials: String,
+    state: Arc<ClientState>,
+    proxy: Arc<ProxyState>,
+}
+
+impl ProxyClient {
+    /// Returns the requests blocked since the last call, once each.
+    pub(crate) fn take_blocked(&self) -> Vec<BlockedRequest> {
+        #[expect(clippy::unwrap_used)]
+        let mut blocked = self.state.blocked.lock().unwrap();
+        std::mem::take(&mut *blocked)
+    }
+
+    /// Points the proxy variables of `env` at the proxy, with the
+    /// credentials of this command.
+    pub(crate) fn apply_to_env(&self, env: &mut HashMap<String, String>) {
+        let url = format!("http://{PROXY_USER}:{}@{}", self.password, self.addr);
+        for var in PROXY_ENV_VARS {
+            env.insert((*var).to_string(), url.clone());
+        }
+        for var in NO_PROXY_ENV_VARS {
+            env.remove(*var);
+        }
+        env.insert(
+            CODEX_SANDBOX_NETWORK_PROXY_ENV_VAR.to_string(),
+            self.addr.to_string(),
+        );
+    }
+}
+
+impl Drop for ProxyClient {
+    fn drop(&mut self) {
+        #[expect(clippy::unwrap_used)]
+        let mut clients = self.proxy.clients.lock().unwrap();
+        clients.remove(&self.credentials);
+    }
+}
+
+/// Returns whether `host` matches `pattern`, where `*.example.com` matches
+/// the subdomains of `example.com` and anything else must match exactly.
+/// Comparison ignores ASCII case and a trailing dot.
+fn domain_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim_end_matches('.').to_ascii_lowercase();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.') && subdomain.len() > 1),
+        None => host == pattern,
+    }
+}
+
+/// What the client asked the proxy to do.
+#[derive(Debug, PartialEq, Eq)]
+struct ProxyRequest {
+    host: String,
+    port: u16,
+    /// The base64 credentials of a `Basic` `Proxy-Authorization` header.
+    credentials: Option<String>,
+    /// `None` for `CONNECT`, otherwise the request head rewritten to origin
+    /// form and without `Proxy-Authorization`, to send upstream.
+    forward_head: Option<Vec<u8>>,
+}
+
+async fn handle_connection(mut client: TcpStream, state: &ProxyState) -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    let head_len = loop {
+        if let Some(pos) = find_head_end(&buf) {
+            break pos;
+        }
+        if buf.len() > MAX_REQUEST_HEAD_BYTES {
+            return respond(&mut client, "431 Request Header Fields Too Large", "").await;
+        }
+        let mut chunk = [0u8; 4096];
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let (head, rest) = buf.split_at(head_len);
+
+    let request = match parse_request_head(head) {
+        Ok(request) => request,
+        Err(message) => return respond(&mut client, "400 Bad Request", message).await,
+    };
+    let Some(client_state) = request
+        .credentials
+        .as_deref()
+        .and_then(|credentials| state.client(credentials))
+    else {
+        let message = "Codex sandbox: the proxy URL is missing the command's credentials.\n";
+        return respond_with_headers(
+            &mut client,
+            "407 Proxy Authentication Required",
+            &format!("Proxy-Authenticate: Basic realm=\"{PROXY_USER}\"\r\n"),
+            message,
+        )
+        .await;
+    };
+    if !client_state.is_allowed(&request.host) {
+        client_state.record_blocked(&request.host, request.port);
+        let message = format!(
+            "Codex sandbox: {}:{} is not in the network allowlist.\n",
+            request.host, request.port
+        );
+        return respond(&mut client, "403 Forbidden", &message).await;
+    }
+
+    let mut upstream = match TcpStream::connect((request.host.as_str(), request.port)).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let message = format!(
+                "Codex sandbox: failed to connect to {}: {e}\n",
+                request.host
+            );
+            return respond(&mut client, "502 Bad Gateway", &message).await;
+        }
+    };
+    match &request.forward_head {
+        None => {
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await?;
+        }
+        Some(forward_head) => upstream.write_all(forward_head).await?,
+    }
+    upstream.write_all(rest).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+/// Returns the length of the request head, including the blank line that
+/// ends it.
+fn find_head_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|pos| pos + 4)
+}
+
+fn parse_request_head(head: &[u8]) -> Result<ProxyRequest, &'static str> {
+    let head = std::str::from_utf8(head).map_err(|_| "request head is not UTF-8")?;
+    let (request_line, headers) = head.split_once("\r\n").ok_or("missing request line")?;
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err("malformed request line");
+    };
+
+    let mut credentials = None;
+    let mut forwarded_headers = String::new();
+    for header in headers.split_inclusive("\r\n") {
+        match header.split_once(':') {
+            Some((name, value)) if name.eq_ignore_ascii_case("Proxy-Authorization") => {
+                credentials = value
+                    .trim()
+                    .strip_prefix("Basic ")
+                    .map(|credentials| credentials.trim().to_string());
+            }
+            _ => forwarded_headers.push_str(header),
+        }
+    }
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_host_port(target, None).ok_or("malformed CONNECT target")?;
+        return Ok(ProxyRequest {
+            host,
+            port,
+            credentials,
+            forward_head: None,
+        });
+    }
+
+    let rest = target
+        .strip_prefix("http://")
+        .ok_or("only http:// URLs and CONNECT are supported")?;
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(pos) => rest.split_at(pos),
+        None => (rest, "/"),
+    };
+    let path = if path.starts_with('?') {
+        format!("/{path}")
+    } else {
+        path.to_string()
+    };
+    let (host, port) = split_host_port(authority, Some(80)).ok_or("malformed request URL")?;
+    let forward_head = format!("{method} {path} {version}\r\n{forwarded_headers}");
+    Ok(ProxyRequest {
+        host,
+        port,
+        credentials,
+        forward_head: Some(forward_head.into_bytes()),
+    })
+}
+
+/// Splits `host:port`, with an optional `[...]` around IPv6 addresses. The
+/// port may only be omitted when there is a default.
+fn split_host_port(authority: &str, default_port: Option<u16>) -> Option<(String, u16)> {
+    if authority.contains('@') {
+        return None;
+    }
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(rest) => {
+            let (host, after) = rest.split_once(']')?;
+            (host, after.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port?,
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((host.to_string(), port))
+}
+
+async fn respond(client: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    respond_with_headers(client, status, "", body).await
+}
+
+/// Like [`respond`], with `headers` (each ending in `\r\n`) added to the
+/// response.
+async fn respond_with_headers(
+    client: &mut TcpStream,
+    status: &str,
+    headers: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\n{headers}Content-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    client.write_all(response.as_bytes()).await?;
+    client.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Serves a single HTTP response per connection and returns the
+    /// requests it received.
+    async fn start_upstream() -> (SocketAddr, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = Vec::new();
+                while find_head_end(&buf).is_none() {
+                    let mut chunk = [0u8; 1024];
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                tx.send(String::from_utf8(buf).unwrap()).unwrap();
+                stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                    )
+                    .await
+                    .unwrap();
+            }
+        });
+        (addr, rx)
+    }
+
+    /// Sends `request` with the credentials of `client` and returns the
+    /// response.
+    async fn send(client: &ProxyClient, request: &str) -> String {
+        let authorization = format!("\r\nProxy-Authorization: Basic {}\r\n", client.credentials);
+        send_raw(client.addr, &request.replacen("\r\n", &authorization, 1)).await
+    }
+
+    async fn send_raw(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn domain_patterns() {
+        assert!(domain_matches("crates.io", "crates.io"));
+        assert!(domain_matches("crates.io", "Crates.IO."));
+        assert!(!domain_matches("crates.io", "static.crates.io"));
+        assert!(domain_matches("*.crates.io", "static.crates.io"));
+        assert!(!domain_matches("*.crates.io", "crates.io"));
+        assert!(!domain_matches("*.crates.io", "evilcrates.io"));
+    }
+
+    #[test]
+    fn parses_connect_and_absolute_form_requests() {
+        assert_eq!(
+            parse_request_head(b"CONNECT crates.io:443 HTTP/1.1\r\nHost: crates.io\r\n\r\n"),
+            Ok(ProxyRequest {
+                host: "crates.io".to_string(),
+                port: 443,
+                credentials: None,
+                forward_head: None,
+            })
+        );
+        assert_eq!(
+            parse_request_head(
+                b"GET http://[::1]:8080?q=1 HTTP/1.1\r\nHost: x\r\nproxy-authorization: Basic Y29kZXg6MQ==\r\n\r\n"
+            ),
+            Ok(ProxyRequest {
+                host: "::1".to_string(),
+                port: 8080,
+                credentials: Some("Y29kZXg6MQ==".to_string()),
+                forward_head: Some(b"GET /?q=1 HTTP/1.1\r\nHost: x\r\n\r\n".to_vec()),
+            })
+        );
+        assert!(parse_request_head(b"GET /index.html HTTP/1.1\r\n\r\n").is_err());
+        assert!(parse_request_head(b"CONNECT crates.io HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn forwards_allowlisted_http_requests() {
+        let (upstream, mut requests) = start_upstream().await;
+        let proxy = NetworkProxy::start().await.unwrap();
+        let client = proxy.register(&["127.0.0.1".to_string()]);
+
+        let response = send(
+            &client,
+            &format!("GET http://{upstream}/index.html HTTP/1.1\r\nHost: {upstream}\r\n\r\n"),
+        )
+        .await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("hello"), "{response}");
+        assert_eq!(
+            requests.recv().await.unwrap(),
+            format!("GET /index.html HTTP/1.1\r\nHost: {upstream}\r\n\r\n")
+        );
+        assert_eq!(client.take_blocked(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn tunnels_allowlisted_connect_requests() {
+        let (upstream, mut requests) = start_upstream().await;
+        let proxy = NetworkProxy::start().await.unwrap();
+        let client = proxy.register(&["127.0.0.1".to_string()]);
+
+        let response = send(
+            &client,
+            &format!("CONNECT {upstream} HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\nHost: tunnel\r\n\r\n"),
+        )
+        .await;
+
+        assert!(
+            response.starts_with("HTTP/1.1 200 Connection Established\r\n\r\nHTTP/1.1 200 OK"),
+            "{response}"
+        );
+        assert_eq!(
+            requests.recv().await.unwrap(),
+            "GET / HTTP/1.1\r\nHost: tunnel\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn blocks_and_records_other_hosts() {
+        let (upstream, _requests) = start_upstream().await;
+        let proxy = NetworkProxy::start().await.unwrap();
+        let client = proxy.register(&["crates.io".to_string()]);
+
+        let connect = send(&client, &format!("CONNECT {upstream} HTTP/1.1\r\n\r\n")).await;
+        let get = send(
+            &client,
+            &format!("GET http://{upstream}/ HTTP/1.1\r\nHost: {upstream}\r\n\r\n"),
+        )
+        .await;
+
+        assert!(connect.starts_with("HTTP/1.1 403 Forbidden"), "{connect}");
+        assert!(get.starts_with("HTTP/1.1 403 Forbidden"), "{get}");
+        assert_eq!(
+            client.take_blocked(),
+            vec![BlockedRequest {
+                host: "127.0.0.1".to_string(),
+                port: upstream.port(),
+            }]
+        );
+        assert_eq!(client.take_blocked(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn keeps_concurrent_commands_apart() {
+        let (upstream, mut requests) = start_upstream().await;
+        let proxy = NetworkProxy::start().await.unwrap();
+        let allowed = proxy.register(&["127.0.0.1".to_string()]);
+        let restricted = proxy.register(&["crates.io".to_string()]);
+
+        let request = format!("GET http://{upstream}/ HTTP/1.1\r\nHost: {upstream}\r\n\r\n");
+        let (allowed_response, restricted_response) =
+            tokio::join!(send(&allowed, &request), send(&restricted, &request));
+
+        assert!(
+            allowed_response.starts_with("HTTP/1.1 200 OK"),
+            "{allowed_response}"
+        );
+        assert!(
+            restricted_response.starts_with("HTTP/1.1 403 Forbidden"),
+            "{restricted_response}"
+        );
+        // The credentials are not forwarded upstream.
+        assert_eq!(
+            requests.recv().await.unwrap(),
+            format!("GET / HTTP/1.1\r\nHost: {upstream}\r\n\r\n")
+        );
+        assert_eq!(allowed.take_blocked(), vec![]);
+        assert_eq!(
+            restricted.take_blocked(),
+            vec![BlockedRequest {
+                host: "127.0.0.1".to_string(),
+                port: upstream.port(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn requires_the_credentials_of_a_registered_command() {
+        let (upstream, _requests) = start_upstream().await;
+        let proxy = NetworkProxy::start().await.unwrap();
+        let client = proxy.register(&["127.0.0.1".to_string()]);
+        let request = format!("CONNECT {upstream} HTTP/1.1\r\n\r\n");
+
+        let anonymous = send_raw(proxy.addr(), &request).await;
+        assert!(
+            anonymous.starts_with("HTTP/1.1 407 Proxy Authentication Required"),
+            "{anonymous}"
+        );
+
+        let authorization = format!(
+            "CONNECT {upstream} HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\n",
+            client.credentials
+        );
+        drop(client);
+        let unregistered = send_raw(proxy.addr(), &authorization).await;
+        assert!(
+            unregistered.starts_with("HTTP/1.1 407 Proxy Authentication Required"),
+            "{unregistered}"
+        );
+    }
+
+    #[tokio::test]
+    async fn points_proxy_variables_at_the_proxy() {
+        let proxy = NetworkProxy::start().await.unwrap();
+        let client = proxy.register(&[]);
+        let mut env = HashMap::from([("NO_PROXY".to_string(), "github.com".to_string())]);
+
+        client.apply_to_env(&mut env);
+
+        let url = format!("http://codex:{}@{}", client.password, proxy.addr());
+        assert_eq!(env.get("HTTPS_PROXY"), Some(&url));
+        assert_eq!(env.get("http_proxy"), Some(&url));
+        assert_eq!(env.get("NO_PROXY"), None);
+        assert_eq!(
+            env.get(CODEX_SANDBOX_NETWORK_PROXY_ENV_VAR),
+            Some(&proxy.addr().to_string())
+        );
+    }
+}
@@ -72,6 +72,18 @@ pub(crate) fn get_model_info(model_family: &ModelFamily) -> Option<ModelInfo> {
 
         _ if slug.starts_with("codex-") => Some(ModelInfo::new(272_000, 128_000)),
 
+        // https://docs.anthropic.com/en/docs/about-claude/models/overview
+        _ if slug.starts_with("claude-opus-4") => Some(ModelInfo::new(200_000, 32_000)),
+
+        _ if slug.starts_with("claude-sonnet-4")
+            || slug.starts_with("claude-haiku-4")
+            || slug.starts_with("claude-3-7-sonnet") =>
+        {
+            Some(ModelInfo::new(200_000, 64_000))
+        }
+
+        _ if slug.starts_with("claude-") => Some(ModelInfo::new(200_000, 8_192)),
+
         _ => None,
     }
 }
@@ -6,7 +6,9 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 
 use crate::model_family::ModelFamily;
+use crate::output_artifacts::READ_OUTPUT_TOOL_NAME;
 use crate::plan_tool::PLAN_TOOL;
+use crate::subagents::SubagentDefinition;
 use crate::tool_apply_patch::ApplyPatchToolType;
 use crate::tool_apply_patch::create_apply_patch_freeform_tool;
 use crate::tool_apply_patch::create_apply_patch_json_tool;
@@ -53,6 +55,19 @@ pub(crate) enum OpenAiTool {
     Freeform(FreeformTool),
 }
 
+impl OpenAiTool {
+    /// Name the model uses to call this tool. The built-in local shell tool is
+    /// called `shell`, like the function-based shell tool.
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            OpenAiTool::Function(tool) => &tool.name,
+            OpenAiTool::LocalShell {} => "shell",
+            OpenAiTool::WebSearch {} => "web_search",
+            OpenAiTool::Freeform(tool) => &tool.name,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ConfigShellToolType {
     Default,
@@ -68,6 +83,13 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub experimental_unified_exec_tool: bool,
+    /// Offer `bg_start`, `bg_logs` and `bg_stop` for long-running processes.
+    pub background_process_tools: bool,
+    /// Offer `read_output` for the full output of truncated commands.
+    pub read_output_tool: bool,
+    /// Subagents offered through the `task` tool. Empty for subagent and
+    /// review threads, which cannot delegate further.
+    pub subagents: Vec<SubagentDefinition>,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -78,6 +100,8 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
+    pub(crate) include_background_process_tools: bool,
+    pub(crate) include_read_output_tool: bool,
 }
 
 impl ToolsConfig {
@@ -90,6 +114,8 @@ impl ToolsConfig {
             use_streamable_shell_tool,
             include_view_image_tool,
             experimental_unified_exec_tool,
+            include_background_process_tools,
+            include_read_output_tool,
         } = params;
         let shell_type = if *use_streamable_shell_tool {
             ConfigShellToolType::Streamable
@@ -118,6 +144,9 @@ impl ToolsConfig {
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
+            background_process_tools: *include_background_process_tools,
+            read_output_tool: *include_read_output_tool,
+            subagents: Vec::new(),
         }
     }
 }
@@ -252,6 +281,52 @@ fn create_shell_tool() -> OpenAiTool {
     })
 }
 
+fn create_read_output_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "handle".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Handle of the saved output, as given in the truncated command output (e.g. output-1)"
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "offset".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Number of lines to skip, or of matching lines when grep is set (default 0)"
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "limit".to_string(),
+        JsonSchema::Number {
+            description: Some("Maximum number of lines to return (default 200)".to_string()),
+        },
+    );
+    properties.insert(
+        "grep".to_string(),
+        JsonSchema::String {
+            description: Some("Only return lines matching this regular expression".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_OUTPUT_TOOL_NAME.to_string(),
+        description: "Read the full output of a command whose output was truncated. Lines are prefixed with their line number; use grep to find the interesting part of a long log, then offset to read around it."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["handle".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
 fn create_view_image_tool() -> OpenAiTool {
     // Support only local filesystem path.
     let mut properties = BTreeMap::new();
@@ -275,6 +350,50 @@ fn create_view_image_tool() -> OpenAiTool {
         },
     })
 }
+
+pub(crate) const TASK_TOOL_NAME: &str = "task";
+
+fn create_task_tool(subagents: &[SubagentDefinition]) -> OpenAiTool {
+    let agents = subagents
+        .iter()
+        .map(|agent| match &agent.description {
+            Some(description) => format!("- {}: {description}", agent.name),
+            None => format!("- {}", agent.name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "agent".to_string(),
+        JsonSchema::String {
+            description: Some("Name of the subagent to hand the task to".to_string()),
+        },
+    );
+    properties.insert(
+        "prompt".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Complete description of the task; the subagent does not see this conversation"
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: TASK_TOOL_NAME.to_string(),
+        description: format!(
+            "Hand a self-contained task to a subagent and wait for its final report. The subagent works in a fresh conversation with its own instructions and tools. Available subagents:\n{agents}"
+        ),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["agent".to_string(), "prompt".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
 /// TODO(dylan): deprecate once we get rid of json tool
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ApplyPatchToolArgs {
@@ -503,6 +622,22 @@ pub(crate) fn get_openai_tools(
         }
     }
 
+    if config.background_process_tools {
+        tools.push(OpenAiTool::Function(
+            crate::exec_command::create_bg_start_tool_for_responses_api(),
+        ));
+        tools.push(OpenAiTool::Function(
+            crate::exec_command::create_bg_logs_tool_for_responses_api(),
+        ));
+        tools.push(OpenAiTool::Function(
+            crate::exec_command::create_bg_stop_tool_for_responses_api(),
+        ));
+    }
+
+    if config.read_output_tool {
+        tools.push(create_read_output_tool());
+    }
+
     if config.plan_tool {
         tools.push(PLAN_TOOL.clone());
     }
@@ -526,6 +661,10 @@ pub(crate) fn get_openai_tools(
     if config.include_view_image_tool {
         tools.push(create_view_image_tool());
     }
+
+    if !config.subagents.is_empty() {
+        tools.push(create_task_tool(&config.subagents));
+    }
     if let Some(mcp_tools) = mcp_tools {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
@@ -549,6 +688,7 @@ mod tests {
     use crate::model_family::find_family_for_model;
     use mcp_types::ToolInputSchema;
     use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
 
     use super::*;
 
@@ -588,6 +728,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -597,6 +739,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn background_process_tools_follow_the_shell_tool() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: true,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: false,
+            include_background_process_tools: true,
+            include_read_output_tool: false,
+        });
+
+        assert_eq_tool_names(
+            &get_openai_tools(&config, None),
+            &["shell", "bg_start", "bg_logs", "bg_stop", "update_plan"],
+        );
+    }
+
+    #[test]
+    fn read_output_tool_follows_the_shell_tools() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: true,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: true,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: false,
+            include_background_process_tools: false,
+            include_read_output_tool: true,
+        });
+
+        assert_eq_tool_names(
+            &get_openai_tools(&config, None),
+            &["exec_command", "write_stdin", "read_output", "update_plan"],
+        );
+    }
+
+    #[test]
+    fn task_tool_lists_subagents() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let mut config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: false,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
+        });
+        assert_eq_tool_names(&get_openai_tools(&config, None), &["shell"]);
+
+        config.subagents = vec![SubagentDefinition {
+            name: "reviewer".to_string(),
+            path: PathBuf::from(".codex/agents/reviewer.md"),
+            description: Some("Reviews diffs".to_string()),
+            overrides: Default::default(),
+            instructions: "You review code.".to_string(),
+        }];
+        let tools = get_openai_tools(&config, None);
+        assert_eq_tool_names(&tools, &["shell", "task"]);
+        let OpenAiTool::Function(ResponsesApiTool { description, .. }) = &tools[1] else {
+            panic!("expected a function tool");
+        };
+        assert!(
+            description.ends_with("Available subagents:\n- reviewer: Reviews diffs"),
+            "{description}"
+        );
+    }
+
     #[test]
     fn test_get_openai_tools_default_shell() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -608,6 +826,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -628,6 +848,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
         let tools = get_openai_tools(
             &config,
@@ -732,6 +954,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
 
         // Intentionally construct a map with keys that would sort alphabetically.
@@ -808,6 +1032,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -869,6 +1095,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -925,6 +1153,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -984,6 +1214,8 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_background_process_tools: false,
+            include_read_output_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -0,0 +1,621 @@
+//! Full output of commands whose output was truncated for the model. It is
+//! saved under `CODEX_HOME/artifacts/<conversation id>/`, and the model pages
+//! through or searches it with the `read_output` tool using the handle shown
+//! in the truncated output.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use regex_lite::Regex;
+use serde::Deserialize;
+use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncSeekExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::truncate::truncate_middle;
+
+pub(crate) const READ_OUTPUT_TOOL_NAME: &str = "read_output";
+
+/// Directory under `CODEX_HOME` that holds the saved outputs, one
+/// subdirectory per conversation.
+pub(crate) const ARTIFACTS_SUBDIR: &str = "artifacts";
+
+/// Lines returned by `read_output` when the model does not pass a limit.
+const DEFAULT_READ_LIMIT: usize = 200;
+
+/// Cap on the size of a `read_output` response, so that a page of very long
+/// lines cannot flood the context either.
+const MAX_READ_BYTES: usize = 16 * 1024;
+
+const HANDLE_PREFIX: &str = "output-";
+
+/// The outputs of conversations that have not saved any for this long are
+/// deleted when another conversation first saves output.
+const MAX_ARTIFACT_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Once the outputs of a conversation take more than this, the oldest ones
+/// are deleted.
+const MAX_CONVERSATION_ARTIFACT_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Where every this many lines start is recorded in a [`LineIndex`], so a
+/// page is read by seeking close to it instead of from the start.
+const LINE_INDEX_STRIDE: usize = 256;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadOutputArgs {
+    handle: String,
+    /// Lines (or matching lines, with `grep`) to skip.
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    grep: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct OutputArtifacts {
+    dir: PathBuf,
+    next_id: Mutex<u64>,
+    /// Line indexes of the outputs read or saved so far, by handle.
+    line_indexes: Mutex<HashMap<String, Arc<LineIndex>>>,
+    pruned_old_conversations: AtomicBool,
+    /// [`MAX_CONVERSATION_ARTIFACT_BYTES`], except in tests.
+    max_conversation_bytes: u64,
+}
+
+impl OutputArtifacts {
+    pub(crate) fn new(codex_home: &Path, conversation_id: ConversationId) -> Self {
+        Self {
+            dir: codex_home
+                .join(ARTIFACTS_SUBDIR)
+                .join(conversation_id.to_string()),
+            next_id: Mutex::new(1),
+            line_indexes: Mutex::new(HashMap::new()),
+            pruned_old_conversations: AtomicBool::new(false),
+            max_conversation_bytes: MAX_CONVERSATION_ARTIFACT_BYTES,
+        }
+    }
+
+    /// Saves `output` and returns the handle to pass to `read_output`. The
+    /// file is only readable by the user, as output may contain secrets.
+    pub(crate) async fn save(&self, output: &str) -> std::io::Result<String> {
+        if !self.pruned_old_conversations.swap(true, Ordering::Relaxed) {
+            prune_old_conversations(&self.dir).await;
+        }
+        let mut dir_builder = tokio::fs::DirBuilder::new();
+        dir_builder.recursive(true);
+        #[cfg(unix)]
+        dir_builder.mode(0o700);
+        dir_builder.create(&self.dir).await?;
+
+        let mut next_id = self.next_id.lock().await;
+        // A resumed conversation writes to the same directory, so skip the
+        // handles that are already taken rather than overwrite them.
+        loop {
+            let handle = format!("{HANDLE_PREFIX}{next_id}");
+            *next_id += 1;
+            let mut options = tokio::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            let file = options.open(self.dir.join(format!("{handle}.log"))).await;
+            match file {
+                Ok(mut file) => {
+                    file.write_all(output.as_bytes()).await?;
+                    file.flush().await?;
+                    self.line_indexes
+                        .lock()
+                        .await
+                        .insert(handle.clone(), Arc::new(LineIndex::of(output.as_bytes())));
+                    self.delete_oldest_outputs_over_limit(&handle).await;
+                    return Ok(handle);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Handles a `read_output` call: a page of the saved output, or of the
+    /// lines matching `grep`, each prefixed with its line number. Only the
+    /// page is read, except with `grep`, which has to look at every line.
+    pub(crate) async fn read(&self, args: ReadOutputArgs) -> Result<String, String> {
+        let ReadOutputArgs {
+            handle,
+            offset,
+            limit,
+            grep,
+        } = args;
+        let path = self
+            .path_for(&handle)
+            .ok_or_else(|| format!("invalid output handle `{handle}`"))?;
+        let file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(format!("no saved output for handle `{handle}`"));
+            }
+            Err(e) => return Err(format!("failed to read output `{handle}`: {e}")),
+        };
+        let pattern = grep
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("invalid grep pattern: {e}"))?;
+        let limit = limit.unwrap_or(DEFAULT_READ_LIMIT);
+        let mut reader = BufReader::new(file);
+        let page = match &pattern {
+            Some(pattern) => read_matching_lines(&mut reader, pattern, offset, limit).await,
+            None => match self.line_index(&handle, &mut reader).await {
+                Ok(index) => read_lines(&mut reader, &index, offset, limit).await,
+                Err(e) => Err(e),
+            },
+        }
+        .map_err(|e| format!("failed to read output `{handle}`: {e}"))?;
+        Ok(format_page(&handle, &page, offset, pattern.is_some()))
+    }
+
+    /// Path of the file behind `handle`. Handles come from the model, so
+    /// only the ones [`Self::save`] hands out are accepted.
+    fn path_for(&self, handle: &str) -> Option<PathBuf> {
+        let id = handle.strip_prefix(HANDLE_PREFIX)?;
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(self.dir.join(format!("{handle}.log")))
+    }
+
+    /// The line index of the output behind `handle`, built by scanning
+    /// `reader` once if this conversation has not saved or read it yet.
+    async fn line_index(
+        &self,
+        handle: &str,
+        reader: &mut BufReader<tokio::fs::File>,
+    ) -> std::io::Result<Arc<LineIndex>> {
+        if let Some(index) = self.line_indexes.lock().await.get(handle) {
+            return Ok(Arc::clone(index));
+        }
+        let mut index = LineIndex::default();
+        let mut position = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line).await?;
+            if read == 0 {
+                break;
+            }
+            index.push_line(position);
+            position += read as u64;
+        }
+        let index = Arc::new(index);
+        self.line_indexes
+            .lock()
+            .await
+            .insert(handle.to_string(), Arc::clone(&index));
+        Ok(index)
+    }
+
+    /// Deletes the oldest outputs of this conversation until they take no
+    /// more than `max_conversation_bytes`, keeping `newest`.
+    async fn delete_oldest_outputs_over_limit(&self, newest: &str) {
+        let mut outputs = match saved_outputs(&self.dir).await {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                warn!(
+                    "failed to list saved outputs in {}: {e}",
+                    self.dir.display()
+                );
+                return;
+            }
+        };
+        outputs.sort_by_key(|output| output.id);
+        let mut total: u64 = outputs.iter().map(|output| output.len).sum();
+        for output in outputs {
+            if total <= self.max_conversation_bytes {
+                break;
+            }
+            let handle = format!("{HANDLE_PREFIX}{}", output.id);
+            if handle == newest {
+                continue;
+            }
+            match tokio::fs::remove_file(&output.path).await {
+                Ok(()) => {
+                    total -= output.len;
+                    self.line_indexes.lock().await.remove(&handle);
+                }
+                Err(e) => warn!("failed to delete {}: {e}", output.path.display()),
+            }
+        }
+    }
+}
+
+/// Note added to truncated output telling the model where the rest went.
+pub(crate) fn saved_output_note(handle: &str) -> String {
+    format!("full output saved as {handle}, use {READ_OUTPUT_TOOL_NAME} to page or search it")
+}
+
+/// Where the lines of a saved output start, recorded for every
+/// [`LINE_INDEX_STRIDE`]th line. Lines are split like [`str::lines`].
+#[derive(Debug, Default)]
+struct LineIndex {
+    /// Byte offsets of lines `0`, `LINE_INDEX_STRIDE`, `2 * LINE_INDEX_STRIDE`
+    /// and so on.
+    sampled_starts: Vec<u64>,
+    total_lines: usize,
+}
+
+impl LineIndex {
+    fn of(content: &[u8]) -> Self {
+        let mut index = Self::default();
+        let mut start = 0;
+        for (position, byte) in content.iter().enumerate() {
+            if *byte == b'\n' {
+                index.push_line(start as u64);
+                start = position + 1;
+            }
+        }
+        if start < content.len() {
+            index.push_line(start as u64);
+        }
+        index
+    }
+
+    fn push_line(&mut self, start: u64) {
+        if self.total_lines.is_multiple_of(LINE_INDEX_STRIDE) {
+            self.sampled_starts.push(start);
+        }
+        self.total_lines += 1;
+    }
+}
+
+/// Lines of a saved output with their 1-based line numbers, and how many
+/// lines (or matching lines) there are in total.
+struct Page {
+    lines: Vec<(usize, String)>,
+    total: usize,
+}
+
+/// Reads the lines `offset..offset + limit` by seeking to the closest line
+/// recorded in `index`.
+async fn read_lines(
+    reader: &mut BufReader<tokio::fs::File>,
+    index: &LineIndex,
+    offset: usize,
+    limit: usize,
+) -> std::io::Result<Page> {
+    let total = index.total_lines;
+    let mut lines = Vec::new();
+    if offset < total {
+        let sample = offset / LINE_INDEX_STRIDE;
+        reader
+            .seek(std::io::SeekFrom::Start(index.sampled_starts[sample]))
+            .await?;
+        let mut number = sample * LINE_INDEX_STRIDE;
+        let end = offset.saturating_add(limit).min(total);
+        while number < end {
+            let Some(line) = next_line(reader).await? else {
+                break;
+            };
+            if number >= offset {
+                lines.push((number + 1, line));
+            }
+            number += 1;
+        }
+    }
+    Ok(Page { lines, total })
+}
+
+/// Reads every line, keeping the matching lines `offset..offset + limit`.
+async fn read_matching_lines<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    pattern: &Regex,
+    offset: usize,
+    limit: usize,
+) -> std::io::Result<Page> {
+    let mut lines = Vec::new();
+    let mut total = 0;
+    let mut number = 0;
+    while let Some(line) = next_line(reader).await? {
+        number += 1;
+        if !pattern.is_match(&line) {
+            continue;
+        }
+        if total >= offset && total - offset < limit {
+            lines.push((number, line));
+        }
+        total += 1;
+    }
+    Ok(Page { lines, total })
+}
+
+/// The next line without its line ending, or `None` at the end of the file.
+async fn next_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> std::io::Result<Option<String>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line).await? == 0 {
+        return Ok(None);
+    }
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
+
+fn format_page(handle: &str, page: &Page, offset: usize, grep: bool) -> String {
+    let kind = if grep { "matching lines" } else { "lines" };
+    let total = page.total;
+    if total == 0 {
+        return format!("{handle} has no {kind}.");
+    }
+    if offset >= total {
+        return format!("{handle} has only {total} {kind}.");
+    }
+
+    let end = offset + page.lines.len();
+    let mut text_lines = String::new();
+    for (number, line) in &page.lines {
+        text_lines.push_str(&format!("{number}: {line}\n"));
+    }
+    let (text_lines, _) = truncate_middle(&text_lines, MAX_READ_BYTES);
+
+    let mut text = format!("{handle}: {kind} {}-{end} of {total}", offset + 1);
+    if end < total {
+        text.push_str(&format!(" (pass offset={end} for more)"));
+    }
+    text.push_str(":\n");
+    text.push_str(&text_lines);
+    text
+}
+
+struct SavedOutput {
+    id: u64,
+    path: PathBuf,
+    len: u64,
+}
+
+async fn saved_outputs(dir: &Path) -> std::io::Result<Vec<SavedOutput>> {
+    let mut outputs = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name();
+        let Some(id) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(HANDLE_PREFIX))
+            .and_then(|name| name.strip_suffix(".log"))
+            .and_then(|id| id.parse().ok())
+        else {
+            continue;
+        };
+        let len = entry.metadata().await?.len();
+        outputs.push(SavedOutput {
+            id,
+            path: entry.path(),
+            len,
+        });
+    }
+    Ok(outputs)
+}
+
+/// Deletes the outputs of the other conversations that have not saved any
+/// for [`MAX_ARTIFACT_AGE`].
+async fn prune_old_conversations(current_dir: &Path) {
+    let Some(artifacts_dir) = current_dir.parent() else {
+        return;
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(artifacts_dir).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path == current_dir {
+            continue;
+        }
+        let is_old = match entry
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified())
+        {
+            Ok(modified) => modified
+                .elapsed()
+                .is_ok_and(|elapsed| elapsed > MAX_ARTIFACT_AGE),
+            Err(_) => false,
+        };
+        if is_old && let Err(e) = tokio::fs::remove_dir_all(&path).await {
+            warn!("failed to delete old outputs in {}: {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn read_args(handle: &str, offset: usize, limit: usize, grep: Option<&str>) -> ReadOutputArgs {
+        ReadOutputArgs {
+            handle: handle.to_string(),
+            offset,
+            limit: Some(limit),
+            grep: grep.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn saved_output_can_be_paged_and_searched() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let artifacts = OutputArtifacts::new(codex_home.path(), ConversationId::default());
+        let output: String = (1..=10).map(|n| format!("line {n}\n")).collect();
+
+        let handle = artifacts.save(&output).await.unwrap();
+        assert_eq!("output-1", handle);
+
+        assert_eq!(
+            "output-1: lines 3-4 of 10 (pass offset=4 for more):\n3: line 3\n4: line 4\n",
+            artifacts
+                .read(read_args(&handle, 2, 2, None))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            "output-1: matching lines 1-2 of 2:\n1: line 1\n10: line 10\n",
+            artifacts
+                .read(read_args(&handle, 0, 10, Some("line 1")))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            "output-1 has only 10 lines.",
+            artifacts
+                .read(read_args(&handle, 10, 5, None))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn resumed_conversation_does_not_overwrite_saved_output() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let conversation_id = ConversationId::default();
+        let first = OutputArtifacts::new(codex_home.path(), conversation_id);
+        assert_eq!("output-1", first.save("before\n").await.unwrap());
+
+        let resumed = OutputArtifacts::new(codex_home.path(), conversation_id);
+        assert_eq!("output-2", resumed.save("after\n").await.unwrap());
+        assert_eq!(
+            "output-1: lines 1-1 of 1:\n1: before\n",
+            resumed
+                .read(read_args("output-1", 0, 10, None))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn handles_outside_the_artifacts_directory_are_rejected() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let artifacts = OutputArtifacts::new(codex_home.path(), ConversationId::default());
+
+        assert_eq!(
+            Err("invalid output handle `output-../../auth`".to_string()),
+            artifacts
+                .read(read_args("output-../../auth", 0, 10, None))
+                .await
+        );
+        assert_eq!(
+            Err("no saved output for handle `output-7`".to_string()),
+            artifacts.read(read_args("output-7", 0, 10, None)).await
+        );
+    }
+
+    #[tokio::test]
+    async fn long_output_is_paged_from_the_middle() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let conversation_id = ConversationId::default();
+        let artifacts = OutputArtifacts::new(codex_home.path(), conversation_id);
+        let output: String = (1..=1000).map(|n| format!("line {n}\r\n")).collect();
+        let handle = artifacts.save(&output).await.unwrap();
+        let expected = "output-1: lines 600-601 of 1000 (pass offset=601 for more):\n600: line 600\n601: line 601\n";
+
+        assert_eq!(
+            expected,
+            artifacts
+                .read(read_args(&handle, 599, 2, None))
+                .await
+                .unwrap()
+        );
+        // A resumed conversation indexes the file when it first reads it.
+        let resumed = OutputArtifacts::new(codex_home.path(), conversation_id);
+        assert_eq!(
+            expected,
+            resumed
+                .read(read_args(&handle, 599, 2, None))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            "output-1: lines 1000-1000 of 1000:\n1000: line 1000\n",
+            resumed
+                .read(read_args(&handle, 999, 10, None))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn saved_output_is_private_to_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let codex_home = tempfile::tempdir().unwrap();
+        let artifacts = OutputArtifacts::new(codex_home.path(), ConversationId::default());
+        let handle = artifacts.save("secret\n").await.unwrap();
+
+        let mode = |path: PathBuf| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(0o700, mode(artifacts.dir.clone()));
+        assert_eq!(0o600, mode(artifacts.path_for(&handle).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn oldest_outputs_are_deleted_over_the_size_limit() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let artifacts = OutputArtifacts {
+            max_conversation_bytes: 10,
+            ..OutputArtifacts::new(codex_home.path(), ConversationId::default())
+        };
+        assert_eq!("output-1", artifacts.save("12345\n").await.unwrap());
+        assert_eq!("output-2", artifacts.save("12345\n").await.unwrap());
+        assert_eq!(
+            "output-3",
+            artifacts.save("too long to keep more\n").await.unwrap()
+        );
+
+        for handle in ["output-1", "output-2"] {
+            assert_eq!(
+                Err(format!("no saved output for handle `{handle}`")),
+                artifacts.read(read_args(handle, 0, 10, None)).await
+            );
+        }
+        assert_eq!(
+            "output-3: lines 1-1 of 1:\n1: too long to keep more\n",
+            artifacts
+                .read(read_args("output-3", 0, 10, None))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn outputs_of_old_conversations_are_deleted() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let old = OutputArtifacts::new(codex_home.path(), ConversationId::default());
+        let recent = OutputArtifacts::new(codex_home.path(), ConversationId::default());
+        old.save("old\n").await.unwrap();
+        recent.save("recent\n").await.unwrap();
+        let long_ago = std::time::SystemTime::now() - MAX_ARTIFACT_AGE - Duration::from_secs(60);
+        std::fs::File::open(&old.dir)
+            .unwrap()
+            .set_modified(long_ago)
+            .unwrap();
+
+        let current = OutputArtifacts::new(codex_home.path(), ConversationId::default());
+        current.save("current\n").await.unwrap();
+
+        assert!(!old.dir.exists());
+        assert!(recent.dir.exists());
+        assert!(current.dir.exists());
+    }
+}
@@ -0,0 +1,454 @@
+//! Permission rules that approve or reject commands and edits without asking.
+//!
+//! Rules are read from the `[permissions]` table of `config.toml` and from
+//! `[projects."<path>".permissions]` for the project containing the working
+//! directory:
+//!
+//! ```toml
+//! [permissions]
+//! allow = ["Bash(cargo test:*)", "Bash(git status)", "Edit(src/**)"]
+//! deny = ["Bash(rm -rf:*)"]
+//! ```
+//!
+//! - `Bash(<command>)` matches exactly that command.
+//! - `Bash(<prefix>:*)` matches commands whose words start with `<prefix>`.
+//! - `Edit(<glob>)` matches edits to paths matching `<glob>`, relative to the
+//!   working directory unless absolute.
+//!
+//! Deny rules take precedence over allow rules. Allowed commands skip the
+//! approval prompt, not the sandbox.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use wildmatch::WildMatchPattern;
+
+use crate::bash::try_parse_bash;
+use crate::bash::try_parse_word_only_commands_sequence;
+use crate::config_types::PermissionsToml;
+use crate::git_info::resolve_root_git_project_for_trust;
+use crate::safety::normalize_path;
+
+const PREFIX_SUFFIX: &str = ":*";
+
+/// A single `Bash(...)` or `Edit(...)` rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermissionRule {
+    Bash { words: Vec<String>, prefix: bool },
+    Edit { pattern: String },
+}
+
+impl PermissionRule {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        if let Some(inner) = strip_rule_kind(rule, "Bash") {
+            let (command, prefix) = match inner.strip_suffix(PREFIX_SUFFIX) {
+                Some(command) => (command, true),
+                None => (inner, false),
+            };
+            let words = shlex::split(command)
+                .filter(|words| !words.is_empty())
+                .ok_or_else(|| format!("invalid permission rule `{rule}`: expected a command"))?;
+            Ok(Self::Bash { words, prefix })
+        } else if let Some(pattern) = strip_rule_kind(rule, "Edit") {
+            if pattern.trim().is_empty() {
+                return Err(format!(
+                    "invalid permission rule `{rule}`: expected a path glob"
+                ));
+            }
+            Ok(Self::Edit {
+                pattern: pattern.trim().to_string(),
+            })
+        } else {
+            Err(format!(
+                "invalid permission rule `{rule}`: expected `Bash(<command>)`, `Bash(<prefix>:*)` or `Edit(<glob>)`"
+            ))
+        }
+    }
+
+    fn matches_command(&self, command: &[String]) -> bool {
+        match self {
+            Self::Bash {
+                words,
+                prefix: true,
+            } => command.starts_with(words),
+            Self::Bash {
+                words,
+                prefix: false,
+            } => command == words.as_slice(),
+            Self::Edit { .. } => false,
+        }
+    }
+
+    fn matches_path(&self, path: &Path, cwd: &Path) -> bool {
+        let Self::Edit { pattern } = self else {
+            return false;
+        };
+        let Some(pattern) = normalize_path(&cwd.join(pattern)) else {
+            return false;
+        };
+        let Some(path) = normalize_path(&cwd.join(path)) else {
+            return false;
+        };
+        WildMatchPattern::<'*', '?'>::new(&pattern.to_string_lossy())
+            .matches(&path.to_string_lossy())
+    }
+}
+
+impl fmt::Display for PermissionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bash { words, prefix } => {
+                let command = shlex::try_join(words.iter().map(String::as_str))
+                    .unwrap_or_else(|_| words.join(" "));
+                let suffix = if *prefix { PREFIX_SUFFIX } else { "" };
+                write!(f, "Bash({command}{suffix})")
+            }
+            Self::Edit { pattern } => write!(f, "Edit({pattern})"),
+        }
+    }
+}
+
+fn strip_rule_kind<'a>(rule: &'a str, kind: &str) -> Option<&'a str> {
+    rule.strip_prefix(kind)?
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+/// How the permission rules classify a command or an edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PermissionVerdict {
+    Allow,
+    Deny {
+        rule: String,
+    },
+    /// No rule decides; the usual approval flow applies.
+    Ask,
+}
+
+/// The allow and deny rules in effect for a session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PermissionRules {
+    allow: Vec<PermissionRule>,
+    deny: Vec<PermissionRule>,
+}
+
+impl PermissionRules {
+    /// Parse the rules of every `[permissions]` table that applies, in order.
+    pub fn from_toml<'a>(
+        tables: impl IntoIterator<Item = &'a PermissionsToml>,
+    ) -> Result<Self, String> {
+        let mut rules = Self::default();
+        for table in tables {
+            for rule in &table.allow {
+                rules.allow.push(PermissionRule::parse(rule)?);
+            }
+            for rule in &table.deny {
+                rules.deny.push(PermissionRule::parse(rule)?);
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    pub fn add_allow(&mut self, rule: PermissionRule) {
+        if !self.allow.contains(&rule) {
+            self.allow.push(rule);
+        }
+    }
+
+    /// Classify `command`. Commands of the form `bash -lc "<script>"` are
+    /// allowed only if every command in the script is allowed, and denied if
+    /// any of them is denied. Scripts that cannot be split into plain
+    /// commands are never allowed; see [`Self::check_unparsed_script`] for
+    /// how deny rules apply to them.
+    pub(crate) fn check_command(&self, command: &[String]) -> PermissionVerdict {
+        if self.is_empty() {
+            return PermissionVerdict::Ask;
+        }
+        let commands = match command {
+            [bash, flag, script] if bash == "bash" && flag == "-lc" => {
+                match try_parse_bash(script)
+                    .and_then(|tree| try_parse_word_only_commands_sequence(&tree, script))
+                {
+                    Some(commands) if !commands.is_empty() => commands,
+                    _ => return self.check_unparsed_script(script),
+                }
+            }
+            _ => vec![command.to_vec()],
+        };
+
+        for command in &commands {
+            if let Some(rule) = self.deny.iter().find(|rule| rule.matches_command(command)) {
+                return PermissionVerdict::Deny {
+                    rule: rule.to_string(),
+                };
+            }
+        }
+        if commands
+            .iter()
+            .all(|command| self.allow.iter().any(|rule| rule.matches_command(command)))
+        {
+            PermissionVerdict::Allow
+        } else {
+            PermissionVerdict::Ask
+        }
+    }
+
+    /// Best-effort deny check for a script that cannot be split into plain
+    /// commands: denied if the script text contains the words of a deny rule.
+    /// Quoting, variables or extra whitespace get around it, so it only
+    /// catches the obvious cases; such scripts still need approval otherwise.
+    fn check_unparsed_script(&self, script: &str) -> PermissionVerdict {
+        let denied = self.deny.iter().find(|rule| match rule {
+            PermissionRule::Bash { words, .. } => script.contains(&words.join(" ")),
+            PermissionRule::Edit { .. } => false,
+        });
+        match denied {
+            Some(rule) => PermissionVerdict::Deny {
+                rule: rule.to_string(),
+            },
+            None => PermissionVerdict::Ask,
+        }
+    }
+
+    /// Classify an edit of `paths`: denied if any path matches a deny rule,
+    /// allowed if every path matches an allow rule.
+    pub(crate) fn check_paths<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a Path>,
+        cwd: &Path,
+    ) -> PermissionVerdict {
+        if self.is_empty() {
+            return PermissionVerdict::Ask;
+        }
+        let paths: Vec<&Path> = paths.into_iter().collect();
+        for path in &paths {
+            if let Some(rule) = self.deny.iter().find(|rule| rule.matches_path(path, cwd)) {
+                return PermissionVerdict::Deny {
+                    rule: rule.to_string(),
+                };
+            }
+        }
+        if !paths.is_empty()
+            && paths
+                .iter()
+                .all(|path| self.allow.iter().any(|rule| rule.matches_path(path, cwd)))
+        {
+            PermissionVerdict::Allow
+        } else {
+            PermissionVerdict::Ask
+        }
+    }
+}
+
+/// The project whose `[projects."<path>".permissions]` receive rules added
+/// during a session: the root of the repository containing `cwd`, or `cwd`.
+pub(crate) fn permissions_project_root(cwd: &Path) -> PathBuf {
+    resolve_root_git_project_for_trust(cwd).unwrap_or_else(|| cwd.to_path_buf())
+}
+
+/// Programs that run arbitrary code given as arguments, so a prefix rule for
+/// them would allow anything.
+const NO_PREFIX_RULE_PROGRAMS: &[&str] = &[
+    "bash",
+    "sh",
+    "zsh",
+    "fish",
+    "dash",
+    "ksh",
+    "env",
+    "sudo",
+    "doas",
+    "xargs",
+    "exec",
+    "eval",
+    "nohup",
+    "nice",
+    "time",
+    "timeout",
+    "python",
+    "python3",
+    "node",
+    "deno",
+    "bun",
+    "ruby",
+    "perl",
+    "php",
+    "lua",
+    "osascript",
+    "npx",
+    "bunx",
+    "uvx",
+    "pipx",
+];
+
+/// The `Bash(<prefix>:*)` rule offered when approving `command`: the program
+/// and, if present, its subcommand (`cargo test`, `git status`). Returns
+/// `None` for scripts that are not a single plain command and for
+/// interpreters and shells.
+pub fn command_prefix_rule(command: &[String]) -> Option<PermissionRule> {
+    let command = match command {
+        [bash, flag, script] if bash == "bash" && flag == "-lc" => {
+            let tree = try_parse_bash(script)?;
+            let mut commands = try_parse_word_only_commands_sequence(&tree, script)?;
+            if commands.len() != 1 {
+                return None;
+            }
+            commands.pop()?
+        }
+        _ => command.to_vec(),
+    };
+    let mut words = command.into_iter();
+    let program = words.next()?;
+    let name = program.rsplit('/').next().unwrap_or(&program);
+    if NO_PREFIX_RULE_PROGRAMS.contains(&name) {
+        return None;
+    }
+    let mut prefix = vec![program];
+    if let Some(subcommand) = words.next()
+        && is_subcommand(&subcommand)
+    {
+        prefix.push(subcommand);
+    }
+    Some(PermissionRule::Bash {
+        words: prefix,
+        prefix: true,
+    })
+}
+
+fn is_subcommand(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_alphabetic())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| (*s).to_string()).collect()
+    }
+
+    fn rules(allow: &[&str], deny: &[&str]) -> PermissionRules {
+        let table = PermissionsToml {
+            allow: strings(allow),
+            deny: strings(deny),
+        };
+        PermissionRules::from_toml([&table]).unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays_rules() {
+        assert_eq!(
+            PermissionRule::parse("Bash(cargo test:*)"),
+            Ok(PermissionRule::Bash {
+                words: strings(&["cargo", "test"]),
+                prefix: true,
+            })
+        );
+        assert_eq!(
+            PermissionRule::parse("Bash(git status)")
+                .unwrap()
+                .to_string(),
+            "Bash(git status)"
+        );
+        assert_eq!(
+            PermissionRule::parse("Edit(src/**)"),
+            Ok(PermissionRule::Edit {
+                pattern: "src/**".to_string()
+            })
+        );
+        assert!(PermissionRule::parse("Bash()").is_err());
+        assert!(PermissionRule::parse("Read(src)").is_err());
+    }
+
+    #[test]
+    fn checks_commands() {
+        let rules = rules(
+            &["Bash(cargo test:*)", "Bash(git status)"],
+            &["Bash(rm -rf:*)"],
+        );
+
+        assert_eq!(
+            rules.check_command(&strings(&["cargo", "test", "-p", "codex-core"])),
+            PermissionVerdict::Allow
+        );
+        assert_eq!(
+            rules.check_command(&strings(&["git", "status", "--short"])),
+            PermissionVerdict::Ask
+        );
+        assert_eq!(
+            rules.check_command(&strings(&["bash", "-lc", "git status && cargo test"])),
+            PermissionVerdict::Allow
+        );
+        assert_eq!(
+            rules.check_command(&strings(&["bash", "-lc", "cargo test && cargo build"])),
+            PermissionVerdict::Ask
+        );
+        assert_eq!(
+            rules.check_command(&strings(&["bash", "-lc", "cargo test && rm -rf target"])),
+            PermissionVerdict::Deny {
+                rule: "Bash(rm -rf:*)".to_string()
+            }
+        );
+        assert_eq!(
+            rules.check_command(&strings(&["bash", "-lc", "rm -rf $(pwd)"])),
+            PermissionVerdict::Deny {
+                rule: "Bash(rm -rf:*)".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn checks_edited_paths() {
+        let rules = rules(&["Edit(src/**)"], &["Edit(src/generated/*)"]);
+        let cwd = Path::new("/repo");
+
+        assert_eq!(
+            rules.check_paths([Path::new("/repo/src/lib.rs")], cwd),
+            PermissionVerdict::Allow
+        );
+        assert_eq!(
+            rules.check_paths(
+                [Path::new("/repo/src/lib.rs"), Path::new("/repo/README.md")],
+                cwd
+            ),
+            PermissionVerdict::Ask
+        );
+        assert_eq!(
+            rules.check_paths([Path::new("/repo/src/generated/api.rs")], cwd),
+            PermissionVerdict::Deny {
+                rule: "Edit(src/generated/*)".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn offers_program_and_subcommand_as_prefix() {
+        let prefix =
+            |command: &[&str]| command_prefix_rule(&strings(command)).map(|r| r.to_string());
+
+        assert_eq!(
+            prefix(&["cargo", "test", "-p", "codex-core"]),
+            Some("Bash(cargo test:*)".to_string())
+        );
+        assert_eq!(
+            prefix(&["bash", "-lc", "npm run build"]),
+            Some("Bash(npm run:*)".to_string())
+        );
+        assert_eq!(prefix(&["ls", "-la"]), Some("Bash(ls:*)".to_string()));
+        assert_eq!(prefix(&["bash", "-lc", "make && make install"]), None);
+        // Interpreters and shells run whatever they are given.
+        assert_eq!(prefix(&["python3", "script.py"]), None);
+        assert_eq!(prefix(&["bash", "-lc", "node -e 'evil()'"]), None);
+        assert_eq!(prefix(&["/usr/bin/env", "sh", "-c", "ls"]), None);
+    }
+}
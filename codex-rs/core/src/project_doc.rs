@@ -1,24 +1,36 @@
 //! Project-level documentation discovery.
 //!
-//! Project-level documentation can be stored in files named `AGENTS.md`.
-//! We include the concatenation of all files found along the path from the
-//! repository root to the current working directory as follows:
+//! Project-level documentation can be stored in files named `AGENTS.md`,
+//! `CLAUDE.md` or `CLAUDE.local.md` (configurable via
+//! `project_doc_filenames`). We include the concatenation of all files found
+//! along the path from the repository root to the current working directory as
+//! follows:
 //!
 //! 1.  Determine the Git repository root by walking upwards from the current
 //!     working directory until a `.git` directory or file is found. If no Git
 //!     root is found, only the current working directory is considered.
-//! 2.  Collect every `AGENTS.md` found from the repository root down to the
+//! 2.  Collect every project doc found from the repository root down to the
 //!     current working directory (inclusive) and concatenate their contents in
-//!     that order.
+//!     that order. Within a directory, files follow the order of
+//!     `project_doc_filenames`.
 //! 3.  We do **not** walk past the Git root.
+//!
+//! A line consisting of `@relative/path.md` imports another file in its place.
+//! Paths are resolved against the directory of the importing file, imports
+//! nest up to [`MAX_IMPORT_DEPTH`] levels, and a file is never included twice
+//! (which also breaks import cycles). Only files inside the repository (or
+//! the working directory outside of one) that the sandbox's `read_deny` does
+//! not hide can be imported, since the docs are checked into the repository.
 
 use crate::config::Config;
+use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 use tokio::io::AsyncReadExt;
 use tracing::error;
 
-/// Currently, we only match the filename `AGENTS.md` exactly.
-const CANDIDATE_FILENAMES: &[&str] = &["AGENTS.md"];
+/// How deeply `@path` imports may nest.
+const MAX_IMPORT_DEPTH: usize = 5;
 
 /// When both `Config::instructions` and the project doc are present, they will
 /// be concatenated with the following separator.
@@ -45,9 +57,9 @@ pub(crate) async fn get_user_instructions(config: &Config) -> Option<String> {
 /// Attempt to locate and load the project documentation.
 ///
 /// On success returns `Ok(Some(contents))` where `contents` is the
-/// concatenation of all discovered docs. If no documentation file is found the
-/// function returns `Ok(None)`. Unexpected I/O failures bubble up as `Err` so
-/// callers can decide how to handle them.
+/// concatenation of all discovered docs, with their imports inlined. If no
+/// documentation file is found the function returns `Ok(None)`. Unexpected
+/// I/O failures bubble up as `Err` so callers can decide how to handle them.
 pub async fn read_project_docs(config: &Config) -> std::io::Result<Option<String>> {
     let max_total = config.project_doc_max_bytes;
 
@@ -60,37 +72,37 @@ pub async fn read_project_docs(config: &Config) -> std::io::Result<Option<String
         return Ok(None);
     }
 
-    let mut remaining: u64 = max_total as u64;
+    let mut remaining = max_total;
     let mut parts: Vec<String> = Vec::new();
+    let mut reader = DocReader {
+        scope: ImportScope::for_config(config).await,
+        max_bytes: max_total,
+        seen: HashSet::new(),
+    };
 
     for p in paths {
         if remaining == 0 {
             break;
         }
 
-        let file = match tokio::fs::File::open(&p).await {
-            Ok(f) => f,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
-            Err(e) => return Err(e),
+        let Some(text) = reader.read_doc_with_imports(&p, 0).await? else {
+            continue;
         };
 
-        let size = file.metadata().await?.len();
-        let mut reader = tokio::io::BufReader::new(file).take(remaining);
-        let mut data: Vec<u8> = Vec::new();
-        reader.read_to_end(&mut data).await?;
-
-        if size > remaining {
+        let text = if text.len() > remaining {
             tracing::warn!(
                 "Project doc `{}` exceeds remaining budget ({} bytes) - truncating.",
                 p.display(),
                 remaining,
             );
-        }
+            truncate_on_char_boundary(&text, remaining).to_string()
+        } else {
+            text
+        };
 
-        let text = String::from_utf8_lossy(&data).to_string();
         if !text.trim().is_empty() {
+            remaining = remaining.saturating_sub(text.len());
             parts.push(text);
-            remaining = remaining.saturating_sub(data.len() as u64);
         }
     }
 
@@ -101,7 +113,153 @@ pub async fn read_project_docs(config: &Config) -> std::io::Result<Option<String
     }
 }
 
-/// Discover the list of AGENTS.md files using the same search rules as
+/// Where `@path` imports may point.
+struct ImportScope {
+    /// The repository root, or the working directory outside of one.
+    root: PathBuf,
+    read_deny: Vec<PathBuf>,
+}
+
+impl ImportScope {
+    async fn for_config(config: &Config) -> Self {
+        let root =
+            crate::git_info::get_git_repo_root(&config.cwd).unwrap_or_else(|| config.cwd.clone());
+        let mut read_deny = Vec::new();
+        for path in config
+            .sandbox_policy
+            .get_read_deny_paths_with_cwd(&config.cwd)
+        {
+            read_deny.push(canonicalize_or_keep(path).await);
+        }
+        Self {
+            root: canonicalize_or_keep(root).await,
+            read_deny,
+        }
+    }
+
+    /// Why `canonical` may not be imported, if it may not.
+    fn refusal(&self, canonical: &Path) -> Option<&'static str> {
+        if !canonical.starts_with(&self.root) {
+            Some("it is outside the repository")
+        } else if self
+            .read_deny
+            .iter()
+            .any(|denied| canonical.starts_with(denied))
+        {
+            Some("it is hidden by `read_deny`")
+        } else {
+            None
+        }
+    }
+}
+
+async fn canonicalize_or_keep(path: PathBuf) -> PathBuf {
+    tokio::fs::canonicalize(&path).await.unwrap_or(path)
+}
+
+struct DocReader {
+    scope: ImportScope,
+    /// Cap on the bytes read from any one file.
+    max_bytes: usize,
+    /// Files already included, canonicalized.
+    seen: HashSet<PathBuf>,
+}
+
+impl DocReader {
+    /// Read the doc at `path` (at most `max_bytes` of it) and inline its
+    /// `@path` imports. Returns `None` if the file does not exist or was
+    /// already included.
+    async fn read_doc_with_imports(
+        &mut self,
+        path: &Path,
+        depth: usize,
+    ) -> std::io::Result<Option<String>> {
+        let file = match tokio::fs::File::open(path).await {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let canonical = canonicalize_or_keep(path.to_path_buf()).await;
+        if !self.seen.insert(canonical) {
+            return Ok(None);
+        }
+
+        let mut data: Vec<u8> = Vec::new();
+        file.take(self.max_bytes as u64)
+            .read_to_end(&mut data)
+            .await?;
+        let text = String::from_utf8_lossy(&data);
+
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut out = String::with_capacity(text.len());
+        let mut in_code_block = false;
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            let import = trimmed
+                .strip_prefix('@')
+                .filter(|p| !in_code_block && !p.is_empty() && !p.contains(char::is_whitespace));
+            let Some(import) = import else {
+                out.push_str(line);
+                continue;
+            };
+
+            let import_path = base_dir.join(import);
+            if depth >= MAX_IMPORT_DEPTH {
+                tracing::warn!(
+                    "Not importing `{}` from `{}`: imports nest deeper than {MAX_IMPORT_DEPTH} levels.",
+                    import_path.display(),
+                    path.display(),
+                );
+                out.push_str(line);
+                continue;
+            }
+            let is_file = tokio::fs::metadata(&import_path)
+                .await
+                .is_ok_and(|m| m.is_file());
+            if !is_file {
+                tracing::warn!(
+                    "Project doc `{}` imports missing file `{}`.",
+                    path.display(),
+                    import_path.display(),
+                );
+                out.push_str(line);
+                continue;
+            }
+            let canonical = canonicalize_or_keep(import_path.clone()).await;
+            if let Some(reason) = self.scope.refusal(&canonical) {
+                tracing::warn!(
+                    "Not importing `{}` from `{}`: {reason}.",
+                    import_path.display(),
+                    path.display(),
+                );
+                out.push_str(line);
+                continue;
+            }
+            if let Some(imported) =
+                Box::pin(self.read_doc_with_imports(&import_path, depth + 1)).await?
+            {
+                out.push_str(imported.trim_end_matches('\n'));
+                if line.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+        }
+        Ok(Some(out))
+    }
+}
+
+fn truncate_on_char_boundary(s: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Discover the list of project doc files using the same search rules as
 /// `read_project_docs`, but return the file paths instead of concatenated
 /// contents. The list is ordered from repository root to the current working
 /// directory (inclusive). Symlinks are allowed. When `project_doc_max_bytes`
@@ -153,7 +311,7 @@ pub fn discover_project_doc_paths(config: &Config) -> std::io::Result<Vec<PathBu
 
     let mut found: Vec<PathBuf> = Vec::new();
     for d in search_dirs {
-        for name in CANDIDATE_FILENAMES {
+        for name in &config.project_doc_filenames {
             let candidate = d.join(name);
             match std::fs::symlink_metadata(&candidate) {
                 Ok(md) => {
@@ -161,7 +319,6 @@ pub fn discover_project_doc_paths(config: &Config) -> std::io::Result<Vec<PathBu
                     // Allow regular files and symlinks; opening will later fail for dangling links.
                     if ft.is_file() || ft.is_symlink() {
                         found.push(candidate);
-                        break;
                     }
                 }
                 Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
@@ -178,6 +335,7 @@ mod tests {
     use super::*;
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
+    use crate::protocol::SandboxPolicy;
     use std::fs;
     use tempfile::TempDir;
 
@@ -347,4 +505,128 @@ mod tests {
         let res = get_user_instructions(&cfg).await.expect("doc expected");
         assert_eq!(res, "root doc\n\ncrate doc");
     }
+
+    /// `CLAUDE.md` and `CLAUDE.local.md` are picked up alongside `AGENTS.md`,
+    /// in the configured order.
+    #[tokio::test]
+    async fn reads_claude_md_files_after_agents_md() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("CLAUDE.local.md"), "local notes").unwrap();
+        fs::write(tmp.path().join("CLAUDE.md"), "claude doc").unwrap();
+        fs::write(tmp.path().join("AGENTS.md"), "agents doc").unwrap();
+
+        let res = get_user_instructions(&make_config(&tmp, 4096, None))
+            .await
+            .expect("doc expected");
+        assert_eq!(res, "agents doc\n\nclaude doc\n\nlocal notes");
+
+        let mut cfg = make_config(&tmp, 4096, None);
+        cfg.project_doc_filenames = vec!["CLAUDE.md".to_string()];
+        let res = get_user_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(res, "claude doc");
+    }
+
+    /// `@path` lines are replaced by the imported file, resolved relative to
+    /// the importing file. Cycles and repeated imports are included once and
+    /// missing files are left as they are.
+    #[tokio::test]
+    async fn inlines_imports() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(tmp.path().join("docs")).unwrap();
+        fs::write(
+            tmp.path().join("AGENTS.md"),
+            "# Rules\n@docs/style.md\n@docs/missing.md\n```\n@docs/style.md\n```\nend\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp.path().join("docs/style.md"),
+            "style guide\n@testing.md\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp.path().join("docs/testing.md"),
+            "testing guide\n@style.md\n@../AGENTS.md\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("CLAUDE.md"), "@AGENTS.md\n").unwrap();
+
+        let res = get_user_instructions(&make_config(&tmp, 4096, None))
+            .await
+            .expect("doc expected");
+        assert_eq!(
+            res,
+            "# Rules\nstyle guide\ntesting guide\n@docs/missing.md\n```\n@docs/style.md\n```\nend\n"
+        );
+    }
+
+    /// Imports beyond the depth limit are left as `@path` lines.
+    #[tokio::test]
+    async fn stops_importing_past_depth_limit() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("AGENTS.md"), "@doc1.md\n").unwrap();
+        for i in 1..=MAX_IMPORT_DEPTH + 1 {
+            fs::write(
+                tmp.path().join(format!("doc{i}.md")),
+                format!("doc {i}\n@doc{}.md\n", i + 1),
+            )
+            .unwrap();
+        }
+
+        let res = get_user_instructions(&make_config(&tmp, 4096, None))
+            .await
+            .expect("doc expected");
+        let expected_docs: String = (1..=MAX_IMPORT_DEPTH)
+            .map(|i| format!("doc {i}\n"))
+            .collect();
+        assert_eq!(
+            res,
+            format!("{expected_docs}@doc{}.md\n", MAX_IMPORT_DEPTH + 1)
+        );
+    }
+
+    /// Imported content counts towards `project_doc_max_bytes`.
+    #[tokio::test]
+    async fn imports_respect_byte_limit() {
+        const LIMIT: usize = 64;
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("AGENTS.md"), "short\n@big.md\n").unwrap();
+        fs::write(tmp.path().join("big.md"), "B".repeat(LIMIT * 2)).unwrap();
+        fs::write(tmp.path().join("CLAUDE.md"), "never reached").unwrap();
+
+        let res = get_user_instructions(&make_config(&tmp, LIMIT, None))
+            .await
+            .expect("doc expected");
+        assert_eq!(res.len(), LIMIT);
+        assert_eq!(res, format!("short\n{}", "B".repeat(LIMIT - 6)));
+    }
+
+    /// Imports cannot reach outside the repository or into paths hidden by
+    /// `read_deny`; such lines are left as they are.
+    #[tokio::test]
+    async fn imports_are_confined_to_the_repository_and_read_deny() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let outside = tempfile::tempdir().expect("tempdir");
+        fs::write(outside.path().join("secret.md"), "outside secret\n").unwrap();
+        std::fs::create_dir_all(tmp.path().join("private")).unwrap();
+        fs::write(tmp.path().join("private/keys.md"), "denied secret\n").unwrap();
+        fs::write(tmp.path().join("ok.md"), "allowed\n").unwrap();
+        let absolute = outside.path().join("secret.md");
+        let relative = format!(
+            "../{}/secret.md",
+            outside.path().file_name().unwrap().to_string_lossy()
+        );
+        let agents = format!(
+            "@ok.md\n@{}\n@{relative}\n@private/keys.md\n",
+            absolute.display()
+        );
+        fs::write(tmp.path().join("AGENTS.md"), &agents).unwrap();
+
+        let mut config = make_config(&tmp, 4096, None);
+        config.sandbox_policy = SandboxPolicy::new_read_only_policy();
+        config
+            .sandbox_policy
+            .set_read_deny(vec![PathBuf::from("private")]);
+        let res = get_user_instructions(&config).await.expect("doc expected");
+        assert_eq!(res, agents.replacen("@ok.md\n", "allowed\n", 1));
+    }
 }
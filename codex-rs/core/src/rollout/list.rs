@@ -325,7 +325,7 @@ async fn read_head_and_flags(
             RolloutItem::TurnContext(_) => {
                 // Not included in `head`; skip.
             }
-            RolloutItem::Compacted(_) => {
+            RolloutItem::Compacted(_) | RolloutItem::SubagentItem(_) => {
                 // Not included in `head`; skip.
             }
             RolloutItem::EventMsg(ev) => {
@@ -0,0 +1,210 @@
+//! Replay the commands recorded in a rollout file through the exec policies
+//! and permission rules, to see how a session would decide on them today.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use tracing::warn;
+
+use crate::exec_policy::ExecPolicy;
+use crate::permissions::PermissionRules;
+use crate::protocol::RolloutItem;
+use crate::protocol::RolloutLine;
+use crate::protocol::TurnContextItem;
+use crate::safety::SafetyCheck;
+use crate::safety::assess_command_safety;
+
+/// What a session would do with a replayed command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayDecision {
+    /// Run without asking the user.
+    AutoApprove,
+    /// Ask the user for approval.
+    Prompt,
+    /// Refuse to run it.
+    Reject { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayedCommand {
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub decision: ReplayDecision,
+}
+
+/// Replay the shell commands in the rollout at `path`. Each command is
+/// assessed with the approval and sandbox policies of the turn that ran it,
+/// as if nothing had been approved for the session yet. Commands recorded
+/// before the first turn context are skipped, since their policies are
+/// unknown.
+pub async fn replay_rollout(
+    path: &Path,
+    exec_policy: &ExecPolicy,
+    permissions: &PermissionRules,
+) -> std::io::Result<Vec<ReplayedCommand>> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let mut turn_context: Option<TurnContextItem> = None;
+    let mut replayed = Vec::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let rollout_line = match serde_json::from_str::<RolloutLine>(line) {
+            Ok(rollout_line) => rollout_line,
+            Err(e) => {
+                warn!("failed to parse rollout line in {path:?}: {e}");
+                continue;
+            }
+        };
+        let item = match rollout_line.item {
+            RolloutItem::TurnContext(item) => {
+                turn_context = Some(item);
+                continue;
+            }
+            RolloutItem::ResponseItem(item) => item,
+            RolloutItem::SubagentItem(item) => item.item,
+            RolloutItem::SessionMeta(_) | RolloutItem::Compacted(_) | RolloutItem::EventMsg(_) => {
+                continue;
+            }
+        };
+        let Some(turn_context) = turn_context.as_ref() else {
+            continue;
+        };
+        let Some((command, workdir, with_escalated_permissions)) = shell_command(item) else {
+            continue;
+        };
+        let cwd = workdir.map_or_else(
+            || turn_context.cwd.clone(),
+            |dir| turn_context.cwd.join(dir),
+        );
+        let safety = assess_command_safety(
+            &command,
+            turn_context.approval_policy,
+            &turn_context.sandbox_policy,
+            exec_policy,
+            permissions,
+            &cwd,
+            &HashSet::new(),
+            with_escalated_permissions,
+        );
+        let decision = match safety {
+            SafetyCheck::AutoApprove { .. } => ReplayDecision::AutoApprove,
+            SafetyCheck::AskUser => ReplayDecision::Prompt,
+            SafetyCheck::Reject { reason } => ReplayDecision::Reject { reason },
+        };
+        replayed.push(ReplayedCommand {
+            command,
+            cwd,
+            decision,
+        });
+    }
+    Ok(replayed)
+}
+
+/// The command, working directory and escalation request of a shell tool
+/// call, or `None` for any other item.
+fn shell_command(item: ResponseItem) -> Option<(Vec<String>, Option<String>, bool)> {
+    match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } if matches!(name.as_str(), "container.exec" | "shell") => {
+            match serde_json::from_str::<ShellToolCallParams>(&arguments) {
+                Ok(params) => Some((
+                    params.command,
+                    params.workdir,
+                    params.with_escalated_permissions.unwrap_or(false),
+                )),
+                Err(e) => {
+                    warn!("failed to parse shell call arguments: {e}");
+                    None
+                }
+            }
+        }
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => Some((action.command, action.working_directory, false)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_types::PermissionsToml;
+    use crate::protocol::AskForApproval;
+    use crate::protocol::SandboxPolicy;
+    use codex_protocol::config_types::ReasoningSummary;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn rollout_line(item: RolloutItem) -> String {
+        serde_json::to_string(&RolloutLine {
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            item,
+        })
+        .unwrap()
+    }
+
+    fn shell_call(command: &[&str]) -> RolloutItem {
+        RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: serde_json::json!({ "command": command }).to_string(),
+            call_id: "call".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn replays_shell_calls_with_the_turn_policies() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        let turn_context = RolloutItem::TurnContext(TurnContextItem {
+            cwd: cwd.clone(),
+            approval_policy: AskForApproval::UnlessTrusted,
+            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            model: "gpt-5".to_string(),
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        });
+        let lines = [
+            // No turn context yet, so the policies are unknown.
+            rollout_line(shell_call(&["cargo", "build"])),
+            rollout_line(turn_context),
+            rollout_line(shell_call(&["cat", "README.md"])),
+            rollout_line(shell_call(&["cargo", "build"])),
+            rollout_line(shell_call(&["rm", "-rf", "target"])),
+        ];
+        let path = cwd.join("rollout.jsonl");
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let permissions = PermissionRules::from_toml([&PermissionsToml {
+            allow: vec![],
+            deny: vec!["Bash(rm:*)".to_string()],
+        }])
+        .unwrap();
+        let (exec_policy, _) = ExecPolicy::load(&cwd.join("home"), &cwd);
+        let replayed = replay_rollout(&path, &exec_policy, &permissions)
+            .await
+            .unwrap();
+
+        let decisions: Vec<_> = replayed
+            .into_iter()
+            .map(|replayed| (replayed.command.join(" "), replayed.decision))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                ("cat README.md".to_string(), ReplayDecision::AutoApprove),
+                ("cargo build".to_string(), ReplayDecision::Prompt),
+                (
+                    "rm -rf target".to_string(),
+                    ReplayDecision::Reject {
+                        reason: "denied by permission rule `Bash(rm:*)`".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+}
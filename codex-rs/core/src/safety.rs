@@ -7,9 +7,14 @@ use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
 
 use crate::exec::SandboxType;
+use crate::exec_policy::ExecPolicy;
+use crate::exec_policy::PolicyVerdict;
 use crate::is_safe_command::is_known_safe_command;
+use crate::permissions::PermissionRules;
+use crate::permissions::PermissionVerdict;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use codex_protocol::config_types::SandboxMode;
 
 #[derive(Debug, PartialEq)]
 pub enum SafetyCheck {
@@ -76,15 +81,44 @@ pub fn assess_patch_safety(
 /// true:
 ///
 /// - the user has explicitly approved the command
-/// - the command is on the "known safe" list
+/// - the command is on the "known safe" list and the sandbox policy does not
+///   hide any files from reads
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
-pub fn assess_command_safety(
+///
+/// Commands matched by the exec policy or by an allow rule in `[permissions]`
+/// are auto-approved inside the sandbox. Commands matched by a deny rule or
+/// forbidden by the exec policy are rejected.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn assess_command_safety(
     command: &[String],
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
+    exec_policy: &ExecPolicy,
+    permissions: &PermissionRules,
+    cwd: &Path,
     approved: &HashSet<Vec<String>>,
     with_escalated_permissions: bool,
 ) -> SafetyCheck {
+    let permission = permissions.check_command(command);
+    if let PermissionVerdict::Deny { rule } = permission {
+        return SafetyCheck::Reject {
+            reason: format!("denied by permission rule `{rule}`"),
+        };
+    }
+
+    match exec_policy.classify(command, sandbox_policy, cwd) {
+        PolicyVerdict::Forbidden { reason } => return SafetyCheck::Reject { reason },
+        // A policy match skips the prompt, not the sandbox: the files it
+        // writes may be links to files outside the writable roots. Without a
+        // sandbox the command is treated like any other.
+        PolicyVerdict::Safe { .. } => {
+            if let Some(sandbox_type) = get_platform_sandbox() {
+                return SafetyCheck::AutoApprove { sandbox_type };
+            }
+        }
+        PolicyVerdict::Unverified => {}
+    }
+
     // A command is "trusted" because either:
     // - it belongs to a set of commands we consider "safe" by default, or
     // - the user has explicitly approved the command for this session
@@ -94,15 +128,33 @@ pub fn assess_command_safety(
     // should be run inside a sandbox or not. (This could be something the user
     // defines as part of `execpolicy`.)
     //
+    // The hard-coded list covers shapes the exec policy cannot describe yet,
+    // such as `git status` or `cargo check`.
+    //
     // For example, when `is_known_safe_command(command)` returns `true`, it
     // would probably be fine to run the command in a sandbox, but when
     // `approved.contains(command)` is `true`, the user may have approved it for
     // the session _because_ they know it needs to run outside a sandbox.
     if is_known_safe_command(command) || approved.contains(command) {
+        // Known safe commands still read files, so keep them in the sandbox
+        // when it hides some (`cat ~/.ssh/id_rsa`).
+        if !approved.contains(command)
+            && !sandbox_policy.has_full_disk_read_access()
+            && let Some(sandbox_type) = get_platform_sandbox()
+        {
+            return SafetyCheck::AutoApprove { sandbox_type };
+        }
         return SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         };
     }
+    // Rules may have been saved from a broad prefix; like policy matches
+    // they skip the prompt but keep the sandbox.
+    if permission == PermissionVerdict::Allow {
+        return SafetyCheck::AutoApprove {
+            sandbox_type: get_platform_sandbox().unwrap_or(SandboxType::None),
+        };
+    }
 
     assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
 }
@@ -127,7 +179,7 @@ pub(crate) fn assess_safety_for_untrusted_command(
         | (OnRequest, DangerFullAccess) => SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         },
-        (OnRequest, ReadOnly) | (OnRequest, WorkspaceWrite { .. }) => {
+        (OnRequest, ReadOnly { .. }) | (OnRequest, WorkspaceWrite { .. }) => {
             if with_escalated_permissions {
                 SafetyCheck::AskUser
             } else {
@@ -139,9 +191,9 @@ pub(crate) fn assess_safety_for_untrusted_command(
                 }
             }
         }
-        (Never, ReadOnly)
+        (Never, ReadOnly { .. })
         | (Never, WorkspaceWrite { .. })
-        | (OnFailure, ReadOnly)
+        | (OnFailure, ReadOnly { .. })
         | (OnFailure, WorkspaceWrite { .. }) => {
             match get_platform_sandbox() {
                 Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
@@ -166,6 +218,51 @@ pub(crate) fn assess_safety_for_untrusted_command(
     }
 }
 
+/// The policy `mode` selects when it may only tighten `current`, as for the
+/// `sandbox:` frontmatter of prompts and subagents, which come from files
+/// that may be checked into the repository. Returns `None` when `mode` would
+/// allow more than `current`.
+pub(crate) fn tightened_sandbox_policy(
+    current: &SandboxPolicy,
+    mode: SandboxMode,
+) -> Option<SandboxPolicy> {
+    match (mode, current) {
+        (SandboxMode::DangerFullAccess, SandboxPolicy::DangerFullAccess) => {
+            Some(SandboxPolicy::DangerFullAccess)
+        }
+        (SandboxMode::DangerFullAccess, _) => None,
+        (SandboxMode::WorkspaceWrite, SandboxPolicy::DangerFullAccess) => {
+            Some(SandboxPolicy::new_workspace_write_policy())
+        }
+        (SandboxMode::WorkspaceWrite, policy @ SandboxPolicy::WorkspaceWrite { .. }) => {
+            Some(policy.clone())
+        }
+        (SandboxMode::WorkspaceWrite, SandboxPolicy::ReadOnly { .. }) => None,
+        (SandboxMode::ReadOnly, SandboxPolicy::DangerFullAccess) => {
+            Some(SandboxPolicy::new_read_only_policy())
+        }
+        (SandboxMode::ReadOnly, _) => Some(SandboxPolicy::ReadOnly {
+            read_deny: current.read_deny().to_vec(),
+        }),
+    }
+}
+
+/// `requested` if it asks the user at least as often as `current` does,
+/// `None` otherwise. See [`tightened_sandbox_policy`].
+pub(crate) fn tightened_approval_policy(
+    current: AskForApproval,
+    requested: AskForApproval,
+) -> Option<AskForApproval> {
+    fn strictness(policy: AskForApproval) -> u8 {
+        match policy {
+            AskForApproval::UnlessTrusted => 2,
+            AskForApproval::OnFailure | AskForApproval::OnRequest => 1,
+            AskForApproval::Never => 0,
+        }
+    }
+    (strictness(requested) >= strictness(current)).then_some(requested)
+}
+
 pub fn get_platform_sandbox() -> Option<SandboxType> {
     if cfg!(target_os = "macos") {
         Some(SandboxType::MacosSeatbelt)
@@ -176,6 +273,22 @@ pub fn get_platform_sandbox() -> Option<SandboxType> {
     }
 }
 
+/// Normalize a path by removing `.` and resolving `..` without touching the
+/// filesystem (works even if the file does not exist).
+pub(crate) fn normalize_path(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => { /* skip */ }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    Some(out)
+}
+
 fn is_write_patch_constrained_to_writable_paths(
     action: &ApplyPatchAction,
     sandbox_policy: &SandboxPolicy,
@@ -183,7 +296,7 @@ fn is_write_patch_constrained_to_writable_paths(
 ) -> bool {
     // Early‑exit if there are no declared writable roots.
     let writable_roots = match sandbox_policy {
-        SandboxPolicy::ReadOnly => {
+        SandboxPolicy::ReadOnly { .. } => {
             return false;
         }
         SandboxPolicy::DangerFullAccess => {
@@ -192,22 +305,6 @@ fn is_write_patch_constrained_to_writable_paths(
         SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
     };
 
-    // Normalize a path by removing `.` and resolving `..` without touching the
-    // filesystem (works even if the file does not exist).
-    fn normalize(path: &Path) -> Option<PathBuf> {
-        let mut out = PathBuf::new();
-        for comp in path.components() {
-            match comp {
-                Component::ParentDir => {
-                    out.pop();
-                }
-                Component::CurDir => { /* skip */ }
-                other => out.push(other.as_os_str()),
-            }
-        }
-        Some(out)
-    }
-
     // Determine whether `path` is inside **any** writable root. Both `path`
     // and roots are converted to absolute, normalized forms before the
     // prefix check.
@@ -217,7 +314,7 @@ fn is_write_patch_constrained_to_writable_paths(
         } else {
             cwd.join(p)
         };
-        let abs = match normalize(&abs) {
+        let abs = match normalize_path(&abs) {
             Some(v) => v,
             None => return false,
         };
@@ -274,8 +371,10 @@ mod tests {
         let policy_workspace_only = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            read_deny: vec![],
         };
 
         assert!(is_write_patch_constrained_to_writable_paths(
@@ -295,8 +394,10 @@ mod tests {
         let policy_with_parent = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![parent],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            read_deny: vec![],
         };
         assert!(is_write_patch_constrained_to_writable_paths(
             &add_outside,
@@ -310,7 +411,7 @@ mod tests {
         // Should not be a trusted command
         let command = vec!["git commit".to_string()];
         let approval_policy = AskForApproval::OnRequest;
-        let sandbox_policy = SandboxPolicy::ReadOnly;
+        let sandbox_policy = SandboxPolicy::new_read_only_policy();
         let approved: HashSet<Vec<String>> = HashSet::new();
         let request_escalated_privileges = true;
 
@@ -318,6 +419,9 @@ mod tests {
             &command,
             approval_policy,
             &sandbox_policy,
+            &ExecPolicy::default(),
+            &PermissionRules::default(),
+            Path::new("/"),
             &approved,
             request_escalated_privileges,
         );
@@ -325,11 +429,47 @@ mod tests {
         assert_eq!(safety_check, SafetyCheck::AskUser);
     }
 
+    #[test]
+    fn test_permission_rules_decide_before_asking() {
+        let permissions = PermissionRules::from_toml([&crate::config_types::PermissionsToml {
+            allow: vec!["Bash(cargo test:*)".to_string()],
+            deny: vec!["Bash(git push:*)".to_string()],
+        }])
+        .unwrap();
+        let check = |command: &[&str]| {
+            let command: Vec<String> = command.iter().map(|s| (*s).to_string()).collect();
+            assess_command_safety(
+                &command,
+                AskForApproval::UnlessTrusted,
+                &SandboxPolicy::new_read_only_policy(),
+                &ExecPolicy::default(),
+                &permissions,
+                Path::new("/"),
+                &HashSet::new(),
+                false,
+            )
+        };
+
+        assert_eq!(
+            check(&["cargo", "test", "--all"]),
+            SafetyCheck::AutoApprove {
+                sandbox_type: get_platform_sandbox().unwrap_or(SandboxType::None)
+            }
+        );
+        assert_eq!(
+            check(&["git", "push", "--force"]),
+            SafetyCheck::Reject {
+                reason: "denied by permission rule `Bash(git push:*)`".to_string()
+            }
+        );
+        assert_eq!(check(&["cargo", "publish"]), SafetyCheck::AskUser);
+    }
+
     #[test]
     fn test_request_escalated_privileges_no_sandbox_fallback() {
         let command = vec!["git".to_string(), "commit".to_string()];
         let approval_policy = AskForApproval::OnRequest;
-        let sandbox_policy = SandboxPolicy::ReadOnly;
+        let sandbox_policy = SandboxPolicy::new_read_only_policy();
         let approved: HashSet<Vec<String>> = HashSet::new();
         let request_escalated_privileges = false;
 
@@ -337,6 +477,9 @@ mod tests {
             &command,
             approval_policy,
             &sandbox_policy,
+            &ExecPolicy::default(),
+            &PermissionRules::default(),
+            Path::new("/"),
             &approved,
             request_escalated_privileges,
         );
@@ -347,4 +490,120 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn known_safe_commands_stay_sandboxed_when_reads_are_denied() {
+        let command = vec!["cat".to_string(), "~/.ssh/id_rsa".to_string()];
+        let check = |sandbox_policy: &SandboxPolicy| {
+            assess_command_safety(
+                &command,
+                AskForApproval::OnRequest,
+                sandbox_policy,
+                &ExecPolicy::default(),
+                &PermissionRules::default(),
+                Path::new("/"),
+                &HashSet::new(),
+                false,
+            )
+        };
+
+        assert_eq!(
+            check(&SandboxPolicy::new_read_only_policy()),
+            SafetyCheck::AutoApprove {
+                sandbox_type: get_platform_sandbox().unwrap_or(SandboxType::None)
+            }
+        );
+        assert_eq!(
+            check(&SandboxPolicy::ReadOnly { read_deny: vec![] }),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None
+            }
+        );
+    }
+
+    #[test]
+    fn exec_policy_matches_keep_the_sandbox() {
+        let policy = codex_execpolicy::PolicyParser::new(
+            "user.policy",
+            r#"
+define_program(
+    program="make",
+    args=[ARG_OPAQUE_VALUE],
+)
+"#,
+        )
+        .parse()
+        .unwrap();
+        let exec_policy = ExecPolicy::with_policies(vec![policy]).unwrap();
+
+        let safety_check = assess_command_safety(
+            &["make".to_string(), "test".to_string()],
+            AskForApproval::UnlessTrusted,
+            &SandboxPolicy::new_read_only_policy(),
+            &exec_policy,
+            &PermissionRules::default(),
+            Path::new("/"),
+            &HashSet::new(),
+            false,
+        );
+
+        let expected = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(safety_check, expected);
+    }
+
+    #[test]
+    fn frontmatter_policies_can_only_tighten() {
+        let read_only = SandboxPolicy::ReadOnly {
+            read_deny: vec![PathBuf::from("/secrets")],
+        };
+        let workspace_write = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![PathBuf::from("/cache")],
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+            read_deny: vec![PathBuf::from("/secrets")],
+        };
+
+        assert_eq!(
+            tightened_sandbox_policy(&read_only, SandboxMode::DangerFullAccess),
+            None
+        );
+        assert_eq!(
+            tightened_sandbox_policy(&read_only, SandboxMode::WorkspaceWrite),
+            None
+        );
+        assert_eq!(
+            tightened_sandbox_policy(&workspace_write, SandboxMode::WorkspaceWrite),
+            Some(workspace_write.clone())
+        );
+        assert_eq!(
+            tightened_sandbox_policy(&workspace_write, SandboxMode::ReadOnly),
+            Some(read_only.clone())
+        );
+        assert_eq!(
+            tightened_sandbox_policy(&SandboxPolicy::DangerFullAccess, SandboxMode::ReadOnly),
+            Some(SandboxPolicy::new_read_only_policy())
+        );
+
+        assert_eq!(
+            tightened_approval_policy(AskForApproval::OnRequest, AskForApproval::Never),
+            None
+        );
+        assert_eq!(
+            tightened_approval_policy(AskForApproval::UnlessTrusted, AskForApproval::OnRequest),
+            None
+        );
+        assert_eq!(
+            tightened_approval_policy(AskForApproval::OnRequest, AskForApproval::UnlessTrusted),
+            Some(AskForApproval::UnlessTrusted)
+        );
+        assert_eq!(
+            tightened_approval_policy(AskForApproval::Never, AskForApproval::OnFailure),
+            Some(AskForApproval::OnFailure)
+        );
+    }
 }
@@ -0,0 +1,376 @@
+//! A guess at the operation the sandbox blocked when a sandboxed command
+//! fails.
+//!
+//! Neither Landlock/seccomp nor Seatbelt tell the parent process what they
+//! denied, but commands almost always report the failing path next to the
+//! `errno` message (`touch: cannot touch '/x': Permission denied`). A path
+//! reported that way which the sandbox policy does not allow writing (or
+//! reading, for `read_deny`) is guessed to be the denied operation.
+//!
+//! The one exception is network access on Linux: `codex-linux-sandbox`
+//! prints [`LINUX_SANDBOX_NETWORK_DENIED_MARKER`] to the command's stderr
+//! the first time its seccomp filter denies a network syscall.
+//!
+//! The output comes from the command itself, which can print anything, so the
+//! guess is only ever shown as a hint in messages and is worded as one. It
+//! must not feed into approval or sandbox decisions.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::protocol::SandboxPolicy;
+use crate::safety::normalize_path;
+
+/// Line `codex-linux-sandbox` prints when it denies network access.
+pub const LINUX_SANDBOX_NETWORK_DENIED_MARKER: &str = "codex-linux-sandbox: denied network access";
+
+/// `errno` messages (lowercased) that accompany a path the sandbox denied.
+const ACCESS_DENIED_MESSAGES: &[&str] = &[
+    "permission denied",
+    "read-only file system",
+    "operation not permitted",
+];
+
+/// Messages (lowercased) printed when name resolution or connecting fails
+/// because the sandbox does not allow network access.
+const NETWORK_DENIED_MESSAGES: &[&str] = &[
+    "could not resolve host",
+    "temporary failure in name resolution",
+    "name or service not known",
+    "failed to lookup address information",
+    "getaddrinfo eai_again",
+    "getaddrinfo enotfound",
+    "nodename nor servname provided",
+    "network is unreachable",
+];
+
+/// Tools sometimes print the path on the line before the `errno` message, as
+/// in cargo's `failed to create directory ...` / `Caused by:` output.
+const MAX_LINES_BEFORE_MESSAGE: usize = 3;
+
+/// The operation a sandboxed command's output suggests it was denied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SandboxDenial {
+    /// Writing a path outside the writable roots.
+    Write(PathBuf),
+
+    /// Reading a path hidden by `read_deny`.
+    Read(PathBuf),
+
+    /// Accessing the network.
+    Network,
+}
+
+impl fmt::Display for SandboxDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SandboxDenial::Write(path) => write!(
+                f,
+                "output suggests the sandbox blocked writing {}",
+                path.display()
+            ),
+            SandboxDenial::Read(path) => write!(
+                f,
+                "output suggests the sandbox blocked reading {}",
+                path.display()
+            ),
+            SandboxDenial::Network => {
+                write!(f, "output suggests the sandbox blocked network access")
+            }
+        }
+    }
+}
+
+/// Guesses the operation the sandbox denied from the `output` of a command
+/// that failed under `sandbox_policy`. Relative paths in the output are
+/// resolved against `command_cwd`. Only use the result for messages.
+pub(crate) fn detect_sandbox_denial(
+    output: &str,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    command_cwd: &Path,
+) -> Option<SandboxDenial> {
+    let writable_roots = sandbox_policy.get_writable_roots_with_cwd(sandbox_policy_cwd);
+    let read_deny = sandbox_policy.get_read_deny_paths_with_cwd(sandbox_policy_cwd);
+    let lines: Vec<String> = output.lines().map(str::to_lowercase).collect();
+    let raw_lines: Vec<&str> = output.lines().collect();
+
+    if !sandbox_policy.has_full_network_access()
+        && raw_lines
+            .iter()
+            .any(|line| line.trim_end() == LINUX_SANDBOX_NETWORK_DENIED_MARKER)
+    {
+        return Some(SandboxDenial::Network);
+    }
+
+    let mut network_denied = false;
+    for (index, line) in lines.iter().enumerate() {
+        if !sandbox_policy.has_full_network_access()
+            && NETWORK_DENIED_MESSAGES
+                .iter()
+                .any(|message| line.contains(message))
+        {
+            network_denied = true;
+        }
+
+        if !ACCESS_DENIED_MESSAGES
+            .iter()
+            .any(|message| line.contains(message))
+        {
+            continue;
+        }
+
+        let first = index.saturating_sub(MAX_LINES_BEFORE_MESSAGE);
+        let candidates = raw_lines[first..=index]
+            .iter()
+            .rev()
+            .map(|line| candidate_paths(line))
+            .find(|candidates| !candidates.is_empty())
+            .unwrap_or_default();
+        for candidate in candidates {
+            let Some(path) = normalize_path(&command_cwd.join(candidate)) else {
+                continue;
+            };
+            if read_deny.iter().any(|denied| path.starts_with(denied)) {
+                return Some(SandboxDenial::Read(path));
+            }
+            if !sandbox_policy.has_full_disk_write_access()
+                && !writable_roots
+                    .iter()
+                    .any(|root| root.is_path_writable(&path))
+                && is_writable_outside_sandbox(&path)
+            {
+                return Some(SandboxDenial::Write(path));
+            }
+        }
+    }
+
+    network_denied.then_some(SandboxDenial::Network)
+}
+
+/// Returns the paths mentioned in an error message: quoted strings, and
+/// unquoted words that start with `/`.
+fn candidate_paths(line: &str) -> Vec<&str> {
+    let mut candidates = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(['\'', '"', '`', '‘']) {
+        let quote = rest[start..].chars().next().unwrap_or('\'');
+        let closing = if quote == '‘' { '’' } else { quote };
+        let after = &rest[start + quote.len_utf8()..];
+        let Some(end) = after.find(closing) else {
+            break;
+        };
+        let quoted = &after[..end];
+        if !quoted.is_empty() && !quoted.contains('\n') {
+            candidates.push(quoted);
+        }
+        rest = &after[end + closing.len_utf8()..];
+    }
+
+    candidates.extend(
+        line.split(|c: char| c.is_whitespace() || matches!(c, '\'' | '"' | '`' | '‘' | '’'))
+            .map(|word| word.trim_end_matches([':', ',', ';']))
+            .filter(|word| word.starts_with('/') && word.len() > 1),
+    );
+    candidates
+}
+
+/// Whether `path`, or the directory it would be created in, is writable by
+/// the current user, so a failed write was caused by the sandbox rather than
+/// ordinary file permissions.
+#[cfg(unix)]
+fn is_writable_outside_sandbox(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Some(existing) = path.ancestors().find(|ancestor| ancestor.exists()) else {
+        return false;
+    };
+    let Ok(existing) = std::ffi::CString::new(existing.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `existing` is a valid NUL-terminated string.
+    unsafe { libc::access(existing.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable_outside_sandbox(_path: &Path) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn workspace_write_policy(read_deny: Vec<PathBuf>) -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            read_deny,
+        }
+    }
+
+    fn detect(output: &str, cwd: &Path, policy: &SandboxPolicy) -> Option<SandboxDenial> {
+        detect_sandbox_denial(output, policy, cwd, cwd)
+    }
+
+    #[test]
+    fn detects_writes_outside_the_writable_roots() {
+        let workspace = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let registry = outside.path().join("registry");
+        let policy = workspace_write_policy(vec![]);
+
+        for output in [
+            format!(
+                "touch: cannot touch '{}': Permission denied",
+                registry.display()
+            ),
+            format!(
+                "PermissionError: [Errno 13] Permission denied: '{}'",
+                registry.display()
+            ),
+            format!(
+                "bash: line 1: {}: Read-only file system",
+                registry.display()
+            ),
+            format!(
+                "sh: 1: cannot create {}: Permission denied",
+                registry.display()
+            ),
+            format!(
+                "Error: EACCES: permission denied, open '{}'",
+                registry.display()
+            ),
+            format!(
+                "error: failed to create directory `{}`\n\nCaused by:\n  Permission denied (os error 13)",
+                registry.display()
+            ),
+        ] {
+            assert_eq!(
+                Some(SandboxDenial::Write(registry.clone())),
+                detect(&output, workspace.path(), &policy),
+                "{output}"
+            );
+        }
+    }
+
+    #[test]
+    fn resolves_relative_paths_against_the_command_cwd() {
+        let workspace = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let policy = workspace_write_policy(vec![]);
+
+        assert_eq!(
+            Some(SandboxDenial::Write(outside.path().join("file"))),
+            detect_sandbox_denial(
+                "touch: cannot touch '../file': Permission denied",
+                &policy,
+                workspace.path(),
+                &outside.path().join("sub"),
+            )
+        );
+    }
+
+    #[test]
+    fn ignores_writable_paths_and_unrelated_errors() {
+        let workspace = TempDir::new().unwrap();
+        let policy = workspace_write_policy(vec![]);
+        let inside = workspace.path().join("file");
+
+        assert_eq!(
+            None,
+            detect(
+                &format!(
+                    "touch: cannot touch '{}': Permission denied",
+                    inside.display()
+                ),
+                workspace.path(),
+                &policy,
+            )
+        );
+        assert_eq!(
+            None,
+            detect("error[E0308]: mismatched types", workspace.path(), &policy)
+        );
+        assert_eq!(
+            None,
+            detect(
+                "touch: cannot touch '/x': Permission denied",
+                workspace.path(),
+                &SandboxPolicy::DangerFullAccess,
+            )
+        );
+    }
+
+    #[test]
+    fn detects_reads_of_denied_paths() {
+        let workspace = TempDir::new().unwrap();
+        let secrets = workspace.path().join("secrets");
+        let policy = workspace_write_policy(vec![secrets.clone()]);
+
+        assert_eq!(
+            Some(SandboxDenial::Read(secrets.join("key"))),
+            detect(
+                &format!("cat: {}/key: Permission denied", secrets.display()),
+                workspace.path(),
+                &policy,
+            )
+        );
+    }
+
+    #[test]
+    fn detects_network_access() {
+        let workspace = TempDir::new().unwrap();
+        let output = "curl: (6) Could not resolve host: example.com";
+        assert_eq!(
+            "output suggests the sandbox blocked network access",
+            SandboxDenial::Network.to_string()
+        );
+
+        assert_eq!(
+            Some(SandboxDenial::Network),
+            detect(output, workspace.path(), &workspace_write_policy(vec![]))
+        );
+        assert_eq!(
+            None,
+            detect(
+                output,
+                workspace.path(),
+                &SandboxPolicy::WorkspaceWrite {
+                    writable_roots: vec![],
+                    network_access: true,
+                    network_allowlist: vec![],
+                    exclude_tmpdir_env_var: true,
+                    exclude_slash_tmp: true,
+                    read_deny: vec![],
+                },
+            )
+        );
+    }
+    #[test]
+    fn detects_the_linux_sandbox_network_marker() {
+        let workspace = TempDir::new().unwrap();
+        let output = format!(
+            "{LINUX_SANDBOX_NETWORK_DENIED_MARKER}\nerror: failed to connect: Operation not permitted (os error 1)"
+        );
+
+        assert_eq!(
+            Some(SandboxDenial::Network),
+            detect(&output, workspace.path(), &workspace_write_policy(vec![]))
+        );
+        assert_eq!(
+            None,
+            detect(
+                "error: failed to connect: Operation not permitted (os error 1)",
+                workspace.path(),
+                &workspace_write_policy(vec![])
+            )
+        );
+    }
+}
@@ -3,9 +3,11 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+use crate::config_types::ExecLimits;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::StdioPolicy;
+use crate::spawn::network_proxy_port;
 use crate::spawn::spawn_child_async;
 
 const MACOS_SEATBELT_BASE_POLICY: &str = include_str!("seatbelt_base_policy.sbpl");
@@ -23,8 +25,14 @@ pub async fn spawn_command_under_seatbelt(
     sandbox_policy_cwd: &Path,
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
+    limits: ExecLimits,
 ) -> std::io::Result<Child> {
-    let args = create_seatbelt_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    let args = create_seatbelt_command_args(
+        command,
+        sandbox_policy,
+        sandbox_policy_cwd,
+        network_proxy_port(&env),
+    );
     let arg0 = None;
     env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "seatbelt".to_string());
     spawn_child_async(
@@ -35,14 +43,19 @@ pub async fn spawn_command_under_seatbelt(
         sandbox_policy,
         stdio_policy,
         env,
+        limits,
     )
     .await
 }
 
+/// `network_proxy_port` is the loopback port of the network proxy, the only
+/// network destination allowed when network access is restricted to an
+/// allowlist.
 fn create_seatbelt_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
+    network_proxy_port: Option<u16>,
 ) -> Vec<String> {
     let (file_write_policy, extra_cli_args) = {
         if sandbox_policy.has_full_disk_write_access() {
@@ -97,17 +110,40 @@ fn create_seatbelt_command_args(
         }
     };
 
-    let file_read_policy = if sandbox_policy.has_full_disk_read_access() {
-        "; allow read-only file operations\n(allow file-read*)"
+    let (file_read_policy, read_deny_cli_args) = if sandbox_policy.has_full_disk_read_access() {
+        (
+            "; allow read-only file operations\n(allow file-read*)".to_string(),
+            Vec::new(),
+        )
     } else {
-        ""
+        let mut require_parts: Vec<String> = Vec::new();
+        let mut cli_args: Vec<String> = Vec::new();
+        let read_deny = sandbox_policy.get_read_deny_paths_with_cwd(sandbox_policy_cwd);
+        for (index, path) in read_deny.iter().enumerate() {
+            let canonical_path = path.canonicalize().unwrap_or_else(|_| path.clone());
+            let deny_param = format!("READ_DENY_{index}");
+            cli_args.push(format!(
+                "-D{deny_param}={}",
+                canonical_path.to_string_lossy()
+            ));
+            require_parts.push(format!("(require-not (subpath (param \"{deny_param}\")))"));
+        }
+        (
+            format!(
+                "; allow read-only file operations outside of the read-denied paths\n(allow file-read*\n(require-all {} )\n)",
+                require_parts.join(" ")
+            ),
+            cli_args,
+        )
     };
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
     let network_policy = if sandbox_policy.has_full_network_access() {
-        "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)"
+        "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)".to_string()
+    } else if let Some(port) = network_proxy_port {
+        format!("(allow network-outbound (remote ip \"localhost:{port}\"))")
     } else {
-        ""
+        String::new()
     };
 
     let full_policy = format!(
@@ -116,6 +152,7 @@ fn create_seatbelt_command_args(
 
     let mut seatbelt_args: Vec<String> = vec!["-p".to_string(), full_policy];
     seatbelt_args.extend(extra_cli_args);
+    seatbelt_args.extend(read_deny_cli_args);
     seatbelt_args.push("--".to_string());
     seatbelt_args.extend(command);
     seatbelt_args
@@ -156,14 +193,17 @@ mod tests {
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![root_with_git, root_without_git],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            read_deny: vec![],
         };
 
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             &cwd,
+            None,
         );
 
         // Build the expected policy text using a raw string for readability.
@@ -231,14 +271,17 @@ mod tests {
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            read_deny: vec![],
         };
 
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             root_with_git.as_path(),
+            None,
         );
 
         let tmpdir_env_var = std::env::var("TMPDIR")
@@ -301,6 +344,87 @@ mod tests {
         assert_eq!(expected_args, args);
     }
 
+    #[test]
+    fn create_seatbelt_args_with_read_deny_paths() {
+        if cfg!(target_os = "windows") {
+            return;
+        }
+
+        let tmp = TempDir::new().expect("tempdir");
+        let secrets = tmp.path().join("secrets");
+        fs::create_dir_all(&secrets).expect("create secrets");
+        let secrets_canon = secrets.canonicalize().expect("canonicalize secrets");
+        let cwd = tmp.path().join("cwd");
+
+        let policy = SandboxPolicy::ReadOnly {
+            read_deny: vec![secrets, PathBuf::from(".env")],
+        };
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string(), "hello".to_string()],
+            &policy,
+            &cwd,
+            None,
+        );
+
+        // Reads are allowed everywhere except beneath READ_DENY_0 and
+        // READ_DENY_1, and there are no writable roots.
+        let expected_policy = format!(
+            r#"{MACOS_SEATBELT_BASE_POLICY}
+; allow read-only file operations outside of the read-denied paths
+(allow file-read*
+(require-all (require-not (subpath (param "READ_DENY_0"))) (require-not (subpath (param "READ_DENY_1"))) )
+)
+
+"#,
+        );
+
+        let expected_args = vec![
+            "-p".to_string(),
+            expected_policy,
+            format!("-DREAD_DENY_0={}", secrets_canon.to_string_lossy()),
+            // `.env` does not exist, so it is resolved against the cwd but
+            // cannot be canonicalized.
+            format!("-DREAD_DENY_1={}", cwd.join(".env").to_string_lossy()),
+            "--".to_string(),
+            "/bin/echo".to_string(),
+            "hello".to_string(),
+        ];
+
+        assert_eq!(expected_args, args);
+    }
+
+    #[test]
+    fn create_seatbelt_args_with_network_proxy() {
+        let policy = SandboxPolicy::ReadOnly { read_deny: vec![] };
+
+        let args = create_seatbelt_command_args(
+            vec!["/usr/bin/curl".to_string(), "https://crates.io".to_string()],
+            &policy,
+            Path::new("/"),
+            Some(8080),
+        );
+
+        // The only network destination is the proxy on the loopback port.
+        let expected_policy = format!(
+            r#"{MACOS_SEATBELT_BASE_POLICY}
+; allow read-only file operations
+(allow file-read*)
+
+(allow network-outbound (remote ip "localhost:8080"))"#,
+        );
+
+        let expected_args = vec![
+            "-p".to_string(),
+            expected_policy,
+            "--".to_string(),
+            "/usr/bin/curl".to_string(),
+            "https://crates.io".to_string(),
+        ];
+
+        assert_eq!(expected_args, args);
+    }
+
     struct PopulatedTmp {
         root_with_git: PathBuf,
         root_without_git: PathBuf,
@@ -1,10 +1,14 @@
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::process::Stdio;
 use tokio::process::Child;
 use tokio::process::Command;
 use tracing::trace;
 
+use crate::config_types::ExecLimits;
+#[cfg(unix)]
+use crate::exec_limits::set_resource_limits;
 use crate::protocol::SandboxPolicy;
 
 /// Experimental environment variable that will be set to some non-empty value
@@ -17,14 +21,30 @@ use crate::protocol::SandboxPolicy;
 /// attributes, so this may change in the future.
 pub const CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR: &str = "CODEX_SANDBOX_NETWORK_DISABLED";
 
+/// Set to the loopback address of the network proxy when a sandboxed shell
+/// tool call may only reach the network through it, i.e. when network access
+/// is restricted to a `network_allowlist`. The sandbox then only allows
+/// connections to the port of that address.
+pub const CODEX_SANDBOX_NETWORK_PROXY_ENV_VAR: &str = "CODEX_SANDBOX_NETWORK_PROXY";
+
 /// Should be set when the process is spawned under a sandbox. Currently, the
 /// value is "seatbelt" for macOS, but it may change in the future to
 /// accommodate sandboxing configuration and other sandboxing mechanisms.
 pub const CODEX_SANDBOX_ENV_VAR: &str = "CODEX_SANDBOX";
 
+/// Returns the port of the network proxy named in `env`, if any.
+pub(crate) fn network_proxy_port(env: &HashMap<String, String>) -> Option<u16> {
+    env.get(CODEX_SANDBOX_NETWORK_PROXY_ENV_VAR)
+        .and_then(|addr| addr.parse::<SocketAddr>().ok())
+        .map(|addr| addr.port())
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum StdioPolicy {
     RedirectForShellTool,
+    /// Like `RedirectForShellTool`, and the child leads a new process group,
+    /// so it can be stopped together with everything it started.
+    RedirectForBackgroundProcess,
     Inherit,
 }
 
@@ -43,9 +63,10 @@ pub(crate) async fn spawn_child_async(
     sandbox_policy: &SandboxPolicy,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    limits: ExecLimits,
 ) -> std::io::Result<Child> {
     trace!(
-        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?}"
+        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?} {limits:?}"
     );
 
     let mut cmd = Command::new(&program);
@@ -85,8 +106,17 @@ pub(crate) async fn spawn_child_async(
         });
     }
 
+    // Resource limits are inherited by everything the command spawns,
+    // including the sandbox helpers.
+    #[cfg(unix)]
+    if limits != ExecLimits::default() {
+        unsafe {
+            cmd.pre_exec(move || set_resource_limits(&limits));
+        }
+    }
+
     match stdio_policy {
-        StdioPolicy::RedirectForShellTool => {
+        StdioPolicy::RedirectForShellTool | StdioPolicy::RedirectForBackgroundProcess => {
             // Do not create a file descriptor for stdin because otherwise some
             // commands may hang forever waiting for input. For example, ripgrep has
             // a heuristic where it may try to read from stdin as explained here:
@@ -103,5 +133,12 @@ pub(crate) async fn spawn_child_async(
         }
     }
 
+    // Only for children without a terminal: a process group in the
+    // background of the terminal would be stopped when it reads from it.
+    #[cfg(unix)]
+    if matches!(stdio_policy, StdioPolicy::RedirectForBackgroundProcess) {
+        cmd.process_group(0);
+    }
+
     cmd.kill_on_drop(true).spawn()
 }
@@ -0,0 +1,140 @@
+//! Subagents defined by a repository.
+//!
+//! Each markdown file in `.codex/agents/` at the root of the repository (or
+//! the working directory outside of a repository) defines one subagent, named
+//! after the file. The frontmatter accepts the same keys as custom prompts
+//! (`description`, `model`, `reasoning-effort`, `sandbox`, `approval` and
+//! `allowed-tools`) and the body becomes the subagent's system prompt. The
+//! agent hands work to a subagent through the `task` tool.
+
+use crate::custom_prompts::parse_field;
+use crate::custom_prompts::parse_turn_overrides;
+use crate::custom_prompts::split_frontmatter;
+use crate::git_info::get_git_repo_root;
+use codex_protocol::custom_prompts::PromptTurnOverrides;
+use std::path::Path;
+use std::path::PathBuf;
+use tokio::fs;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SubagentDefinition {
+    pub name: String,
+    pub path: PathBuf,
+    pub description: Option<String>,
+    pub overrides: PromptTurnOverrides,
+    /// System prompt of the subagent.
+    pub instructions: String,
+}
+
+/// Directory holding the subagents of the project that contains `cwd`.
+pub(crate) fn project_agents_dir(cwd: &Path) -> PathBuf {
+    get_git_repo_root(cwd)
+        .unwrap_or_else(|| cwd.to_path_buf())
+        .join(".codex")
+        .join("agents")
+}
+
+/// Discover subagent definitions in `dir`, sorted by name. Files that are not
+/// markdown or cannot be read are skipped; a missing directory yields none.
+pub(crate) async fn discover_subagents_in(dir: &Path) -> Vec<SubagentDefinition> {
+    let mut out = Vec::new();
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return out;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_file = entry
+            .file_type()
+            .await
+            .map(|ft| ft.is_file())
+            .unwrap_or(false);
+        let is_md = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+        if !is_file || !is_md {
+            continue;
+        }
+        let Some(name) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let Ok(content) = fs::read_to_string(&path).await else {
+            continue;
+        };
+        let (description, body) = match split_frontmatter(&content) {
+            Some((frontmatter, body)) => (parse_field(frontmatter, "description"), body),
+            None => (None, content.as_str()),
+        };
+        out.push(SubagentDefinition {
+            name,
+            description,
+            overrides: parse_turn_overrides(&path, &content).unwrap_or_default(),
+            instructions: body.trim().to_string(),
+            path,
+        });
+    }
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::config_types::SandboxMode;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn discovers_agents_with_frontmatter() {
+        let tmp = tempdir().expect("create TempDir");
+        let dir = tmp.path();
+        fs::write(
+            dir.join("reviewer.md"),
+            "---\ndescription: Reviews diffs\nmodel: o3\nsandbox: read-only\nallowed-tools: shell\n---\nYou review code.\n",
+        )
+        .unwrap();
+        fs::write(dir.join("helper.md"), "Just help.").unwrap();
+        fs::write(dir.join("notes.txt"), "not an agent").unwrap();
+
+        let agents = discover_subagents_in(dir).await;
+        assert_eq!(
+            agents,
+            vec![
+                SubagentDefinition {
+                    name: "helper".to_string(),
+                    path: dir.join("helper.md"),
+                    description: None,
+                    overrides: PromptTurnOverrides::default(),
+                    instructions: "Just help.".to_string(),
+                },
+                SubagentDefinition {
+                    name: "reviewer".to_string(),
+                    path: dir.join("reviewer.md"),
+                    description: Some("Reviews diffs".to_string()),
+                    overrides: PromptTurnOverrides {
+                        model: Some("o3".to_string()),
+                        sandbox: Some(SandboxMode::ReadOnly),
+                        allowed_tools: Some(vec!["shell".to_string()]),
+                        ..Default::default()
+                    },
+                    instructions: "You review code.".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_dir_has_no_agents() {
+        let tmp = tempdir().expect("create TempDir");
+        assert!(
+            discover_subagents_in(&tmp.path().join("nope"))
+                .await
+                .is_empty()
+        );
+    }
+}
@@ -1,15 +1,242 @@
 use std::collections::HashMap;
 
-#[derive(Debug)]
+use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::TemplateArg;
+pub use codex_protocol::custom_prompts::TemplateSyntax;
+use minijinja::Environment;
+use minijinja::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
 pub enum TemplateError {
+    #[error("missing required argument `{0}`")]
     MissingVariable(String),
+    #[error("{0}")]
     ProcessingError(String),
 }
 
-#[derive(Debug, Clone)]
-pub enum TemplateSyntax {
-    Simple, // {0}, {1}, {2}
-    Askama, // {{ variable }}
+/// Arguments typed after a slash prompt. Tokens of the form `key=value` are
+/// named arguments; everything else is positional and available to templates
+/// as `ARGUMENTS` (or `$ARGUMENTS`, `$1`, `$2`, ...).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PromptArguments {
+    pub positional: Vec<String>,
+    pub named: HashMap<String, String>,
+}
+
+impl PromptArguments {
+    pub fn parse(tokens: &[String]) -> Self {
+        let mut args = Self::default();
+        for token in tokens {
+            match token.split_once('=') {
+                Some((key, value)) if is_identifier(key) => {
+                    args.named.insert(key.to_string(), value.to_string());
+                }
+                _ => args.positional.push(token.clone()),
+            }
+        }
+        args
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Expand a custom prompt with the arguments typed after its slash command.
+///
+/// Arguments declared in the prompt's frontmatter are bound by name first and
+/// then from the positional arguments in declaration order, falling back to
+/// their default value. A required argument without a value is an error.
+pub fn render_prompt(prompt: &CustomPrompt, args: &[String]) -> Result<String, TemplateError> {
+    let args = PromptArguments::parse(args);
+    let declared =
+        bind_declared_arguments(prompt.template_args.as_deref().unwrap_or_default(), &args)?;
+    match prompt.template_syntax {
+        Some(TemplateSyntax::Simple) => process_simple_template(&prompt.content, &args.positional),
+        Some(TemplateSyntax::Jinja) => {
+            let mut context: HashMap<String, Value> = HashMap::new();
+            for (i, arg) in args.positional.iter().enumerate() {
+                context.insert(format!("arg{i}"), Value::from(arg.as_str()));
+            }
+            if let Some(first) = args.positional.first() {
+                context.insert("subject".to_string(), Value::from(first.as_str()));
+            }
+            for (name, value) in args.named.iter().chain(declared.iter()) {
+                context.insert(name.clone(), Value::from(value.as_str()));
+            }
+            context.insert("ARGUMENTS".to_string(), Value::from(args.positional));
+            render_jinja(&rewrite_dollar_arguments(&prompt.content), context)
+        }
+        None => Ok(prompt.content.clone()),
+    }
+}
+
+/// Expand a prompt with the arguments typed after its command into the text
+/// that is sent. Prompts that use template syntax are rendered by the template
+/// engine; plain prompts get the arguments prepended as a header.
+pub fn expand_prompt(prompt: &CustomPrompt, args: &[String]) -> Result<String, TemplateError> {
+    if prompt.template_syntax.is_some() {
+        render_prompt(prompt, args)
+    } else if args.is_empty() {
+        Ok(prompt.content.clone())
+    } else {
+        Ok(prepend_argument_header(prompt, args))
+    }
+}
+
+fn prepend_argument_header(prompt: &CustomPrompt, args: &[String]) -> String {
+    let mut result = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        result.push_str(&format!("argument_{}: {}\n", i + 1, arg));
+    }
+    if let Some(hint) = &prompt.argument_hint {
+        result.push_str(&format!("argument-hint: {hint}\n"));
+    }
+    if let Some(desc) = &prompt.description {
+        result.push_str(&format!("description: {desc}\n"));
+    }
+    result.push('\n');
+    result.push_str(&prompt.content);
+    result
+}
+
+/// Variables a template prompt reads that are neither declared in its
+/// `arguments:` nor provided by the renderer (`ARGUMENTS`, `subject`,
+/// `arg0`, ...), sorted by name. Such variables are only set when passed as
+/// `name=value`. Returns an error if the template does not parse.
+pub fn undeclared_prompt_variables(prompt: &CustomPrompt) -> Result<Vec<String>, TemplateError> {
+    if prompt.template_syntax != Some(TemplateSyntax::Jinja) {
+        return Ok(Vec::new());
+    }
+    let content = rewrite_dollar_arguments(&prompt.content);
+    let env = jinja_environment();
+    let template = env
+        .template_from_str(&content)
+        .map_err(|err| TemplateError::ProcessingError(err.to_string()))?;
+    let declared = prompt.template_args.as_deref().unwrap_or_default();
+    let mut undeclared: Vec<String> = template
+        .undeclared_variables(false)
+        .into_iter()
+        .filter(|name| {
+            !matches!(name.as_str(), "ARGUMENTS" | "subject" | "loop")
+                && !is_positional_variable(name)
+                && !declared.iter().any(|arg| &arg.name == name)
+                && !env.globals().any(|(global, _)| global == name)
+        })
+        .collect();
+    undeclared.sort();
+    Ok(undeclared)
+}
+
+/// Whether `name` is one of the `arg0`, `arg1`, ... positional variables.
+fn is_positional_variable(name: &str) -> bool {
+    name.strip_prefix("arg")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Bind the arguments typed after a slash prompt to the arguments it declares,
+/// without rendering its body. Used for prompts whose body is produced
+/// elsewhere, such as MCP prompts fetched with `prompts/get`.
+pub fn bind_prompt_arguments(
+    prompt: &CustomPrompt,
+    args: &[String],
+) -> Result<HashMap<String, String>, TemplateError> {
+    let args = PromptArguments::parse(args);
+    bind_declared_arguments(prompt.template_args.as_deref().unwrap_or_default(), &args)
+}
+
+fn bind_declared_arguments(
+    declared: &[TemplateArg],
+    args: &PromptArguments,
+) -> Result<HashMap<String, String>, TemplateError> {
+    let mut positional = args.positional.iter();
+    let mut values = HashMap::new();
+    for arg in declared {
+        let value = match args.named.get(&arg.name) {
+            Some(value) => Some(value.clone()),
+            None => positional.next().cloned(),
+        };
+        match value.or_else(|| arg.default_value.clone()) {
+            Some(value) => {
+                values.insert(arg.name.clone(), value);
+            }
+            None if arg.required => return Err(TemplateError::MissingVariable(arg.name.clone())),
+            None => {}
+        }
+    }
+    Ok(values)
+}
+
+/// Rewrite the shell-style `$ARGUMENTS` and `$1`, `$2`, ... placeholders into
+/// template expressions. Outside of `{{ }}`/`{% %}` tags they render the
+/// arguments; inside tags they become plain variable references so that
+/// `{% for arg in $ARGUMENTS %}` works.
+fn rewrite_dollar_arguments(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_tag = false;
+    let mut rest = content;
+    while let Some(c) = rest.chars().next() {
+        if !in_tag && (rest.starts_with("{{") || rest.starts_with("{%")) {
+            in_tag = true;
+            out.push_str(&rest[..2]);
+            rest = &rest[2..];
+            continue;
+        }
+        if in_tag && (rest.starts_with("}}") || rest.starts_with("%}")) {
+            in_tag = false;
+            out.push_str(&rest[..2]);
+            rest = &rest[2..];
+            continue;
+        }
+        if c == '$' {
+            if let Some(after) = rest[1..].strip_prefix("ARGUMENTS") {
+                out.push_str(if in_tag {
+                    "ARGUMENTS"
+                } else {
+                    "{{ ARGUMENTS | join(\" \") }}"
+                });
+                rest = after;
+                continue;
+            }
+            let digits = rest[1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len() - 1);
+            if let Ok(n) = rest[1..1 + digits].parse::<usize>()
+                && n > 0
+            {
+                let expr = format!("ARGUMENTS[{}]", n - 1);
+                if in_tag {
+                    out.push_str(&expr);
+                } else {
+                    out.push_str(&format!("{{{{ {expr} }}}}"));
+                }
+                rest = &rest[1 + digits..];
+                continue;
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+fn jinja_environment<'source>() -> Environment<'source> {
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env
+}
+
+fn render_jinja(content: &str, context: HashMap<String, Value>) -> Result<String, TemplateError> {
+    jinja_environment()
+        .render_str(content, context)
+        .map_err(|err| TemplateError::ProcessingError(err.to_string()))
 }
 
 pub fn process_simple_template(content: &str, args: &[String]) -> Result<String, TemplateError> {
@@ -21,17 +248,15 @@ pub fn process_simple_template(content: &str, args: &[String]) -> Result<String,
     Ok(result)
 }
 
-pub fn process_askama_template(
+pub fn process_jinja_template(
     content: &str,
     args: &HashMap<String, String>,
 ) -> Result<String, TemplateError> {
-    // Simple variable substitution for {{ variable }} syntax
-    let mut result = content.to_string();
-    for (key, value) in args {
-        let placeholder = format!("{{{{ {key} }}}}");
-        result = result.replace(&placeholder, value);
-    }
-    Ok(result)
+    let context = args
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+        .collect();
+    render_jinja(content, context)
 }
 
 pub fn process_template(
@@ -41,8 +266,8 @@ pub fn process_template(
 ) -> Result<String, TemplateError> {
     match syntax {
         TemplateSyntax::Simple => process_simple_template(content, args),
-        TemplateSyntax::Askama => {
-            // Convert positional arguments to named arguments for Askama
+        TemplateSyntax::Jinja => {
+            // Convert positional arguments to named arguments for Jinja
             // For now, use simple numeric names: arg0, arg1, etc.
             let mut named_args = HashMap::new();
             for (i, arg) in args.iter().enumerate() {
@@ -52,7 +277,7 @@ pub fn process_template(
             if let Some(first_arg) = args.first() {
                 named_args.insert("subject".to_string(), first_arg.clone());
             }
-            process_askama_template(content, &named_args)
+            process_jinja_template(content, &named_args)
         }
     }
 }
@@ -88,20 +313,20 @@ mod tests {
     }
 
     #[test]
-    fn test_askama_template_single_variable() {
+    fn test_jinja_template_single_variable() {
         let mut args = HashMap::new();
         args.insert("subject".to_string(), "AI safety".to_string());
-        let result = process_askama_template("Research {{ subject }}", &args).unwrap();
+        let result = process_jinja_template("Research {{ subject }}", &args).unwrap();
         assert_eq!(result, "Research AI safety");
     }
 
     #[test]
-    fn test_askama_template_multiple_variables() {
+    fn test_jinja_template_multiple_variables() {
         let mut args = HashMap::new();
         args.insert("topic".to_string(), "Rust".to_string());
         args.insert("level".to_string(), "beginner".to_string());
         let result =
-            process_askama_template("Learn {{ topic }} at {{ level }} level", &args).unwrap();
+            process_jinja_template("Learn {{ topic }} at {{ level }} level", &args).unwrap();
         assert_eq!(result, "Learn Rust at beginner level");
     }
 
@@ -113,18 +338,154 @@ mod tests {
     }
 
     #[test]
-    fn test_process_template_askama_with_subject() {
+    fn test_process_template_jinja_with_subject() {
         let args = vec!["AI safety".to_string()];
         let result =
-            process_template("Research {{ subject }}", &args, TemplateSyntax::Askama).unwrap();
+            process_template("Research {{ subject }}", &args, TemplateSyntax::Jinja).unwrap();
         assert_eq!(result, "Research AI safety");
     }
 
     #[test]
-    fn test_process_template_askama_with_positional() {
+    fn test_process_template_jinja_with_positional() {
         let args = vec!["first".to_string(), "second".to_string()];
         let result =
-            process_template("{{ arg0 }} and {{ arg1 }}", &args, TemplateSyntax::Askama).unwrap();
+            process_template("{{ arg0 }} and {{ arg1 }}", &args, TemplateSyntax::Jinja).unwrap();
         assert_eq!(result, "first and second");
     }
+
+    fn prompt(content: &str, template_args: Option<Vec<TemplateArg>>) -> CustomPrompt {
+        CustomPrompt {
+            name: "test".to_string(),
+            path: "/tmp/test.md".into(),
+            content: content.to_string(),
+            category: None,
+            argument_hint: None,
+            description: None,
+            template_args,
+            template_syntax: Some(TemplateSyntax::Jinja),
+            turn_overrides: None,
+            mcp_server: None,
+        }
+    }
+
+    fn template_arg(name: &str, required: bool, default_value: Option<&str>) -> TemplateArg {
+        TemplateArg {
+            name: name.to_string(),
+            description: None,
+            required,
+            default_value: default_value.map(str::to_string),
+        }
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| (*s).to_string()).collect()
+    }
+
+    #[test]
+    fn parses_named_and_positional_arguments() {
+        let args = PromptArguments::parse(&strings(&[
+            "src/lib.rs",
+            "focus=error handling",
+            "a=b=c",
+            "x=",
+            "=y",
+        ]));
+        assert_eq!(args.positional, strings(&["src/lib.rs", "=y"]));
+        assert_eq!(
+            args.named,
+            HashMap::from([
+                ("focus".to_string(), "error handling".to_string()),
+                ("a".to_string(), "b=c".to_string()),
+                ("x".to_string(), String::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn renders_conditionals_loops_and_filters() {
+        let content = "Review {{ ARGUMENTS | join(\", \") }}.\n{% if focus %}Focus on {{ focus | upper }}.\n{% endif %}{% for file in $ARGUMENTS %}- {{ file }}\n{% endfor %}Depth: {{ depth | default(\"shallow\") }}";
+        let rendered = render_prompt(
+            &prompt(content, None),
+            &strings(&["a.rs", "b.rs", "focus=tests"]),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "Review a.rs, b.rs.\nFocus on TESTS.\n- a.rs\n- b.rs\nDepth: shallow"
+        );
+    }
+
+    #[test]
+    fn renders_shell_style_placeholders() {
+        let rendered = render_prompt(
+            &prompt("Fix $1 in $2. All: $ARGUMENTS. Missing: [$3]", None),
+            &strings(&["bug", "main.rs"]),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "Fix bug in main.rs. All: bug main.rs. Missing: []"
+        );
+    }
+
+    #[test]
+    fn binds_declared_arguments_by_name_position_and_default() {
+        let declared = vec![
+            template_arg("file", true, None),
+            template_arg("focus", false, None),
+            template_arg("depth", false, Some("shallow")),
+        ];
+        let content = "{{ file }}|{{ focus }}|{{ depth }}";
+        let rendered = render_prompt(
+            &prompt(content, Some(declared.clone())),
+            &strings(&["focus=perf", "main.rs"]),
+        )
+        .unwrap();
+        assert_eq!(rendered, "main.rs|perf|shallow");
+
+        let rendered = render_prompt(
+            &prompt(content, Some(declared)),
+            &strings(&["main.rs", "tests", "depth=deep"]),
+        )
+        .unwrap();
+        assert_eq!(rendered, "main.rs|tests|deep");
+    }
+
+    #[test]
+    fn missing_required_argument_is_an_error() {
+        let declared = vec![template_arg("file", true, None)];
+        let err = render_prompt(&prompt("Review {{ file }}", Some(declared)), &[]).unwrap_err();
+        assert_eq!(err, TemplateError::MissingVariable("file".to_string()));
+        assert_eq!(err.to_string(), "missing required argument `file`");
+    }
+
+    #[test]
+    fn plain_prompts_get_an_argument_header() {
+        let mut plain = prompt("Fix the bug.", None);
+        plain.argument_hint = Some("<file>".to_string());
+        assert_eq!(expand_prompt(&plain, &[]).unwrap(), "Fix the bug.");
+        assert_eq!(
+            expand_prompt(&plain, &strings(&["main.rs"])).unwrap(),
+            "argument_1: main.rs\nargument-hint: <file>\n\nFix the bug."
+        );
+    }
+
+    #[test]
+    fn reports_undeclared_template_variables() {
+        let declared = vec![template_arg("file", true, None)];
+        let content = "{{ file }} {{ subject }} {{ arg1 }} {{ focus }}\n\
+                       {% for f in $ARGUMENTS %}{{ loop.index }}{{ f }}{{ depth }}{% endfor %}\n\
+                       {{ range(3) | join }}";
+        assert_eq!(
+            undeclared_prompt_variables(&prompt(content, Some(declared))).unwrap(),
+            vec!["depth".to_string(), "focus".to_string()]
+        );
+        assert!(undeclared_prompt_variables(&prompt("{% if %}", None)).is_err());
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        let err = render_prompt(&prompt("{% if %}", None), &[]).unwrap_err();
+        assert!(matches!(err, TemplateError::ProcessingError(_)), "{err:?}");
+    }
 }
@@ -17,7 +17,9 @@ use tokio::task::JoinHandle;
 use tokio::time::Duration;
 use tokio::time::Instant;
 
+use crate::config_types::ExecLimits;
 use crate::exec_command::ExecCommandSession;
+use crate::exec_limits::command_with_limits;
 use crate::truncate::truncate_middle;
 
 mod errors;
@@ -33,6 +35,8 @@ pub(crate) struct UnifiedExecRequest<'a> {
     pub session_id: Option<i32>,
     pub input_chunks: &'a [String],
     pub timeout_ms: Option<u64>,
+    /// Limits for the command of a new session.
+    pub limits: ExecLimits,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -196,7 +200,8 @@ impl UnifiedExecSessionManager {
         } else {
             let command = request.input_chunks.to_vec();
             let new_id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
-            let (session, initial_output_rx) = create_unified_exec_session(&command).await?;
+            let (session, initial_output_rx) =
+                create_unified_exec_session(&command, &request.limits).await?;
             let managed_session = ManagedUnifiedExecSession::new(session, initial_output_rx);
             let (buffer, notify) = managed_session.output_handles();
             writer_tx = managed_session.writer_sender();
@@ -300,6 +305,7 @@ impl UnifiedExecSessionManager {
 
 async fn create_unified_exec_session(
     command: &[String],
+    limits: &ExecLimits,
 ) -> Result<
     (
         ExecCommandSession,
@@ -322,6 +328,8 @@ async fn create_unified_exec_session(
         })
         .map_err(UnifiedExecError::create_session)?;
 
+    let command = command_with_limits(command.to_vec(), limits)
+        .map_err(|err| UnifiedExecError::create_session(err.into()))?;
     // Safe thanks to the check at the top of the function.
     let mut command_builder = CommandBuilder::new(command[0].clone());
     for arg in &command[1..] {
@@ -432,6 +440,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session_id");
@@ -444,6 +453,7 @@ mod tests {
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
 
@@ -452,6 +462,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
         assert!(out_2.output.contains("codex"));
@@ -469,6 +480,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
         let session_a = shell_a.session_id.expect("expected session id");
@@ -478,6 +490,7 @@ mod tests {
                 session_id: Some(session_a),
                 input_chunks: &["export CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
 
@@ -489,6 +502,7 @@ mod tests {
                     "$CODEX_INTERACTIVE_SHELL_VAR\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
         assert!(!out_2.output.contains("codex"));
@@ -498,6 +512,7 @@ mod tests {
                 session_id: Some(session_a),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
         assert!(out_3.output.contains("codex"));
@@ -515,6 +530,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session id");
@@ -527,6 +543,7 @@ mod tests {
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
 
@@ -535,6 +552,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["sleep 5 && echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(10),
+                limits: ExecLimits::default(),
             })
             .await?;
         assert!(!out_2.output.contains("codex"));
@@ -547,6 +565,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &empty,
                 timeout_ms: Some(100),
+                limits: ExecLimits::default(),
             })
             .await?;
 
@@ -566,6 +585,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["echo".to_string(), "codex".to_string()],
                 timeout_ms: Some(120_000),
+                limits: ExecLimits::default(),
             })
             .await?;
 
@@ -587,6 +607,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/echo".to_string(), "codex".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
 
@@ -608,6 +629,7 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session id");
@@ -617,6 +639,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["exit\n".to_string()],
                 timeout_ms: Some(2_500),
+                limits: ExecLimits::default(),
             })
             .await?;
 
@@ -627,6 +650,7 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &[],
                 timeout_ms: Some(100),
+                limits: ExecLimits::default(),
             })
             .await
             .expect_err("expected unknown session error");
@@ -57,14 +57,6 @@ pub(crate) enum UserNotification {
         /// The last message sent by the assistant in the turn.
         last_assistant_message: Option<String>,
     },
-    #[serde(rename_all = "kebab-case")]
-    AgentTurnStopped {
-        turn_id: String,
-        session_id: String,
-        cwd: String,
-        input_messages: Vec<String>,
-        last_assistant_message: Option<String>,
-    },
 }
 
 #[cfg(test)]
@@ -88,20 +80,4 @@ mod tests {
         );
         Ok(())
     }
-
-    #[test]
-    fn test_agent_turn_stopped_notification() {
-        let notification = UserNotification::AgentTurnStopped {
-            turn_id: "12345".to_string(),
-            session_id: "abc123".to_string(),
-            cwd: "/home/user/project".to_string(),
-            input_messages: vec!["Fix the authentication bug".to_string()],
-            last_assistant_message: Some("I've fixed the authentication issue.".to_string()),
-        };
-        let serialized = serde_json::to_string(&notification).unwrap();
-        assert_eq!(
-            serialized,
-            r#"{"type":"agent-turn-stopped","turn-id":"12345","session-id":"abc123","cwd":"/home/user/project","input-messages":["Fix the authentication bug"],"last-assistant-message":"I've fixed the authentication issue."}"#
-        );
-    }
 }
@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use codex_core::ContentItem;
+use codex_core::ModelClient;
+use codex_core::ModelProviderInfo;
+use codex_core::Prompt;
+use codex_core::ResponseEvent;
+use codex_core::ResponseItem;
+use codex_core::WireApi;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use core_test_support::load_default_config_for_test;
+use futures::StreamExt;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+const TEXT_FIXTURE: &str = include_str!("fixtures/anthropic/text.sse");
+const THINKING_TOOL_USE_FIXTURE: &str = include_str!("fixtures/anthropic/thinking_tool_use.sse");
+const TOOL_USE_NO_INPUT_FIXTURE: &str = include_str!("fixtures/anthropic/tool_use_no_input.sse");
+const OVERLOADED_ERROR_FIXTURE: &str = include_str!("fixtures/anthropic/overloaded_error.sse");
+
+fn network_disabled() -> bool {
+    std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok()
+}
+
+async fn run_stream(sse_body: &str) -> Vec<Result<ResponseEvent, String>> {
+    let server = MockServer::start().await;
+
+    let template = ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(sse_body.to_string(), "text/event-stream");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(template)
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = ModelProviderInfo {
+        name: "mock".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::AnthropicMessages,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+    };
+
+    let codex_home = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => panic!("failed to create TempDir: {e}"),
+    };
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let config = Arc::new(config);
+
+    let client = ModelClient::new(
+        Arc::clone(&config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = vec![ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: "hello".to_string(),
+        }],
+    }];
+
+    let mut stream = match client.stream(&prompt).await {
+        Ok(s) => s,
+        Err(e) => panic!("stream messages failed: {e}"),
+    };
+    let mut events = Vec::new();
+    while let Some(event) = stream.next().await {
+        events.push(event.map_err(|e| e.to_string()));
+    }
+    events
+}
+
+fn unwrap_events(events: Vec<Result<ResponseEvent, String>>) -> Vec<ResponseEvent> {
+    events
+        .into_iter()
+        .map(|ev| match ev {
+            Ok(ev) => ev,
+            Err(e) => panic!("stream event error: {e}"),
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_text_blocks() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let events = unwrap_events(run_stream(TEXT_FIXTURE).await);
+    assert_eq!(events.len(), 5, "unexpected events: {events:?}");
+
+    assert!(matches!(events[0], ResponseEvent::Created));
+
+    match &events[1] {
+        ResponseEvent::OutputTextDelta(text) => assert_eq!(text, "Hello"),
+        other => panic!("expected text delta, got {other:?}"),
+    }
+
+    match &events[2] {
+        ResponseEvent::OutputTextDelta(text) => assert_eq!(text, " world"),
+        other => panic!("expected text delta, got {other:?}"),
+    }
+
+    match &events[3] {
+        ResponseEvent::OutputItemDone(ResponseItem::Message { role, content, .. }) => {
+            assert_eq!(role, "assistant");
+            assert_eq!(
+                content,
+                &vec![ContentItem::OutputText {
+                    text: "Hello world".to_string()
+                }]
+            );
+        }
+        other => panic!("expected terminal message, got {other:?}"),
+    }
+
+    match &events[4] {
+        ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(usage),
+        } => {
+            assert_eq!(response_id, "msg_01");
+            assert_eq!(usage.input_tokens, 312);
+            assert_eq!(usage.cached_input_tokens, 200);
+            assert_eq!(usage.output_tokens, 7);
+            assert_eq!(usage.total_tokens, 319);
+        }
+        other => panic!("expected completed with usage, got {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_thinking_then_tool_use() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let events = unwrap_events(run_stream(THINKING_TOOL_USE_FIXTURE).await);
+    assert_eq!(events.len(), 6, "unexpected events: {events:?}");
+
+    assert!(matches!(events[0], ResponseEvent::Created));
+
+    match &events[1] {
+        ResponseEvent::ReasoningSummaryDelta(text) => assert_eq!(text, "Need to list "),
+        other => panic!("expected reasoning delta, got {other:?}"),
+    }
+
+    match &events[2] {
+        ResponseEvent::ReasoningSummaryDelta(text) => assert_eq!(text, "the files."),
+        other => panic!("expected reasoning delta, got {other:?}"),
+    }
+
+    match &events[3] {
+        ResponseEvent::OutputItemDone(ResponseItem::Reasoning {
+            summary,
+            encrypted_content,
+            ..
+        }) => {
+            assert_eq!(
+                summary,
+                &vec![ReasoningItemReasoningSummary::SummaryText {
+                    text: "Need to list the files.".to_string()
+                }]
+            );
+            assert_eq!(encrypted_content.as_deref(), Some("sig-abc"));
+        }
+        other => panic!("expected reasoning item, got {other:?}"),
+    }
+
+    match &events[4] {
+        ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        }) => {
+            assert_eq!(name, "shell");
+            assert_eq!(arguments, "{\"command\": [\"ls\", \"-l\"]}");
+            assert_eq!(call_id, "toolu_01");
+        }
+        other => panic!("expected function call, got {other:?}"),
+    }
+
+    assert!(matches!(events[5], ResponseEvent::Completed { .. }));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tool_use_without_input_deltas_has_empty_object_arguments() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let events = unwrap_events(run_stream(TOOL_USE_NO_INPUT_FIXTURE).await);
+    assert_eq!(events.len(), 3, "unexpected events: {events:?}");
+
+    match &events[1] {
+        ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+            name, arguments, ..
+        }) => {
+            assert_eq!(name, "update_plan");
+            assert_eq!(arguments, "{}");
+        }
+        other => panic!("expected function call, got {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn error_event_surfaces_as_stream_error() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let events = run_stream(OVERLOADED_ERROR_FIXTURE).await;
+    assert_eq!(events.len(), 2, "unexpected events: {events:?}");
+    assert!(matches!(events[0], Ok(ResponseEvent::Created)));
+    match &events[1] {
+        Err(message) => assert!(
+            message.contains("overloaded_error: Overloaded"),
+            "unexpected error: {message}"
+        ),
+        other => panic!("expected stream error, got {other:?}"),
+    }
+}
@@ -0,0 +1,765 @@
+#![cfg(not(target_os = "windows"))]
+
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use codex_core::config_types::HookConfig;
+use codex_core::config_types::HookMatcher;
+use codex_core::config_types::HooksConfig;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_once;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::MockServer;
+
+const CALL_ID: &str = "call-hook";
+
+fn write_hook(dir: &Path, name: &str, script: &str) -> anyhow::Result<HookConfig> {
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{script}\n"))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(HookConfig {
+        command: path.to_string_lossy().to_string(),
+        args: Vec::new(),
+        env: None,
+        timeout_ms: Some(10_000),
+        enabled: true,
+        matcher: None,
+        fail_closed: false,
+    })
+}
+
+/// Mount a model that calls `shell` with `command` and then finishes once the
+/// tool output comes back.
+async fn mount_shell_call_then_done(server: &MockServer, command: &[&str]) {
+    let arguments = json!({ "command": command }).to_string();
+    let first = sse(vec![
+        ev_function_call(CALL_ID, "shell", &arguments),
+        ev_completed("r1"),
+    ]);
+    mount_sse_once(
+        server,
+        |req: &wiremock::Request| {
+            !String::from_utf8_lossy(&req.body).contains("function_call_output")
+        },
+        first,
+    )
+    .await;
+
+    let second = sse(vec![ev_assistant_message("m2", "done"), ev_completed("r2")]);
+    mount_sse_once(
+        server,
+        |req: &wiremock::Request| {
+            String::from_utf8_lossy(&req.body).contains("function_call_output")
+        },
+        second,
+    )
+    .await;
+}
+
+/// Run one turn and return the events it produced, up to `TaskComplete`.
+async fn run_turn_with_hooks(
+    server: &MockServer,
+    hooks: HooksConfig,
+) -> anyhow::Result<Vec<EventMsg>> {
+    // Keep the whole `TestCodex` alive: dropping it removes the session cwd.
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.approval_policy = AskForApproval::Never;
+            cfg.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            cfg.hooks = hooks;
+        })
+        .build(server)
+        .await?;
+
+    let codex = &test.codex;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "publish the crate".into(),
+            }],
+        })
+        .await?;
+    let mut events = Vec::new();
+    loop {
+        let event = wait_for_event(codex, |_| true).await;
+        let done = matches!(event, EventMsg::TaskComplete(_));
+        events.push(event);
+        if done {
+            return Ok(events);
+        }
+    }
+}
+
+fn background_messages(events: &[EventMsg]) -> Vec<&str> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            EventMsg::BackgroundEvent(event) => Some(event.message.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Output the model received for `CALL_ID` in the follow-up request.
+async fn tool_output(server: &MockServer) -> anyhow::Result<String> {
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 2, "expected a follow-up request");
+    let body: Value = requests[1].body_json()?;
+    let output = body["input"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|item| item["type"] == "function_call_output" && item["call_id"] == CALL_ID)
+        .ok_or_else(|| anyhow::anyhow!("no function_call_output for the shell call"))?;
+    Ok(output["output"].as_str().unwrap_or_default().to_string())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_tool_use_hook_denies_tool_call() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    let marker_dir = TempDir::new()?;
+    let marker = marker_dir.path().join("published");
+    mount_shell_call_then_done(&server, &["touch", marker.to_string_lossy().as_ref()]).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(
+        hook_dir.path(),
+        "deny.sh",
+        r#"if grep -q '"touch"'; then
+  echo '{"decision":"deny","reason":"never run cargo publish"}'
+fi"#,
+    )?;
+    let events = run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            pre_tool_use: HashMap::from([("guard".to_string(), hook)]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let output = tool_output(&server).await?;
+    assert!(
+        output.contains("blocked by pre_tool_use hook 'guard': never run cargo publish"),
+        "unexpected tool output: {output}"
+    );
+    assert!(!marker.exists(), "denied command must not run");
+
+    let begin = events
+        .iter()
+        .find_map(|event| match event {
+            EventMsg::HookBegin(begin) => Some(begin),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("no HookBegin event"))?;
+    assert_eq!(
+        (begin.name.as_str(), begin.event.as_str()),
+        ("guard", "pre_tool_use")
+    );
+    let end = events
+        .iter()
+        .find_map(|event| match event {
+            EventMsg::HookEnd(end) => Some(end),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("no HookEnd event"))?;
+    assert_eq!(end.call_id, begin.call_id);
+    assert_eq!(end.exit_code, Some(0));
+    assert_eq!(
+        end.blocked_reason.as_deref(),
+        Some("never run cargo publish")
+    );
+    assert!(end.stdout.contains(r#""decision":"deny""#));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_tool_use_hook_rewrites_tool_arguments() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_shell_call_then_done(&server, &["echo", "original"]).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(
+        hook_dir.path(),
+        "rewrite.sh",
+        r#"cat > /dev/null
+echo '{"decision":"allow","updated_input":{"command":["echo","rewritten"]}}'"#,
+    )?;
+    run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            pre_tool_use: HashMap::from([("rewrite".to_string(), hook)]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let output = tool_output(&server).await?;
+    assert!(
+        output.contains("rewritten"),
+        "unexpected tool output: {output}"
+    );
+    assert!(
+        !output.contains("original"),
+        "unexpected tool output: {output}"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failing_pre_tool_use_hook_does_not_block_tool_call() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_shell_call_then_done(&server, &["echo", "still runs"]).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(hook_dir.path(), "broken.sh", "echo 'not json'; exit 1")?;
+    let events = run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            pre_tool_use: HashMap::from([("broken".to_string(), hook)]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let messages = background_messages(&events);
+    assert!(
+        messages.contains(&"pre_tool_use hook 'broken' exited with code 1; its reply is ignored"),
+        "unexpected background events: {messages:?}"
+    );
+    let output = tool_output(&server).await?;
+    assert!(
+        output.contains("still runs"),
+        "unexpected tool output: {output}"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failing_fail_closed_pre_tool_use_hook_blocks_tool_call() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_shell_call_then_done(&server, &["echo", "must not run"]).await;
+
+    let hook_dir = TempDir::new()?;
+    let mut hook = write_hook(hook_dir.path(), "slow.sh", "sleep 5")?;
+    hook.timeout_ms = Some(100);
+    hook.fail_closed = true;
+    run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            pre_tool_use: HashMap::from([("slow".to_string(), hook)]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let output = tool_output(&server).await?;
+    assert!(
+        output.contains(
+            "shell call not run: pre_tool_use hook 'slow' failed and is configured with fail_closed"
+        ),
+        "unexpected tool output: {output}"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_tool_use_hook_ask_prompts_the_user_once() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    let marker_dir = TempDir::new()?;
+    let marker = marker_dir.path().join("published");
+    mount_shell_call_then_done(&server, &["touch", marker.to_string_lossy().as_ref()]).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(
+        hook_dir.path(),
+        "ask.sh",
+        r#"cat > /dev/null
+echo '{"decision":"ask","reason":"publishing needs a human"}'"#,
+    )?;
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.approval_policy = AskForApproval::UnlessTrusted;
+            cfg.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            cfg.hooks.pre_tool_use = HashMap::from([("ask".to_string(), hook)]);
+        })
+        .build(&server)
+        .await?;
+
+    let codex = &test.codex;
+    let sub_id = codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "publish the crate".into(),
+            }],
+        })
+        .await?;
+    let mut approval_requests = 0;
+    loop {
+        match wait_for_event(codex, |_| true).await {
+            EventMsg::ExecApprovalRequest(request) => {
+                approval_requests += 1;
+                assert_eq!(request.reason.as_deref(), Some("publishing needs a human"));
+                codex
+                    .submit(Op::ExecApproval {
+                        id: sub_id.clone(),
+                        decision: ReviewDecision::Approved,
+                    })
+                    .await?;
+            }
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(approval_requests, 1, "the user must be asked only once");
+    assert!(marker.exists(), "approved command must run");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn post_tool_use_hook_output_is_appended_to_tool_output() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_shell_call_then_done(&server, &["sh", "-c", "exit 3"]).await;
+
+    let hook_dir = TempDir::new()?;
+    let payload = hook_dir.path().join("payload.json");
+    let plain = write_hook(
+        hook_dir.path(),
+        "lint.sh",
+        &format!("cat > '{}'\necho 'lint: 1 warning'", payload.display()),
+    )?;
+    let structured = write_hook(
+        hook_dir.path(),
+        "fmt.sh",
+        r#"cat > /dev/null
+echo '{"additional_context":"formatted 2 files"}'"#,
+    )?;
+    run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            post_tool_use: HashMap::from([
+                ("lint".to_string(), plain),
+                ("fmt".to_string(), structured),
+            ]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let output = tool_output(&server).await?;
+    assert!(
+        output.ends_with(
+            "\n\npost_tool_use hook 'fmt' output:\nformatted 2 files\n\npost_tool_use hook 'lint' output:\nlint: 1 warning"
+        ),
+        "unexpected tool output: {output}"
+    );
+
+    let payload: Value = serde_json::from_str(&std::fs::read_to_string(payload)?)?;
+    assert_eq!(payload["type"], "post-tool-use");
+    assert_eq!(payload["tool-name"], "shell");
+    assert_eq!(
+        payload["tool-input"]["command"],
+        json!(["sh", "-c", "exit 3"])
+    );
+    assert_eq!(payload["success"], false);
+    assert_eq!(payload["exit-code"], 3);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn user_prompt_submit_hook_blocks_prompt() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(
+        hook_dir.path(),
+        "block.sh",
+        r#"if grep -q 'publish'; then
+  echo '{"decision":"block","reason":"publishing is frozen"}'
+fi"#,
+    )?;
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.hooks.user_prompt_submit = HashMap::from([("freeze".to_string(), hook)]);
+        })
+        .build(&server)
+        .await?;
+
+    let codex = &test.codex;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "publish the crate".into(),
+            }],
+        })
+        .await?;
+    let error = wait_for_event(codex, |ev| matches!(ev, EventMsg::Error(_))).await;
+    let EventMsg::Error(error) = error else {
+        unreachable!("wait_for_event only returns matching events");
+    };
+    assert_eq!(
+        error.message,
+        "prompt blocked by user_prompt_submit hook 'freeze': publishing is frozen"
+    );
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert!(
+        requests.is_empty(),
+        "blocked prompt must not reach the model"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_hooks_add_context_and_run_on_shutdown() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_sse_once(
+        &server,
+        |_req: &wiremock::Request| true,
+        sse(vec![ev_assistant_message("m1", "done"), ev_completed("r1")]),
+    )
+    .await;
+
+    let hook_dir = TempDir::new()?;
+    let ended = hook_dir.path().join("ended.json");
+    let start = write_hook(
+        hook_dir.path(),
+        "start.sh",
+        "cat > /dev/null\necho 'on branch main'",
+    )?;
+    let prompt = write_hook(
+        hook_dir.path(),
+        "prompt.sh",
+        r#"cat > /dev/null
+echo '{"additional_context":"ticket ABC-1 is open"}'"#,
+    )?;
+    let end = write_hook(
+        hook_dir.path(),
+        "end.sh",
+        &format!("cat > '{}'", ended.display()),
+    )?;
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.hooks.session_start = HashMap::from([("git".to_string(), start)]);
+            cfg.hooks.user_prompt_submit = HashMap::from([("tickets".to_string(), prompt)]);
+            cfg.hooks.session_end = HashMap::from([("cleanup".to_string(), end)]);
+        })
+        .build(&server)
+        .await?;
+
+    let codex = &test.codex;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "what is open?".into(),
+            }],
+        })
+        .await?;
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    let body = String::from_utf8_lossy(&requests[0].body).to_string();
+    assert!(
+        body.contains(
+            r#"<hook_context source=\"session_start:git\">\non branch main\n</hook_context>"#
+        ),
+        "missing session_start context: {body}"
+    );
+    assert!(
+        body.contains(r#"<hook_context source=\"user_prompt_submit:tickets\">\nticket ABC-1 is open\n</hook_context>"#),
+        "missing user_prompt_submit context: {body}"
+    );
+
+    codex.submit(Op::Shutdown).await?;
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+    let payload: Value = serde_json::from_str(&std::fs::read_to_string(ended)?)?;
+    assert_eq!(payload["type"], "session-end");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn slow_user_prompt_submit_hook_can_be_interrupted() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(hook_dir.path(), "slow.sh", "sleep 30")?;
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.hooks.user_prompt_submit = HashMap::from([("slow".to_string(), hook)]);
+        })
+        .build(&server)
+        .await?;
+
+    let codex = &test.codex;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "publish the crate".into(),
+            }],
+        })
+        .await?;
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::HookBegin(_))).await;
+    codex.submit(Op::Interrupt).await?;
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::TurnAborted(_))).await;
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert!(
+        requests.is_empty(),
+        "interrupted prompt must not reach the model"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_tool_use_hooks_only_run_for_matching_calls() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_shell_call_then_done(&server, &["touch", "published"]).await;
+
+    let hook_dir = TempDir::new()?;
+    let mut patches_only = write_hook(
+        hook_dir.path(),
+        "patches.sh",
+        r#"echo '{"decision":"block","reason":"patches are frozen"}'"#,
+    )?;
+    patches_only.matcher = Some(HookMatcher {
+        tool: Some("apply_patch".to_string()),
+        glob: None,
+    });
+    let mut touch_only = write_hook(
+        hook_dir.path(),
+        "touch.sh",
+        "echo 'no touching' >&2; exit 2",
+    )?;
+    touch_only.matcher = Some(HookMatcher {
+        tool: Some("shell".to_string()),
+        glob: Some("touch *".to_string()),
+    });
+    run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            pre_tool_use: HashMap::from([
+                ("patches".to_string(), patches_only),
+                ("touch".to_string(), touch_only),
+            ]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let output = tool_output(&server).await?;
+    assert_eq!(
+        output,
+        "shell call blocked by pre_tool_use hook 'touch': no touching"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn slow_session_start_hook_does_not_block_shutdown() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(hook_dir.path(), "slow.sh", "sleep 30")?;
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.hooks.session_start = HashMap::from([("slow".to_string(), hook)]);
+        })
+        .build(&server)
+        .await?;
+
+    let codex = &test.codex;
+    codex.submit(Op::Shutdown).await?;
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn invalid_hook_reply_is_reported_as_background_event() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_shell_call_then_done(&server, &["echo", "still runs"]).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(
+        hook_dir.path(),
+        "typo.sh",
+        r#"echo '{"decision":"deny-all"}'"#,
+    )?;
+    let events = run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            pre_tool_use: HashMap::from([("typo".to_string(), hook)]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let messages = background_messages(&events);
+    assert!(
+        messages.iter().any(|message| message
+            .starts_with("pre_tool_use hook 'typo' replied with an invalid response: invalid JSON reply: unknown variant `deny-all`")),
+        "unexpected background events: {messages:?}"
+    );
+    let output = tool_output(&server).await?;
+    assert!(
+        output.contains("still runs"),
+        "unexpected tool output: {output}"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn post_tool_use_hook_can_stop_the_task() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_shell_call_then_done(&server, &["echo", "built"]).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(
+        hook_dir.path(),
+        "stop.sh",
+        r#"cat > /dev/null
+echo '{"continue":false,"reason":"build is red","system_message":"fix the build first"}'"#,
+    )?;
+    let events = run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            post_tool_use: HashMap::from([("ci".to_string(), hook)]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        background_messages(&events),
+        vec![
+            "post_tool_use hook 'ci': fix the build first",
+            "stopped by post_tool_use hook 'ci': build is red",
+        ]
+    );
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1, "the task must end without a follow-up");
+    Ok(())
+}
+
+/// Mount a model that answers every request with a final message.
+async fn mount_final_message(server: &MockServer) {
+    mount_sse_once(
+        server,
+        |_req: &wiremock::Request| true,
+        sse(vec![ev_assistant_message("m1", "done"), ev_completed("r1")]),
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stop_hook_block_starts_another_turn() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_final_message(&server).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(
+        hook_dir.path(),
+        "tests.sh",
+        r#"if grep -q '"stop-hook-active":false'; then
+  echo '{"decision":"block","reason":"tests still failing"}'
+fi"#,
+    )?;
+    let events = run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            stop: HashMap::from([("tests".to_string(), hook)]),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        background_messages(&events),
+        vec!["stop hook 'tests' kept the agent working: tests still failing"]
+    );
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 2);
+    let body = String::from_utf8_lossy(&requests[1].body).to_string();
+    assert!(
+        body.contains(
+            r#"<hook_context source=\"stop:tests\">\nYou are not done yet: tests still failing\n</hook_context>"#
+        ),
+        "missing stop hook reason: {body}"
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stop_hook_loop_is_limited() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_final_message(&server).await;
+
+    let hook_dir = TempDir::new()?;
+    let hook = write_hook(hook_dir.path(), "never.sh", "echo 'still red' >&2; exit 2")?;
+    let events = run_turn_with_hooks(
+        &server,
+        HooksConfig {
+            stop: HashMap::from([("never".to_string(), hook)]),
+            stop_loop_limit: Some(2),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        background_messages(&events),
+        vec![
+            "stop hook 'never' kept the agent working: still red",
+            "stop hook 'never' kept the agent working: still red",
+            "stop hook 'never' blocked the stop again, but the limit of 2 extra turns was reached: still red",
+        ]
+    );
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 3);
+    Ok(())
+}
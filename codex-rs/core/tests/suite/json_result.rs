@@ -69,6 +69,7 @@ async fn codex_returns_json_result() -> anyhow::Result<()> {
                 text: "hello world".into(),
             }],
             final_output_json_schema: Some(serde_json::from_str(SCHEMA)?),
+            allowed_tools: None,
             cwd: cwd.path().to_path_buf(),
             approval_policy: AskForApproval::Never,
             sandbox_policy: SandboxPolicy::DangerFullAccess,
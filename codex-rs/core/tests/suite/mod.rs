@@ -7,13 +7,16 @@ mod compact_resume_fork;
 mod exec;
 mod exec_stream_events;
 mod fork_conversation;
+mod hooks;
 mod json_result;
 mod live_cli;
 mod model_overrides;
 mod prompt_caching;
+mod prompt_turn;
 mod review;
 mod rollout_list_find;
 mod seatbelt;
 mod stream_error_allows_next_turn;
 mod stream_no_completed;
+mod subagents;
 mod user_notification;
@@ -216,7 +216,13 @@ async fn prompt_tools_are_consistent_across_requests() {
 
     // our internal implementation is responsible for keeping tools in sync
     // with the OpenAI schema, so we just verify the tool presence here
-    let expected_tools_names: &[&str] = &["shell", "update_plan", "apply_patch", "view_image"];
+    let expected_tools_names: &[&str] = &[
+        "shell",
+        "read_output",
+        "update_plan",
+        "apply_patch",
+        "view_image",
+    ];
     let body0 = requests[0].body_json::<serde_json::Value>().unwrap();
     assert_eq!(
         body0["instructions"],
@@ -232,6 +238,90 @@ async fn prompt_tools_are_consistent_across_requests() {
     assert_tool_names(&body1, expected_tools_names);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn user_turn_allowed_tools_limit_tools_for_that_turn() {
+    let server = MockServer::start().await;
+
+    let sse = sse_completed("resp");
+    let template = ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(sse, "text/event-stream");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(template)
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.cwd = cwd.path().to_path_buf();
+    config.model_provider = model_provider;
+    config.include_apply_patch_tool = true;
+    config.include_plan_tool = true;
+
+    let default_approval_policy = config.approval_policy;
+    let default_sandbox_policy = config.sandbox_policy.clone();
+    let default_model = config.model.clone();
+    let default_effort = config.model_reasoning_effort;
+    let default_summary = config.model_reasoning_summary;
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    for (text, allowed_tools) in [
+        (
+            "restricted",
+            Some(vec!["shell".to_string(), "update_*".to_string()]),
+        ),
+        ("unrestricted", None),
+    ] {
+        codex
+            .submit(Op::UserTurn {
+                items: vec![InputItem::Text { text: text.into() }],
+                cwd: cwd.path().to_path_buf(),
+                approval_policy: default_approval_policy,
+                sandbox_policy: default_sandbox_policy.clone(),
+                model: default_model.clone(),
+                effort: default_effort,
+                summary: default_summary,
+                final_output_json_schema: None,
+                allowed_tools,
+            })
+            .await
+            .unwrap();
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    }
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2, "expected two POST requests");
+    let body0 = requests[0].body_json::<serde_json::Value>().unwrap();
+    assert_tool_names(&body0, &["shell", "update_plan"]);
+    let body1 = requests[1].body_json::<serde_json::Value>().unwrap();
+    assert_tool_names(
+        &body1,
+        &[
+            "shell",
+            "read_output",
+            "update_plan",
+            "apply_patch",
+            "view_image",
+        ],
+    );
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn prefixes_context_and_instructions_once_and_consistently_across_requests() {
     use pretty_assertions::assert_eq;
@@ -408,8 +498,10 @@ async fn overrides_turn_context_but_keeps_cached_prefix_and_key_constant() {
             sandbox_policy: Some(SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
                 network_access: true,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                read_deny: vec![],
             }),
             model: Some("o3".to_string()),
             effort: Some(Some(ReasoningEffort::High)),
@@ -540,13 +632,16 @@ async fn per_turn_overrides_keep_cached_prefix_and_key_constant() {
             sandbox_policy: SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
                 network_access: true,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                read_deny: vec![],
             },
             model: "o3".to_string(),
             effort: Some(ReasoningEffort::High),
             summary: ReasoningSummary::Detailed,
             final_output_json_schema: None,
+            allowed_tools: None,
         })
         .await
         .unwrap();
@@ -657,6 +752,7 @@ async fn send_user_turn_with_no_changes_does_not_send_environment_context() {
             effort: default_effort,
             summary: default_summary,
             final_output_json_schema: None,
+            allowed_tools: None,
         })
         .await
         .unwrap();
@@ -674,6 +770,7 @@ async fn send_user_turn_with_no_changes_does_not_send_environment_context() {
             effort: default_effort,
             summary: default_summary,
             final_output_json_schema: None,
+            allowed_tools: None,
         })
         .await
         .unwrap();
@@ -770,6 +867,7 @@ async fn send_user_turn_with_changes_sends_environment_context() {
             effort: default_effort,
             summary: default_summary,
             final_output_json_schema: None,
+            allowed_tools: None,
         })
         .await
         .unwrap();
@@ -787,6 +885,7 @@ async fn send_user_turn_with_changes_sends_environment_context() {
             effort: Some(ReasoningEffort::High),
             summary: ReasoningSummary::Detailed,
             final_output_json_schema: None,
+            allowed_tools: None,
         })
         .await
         .unwrap();
@@ -0,0 +1,91 @@
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::custom_prompts::PromptTurnOverrides;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::mount_sse_once;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn prompt_overrides_last_one_task_and_cannot_loosen_policies() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_sse_once(
+        &server,
+        |_req: &wiremock::Request| true,
+        sse(vec![ev_assistant_message("m1", "done"), ev_completed("r1")]),
+    )
+    .await;
+    let test = test_codex()
+        .with_config(|cfg| {
+            cfg.approval_policy = AskForApproval::OnRequest;
+            cfg.sandbox_policy = SandboxPolicy::new_read_only_policy();
+        })
+        .build(&server)
+        .await?;
+    let codex = &test.codex;
+
+    codex
+        .submit(Op::PromptTurn {
+            items: vec![InputItem::Text {
+                text: "review".into(),
+            }],
+            overrides: PromptTurnOverrides {
+                model: Some("gpt-5-codex".to_string()),
+                sandbox: Some(SandboxMode::DangerFullAccess),
+                approval: Some(AskForApproval::Never),
+                ..Default::default()
+            },
+        })
+        .await?;
+    let mut background = Vec::new();
+    loop {
+        match wait_for_event(codex, |_| true).await {
+            EventMsg::BackgroundEvent(event) => background.push(event.message),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        background,
+        vec![
+            "ignored the prompt's `sandbox: danger-full-access`, which is less restrictive than the session's sandbox".to_string(),
+            "ignored the prompt's `approval: never`, which asks for approval less often than the session's `on-request`".to_string(),
+        ]
+    );
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "follow up".into(),
+            }],
+        })
+        .await?;
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 2);
+    let bodies: Vec<Value> = requests
+        .iter()
+        .map(|request| serde_json::from_slice(&request.body))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(bodies[0]["model"], "gpt-5-codex");
+    assert_eq!(bodies[1]["model"], test.session_configured.model.as_str());
+    let first = bodies[0].to_string();
+    assert!(
+        !first.contains("danger-full-access") && !first.contains("<approval_policy>never"),
+        "loosening overrides reached the model: {first}"
+    );
+    Ok(())
+}
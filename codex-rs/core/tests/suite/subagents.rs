@@ -0,0 +1,254 @@
+#![cfg(not(target_os = "windows"))]
+
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_once;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+
+const CALL_ID: &str = "call-task";
+const AGENT_INSTRUCTIONS: &str = "You are the reviewer subagent.";
+
+fn body_contains(req: &wiremock::Request, needle: &str) -> bool {
+    String::from_utf8_lossy(&req.body).contains(needle)
+}
+
+fn tool_names(body: &Value) -> Vec<String> {
+    body["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| tool["name"].as_str().map(str::to_string))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn task_tool_runs_subagent_and_returns_its_answer() -> anyhow::Result<()> {
+    let server = start_mock_server().await;
+
+    // Parent: delegate to the reviewer, then finish once its answer is back.
+    let arguments = json!({ "agent": "reviewer", "prompt": "Review the diff" }).to_string();
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            !body_contains(req, AGENT_INSTRUCTIONS) && !body_contains(req, "function_call_output")
+        },
+        sse(vec![
+            ev_function_call(CALL_ID, "task", &arguments),
+            ev_completed("r1"),
+        ]),
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            !body_contains(req, AGENT_INSTRUCTIONS) && body_contains(req, "function_call_output")
+        },
+        sse(vec![
+            ev_assistant_message("m2", "all done"),
+            ev_completed("r2"),
+        ]),
+    )
+    .await;
+    // Subagent: answer right away.
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| body_contains(req, AGENT_INSTRUCTIONS),
+        sse(vec![
+            ev_assistant_message("m-sub", "looks good to me"),
+            ev_completed("r-sub"),
+        ]),
+    )
+    .await;
+
+    let project = TempDir::new()?;
+    let agents_dir = project.path().join(".codex/agents");
+    std::fs::create_dir_all(&agents_dir)?;
+    std::fs::write(
+        agents_dir.join("reviewer.md"),
+        format!(
+            "---\ndescription: Reviews diffs\nallowed-tools: shell\n---\n{AGENT_INSTRUCTIONS}\n"
+        ),
+    )?;
+    let cwd = project.path().to_path_buf();
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.cwd = cwd;
+            cfg.approval_policy = AskForApproval::Never;
+            cfg.sandbox_policy = SandboxPolicy::DangerFullAccess;
+        })
+        .build(&server)
+        .await?;
+    let codex = &test.codex;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "get the diff reviewed".into(),
+            }],
+        })
+        .await?;
+    let mut events = Vec::new();
+    loop {
+        let event = wait_for_event(codex, |_| true).await;
+        let done = matches!(event, EventMsg::TaskComplete(_));
+        events.push(event);
+        if done {
+            break;
+        }
+    }
+
+    let begin = events
+        .iter()
+        .position(|ev| matches!(ev, EventMsg::SubagentBegin(ev) if ev.call_id == CALL_ID && ev.agent == "reviewer" && ev.prompt == "Review the diff"))
+        .expect("SubagentBegin event");
+    let end = events
+        .iter()
+        .position(|ev| matches!(ev, EventMsg::SubagentEnd(ev) if ev.call_id == CALL_ID && ev.last_agent_message.as_deref() == Some("looks good to me") && ev.error.is_none()))
+        .expect("SubagentEnd event");
+    assert!(begin < end);
+    // The subagent's answer goes back to the agent, not to the user.
+    assert!(!events.iter().any(|ev| matches!(
+        ev,
+        EventMsg::AgentMessage(msg) if msg.message == "looks good to me"
+    )));
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 3);
+    let parent_first: Value = requests[0].body_json()?;
+    let subagent: Value = requests[1].body_json()?;
+    let parent_second: Value = requests[2].body_json()?;
+
+    assert!(tool_names(&parent_first).contains(&"task".to_string()));
+    // The subagent only gets its allowed tools and cannot delegate further.
+    assert_eq!(tool_names(&subagent), vec!["shell".to_string()]);
+    assert_eq!(subagent["instructions"], AGENT_INSTRUCTIONS);
+    let subagent_input = subagent["input"].to_string();
+    assert!(subagent_input.contains("Review the diff"));
+    assert!(!subagent_input.contains("get the diff reviewed"));
+
+    let output = parent_second["input"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|item| item["type"] == "function_call_output" && item["call_id"] == CALL_ID)
+        .expect("task output");
+    assert_eq!(output["output"], "looks good to me");
+
+    // The subagent thread is recorded in the rollout, apart from the main history.
+    codex.submit(Op::Shutdown).await?;
+    wait_for_event(codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+    let rollout = std::fs::read_to_string(&test.session_configured.rollout_path)?;
+    let subagent_items: Vec<Value> = rollout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|line| line["type"] == "subagent_item")
+        .collect();
+    assert_eq!(subagent_items.len(), 2, "task prompt and answer: {rollout}");
+    assert!(subagent_items.iter().all(
+        |line| line["payload"]["call_id"] == CALL_ID && line["payload"]["agent"] == "reviewer"
+    ));
+
+    Ok(())
+}
+
+/// Agents are checked into the repository, so their frontmatter cannot give
+/// them a looser sandbox or approval policy than the session's.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn subagent_cannot_loosen_sandbox_or_approval() -> anyhow::Result<()> {
+    let server = start_mock_server().await;
+
+    let arguments = json!({ "agent": "escalator", "prompt": "Do it" }).to_string();
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            !body_contains(req, AGENT_INSTRUCTIONS) && !body_contains(req, "function_call_output")
+        },
+        sse(vec![
+            ev_function_call(CALL_ID, "task", &arguments),
+            ev_completed("r1"),
+        ]),
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            !body_contains(req, AGENT_INSTRUCTIONS) && body_contains(req, "function_call_output")
+        },
+        sse(vec![ev_assistant_message("m2", "done"), ev_completed("r2")]),
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| body_contains(req, AGENT_INSTRUCTIONS),
+        sse(vec![
+            ev_assistant_message("m-sub", "did it"),
+            ev_completed("r-sub"),
+        ]),
+    )
+    .await;
+
+    let project = TempDir::new()?;
+    let agents_dir = project.path().join(".codex/agents");
+    std::fs::create_dir_all(&agents_dir)?;
+    std::fs::write(
+        agents_dir.join("escalator.md"),
+        format!("---\nsandbox: danger-full-access\napproval: never\n---\n{AGENT_INSTRUCTIONS}\n"),
+    )?;
+    let cwd = project.path().to_path_buf();
+    let test = test_codex()
+        .with_config(move |cfg| {
+            cfg.cwd = cwd;
+            cfg.approval_policy = AskForApproval::OnRequest;
+            cfg.sandbox_policy = SandboxPolicy::new_read_only_policy();
+        })
+        .build(&server)
+        .await?;
+    let codex = &test.codex;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "delegate".into(),
+            }],
+        })
+        .await?;
+    let mut notices = Vec::new();
+    loop {
+        match wait_for_event(codex, |_| true).await {
+            EventMsg::BackgroundEvent(ev) => notices.push(ev.message),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        notices,
+        vec![
+            "ignored `sandbox: danger-full-access` of agent escalator, which is less restrictive than the session's sandbox".to_string(),
+            "ignored `approval: never` of agent escalator, which asks for approval less often than the session's `on-request`".to_string(),
+        ]
+    );
+    let requests = server.received_requests().await.unwrap_or_default();
+    let subagent = requests
+        .iter()
+        .find(|req| body_contains(req, AGENT_INSTRUCTIONS))
+        .expect("subagent request");
+    let subagent_input = String::from_utf8_lossy(&subagent.body);
+    assert!(subagent_input.contains("<sandbox_mode>read-only</sandbox_mode>"));
+    assert!(subagent_input.contains("<approval_policy>on-request</approval_policy>"));
+
+    Ok(())
+}
@@ -14,6 +14,7 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::HookEndEvent;
 use codex_core::protocol::McpInvocation;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
@@ -21,6 +22,8 @@ use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::StreamErrorEvent;
+use codex_core::protocol::SubagentBeginEvent;
+use codex_core::protocol::SubagentEndEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
@@ -370,6 +373,75 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     }
                 }
             }
+            EventMsg::HookBegin(_) => {}
+            EventMsg::HookEnd(HookEndEvent {
+                call_id: _,
+                name,
+                event,
+                duration,
+                exit_code,
+                timed_out,
+                stdout: _,
+                stderr,
+                blocked_reason,
+            }) => {
+                let status = match (&blocked_reason, timed_out, exit_code) {
+                    (Some(_), _, _) => "blocked".to_string(),
+                    (None, true, _) => "timed out".to_string(),
+                    (None, false, Some(0)) => "succeeded".to_string(),
+                    (None, false, Some(code)) => format!("exited {code}"),
+                    (None, false, None) => "failed".to_string(),
+                };
+                let succeeded = blocked_reason.is_none() && !timed_out && exit_code == Some(0);
+                let title_style = if succeeded { self.green } else { self.red };
+                ts_println!(
+                    self,
+                    "{} {}",
+                    "hook".style(self.magenta),
+                    format!("{event} {name} {status} in {}", format_duration(duration))
+                        .style(title_style),
+                );
+                if let Some(reason) = blocked_reason {
+                    println!("{}", reason.style(self.dimmed));
+                } else if !succeeded {
+                    for line in stderr.lines().take(MAX_OUTPUT_LINES_FOR_EXEC_TOOL_CALL) {
+                        println!("{}", line.style(self.dimmed));
+                    }
+                }
+            }
+            EventMsg::SubagentBegin(SubagentBeginEvent {
+                call_id: _,
+                agent,
+                prompt,
+            }) => {
+                ts_println!(
+                    self,
+                    "{} {}",
+                    "task".style(self.magenta),
+                    agent.style(self.bold),
+                );
+                println!("{}", prompt.style(self.dimmed));
+            }
+            EventMsg::SubagentEnd(SubagentEndEvent {
+                call_id: _,
+                agent,
+                last_agent_message,
+                error,
+            }) => match error {
+                Some(error) => {
+                    ts_println!(
+                        self,
+                        "{}",
+                        format!("{agent} failed: {error}").style(self.red)
+                    );
+                }
+                None => {
+                    ts_println!(self, "{}", format!("{agent} finished:").style(self.green));
+                    if let Some(message) = last_agent_message {
+                        println!("{}", message.style(self.dimmed));
+                    }
+                }
+            },
             EventMsg::WebSearchBegin(WebSearchBeginEvent { call_id: _ }) => {}
             EventMsg::WebSearchEnd(WebSearchEndEvent { call_id: _, query }) => {
                 ts_println!(self, "🌐 Searched: {query}");
@@ -580,6 +652,12 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ListCustomPromptsResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::GetMcpPromptResponse(_) => {
+                // Currently ignored in exec output.
+            }
+            EventMsg::ListBackgroundProcessesResponse(_) => {
+                // Currently ignored in exec output.
+            }
             EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
                 TurnAbortReason::Interrupted => {
                     ts_println!(self, "task interrupted");
@@ -310,6 +310,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
             effort: default_effort,
             summary: default_summary,
             final_output_json_schema: output_schema,
+            allowed_tools: None,
         })
         .await?;
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
@@ -118,6 +118,14 @@ impl PolicyBuilder {
     }
 }
 
+/// The `file:line` of the `define_program()` call being evaluated.
+fn define_program_location(eval: &Evaluator) -> Option<String> {
+    eval.call_stack_top_location().map(|span| {
+        let span = span.resolve();
+        format!("{}:{}", span.file, span.span.begin.line + 1)
+    })
+}
+
 #[starlark_module]
 fn policy_builtins(builder: &mut GlobalsBuilder) {
     fn define_program<'v>(
@@ -167,6 +175,7 @@ fn policy_builtins(builder: &mut GlobalsBuilder) {
                 .into_iter()
                 .map(|v| v.items.to_vec())
                 .collect(),
+            define_program_location(eval),
         );
 
         #[expect(clippy::unwrap_used)]
@@ -27,6 +27,9 @@ pub struct ProgramSpec {
     required_options: HashSet<String>,
     should_match: Vec<Vec<String>>,
     should_not_match: Vec<Vec<String>>,
+    /// Where the program was defined, as `file:line`, so failing examples
+    /// can point at their definition.
+    pub location: Option<String>,
 }
 
 impl ProgramSpec {
@@ -40,6 +43,7 @@ impl ProgramSpec {
         forbidden: Option<String>,
         should_match: Vec<Vec<String>>,
         should_not_match: Vec<Vec<String>>,
+        location: Option<String>,
     ) -> Self {
         let required_options = allowed_options
             .iter()
@@ -62,6 +66,7 @@ impl ProgramSpec {
             required_options,
             should_match,
             should_not_match,
+            location,
         }
     }
 }
@@ -134,6 +139,39 @@ impl ProgramSpec {
                     }
                     None => {
                         // It could be an --option=value style flag...
+                        if self.combined_format
+                            && arg.starts_with("--")
+                            && let Some((name, value)) = arg.split_once('=')
+                            && let Some(opt) = self.allowed_options.get(name)
+                        {
+                            match &opt.meta {
+                                OptMeta::Flag => {
+                                    return Err(Error::FlagFollowedByValue {
+                                        program: self.program.clone(),
+                                        flag: name.to_string(),
+                                        value: value.to_string(),
+                                    });
+                                }
+                                OptMeta::Value(arg_type) => {
+                                    matched_opts.push(MatchedOpt::new(
+                                        name,
+                                        value,
+                                        arg_type.clone(),
+                                    )?);
+                                    continue;
+                                }
+                            }
+                        }
+
+                        // ...or a bundle of short options such as -al.
+                        if self.option_bundling && !arg.starts_with("--") && arg.len() > 2 {
+                            expecting_option_value = self.resolve_bundled_options(
+                                arg,
+                                &mut matched_flags,
+                                &mut matched_opts,
+                            )?;
+                            continue;
+                        }
                     }
                 }
 
@@ -194,6 +232,41 @@ impl ProgramSpec {
         }
     }
 
+    /// Resolves a bundle of short options such as `-al` into `-a -l`. Every
+    /// option in the bundle but the last must be a flag. If an option that
+    /// takes a value appears, the rest of the bundle is its value (`-n5`); if
+    /// nothing follows it, the value is expected in the next argument, in
+    /// which case the option name and type are returned.
+    fn resolve_bundled_options(
+        &self,
+        bundle: &str,
+        matched_flags: &mut Vec<MatchedFlag>,
+        matched_opts: &mut Vec<MatchedOpt>,
+    ) -> Result<Option<(String, ArgType)>> {
+        let short_options = &bundle[1..];
+        for (offset, ch) in short_options.char_indices() {
+            let name = format!("-{ch}");
+            let Some(opt) = self.allowed_options.get(&name) else {
+                return Err(Error::UnknownOption {
+                    program: self.program.clone(),
+                    option: name,
+                });
+            };
+            match &opt.meta {
+                OptMeta::Flag => matched_flags.push(MatchedFlag { name }),
+                OptMeta::Value(arg_type) => {
+                    let value = &short_options[offset + ch.len_utf8()..];
+                    if value.is_empty() {
+                        return Ok(Some((name, arg_type.clone())));
+                    }
+                    matched_opts.push(MatchedOpt::new(&name, value, arg_type.clone())?);
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(None)
+    }
+
     pub fn verify_should_match_list(&self) -> Vec<PositiveExampleFailedCheck> {
         let mut violations = Vec::new();
         for good in &self.should_match {
@@ -208,6 +281,7 @@ impl ProgramSpec {
                         program: self.program.clone(),
                         args: good.clone(),
                         error,
+                        location: self.location.clone(),
                     });
                 }
             }
@@ -226,6 +300,7 @@ impl ProgramSpec {
                 violations.push(NegativeExamplePassedCheck {
                     program: self.program.clone(),
                     args: bad.clone(),
+                    location: self.location.clone(),
                 });
             }
         }
@@ -238,10 +313,12 @@ pub struct PositiveExampleFailedCheck {
     pub program: String,
     pub args: Vec<String>,
     pub error: Error,
+    pub location: Option<String>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct NegativeExamplePassedCheck {
     pub program: String,
     pub args: Vec<String>,
+    pub location: Option<String>,
 }
@@ -1,4 +1,5 @@
 use codex_execpolicy::NegativeExamplePassedCheck;
+use codex_execpolicy::PolicyParser;
 use codex_execpolicy::get_default_policy;
 
 #[test]
@@ -7,3 +8,26 @@ fn verify_everything_in_bad_list_is_rejected() {
     let violations = policy.check_each_bad_list_individually();
     assert_eq!(Vec::<NegativeExamplePassedCheck>::new(), violations);
 }
+
+#[test]
+fn passed_negative_example_reports_where_the_program_is_defined() {
+    let unparsed_policy = r#"
+define_program(
+    program="ls",
+    options=[flag("-l")],
+    should_not_match=[["-a"], ["-l"]],
+)
+"#;
+    let policy = PolicyParser::new("team.policy", unparsed_policy)
+        .parse()
+        .expect("failed to parse policy");
+    let violations = policy.check_each_bad_list_individually();
+    assert_eq!(
+        vec![NegativeExamplePassedCheck {
+            program: "ls".to_string(),
+            args: vec!["-l".to_string()],
+            location: Some("team.policy:2".to_string()),
+        }],
+        violations
+    );
+}
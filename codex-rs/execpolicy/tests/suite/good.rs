@@ -1,3 +1,5 @@
+use codex_execpolicy::Error;
+use codex_execpolicy::PolicyParser;
 use codex_execpolicy::PositiveExampleFailedCheck;
 use codex_execpolicy::get_default_policy;
 
@@ -7,3 +9,30 @@ fn verify_everything_in_good_list_is_allowed() {
     let violations = policy.check_each_good_list_individually();
     assert_eq!(Vec::<PositiveExampleFailedCheck>::new(), violations);
 }
+
+#[test]
+fn failed_positive_example_reports_where_the_program_is_defined() {
+    let unparsed_policy = r#"
+define_program(
+    program="ls",
+    options=[flag("-l")],
+    should_match=[["-l"], ["-a"]],
+)
+"#;
+    let policy = PolicyParser::new("team.policy", unparsed_policy)
+        .parse()
+        .expect("failed to parse policy");
+    let violations = policy.check_each_good_list_individually();
+    assert_eq!(
+        vec![PositiveExampleFailedCheck {
+            program: "ls".to_string(),
+            args: vec!["-a".to_string()],
+            error: Error::UnknownOption {
+                program: "ls".to_string(),
+                option: "-a".to_string(),
+            },
+            location: Some("team.policy:2".to_string()),
+        }],
+        violations
+    );
+}
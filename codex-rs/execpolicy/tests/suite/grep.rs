@@ -0,0 +1,119 @@
+extern crate codex_execpolicy;
+
+use codex_execpolicy::ArgType;
+use codex_execpolicy::Error;
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedArg;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::MatchedFlag;
+use codex_execpolicy::MatchedOpt;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::Result;
+use codex_execpolicy::ValidExec;
+use codex_execpolicy::get_default_policy;
+
+#[expect(clippy::expect_used)]
+fn setup() -> Policy {
+    get_default_policy().expect("failed to load default policy")
+}
+
+#[test]
+fn test_grep_bundled_flags_and_combined_option() -> Result<()> {
+    let policy = setup();
+    let grep = ExecCall::new("grep", &["-rn", "--include=*.rs", "TODO", "src"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "grep".to_string(),
+                flags: vec![MatchedFlag::new("-r"), MatchedFlag::new("-n")],
+                opts: vec![MatchedOpt::new(
+                    "--include",
+                    "*.rs",
+                    ArgType::OpaqueNonFile
+                )?],
+                args: vec![
+                    MatchedArg::new(2, ArgType::OpaqueNonFile, "TODO")?,
+                    MatchedArg::new(3, ArgType::ReadableFile, "src")?,
+                ],
+                system_path: vec!["/bin/grep".to_string(), "/usr/bin/grep".to_string()],
+            }
+        }),
+        policy.check(&grep)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_grep_bundled_option_with_attached_value() -> Result<()> {
+    let policy = setup();
+    let grep = ExecCall::new("grep", &["-iA2", "todo", "main.rs"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "grep".to_string(),
+                flags: vec![MatchedFlag::new("-i")],
+                opts: vec![MatchedOpt::new("-A", "2", ArgType::PositiveInteger)?],
+                args: vec![
+                    MatchedArg::new(1, ArgType::OpaqueNonFile, "todo")?,
+                    MatchedArg::new(2, ArgType::ReadableFile, "main.rs")?,
+                ],
+                system_path: vec!["/bin/grep".to_string(), "/usr/bin/grep".to_string()],
+            }
+        }),
+        policy.check(&grep)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_option_formats_are_opt_in() {
+    let unparsed_policy = r#"
+define_program(
+    program="grep",
+    options=[
+        opt("--include", ARG_OPAQUE_VALUE),
+        flag("-n"),
+        flag("-r"),
+        flag("--count"),
+    ],
+    args=[ARG_OPAQUE_VALUE, ARG_RFILES],
+)
+
+define_program(
+    program="egrep",
+    option_bundling=True,
+    combined_format=True,
+    options=[
+        flag("-n"),
+        flag("--count"),
+    ],
+    args=[ARG_OPAQUE_VALUE, ARG_RFILES],
+)
+"#;
+    let parser = PolicyParser::new("test_option_formats_are_opt_in", unparsed_policy);
+    let policy = parser.parse().expect("failed to parse policy");
+
+    assert_eq!(
+        Err(Error::UnknownOption {
+            program: "grep".to_string(),
+            option: "-rn".to_string(),
+        }),
+        policy.check(&ExecCall::new("grep", &["-rn", "TODO", "src"]))
+    );
+    assert_eq!(
+        Err(Error::UnknownOption {
+            program: "grep".to_string(),
+            option: "--include=*.rs".to_string(),
+        }),
+        policy.check(&ExecCall::new("grep", &["--include=*.rs", "TODO", "src"]))
+    );
+    assert_eq!(
+        Err(Error::FlagFollowedByValue {
+            program: "egrep".to_string(),
+            flag: "--count".to_string(),
+            value: "3".to_string(),
+        }),
+        policy.check(&ExecCall::new("egrep", &["--count=3", "TODO", "main.rs"]))
+    );
+}
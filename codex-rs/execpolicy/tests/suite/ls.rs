@@ -66,14 +66,32 @@ fn test_ls_dash_z() {
 fn test_ls_dash_al() {
     let policy = setup();
 
-    // This currently fails, but it should pass once option_bundling=True is implemented.
+    // `ls` is defined with option_bundling=True, so -al is read as -a -l.
     let ls_al = ExecCall::new("ls", &["-al"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "ls".into(),
+                flags: vec![MatchedFlag::new("-a"), MatchedFlag::new("-l")],
+                system_path: ["/bin/ls".into(), "/usr/bin/ls".into()].into(),
+                ..Default::default()
+            }
+        }),
+        policy.check(&ls_al)
+    );
+}
+
+#[test]
+fn test_ls_bundle_with_unknown_option() {
+    let policy = setup();
+
+    let ls_alz = ExecCall::new("ls", &["-alz"]);
     assert_eq!(
         Err(Error::UnknownOption {
             program: "ls".into(),
-            option: "-al".into()
+            option: "-z".into()
         }),
-        policy.check(&ls_al)
+        policy.check(&ls_alz)
     );
 }
 
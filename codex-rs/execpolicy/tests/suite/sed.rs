@@ -61,6 +61,55 @@ fn test_sed_print_specific_lines_with_e_flag() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_sed_bundled_n_and_e_flags() -> Result<()> {
+    let policy = setup();
+    let sed = ExecCall::new("sed", &["-ne", "122,202p", "hello.txt"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "sed".to_string(),
+                flags: vec![MatchedFlag::new("-n")],
+                opts: vec![
+                    MatchedOpt::new("-e", "122,202p", ArgType::SedCommand)
+                        .expect("should validate")
+                ],
+                args: vec![MatchedArg::new(2, ArgType::ReadableFile, "hello.txt")?],
+                system_path: vec!["/usr/bin/sed".to_string()],
+            }
+        }),
+        policy.check(&sed)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_sed_expression_in_combined_format() -> Result<()> {
+    let policy = setup();
+    let sed = ExecCall::new("sed", &["--expression=122,202p", "hello.txt"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "sed".to_string(),
+                opts: vec![
+                    MatchedOpt::new("--expression", "122,202p", ArgType::SedCommand)
+                        .expect("should validate")
+                ],
+                args: vec![MatchedArg::new(1, ArgType::ReadableFile, "hello.txt")?],
+                system_path: vec!["/usr/bin/sed".to_string()],
+                ..Default::default()
+            }
+        }),
+        policy.check(&sed)
+    );
+
+    // The error comes from the last sed definition tried (the -e variant),
+    // so only check that the command is not matched.
+    let dangerous = ExecCall::new("sed", &["--expression=s/y/echo hi/e", "hello.txt"]);
+    assert!(policy.check(&dangerous).is_err());
+    Ok(())
+}
+
 #[test]
 fn test_sed_reject_dangerous_command() {
     let policy = setup();
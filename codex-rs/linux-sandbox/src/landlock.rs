@@ -10,8 +10,10 @@ use codex_core::protocol::SandboxPolicy;
 use landlock::ABI;
 use landlock::Access;
 use landlock::AccessFs;
+use landlock::AccessNet;
 use landlock::CompatLevel;
 use landlock::Compatible;
+use landlock::NetPort;
 use landlock::Ruleset;
 use landlock::RulesetAttr;
 use landlock::RulesetCreatedAttr;
@@ -25,52 +27,89 @@ use seccompiler::SeccompRule;
 use seccompiler::TargetArch;
 use seccompiler::apply_filter;
 
+use crate::network_denials::apply_filter_reporting_denials;
+
 /// Apply sandbox policies inside this thread so only the child inherits
 /// them, not the entire CLI process.
+///
+/// When network access is restricted and `network_proxy_port` is set, TCP
+/// sockets remain available so commands can reach the network proxy. The
+/// caller must have moved the command into a network namespace whose only
+/// way out is the proxy relay; where Landlock can restrict TCP connections,
+/// they are also limited to the proxy's port.
 pub(crate) fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+    network_proxy_port: Option<u16>,
 ) -> Result<()> {
     if !sandbox_policy.has_full_network_access() {
-        install_network_seccomp_filter_on_current_thread()?;
+        let allow_tcp_connect = match network_proxy_port {
+            Some(port) => {
+                install_network_proxy_landlock_rules_on_current_thread(port)?;
+                true
+            }
+            None => false,
+        };
+        install_network_seccomp_filter_on_current_thread(allow_tcp_connect)?;
     }
 
-    if !sandbox_policy.has_full_disk_write_access() {
+    if !sandbox_policy.has_full_disk_write_access() || !sandbox_policy.has_full_disk_read_access() {
         let writable_roots = sandbox_policy
             .get_writable_roots_with_cwd(cwd)
             .into_iter()
             .map(|writable_root| writable_root.root)
             .collect();
-        install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
+        let read_deny = sandbox_policy.get_read_deny_paths_with_cwd(cwd);
+        install_filesystem_landlock_rules_on_current_thread(writable_roots, &read_deny)?;
     }
 
-    // TODO(ragona): Add appropriate restrictions if
-    // `sandbox_policy.has_full_disk_read_access()` is `false`.
-
     Ok(())
 }
 
 /// Installs Landlock file-system rules on the current thread allowing read
-/// access to the entire file-system while restricting write access to
-/// `/dev/null` and the provided list of `writable_roots`.
+/// access to the entire file-system except `read_deny`, while restricting
+/// write access to `/dev/null` and the provided list of `writable_roots`.
 ///
 /// # Errors
 /// Returns [`CodexErr::Sandbox`] variants when the ruleset fails to apply.
-fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathBuf>) -> Result<()> {
+fn install_filesystem_landlock_rules_on_current_thread(
+    writable_roots: Vec<PathBuf>,
+    read_deny: &[PathBuf],
+) -> Result<()> {
     let abi = ABI::V5;
     let access_rw = AccessFs::from_all(abi);
     let access_ro = AccessFs::from_read(abi);
+    // Directories leading to a read-denied path can be listed (and written,
+    // inside a writable root), but the files in them cannot be read.
+    let file_read = AccessFs::ReadFile | AccessFs::Execute;
+
+    let read_deny: Vec<PathBuf> = read_deny
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+    let readable = split_around_read_deny(&[PathBuf::from("/")], &read_deny);
+    let writable = split_around_read_deny(&writable_roots, &read_deny);
 
     let mut ruleset = Ruleset::default()
         .set_compatibility(CompatLevel::BestEffort)
         .handle_access(access_rw)?
         .create()?
-        .add_rules(landlock::path_beneath_rules(&["/"], access_ro))?
+        .add_rules(landlock::path_beneath_rules(&readable.granted, access_ro))?
+        .add_rules(landlock::path_beneath_rules(
+            &readable.ancestors,
+            access_ro & !file_read,
+        ))?
         .add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?
         .set_no_new_privs(true);
 
-    if !writable_roots.is_empty() {
-        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable_roots, access_rw))?;
+    if !writable.granted.is_empty() {
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable.granted, access_rw))?;
+    }
+    if !writable.ancestors.is_empty() {
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(
+            &writable.ancestors,
+            access_rw & !file_read,
+        ))?;
     }
 
     let status = ruleset.restrict_self()?;
@@ -82,9 +121,98 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
     Ok(())
 }
 
+/// Paths to grant access beneath so that nothing under a read-denied path is
+/// covered. Landlock rules can only grant access, so every directory on the
+/// way to a denied path is split into its entries.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SplitPaths {
+    /// Paths with no denied path beneath them.
+    granted: Vec<PathBuf>,
+    /// Directories with a denied path beneath them.
+    ancestors: Vec<PathBuf>,
+}
+
+/// Splits `roots` around the canonical paths in `read_deny`. Entries that
+/// resolve into a denied path are left out, as are dangling symlinks, which
+/// landlock cannot open. Entries created after the split are only covered by
+/// the `ancestors` rule of their directory.
+fn split_around_read_deny(roots: &[PathBuf], read_deny: &[PathBuf]) -> SplitPaths {
+    let mut split = SplitPaths::default();
+    for root in roots {
+        split_path(root, read_deny, false, &mut split);
+    }
+    split
+}
+
+fn split_path(path: &Path, read_deny: &[PathBuf], is_entry: bool, split: &mut SplitPaths) {
+    let Ok(canonical) = path.canonicalize() else {
+        return;
+    };
+    if read_deny.iter().any(|denied| canonical.starts_with(denied)) {
+        return;
+    }
+    if !read_deny
+        .iter()
+        .any(|denied| denied.starts_with(&canonical))
+    {
+        split.granted.push(path.to_path_buf());
+        return;
+    }
+    split.ancestors.push(path.to_path_buf());
+    // A symlinked directory resolves to a directory that is split on its own
+    // path, so following it would only repeat (or loop over) the same work.
+    if is_entry && path.is_symlink() {
+        return;
+    }
+    // A directory that cannot be listed stays limited to its `ancestors` rule.
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        split_path(&entry.path(), read_deny, true, split);
+    }
+}
+
+/// Installs Landlock network rules on the current thread that only allow TCP
+/// connections to `proxy_port` and no TCP binds. The rules cannot tell
+/// addresses apart, so they only add to the network namespace, which is what
+/// keeps other hosts out of reach.
+///
+/// Does nothing when the kernel cannot restrict TCP connections (Landlock
+/// ABI 4, Linux 6.7).
+fn install_network_proxy_landlock_rules_on_current_thread(proxy_port: u16) -> Result<()> {
+    let Ok(ruleset) = Ruleset::default()
+        .set_compatibility(CompatLevel::HardRequirement)
+        .handle_access(AccessNet::from_all(ABI::V4))
+    else {
+        return Ok(());
+    };
+    let status = ruleset
+        .create()?
+        .add_rule(NetPort::new(proxy_port, AccessNet::ConnectTcp))?
+        .set_no_new_privs(true)
+        .restrict_self()?;
+
+    if status.ruleset != landlock::RulesetStatus::FullyEnforced {
+        return Err(CodexErr::Sandbox(SandboxErr::LandlockRestrict));
+    }
+
+    Ok(())
+}
+
 /// Installs a seccomp filter that blocks outbound network access except for
 /// AF_UNIX domain sockets.
-fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+///
+/// With `allow_tcp_connect`, TCP sockets can also be created and connected,
+/// which the network namespace limits to the network proxy. AF_UNIX
+/// sockets are then limited to `socketpair`, so that local services such as
+/// the Docker daemon stay out of reach.
+///
+/// Denied calls fail with `EPERM` and are reported on stderr, see
+/// [`crate::network_denials`].
+fn install_network_seccomp_filter_on_current_thread(
+    allow_tcp_connect: bool,
+) -> std::result::Result<(), SandboxErr> {
     // Build rule map.
     let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
 
@@ -93,25 +221,27 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         rules.insert(nr, vec![]); // empty rule vec = unconditional match
     };
 
-    deny_syscall(libc::SYS_connect);
     deny_syscall(libc::SYS_accept);
     deny_syscall(libc::SYS_accept4);
     deny_syscall(libc::SYS_bind);
     deny_syscall(libc::SYS_listen);
-    deny_syscall(libc::SYS_getpeername);
-    deny_syscall(libc::SYS_getsockname);
-    deny_syscall(libc::SYS_shutdown);
-    deny_syscall(libc::SYS_sendto);
-    deny_syscall(libc::SYS_sendmsg);
-    deny_syscall(libc::SYS_sendmmsg);
-    // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
-    // with their socketpair + child processes for sub-proc management
-    // deny_syscall(libc::SYS_recvfrom);
-    deny_syscall(libc::SYS_recvmsg);
-    deny_syscall(libc::SYS_recvmmsg);
-    deny_syscall(libc::SYS_getsockopt);
-    deny_syscall(libc::SYS_setsockopt);
     deny_syscall(libc::SYS_ptrace);
+    if !allow_tcp_connect {
+        deny_syscall(libc::SYS_connect);
+        deny_syscall(libc::SYS_getpeername);
+        deny_syscall(libc::SYS_getsockname);
+        deny_syscall(libc::SYS_shutdown);
+        deny_syscall(libc::SYS_sendto);
+        deny_syscall(libc::SYS_sendmsg);
+        deny_syscall(libc::SYS_sendmmsg);
+        // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
+        // with their socketpair + child processes for sub-proc management
+        // deny_syscall(libc::SYS_recvfrom);
+        deny_syscall(libc::SYS_recvmsg);
+        deny_syscall(libc::SYS_recvmmsg);
+        deny_syscall(libc::SYS_getsockopt);
+        deny_syscall(libc::SYS_setsockopt);
+    }
 
     // For `socket` we allow AF_UNIX (arg0 == AF_UNIX) and deny everything else.
     let unix_only_rule = SeccompRule::new(vec![SeccompCondition::new(
@@ -121,7 +251,15 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         libc::AF_UNIX as u64,
     )?])?;
 
-    rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
+    if allow_tcp_connect {
+        rules.insert(libc::SYS_socket, tcp_only_socket_rules()?);
+        // TCP Fast Open connects from `sendto`/`sendmsg` rather than `connect`.
+        rules.insert(libc::SYS_sendto, vec![fast_open_rule(3)?]);
+        rules.insert(libc::SYS_sendmsg, vec![fast_open_rule(2)?]);
+        rules.insert(libc::SYS_sendmmsg, vec![fast_open_rule(3)?]);
+    } else {
+        rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
+    }
     rules.insert(libc::SYS_socketpair, vec![unix_only_rule]); // always deny (Unix can use socketpair but fine, keep open?)
 
     let filter = SeccompFilter::new(
@@ -139,7 +277,70 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
 
     let prog: BpfProgram = filter.try_into()?;
 
-    apply_filter(&prog)?;
+    // Kernels without seccomp user notifications get the filter as it is, so
+    // denials go unreported there.
+    if apply_filter_reporting_denials(&prog).is_err() {
+        apply_filter(&prog)?;
+    }
 
     Ok(())
 }
+
+/// Rules matching every `socket` call except for plain IPv4 and IPv6 TCP
+/// sockets, the only ones covered by the Landlock network rules.
+fn tcp_only_socket_rules() -> std::result::Result<Vec<SeccompRule>, SandboxErr> {
+    // The socket type shares its argument with SOCK_NONBLOCK and SOCK_CLOEXEC.
+    const SOCK_TYPE_MASK: u64 = 0xf;
+
+    let mut rules = vec![SeccompRule::new(vec![
+        SeccompCondition::new(
+            0,
+            SeccompCmpArgLen::Dword,
+            SeccompCmpOp::Ne,
+            libc::AF_INET as u64,
+        )?,
+        SeccompCondition::new(
+            0,
+            SeccompCmpArgLen::Dword,
+            SeccompCmpOp::Ne,
+            libc::AF_INET6 as u64,
+        )?,
+    ])?];
+    for sock_type in [
+        libc::SOCK_DGRAM,
+        libc::SOCK_RAW,
+        libc::SOCK_RDM,
+        libc::SOCK_SEQPACKET,
+        libc::SOCK_DCCP,
+    ] {
+        rules.push(SeccompRule::new(vec![SeccompCondition::new(
+            1, // second argument (type)
+            SeccompCmpArgLen::Dword,
+            SeccompCmpOp::MaskedEq(SOCK_TYPE_MASK),
+            sock_type as u64,
+        )?])?);
+    }
+    // Stream protocols other than TCP, such as MPTCP.
+    rules.push(SeccompRule::new(vec![
+        SeccompCondition::new(2, SeccompCmpArgLen::Dword, SeccompCmpOp::Ne, 0)?,
+        SeccompCondition::new(
+            2,
+            SeccompCmpArgLen::Dword,
+            SeccompCmpOp::Ne,
+            libc::IPPROTO_TCP as u64,
+        )?,
+    ])?);
+    Ok(rules)
+}
+
+/// Rule matching calls whose `flags` argument, at `flags_arg`, has
+/// `MSG_FASTOPEN` set.
+fn fast_open_rule(flags_arg: u8) -> std::result::Result<SeccompRule, SandboxErr> {
+    let condition = SeccompCondition::new(
+        flags_arg,
+        SeccompCmpArgLen::Dword,
+        SeccompCmpOp::MaskedEq(libc::MSG_FASTOPEN as u64),
+        libc::MSG_FASTOPEN as u64,
+    )?;
+    SeccompRule::new(vec![condition]).map_err(SandboxErr::from)
+}
@@ -2,6 +2,12 @@
 mod landlock;
 #[cfg(target_os = "linux")]
 mod linux_run_main;
+#[cfg(target_os = "linux")]
+mod namespaces;
+#[cfg(target_os = "linux")]
+mod network_denials;
+#[cfg(target_os = "linux")]
+mod proxy_relay;
 
 #[cfg(target_os = "linux")]
 pub fn run_main() -> ! {
@@ -3,9 +3,27 @@ use std::ffi::CString;
 use std::path::PathBuf;
 
 use crate::landlock::apply_sandbox_policy_to_current_thread;
+use crate::namespaces::NamespaceError;
+use crate::namespaces::enter_namespaces;
+use crate::namespaces::enter_network_namespace;
+use crate::proxy_relay::spawn_host_relay;
 
 #[derive(Debug, Parser)]
 pub struct LandlockCommand {
+    /// Loopback port of the Codex network proxy. When network access is
+    /// restricted, the command runs in a network namespace where this port
+    /// on the loopback interface leads to the proxy and nothing else leads
+    /// out. Refuses to run the command without user namespaces.
+    #[arg(long)]
+    pub network_proxy_port: Option<u16>,
+
+    /// Also run the command in new user, mount, PID and (without network
+    /// access) network namespaces, with a private `/tmp` and `/proc`. Falls
+    /// back to Landlock alone when user namespaces are unavailable, and has
+    /// no effect when the policy allows writing everywhere.
+    #[arg(long)]
+    pub namespaces: bool,
+
     /// It is possible that the cwd used in the context of the sandbox policy
     /// is different from the cwd of the process to spawn.
     pub sandbox_policy_cwd: PathBuf,
@@ -19,12 +37,52 @@ pub struct LandlockCommand {
 
 pub fn run_main() -> ! {
     let LandlockCommand {
+        network_proxy_port,
+        namespaces,
         sandbox_policy_cwd,
         sandbox_policy,
         command,
     } = LandlockCommand::parse();
 
-    if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &sandbox_policy_cwd) {
+    // The relay is started before leaving the network namespace, which it
+    // connects to.
+    let proxy_relay = match network_proxy_port {
+        Some(port) if !sandbox_policy.has_full_network_access() => Some(
+            spawn_host_relay(port)
+                .unwrap_or_else(|e| panic!("error starting the network proxy relay: {e}")),
+        ),
+        _ => None,
+    };
+    let uses_proxy = proxy_relay.is_some();
+    // With full disk access there is nothing to remount read-only, and a
+    // private `/tmp` would hide files the command may write there.
+    let entered = if namespaces && !sandbox_policy.has_full_disk_write_access() {
+        let isolate_network = !sandbox_policy.has_full_network_access();
+        enter_namespaces(
+            &sandbox_policy,
+            &sandbox_policy_cwd,
+            isolate_network,
+            proxy_relay,
+        )
+    } else if let Some(proxy_relay) = proxy_relay {
+        enter_network_namespace(proxy_relay)
+    } else {
+        Ok(())
+    };
+    match entered {
+        Ok(()) => {}
+        Err(NamespaceError::Unavailable) if uses_proxy => panic!(
+            "network_allowlist needs unprivileged user namespaces, which this host does not allow"
+        ),
+        Err(NamespaceError::Unavailable) => {}
+        Err(NamespaceError::Setup(e)) => panic!("error setting up namespaces: {e}"),
+    }
+
+    if let Err(e) = apply_sandbox_policy_to_current_thread(
+        &sandbox_policy,
+        &sandbox_policy_cwd,
+        network_proxy_port,
+    ) {
         panic!("error running landlock: {e:?}");
     }
 
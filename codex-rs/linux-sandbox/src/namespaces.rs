@@ -0,0 +1,423 @@
+//! Runs the sandboxed command in new user, mount and PID namespaces (and,
+//! without full network access, a network namespace), similar to
+//! bubblewrap.
+//!
+//! Inside the namespaces the file-system is remounted read-only except for
+//! the writable roots, `/tmp` is a fresh tmpfs and `/proc` only shows the
+//! processes of the sandbox. Landlock and seccomp are still applied on top,
+//! so the namespaces only add isolation.
+
+use std::ffi::CString;
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::SandboxPolicy;
+
+use crate::proxy_relay::ProxyRelay;
+
+/// `_LINUX_CAPABILITY_VERSION_3`, which uses two `CapData` entries.
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+/// Errors entering the namespaces.
+#[derive(Debug)]
+pub(crate) enum NamespaceError {
+    /// The kernel or its configuration does not allow unprivileged user
+    /// namespaces. Nothing was changed, so the caller can fall back to
+    /// Landlock alone.
+    Unavailable,
+    /// Setting up the namespaces failed half-way.
+    Setup(std::io::Error),
+}
+
+/// Moves the helper into new namespaces and sets up the file-system of the
+/// sandbox. On success, returns in a process that is ready to apply the
+/// remaining restrictions and exec the command. The processes it leaves
+/// behind wait for the command and exit with its status.
+///
+/// With `proxy_relay`, the network namespace gets a listener on the proxy's
+/// port that leads to the relay; `isolate_network` must be set then.
+pub(crate) fn enter_namespaces(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    isolate_network: bool,
+    proxy_relay: Option<ProxyRelay>,
+) -> Result<(), NamespaceError> {
+    // SAFETY: plain libc calls without pointer arguments.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let mut flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWPID;
+    if isolate_network {
+        flags |= libc::CLONE_NEWNET;
+    }
+    // SAFETY: `unshare` only takes flags.
+    if unsafe { libc::unshare(flags) } == -1 {
+        return Err(NamespaceError::Unavailable);
+    }
+    write_id_maps(uid, gid).map_err(NamespaceError::Setup)?;
+
+    // The first child is PID 1 of the new PID namespace; when it exits, the
+    // kernel kills every process left in the sandbox.
+    let init = fork().map_err(NamespaceError::Setup)?;
+    if init != 0 {
+        exit_with_status_of(init);
+    }
+    kill_on_parent_death().map_err(NamespaceError::Setup)?;
+    setup_mounts(sandbox_policy, cwd).map_err(NamespaceError::Setup)?;
+    if let Some(proxy_relay) = proxy_relay {
+        proxy_relay
+            .listen_in_current_namespace()
+            .map_err(NamespaceError::Setup)?;
+    }
+
+    let command = fork().map_err(NamespaceError::Setup)?;
+    if command != 0 {
+        reap_until_exit_of(command);
+    }
+    drop_capabilities().map_err(NamespaceError::Setup)
+}
+
+/// Moves the helper into new user and network namespaces whose only way out
+/// is `proxy_relay`, leaving the file-system and processes as they are. Used
+/// when the other namespaces are not requested.
+pub(crate) fn enter_network_namespace(proxy_relay: ProxyRelay) -> Result<(), NamespaceError> {
+    // SAFETY: plain libc calls without pointer arguments.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    // SAFETY: `unshare` only takes flags.
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } == -1 {
+        return Err(NamespaceError::Unavailable);
+    }
+    write_id_maps(uid, gid).map_err(NamespaceError::Setup)?;
+    proxy_relay
+        .listen_in_current_namespace()
+        .map_err(NamespaceError::Setup)?;
+    drop_capabilities().map_err(NamespaceError::Setup)
+}
+
+fn write_id_maps(uid: libc::uid_t, gid: libc::gid_t) -> std::io::Result<()> {
+    // Map the current ids onto themselves so file ownership looks the same
+    // inside the sandbox. Writing `gid_map` requires denying `setgroups`.
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))
+}
+
+pub(crate) fn fork() -> std::io::Result<libc::pid_t> {
+    // SAFETY: the helper is single-threaded, so the child can keep running
+    // arbitrary code.
+    match unsafe { libc::fork() } {
+        -1 => Err(std::io::Error::last_os_error()),
+        pid => Ok(pid),
+    }
+}
+
+/// Kills the current process when its parent, which Codex kills on timeout,
+/// dies.
+pub(crate) fn kill_on_parent_death() -> std::io::Result<()> {
+    // SAFETY: `prctl` only takes integers here.
+    if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Waits for `pid` and exits with its exit code, or 128 plus the signal that
+/// killed it.
+fn exit_with_status_of(pid: libc::pid_t) -> ! {
+    let mut status = 0;
+    loop {
+        // SAFETY: `status` is a valid pointer for the duration of the call.
+        let ret = unsafe { libc::waitpid(pid, &mut status, 0) };
+        if ret == pid {
+            std::process::exit(exit_code(status));
+        }
+        if ret == -1 && std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            std::process::exit(1);
+        }
+    }
+}
+
+/// As PID 1, reaps every orphaned process of the sandbox until `pid` exits,
+/// then exits with its status.
+fn reap_until_exit_of(pid: libc::pid_t) -> ! {
+    let mut status = 0;
+    loop {
+        // SAFETY: `status` is a valid pointer for the duration of the call.
+        let ret = unsafe { libc::waitpid(-1, &mut status, 0) };
+        if ret == pid {
+            std::process::exit(exit_code(status));
+        }
+        if ret == -1 && std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            std::process::exit(1);
+        }
+    }
+}
+
+fn exit_code(status: libc::c_int) -> i32 {
+    if libc::WIFSIGNALED(status) {
+        128 + libc::WTERMSIG(status)
+    } else {
+        libc::WEXITSTATUS(status)
+    }
+}
+
+fn setup_mounts(sandbox_policy: &SandboxPolicy, cwd: &Path) -> std::io::Result<()> {
+    let slash_tmp = Path::new("/tmp");
+    // Keep our mounts from propagating back to the host.
+    mount(None, Path::new("/"), None, libc::MS_REC | libc::MS_PRIVATE)?;
+
+    let writable_roots: Vec<(PathBuf, Vec<PathBuf>)> = sandbox_policy
+        .get_writable_roots_with_cwd(cwd)
+        .into_iter()
+        .filter_map(|writable_root| {
+            let root = writable_root.root.canonicalize().ok()?;
+            Some((root, writable_root.read_only_subpaths))
+        })
+        .collect();
+    let tmp_is_writable = writable_roots.iter().any(|(root, _)| root == slash_tmp);
+    // The writable roots other than `/tmp`, which is replaced by a tmpfs.
+    let bound_roots: Vec<&PathBuf> = writable_roots
+        .iter()
+        .map(|(root, _)| root)
+        .filter(|root| root.as_path() != slash_tmp)
+        .collect();
+
+    // Give each writable root its own mount so it stays writable when
+    // everything else is remounted read-only.
+    for root in &bound_roots {
+        bind_mount(root, root)?;
+    }
+    for mount_info in read_mount_info()? {
+        let is_writable = bound_roots
+            .iter()
+            .any(|root| mount_info.mount_point.starts_with(root));
+        if !is_writable {
+            // Mounts that cannot be remounted, e.g. because another mount
+            // hides them, stay as they are; Landlock still denies writes.
+            let _ = remount_read_only(&mount_info);
+        }
+    }
+
+    if slash_tmp.is_dir() {
+        mount_fresh_tmp(slash_tmp, &bound_roots, tmp_is_writable)?;
+    }
+
+    // Folders such as `.git` stay read-only inside writable roots.
+    for subpath in writable_roots.iter().flat_map(|(_, subpaths)| subpaths) {
+        // Subpaths of `/tmp` are gone with the tmpfs.
+        let Ok(subpath) = subpath.canonicalize() else {
+            continue;
+        };
+        bind_mount(&subpath, &subpath)?;
+        if let Some(mount_info) = read_mount_info()?
+            .into_iter()
+            .rfind(|mount_info| mount_info.mount_point == subpath)
+        {
+            remount_read_only(&mount_info)?;
+        }
+    }
+
+    mount(
+        Some(Path::new("proc")),
+        Path::new("/proc"),
+        Some("proc"),
+        libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+    )?;
+
+    // The working directory still points into the mounts as they were
+    // before; resolve it again to land in the new ones.
+    std::env::set_current_dir(std::env::current_dir()?)
+}
+
+/// Mounts an empty tmpfs on `/tmp`, keeping the writable roots beneath it.
+fn mount_fresh_tmp(
+    slash_tmp: &Path,
+    bound_roots: &[&PathBuf],
+    tmp_is_writable: bool,
+) -> std::io::Result<()> {
+    // Once the tmpfs hides them, the roots under `/tmp` are only reachable
+    // through these descriptors.
+    let roots_under_tmp = bound_roots
+        .iter()
+        .filter(|root| root.starts_with(slash_tmp))
+        .map(|root| Ok(((*root).clone(), File::open(root)?)))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let tmp_flags = libc::MS_NOSUID | libc::MS_NODEV;
+    mount(
+        Some(Path::new("tmpfs")),
+        slash_tmp,
+        Some("tmpfs"),
+        tmp_flags,
+    )?;
+    for (root, file) in &roots_under_tmp {
+        std::fs::create_dir_all(root)?;
+        let source = PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd()));
+        bind_mount(&source, root)?;
+    }
+    if !tmp_is_writable {
+        mount(
+            None,
+            slash_tmp,
+            None,
+            libc::MS_REMOUNT | libc::MS_RDONLY | tmp_flags,
+        )?;
+    }
+    Ok(())
+}
+
+/// A line of `/proc/self/mountinfo`.
+struct MountInfo {
+    mount_point: PathBuf,
+    /// The per-mount flags, which must be kept when remounting since the
+    /// user namespace cannot clear them.
+    flags: libc::c_ulong,
+}
+
+fn read_mount_info() -> std::io::Result<Vec<MountInfo>> {
+    let text = std::fs::read_to_string("/proc/self/mountinfo")?;
+    Ok(text.lines().filter_map(parse_mount_info_line).collect())
+}
+
+fn parse_mount_info_line(line: &str) -> Option<MountInfo> {
+    let mut fields = line.split(' ');
+    let mount_point = fields.nth(4)?;
+    let options = fields.next()?;
+    let flags = options
+        .split(',')
+        .map(|option| match option {
+            "nosuid" => libc::MS_NOSUID,
+            "nodev" => libc::MS_NODEV,
+            "noexec" => libc::MS_NOEXEC,
+            "noatime" => libc::MS_NOATIME,
+            "nodiratime" => libc::MS_NODIRATIME,
+            "relatime" => libc::MS_RELATIME,
+            _ => 0,
+        })
+        .fold(0, |flags, flag| flags | flag);
+    Some(MountInfo {
+        mount_point: PathBuf::from(unescape_mount_point(mount_point)),
+        flags,
+    })
+}
+
+/// Decodes the `\ooo` octal escapes used for spaces, tabs, newlines and
+/// backslashes in mount points.
+fn unescape_mount_point(escaped: &str) -> String {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut rest = escaped.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'\\'
+            && let Some(octal) = tail.get(..3)
+            && let Ok(octal) = std::str::from_utf8(octal)
+            && let Ok(decoded) = u8::from_str_radix(octal, 8)
+        {
+            bytes.push(decoded);
+            rest = &tail[3..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn bind_mount(source: &Path, target: &Path) -> std::io::Result<()> {
+    mount(Some(source), target, None, libc::MS_BIND | libc::MS_REC)
+}
+
+fn remount_read_only(mount_info: &MountInfo) -> std::io::Result<()> {
+    mount(
+        None,
+        &mount_info.mount_point,
+        None,
+        libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY | mount_info.flags,
+    )
+}
+
+fn mount(
+    source: Option<&Path>,
+    target: &Path,
+    fstype: Option<&str>,
+    flags: libc::c_ulong,
+) -> std::io::Result<()> {
+    let source = source.map(to_cstring).transpose()?;
+    let target = to_cstring(target)?;
+    let fstype = fstype.map(CString::new).transpose()?;
+    // SAFETY: the strings outlive the call, and null pointers are allowed
+    // for the source and file-system type.
+    let ret = unsafe {
+        libc::mount(
+            source.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            target.as_ptr(),
+            fstype.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            flags,
+            std::ptr::null(),
+        )
+    };
+    if ret == -1 {
+        let err = std::io::Error::last_os_error();
+        return Err(std::io::Error::new(
+            err.kind(),
+            format!("failed to mount {}: {err}", target.to_string_lossy()),
+        ));
+    }
+    Ok(())
+}
+
+fn to_cstring(path: &Path) -> std::io::Result<CString> {
+    Ok(CString::new(path.as_os_str().as_bytes())?)
+}
+
+#[repr(C)]
+struct CapHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct CapData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+/// Drops every capability, including the ones the command would get back
+/// on exec as root, so that it cannot undo the mounts of the sandbox.
+fn drop_capabilities() -> std::io::Result<()> {
+    let last_cap: libc::c_ulong = std::fs::read_to_string("/proc/sys/kernel/cap_last_cap")
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(63);
+    // SAFETY: `prctl` and `capset` get valid pointers or plain integers.
+    unsafe {
+        for cap in 0..=last_cap {
+            if libc::prctl(libc::PR_CAPBSET_DROP, cap) == -1
+                && std::io::Error::last_os_error().raw_os_error() != Some(libc::EINVAL)
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        if libc::prctl(
+            libc::PR_CAP_AMBIENT,
+            libc::PR_CAP_AMBIENT_CLEAR_ALL,
+            0,
+            0,
+            0,
+        ) == -1
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        let header = CapHeader {
+            version: LINUX_CAPABILITY_VERSION_3,
+            pid: 0,
+        };
+        let data = [CapData::default(); 2];
+        if libc::syscall(libc::SYS_capset, &header as *const CapHeader, data.as_ptr()) == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//! Reports the network syscalls the seccomp filter denies.
+//!
+//! Instead of failing them in the kernel, the filter hands the matching
+//! syscalls to a supervisor process through a seccomp user notification
+//! listener. The supervisor fails them with `EPERM`, as the filter would
+//! have, and the first time prints [`LINUX_SANDBOX_NETWORK_DENIED_MARKER`]
+//! to the command's stderr, where Codex looks for it.
+
+use std::io;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::IntoRawFd;
+use std::os::fd::OwnedFd;
+use std::os::fd::RawFd;
+
+use codex_core::sandbox_denial::LINUX_SANDBOX_NETWORK_DENIED_MARKER;
+use seccompiler::BpfProgram;
+
+use crate::namespaces::kill_on_parent_death;
+
+/// `BPF_RET | BPF_K`, the instruction returning a constant action.
+const BPF_RET_K: u16 = 0x06;
+
+/// `SECCOMP_IOCTL_NOTIF_RECV`, `_IOWR('!', 0, struct seccomp_notif)`.
+const SECCOMP_IOCTL_NOTIF_RECV: libc::c_ulong = 0xc050_2100;
+
+/// `SECCOMP_IOCTL_NOTIF_SEND`, `_IOWR('!', 1, struct seccomp_notif_resp)`.
+const SECCOMP_IOCTL_NOTIF_SEND: libc::c_ulong = 0xc018_2101;
+
+/// Installs `filter`, whose matching syscalls return `EPERM`, so that a
+/// supervisor process reports them before failing them. Must be called while
+/// the helper is single-threaded, and the helper must exec the command
+/// afterwards without closing any descriptor.
+///
+/// Returns an error without installing anything when the kernel does not
+/// support user notifications (Linux 5.0), so the caller can install the
+/// filter as it is. Panics when the filter is installed but the supervisor
+/// cannot be told about it.
+pub(crate) fn apply_filter_reporting_denials(filter: &BpfProgram) -> io::Result<()> {
+    let (reader, writer) = pipe()?;
+    // The supervisor is forked before the filter exists, so it is not
+    // subject to it. It shares the descriptor table of the helper, where the
+    // listener is about to appear: passing the listener over a socket would
+    // need `sendmsg`, which the filter denies. The helper gets a table of its
+    // own when it execs the command, which closes the listener and the pipe
+    // there but not in the supervisor.
+    if fork_sharing_descriptors()? == 0 {
+        if kill_on_parent_death().is_ok()
+            && make_undumpable().is_ok()
+            && let Some(listener) = read_listener(&reader)
+        {
+            supervise(listener);
+        }
+        // Exits without dropping anything, which would close the shared
+        // descriptors for the helper too.
+        std::process::exit(0);
+    }
+    let _ = reader.into_raw_fd();
+
+    let listener = match install_filter_with_listener(&notifying(filter)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            // The supervisor reads the end of the pipe and exits.
+            drop(writer);
+            return Err(e);
+        }
+    };
+    // The filter is in place, so there is no falling back anymore.
+    if let Err(e) = write_listener(&writer, listener) {
+        panic!("error handing the seccomp listener to its supervisor: {e}");
+    }
+    let _ = writer.into_raw_fd();
+    Ok(())
+}
+
+/// `filter` with its `EPERM` returns turned into user notifications.
+fn notifying(filter: &BpfProgram) -> BpfProgram {
+    let eperm = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+    filter
+        .iter()
+        .map(|instruction| {
+            let mut instruction = instruction.clone();
+            if instruction.code == BPF_RET_K && instruction.k == eperm {
+                instruction.k = libc::SECCOMP_RET_USER_NOTIF;
+            }
+            instruction
+        })
+        .collect()
+}
+
+/// Returns the listener's descriptor number, which must stay open.
+fn install_filter_with_listener(filter: &BpfProgram) -> io::Result<RawFd> {
+    let program = libc::sock_fprog {
+        len: filter.len() as libc::c_ushort,
+        filter: filter.as_ptr().cast_mut().cast(),
+    };
+    // SAFETY: `prctl` only takes integers here, and `seccomp` reads a valid
+    // program that outlives the call.
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let listener = libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &program as *const libc::sock_fprog,
+        );
+        if listener == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(listener as RawFd)
+    }
+}
+
+/// Like `fork`, but the child shares the descriptor table of the helper.
+fn fork_sharing_descriptors() -> io::Result<libc::pid_t> {
+    let flags = (libc::CLONE_FILES | libc::SIGCHLD) as libc::c_ulong;
+    // SAFETY: without a stack of its own, the child continues on a copy of
+    // the helper's memory, as with `fork`. The helper is single-threaded, so
+    // the child can keep running arbitrary code.
+    match unsafe { libc::syscall(libc::SYS_clone, flags, 0, 0, 0, 0) } {
+        -1 => Err(io::Error::last_os_error()),
+        pid => Ok(pid as libc::pid_t),
+    }
+}
+
+/// Keeps the command from reaching into the supervisor, e.g. with
+/// `pidfd_getfd`, to answer its own notifications.
+fn make_undumpable() -> io::Result<()> {
+    // SAFETY: `prctl` only takes integers here.
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors.
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `pipe2` succeeded, so both descriptors are open and ours.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+fn write_listener(writer: &OwnedFd, listener: RawFd) -> io::Result<()> {
+    let bytes = listener.to_ne_bytes();
+    // SAFETY: `bytes` is valid for reads of its length. Writes to a pipe of
+    // at most `PIPE_BUF` bytes are atomic.
+    let written = unsafe { libc::write(writer.as_raw_fd(), bytes.as_ptr().cast(), bytes.len()) };
+    if written != bytes.len() as isize {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Returns `None` when the helper closed the pipe without installing the
+/// filter.
+fn read_listener(reader: &OwnedFd) -> Option<RawFd> {
+    let mut bytes = [0u8; size_of::<RawFd>()];
+    loop {
+        // SAFETY: `bytes` is valid for writes of its length.
+        let read =
+            unsafe { libc::read(reader.as_raw_fd(), bytes.as_mut_ptr().cast(), bytes.len()) };
+        if read == bytes.len() as isize {
+            return Some(RawFd::from_ne_bytes(bytes));
+        }
+        if read != -1 || io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return None;
+        }
+    }
+}
+
+/// Fails every syscall `listener` is notified of with `EPERM`, reporting the
+/// first one. Runs until the command, and with it the supervisor, dies.
+fn supervise(listener: RawFd) {
+    let mut reported = false;
+    loop {
+        // SAFETY: both structs are plain data, and the ioctls get pointers to
+        // them that are valid for the duration of the call.
+        unsafe {
+            let mut notification: libc::seccomp_notif = std::mem::zeroed();
+            if libc::ioctl(listener, SECCOMP_IOCTL_NOTIF_RECV as _, &mut notification) == -1 {
+                match io::Error::last_os_error().raw_os_error() {
+                    // Interrupted, or the caller died before the notification
+                    // was received.
+                    Some(libc::EINTR | libc::ENOENT) => continue,
+                    _ => return,
+                }
+            }
+            if !reported {
+                reported = true;
+                let _ = writeln!(io::stderr(), "{LINUX_SANDBOX_NETWORK_DENIED_MARKER}");
+            }
+            let mut response = libc::seccomp_notif_resp {
+                id: notification.id,
+                val: 0,
+                error: -libc::EPERM,
+                flags: 0,
+            };
+            // Fails with `ENOENT` when the caller died in the meantime, which
+            // needs no answer.
+            libc::ioctl(listener, SECCOMP_IOCTL_NOTIF_SEND as _, &mut response);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Network access for commands that may only reach the network proxy.
+//!
+//! The command runs in a network namespace of its own, which has nothing but
+//! a loopback interface. A listener on the proxy's port inside the namespace
+//! passes every connection it accepts over a Unix socket to a relay process
+//! that stayed in the original network namespace, and the relay connects it
+//! to the proxy. Nothing else in the namespace leads out of it.
+
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::Shutdown;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::fd::AsFd;
+use std::os::fd::AsRawFd;
+use std::os::fd::BorrowedFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::fd::RawFd;
+
+use crate::namespaces::fork;
+use crate::namespaces::kill_on_parent_death;
+
+/// The end of the relay's Unix socket that stays with the sandbox, see
+/// [`spawn_host_relay`].
+pub(crate) struct ProxyRelay {
+    proxy_port: u16,
+    socket: OwnedFd,
+}
+
+/// Forks the relay process, which stays in the current network namespace and
+/// connects the connections it is handed to `127.0.0.1:proxy_port`. Must be
+/// called while the helper is single-threaded and before it leaves the
+/// network namespace. The relay exits when the other end of its socket is
+/// closed, or when the helper dies.
+pub(crate) fn spawn_host_relay(proxy_port: u16) -> io::Result<ProxyRelay> {
+    let (relay_socket, sandbox_socket) = seqpacket_pair()?;
+    if fork()? == 0 {
+        drop(sandbox_socket);
+        if kill_on_parent_death().is_err() {
+            std::process::exit(1);
+        }
+        relay_connections(&relay_socket, proxy_port);
+        std::process::exit(0);
+    }
+    drop(relay_socket);
+    Ok(ProxyRelay {
+        proxy_port,
+        socket: sandbox_socket,
+    })
+}
+
+impl ProxyRelay {
+    /// Brings up the loopback interface of the current network namespace and
+    /// forks a listener on `127.0.0.1:<proxy port>` that hands its
+    /// connections to the relay. Setting up the interface needs
+    /// `CAP_NET_ADMIN` over the namespace, so call this before dropping
+    /// capabilities.
+    pub(crate) fn listen_in_current_namespace(self) -> io::Result<()> {
+        bring_up_loopback()?;
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, self.proxy_port))?;
+        if fork()? == 0 {
+            if kill_on_parent_death().is_err() {
+                std::process::exit(1);
+            }
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                if send_fd(&self.socket, stream.as_fd()).is_err() {
+                    break;
+                }
+            }
+            std::process::exit(0);
+        }
+        Ok(())
+    }
+}
+
+fn relay_connections(socket: &OwnedFd, proxy_port: u16) {
+    while let Ok(Some(fd)) = recv_fd(socket) {
+        std::thread::spawn(move || {
+            let client = TcpStream::from(fd);
+            if let Ok(proxy) = TcpStream::connect((Ipv4Addr::LOCALHOST, proxy_port)) {
+                copy_both_ways(client, proxy);
+            }
+        });
+    }
+}
+
+fn copy_both_ways(a: TcpStream, b: TcpStream) {
+    let (Ok(a_reader), Ok(b_writer)) = (a.try_clone(), b.try_clone()) else {
+        return;
+    };
+    let forward = std::thread::spawn(move || copy_then_shutdown(a_reader, b_writer));
+    copy_then_shutdown(b, a);
+    let _ = forward.join();
+}
+
+fn copy_then_shutdown(mut from: TcpStream, mut to: TcpStream) {
+    let _ = io::copy(&mut from, &mut to);
+    let _ = to.shutdown(Shutdown::Write);
+}
+
+fn seqpacket_pair() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors.
+    if unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+            0,
+            fds.as_mut_ptr(),
+        )
+    } == -1
+    {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `socketpair` succeeded, so both descriptors are open and ours.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+/// Room for one `SCM_RIGHTS` message carrying a single descriptor, aligned
+/// for `cmsghdr`.
+type FdControlBuffer = [u64; 4];
+
+fn send_fd(socket: &OwnedFd, fd: BorrowedFd<'_>) -> io::Result<()> {
+    let mut byte = [0u8; 1];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: byte.len(),
+    };
+    let mut control: FdControlBuffer = [0; 4];
+    // SAFETY: the message points at `iov` and `control`, which outlive the
+    // call, and `control` is large and aligned enough for one descriptor.
+    unsafe {
+        let mut msg: libc::msghdr = std::mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = libc::CMSG_SPACE(size_of::<RawFd>() as u32) as _;
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<RawFd>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>(), fd.as_raw_fd());
+        if libc::sendmsg(socket.as_raw_fd(), &msg, libc::MSG_NOSIGNAL) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Receives a descriptor sent with [`send_fd`]. Returns `None` once the other
+/// end is closed.
+fn recv_fd(socket: &OwnedFd) -> io::Result<Option<OwnedFd>> {
+    let mut byte = [0u8; 1];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: byte.len(),
+    };
+    let mut control: FdControlBuffer = [0; 4];
+    // SAFETY: as in `send_fd`; the descriptor is only read from a control
+    // message of the expected level, type and length.
+    unsafe {
+        let mut msg: libc::msghdr = std::mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = size_of::<FdControlBuffer>() as _;
+        let received = libc::recvmsg(socket.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC);
+        if received == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        if received == 0 {
+            return Ok(None);
+        }
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        if cmsg.is_null()
+            || (*cmsg).cmsg_level != libc::SOL_SOCKET
+            || (*cmsg).cmsg_type != libc::SCM_RIGHTS
+            || (*cmsg).cmsg_len != libc::CMSG_LEN(size_of::<RawFd>() as u32) as _
+        {
+            return Err(io::Error::other("expected a socket from the sandbox"));
+        }
+        let fd = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>());
+        Ok(Some(OwnedFd::from_raw_fd(fd)))
+    }
+}
+
+/// A new network namespace starts with its loopback interface down.
+fn bring_up_loopback() -> io::Result<()> {
+    // SAFETY: plain socket creation; the descriptor is owned right away.
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if socket == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `socket` is a descriptor we just opened.
+    let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+    // SAFETY: `ifreq` is plain data, and the ioctl reads a valid `ifreq`.
+    unsafe {
+        let mut request: libc::ifreq = std::mem::zeroed();
+        for (dst, src) in request.ifr_name.iter_mut().zip(b"lo\0") {
+            *dst = *src as libc::c_char;
+        }
+        request.ifr_ifru.ifru_flags = (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+        if libc::ioctl(socket.as_raw_fd(), libc::SIOCSIFFLAGS as _, &request) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 #![cfg(target_os = "linux")]
+use codex_core::config_types::ExecLimits;
 use codex_core::config_types::ShellEnvironmentPolicy;
 use codex_core::error::CodexErr;
 use codex_core::error::SandboxErr;
@@ -7,10 +8,16 @@ use codex_core::exec::SandboxType;
 use codex_core::exec::process_exec_tool_call;
 use codex_core::exec_env::create_env;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::sandbox_denial::LINUX_SANDBOX_NETWORK_DENIED_MARKER;
+use codex_core::sandbox_denial::SandboxDenial;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_PROXY_ENV_VAR;
 use std::collections::HashMap;
+use std::io::Read;
 use std::path::PathBuf;
 use tempfile::NamedTempFile;
 
+use super::namespaces::user_namespaces_available;
+
 // At least on GitHub CI, the arm64 tests appear to need longer timeouts.
 
 #[cfg(not(target_arch = "aarch64"))]
@@ -33,8 +40,17 @@ fn create_env_from_core_vars() -> HashMap<String, String> {
     create_env(&policy)
 }
 
-#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
 async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
+    run_cmd_with_read_deny(cmd, writable_roots, &[], timeout_ms).await;
+}
+
+#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
+async fn run_cmd_with_read_deny(
+    cmd: &[&str],
+    writable_roots: &[PathBuf],
+    read_deny: &[PathBuf],
+    timeout_ms: u64,
+) {
     let cwd = std::env::current_dir().expect("cwd should exist");
     let sandbox_cwd = cwd.clone();
     let params = ExecParams {
@@ -44,16 +60,19 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
         env: create_env_from_core_vars(),
         with_escalated_permissions: None,
         justification: None,
+        limits: ExecLimits::default(),
     };
 
     let sandbox_policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: writable_roots.to_vec(),
         network_access: false,
+        network_allowlist: vec![],
         // Exclude tmp-related folders from writable roots because we need a
         // folder that is writable by tests but that we intentionally disallow
         // writing to in the sandbox.
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        read_deny: read_deny.to_vec(),
     };
     let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
     let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
@@ -123,6 +142,153 @@ async fn test_writable_root() {
     .await;
 }
 
+#[tokio::test]
+async fn test_denied_write_is_reported() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let file_path = tmpdir.path().join("test");
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let sandbox_cwd = cwd.clone();
+    let params = ExecParams {
+        command: vec![
+            "bash".to_string(),
+            "-c".to_string(),
+            format!("echo blah > {}", file_path.to_string_lossy()),
+        ],
+        cwd,
+        timeout_ms: Some(LONG_TIMEOUT_MS),
+        env: create_env_from_core_vars(),
+        with_escalated_permissions: None,
+        justification: None,
+        limits: ExecLimits::default(),
+    };
+
+    let sandbox_policy = SandboxPolicy::new_read_only_policy();
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        sandbox_cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await;
+
+    match result {
+        Err(CodexErr::Sandbox(SandboxErr::Denied { denial, .. })) => {
+            assert_eq!(denial, Some(SandboxDenial::Write(file_path)));
+        }
+        _ => panic!("expected sandbox denied error, got: {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_denied_network_access_is_reported() {
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let sandbox_cwd = cwd.clone();
+    let params = ExecParams {
+        command: vec![
+            "python3".to_string(),
+            "-c".to_string(),
+            "import socket; socket.socket(socket.AF_INET, socket.SOCK_DGRAM)".to_string(),
+        ],
+        cwd,
+        timeout_ms: Some(LONG_TIMEOUT_MS),
+        env: create_env_from_core_vars(),
+        with_escalated_permissions: None,
+        justification: None,
+        limits: ExecLimits::default(),
+    };
+
+    let sandbox_policy = SandboxPolicy::new_read_only_policy();
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        sandbox_cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await;
+
+    match result {
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, denial })) => {
+            assert_eq!(denial, Some(SandboxDenial::Network));
+            assert!(
+                output
+                    .stderr
+                    .text
+                    .contains(LINUX_SANDBOX_NETWORK_DENIED_MARKER),
+                "stderr: {}",
+                output.stderr.text
+            );
+        }
+        _ => panic!("expected sandbox denied error, got: {result:?}"),
+    }
+}
+
+/// Creates `secrets/key` and `notes.txt` in a temporary directory.
+#[expect(clippy::unwrap_used)]
+fn create_read_deny_scenario() -> (tempfile::TempDir, PathBuf, PathBuf) {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let secrets = tmpdir.path().join("secrets");
+    std::fs::create_dir(&secrets).unwrap();
+    std::fs::write(secrets.join("key"), "hunter2").unwrap();
+    let notes = tmpdir.path().join("notes.txt");
+    std::fs::write(&notes, "hello").unwrap();
+    (tmpdir, secrets, notes)
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_read_deny_directory_cannot_be_read() {
+    let (_tmpdir, secrets, _notes) = create_read_deny_scenario();
+    let key = secrets.join("key");
+    run_cmd_with_read_deny(
+        &["cat", &key.to_string_lossy()],
+        &[],
+        &[secrets],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_read_deny_file_cannot_be_read() {
+    let (_tmpdir, _secrets, notes) = create_read_deny_scenario();
+    run_cmd_with_read_deny(
+        &["cat", &notes.to_string_lossy()],
+        &[],
+        &[notes.clone()],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_read_deny_keeps_the_rest_readable() {
+    let (tmpdir, secrets, notes) = create_read_deny_scenario();
+    run_cmd_with_read_deny(
+        &[
+            "bash",
+            "-lc",
+            &format!(
+                "cat {} && ls {} && ls /bin",
+                notes.to_string_lossy(),
+                tmpdir.path().to_string_lossy()
+            ),
+        ],
+        &[],
+        &[secrets],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
 #[tokio::test]
 #[should_panic(expected = "Sandbox(Timeout")]
 async fn test_timeout() {
@@ -146,6 +312,7 @@ async fn assert_network_blocked(cmd: &[&str]) {
         env: create_env_from_core_vars(),
         with_escalated_permissions: None,
         justification: None,
+        limits: ExecLimits::default(),
     };
 
     let sandbox_policy = SandboxPolicy::new_read_only_policy();
@@ -163,7 +330,7 @@ async fn assert_network_blocked(cmd: &[&str]) {
 
     let output = match result {
         Ok(output) => output,
-        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => *output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, .. })) => *output,
         _ => {
             panic!("expected sandbox denied error, got: {result:?}");
         }
@@ -234,3 +401,86 @@ async fn sandbox_blocks_dev_tcp_redirection() {
     // all images ship bash, so we guard against 127 as well.
     assert_network_blocked(&["bash", "-c", "echo hi > /dev/tcp/127.0.0.1/80"]).await;
 }
+
+/// Runs `cmd` in a read-only sandbox whose only reachable network destination
+/// is the network proxy port, and returns its exit code.
+#[expect(clippy::expect_used)]
+async fn run_cmd_with_network_proxy(cmd: &[&str], proxy_port: u16) -> i32 {
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let sandbox_cwd = cwd.clone();
+    let mut env = create_env_from_core_vars();
+    env.insert(
+        CODEX_SANDBOX_NETWORK_PROXY_ENV_VAR.to_string(),
+        format!("127.0.0.1:{proxy_port}"),
+    );
+    let params = ExecParams {
+        command: cmd.iter().copied().map(str::to_owned).collect(),
+        cwd,
+        timeout_ms: Some(NETWORK_TIMEOUT_MS),
+        env,
+        with_escalated_permissions: None,
+        justification: None,
+        limits: ExecLimits::default(),
+    };
+
+    let sandbox_policy = SandboxPolicy::new_read_only_policy();
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe: Option<PathBuf> = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        sandbox_cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await;
+
+    match result {
+        Ok(output) => output.exit_code,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, .. })) => output.exit_code,
+        _ => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn sandbox_only_allows_the_network_proxy_port() {
+    let proxy = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_port = proxy.local_addr().unwrap().port();
+    let other = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let other_port = other.local_addr().unwrap().port();
+
+    let connect = |port: u16| format!("echo hi > /dev/tcp/127.0.0.1/{port}");
+    let other_exit_code =
+        run_cmd_with_network_proxy(&["bash", "-c", &connect(other_port)], proxy_port).await;
+    assert_ne!(
+        other_exit_code, 0,
+        "connected to a port other than the proxy"
+    );
+
+    let proxy_exit_code =
+        run_cmd_with_network_proxy(&["bash", "-c", &connect(proxy_port)], proxy_port).await;
+    if user_namespaces_available() {
+        assert_eq!(proxy_exit_code, 0, "could not connect to the proxy port");
+        // The connection reached the proxy through the relay.
+        let (mut stream, _) = proxy.accept().unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "hi\n");
+    } else {
+        // Without a network namespace to confine it, the helper refuses to
+        // run the command.
+        assert_ne!(proxy_exit_code, 0);
+    }
+}
+
+#[tokio::test]
+async fn sandbox_with_network_proxy_blocks_udp_and_unix_sockets() {
+    for cmd in [
+        "import socket; socket.socket(socket.AF_INET, socket.SOCK_DGRAM)",
+        "import socket; socket.socket(socket.AF_UNIX)",
+    ] {
+        let exit_code = run_cmd_with_network_proxy(&["python3", "-c", cmd], 1).await;
+        assert_ne!(exit_code, 0, "{cmd} was not blocked");
+    }
+}
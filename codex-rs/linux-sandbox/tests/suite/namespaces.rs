@@ -0,0 +1,160 @@
+#![cfg(target_os = "linux")]
+use codex_core::config_types::ExecLimits;
+use codex_core::config_types::ShellEnvironmentPolicy;
+use codex_core::error::CodexErr;
+use codex_core::error::SandboxErr;
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::exec_env::create_env;
+use codex_core::protocol::SandboxPolicy;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[cfg(not(target_arch = "aarch64"))]
+const TIMEOUT_MS: u64 = 2_000;
+#[cfg(target_arch = "aarch64")]
+const TIMEOUT_MS: u64 = 10_000;
+
+/// Whether unprivileged user namespaces can be created on this host. When
+/// they cannot, the helper falls back to the Landlock sandbox and the
+/// namespace-specific assertions below do not apply.
+pub(crate) fn user_namespaces_available() -> bool {
+    let mut command = Command::new("true");
+    // SAFETY: `unshare` is async-signal-safe and only affects the child.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::unshare(
+                libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWPID | libc::CLONE_NEWNET,
+            ) == 0
+            {
+                Ok(())
+            } else {
+                Err(std::io::Error::last_os_error())
+            }
+        });
+    }
+    command.status().is_ok_and(|status| status.success())
+}
+
+async fn run_cmd_in_namespaces(cmd: &[&str], writable_roots: &[PathBuf]) -> ExecToolCallOutput {
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: writable_roots.to_vec(),
+        network_access: false,
+        network_allowlist: vec![],
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+        read_deny: vec![],
+    };
+    run_cmd_with_policy(cmd, &sandbox_policy).await
+}
+
+#[expect(clippy::expect_used)]
+async fn run_cmd_with_policy(cmd: &[&str], sandbox_policy: &SandboxPolicy) -> ExecToolCallOutput {
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let sandbox_cwd = cwd.clone();
+    let params = ExecParams {
+        command: cmd.iter().copied().map(str::to_owned).collect(),
+        cwd,
+        timeout_ms: Some(TIMEOUT_MS),
+        env: create_env(&ShellEnvironmentPolicy::default()),
+        with_escalated_permissions: None,
+        justification: None,
+        limits: ExecLimits::default(),
+    };
+
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxNamespaces,
+        sandbox_policy,
+        sandbox_cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await;
+
+    match result {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, .. })) => *output,
+        _ => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn namespaces_keep_writable_roots_writable_and_the_rest_read_only() {
+    let writable = tempfile::tempdir().unwrap();
+    let read_only = tempfile::tempdir().unwrap();
+
+    let output = run_cmd_in_namespaces(
+        &[
+            "bash",
+            "-c",
+            &format!("echo blah > {}/file", writable.path().display()),
+        ],
+        &[writable.path().to_path_buf()],
+    )
+    .await;
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+
+    let output = run_cmd_in_namespaces(
+        &[
+            "bash",
+            "-c",
+            &format!("echo blah > {}/file", read_only.path().display()),
+        ],
+        &[writable.path().to_path_buf()],
+    )
+    .await;
+    assert_ne!(output.exit_code, 0, "wrote outside the writable roots");
+}
+
+#[tokio::test]
+async fn namespaces_mount_a_private_tmp() {
+    if !user_namespaces_available() {
+        return;
+    }
+    let host_file = tempfile::Builder::new().tempfile_in("/tmp").unwrap();
+
+    let output =
+        run_cmd_in_namespaces(&["test", "-e", &host_file.path().to_string_lossy()], &[]).await;
+    assert_ne!(output.exit_code, 0, "host /tmp is visible in the sandbox");
+}
+
+#[tokio::test]
+async fn namespaces_hide_host_processes() {
+    if !user_namespaces_available() {
+        return;
+    }
+
+    let test_pid = std::process::id();
+    let output = run_cmd_in_namespaces(&["test", "-e", &format!("/proc/{test_pid}")], &[]).await;
+    assert_ne!(
+        output.exit_code, 0,
+        "host processes are visible in the sandbox"
+    );
+
+    let output = run_cmd_in_namespaces(&["sh", "-c", "echo $$"], &[]).await;
+    assert_eq!(output.stdout.text.trim(), "2");
+}
+
+#[tokio::test]
+#[expect(clippy::unwrap_used)]
+async fn namespaces_are_skipped_with_full_disk_access() {
+    let host_file = tempfile::Builder::new().tempfile_in("/tmp").unwrap();
+
+    let output = run_cmd_with_policy(
+        &[
+            "bash",
+            "-c",
+            &format!("echo blah > {}", host_file.path().display()),
+        ],
+        &SandboxPolicy::DangerFullAccess,
+    )
+    .await;
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert_eq!(std::fs::read_to_string(host_file.path()).unwrap(), "blah\n");
+}
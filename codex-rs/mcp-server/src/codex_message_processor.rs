@@ -573,6 +573,7 @@ impl CodexMessageProcessor {
             env,
             with_escalated_permissions: None,
             justification: None,
+            limits: self.config.exec_limits,
         };
 
         let effective_policy = params
@@ -583,7 +584,9 @@ impl CodexMessageProcessor {
             codex_core::protocol::SandboxPolicy::DangerFullAccess => {
                 codex_core::exec::SandboxType::None
             }
-            _ => get_platform_sandbox().unwrap_or(codex_core::exec::SandboxType::None),
+            _ => get_platform_sandbox()
+                .unwrap_or(codex_core::exec::SandboxType::None)
+                .with_linux_sandbox(self.config.linux_sandbox),
         };
         tracing::debug!("Sandbox type: {sandbox_type:?}");
         let codex_linux_sandbox_exe = self.config.codex_linux_sandbox_exe.clone();
@@ -1010,6 +1013,7 @@ impl CodexMessageProcessor {
                 effort,
                 summary,
                 final_output_json_schema: None,
+                allowed_tools: None,
             })
             .await;
 
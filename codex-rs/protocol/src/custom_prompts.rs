@@ -1,3 +1,6 @@
+use crate::config_types::ReasoningEffort;
+use crate::config_types::SandboxMode;
+use crate::protocol::AskForApproval;
 use serde::Deserialize;
 use serde::Serialize;
 use std::path::PathBuf;
@@ -14,6 +17,27 @@ pub struct CustomPrompt {
     // New fields for template support
     pub template_args: Option<Vec<TemplateArg>>,
     pub template_syntax: Option<TemplateSyntax>,
+    /// Turn settings from frontmatter that apply to the turn this prompt starts.
+    #[serde(default)]
+    pub turn_overrides: Option<PromptTurnOverrides>,
+    /// MCP server that provides this prompt. Its messages are fetched with
+    /// `prompts/get` when the prompt is used instead of being read from `path`.
+    #[serde(default)]
+    pub mcp_server: Option<String>,
+}
+
+/// Per-turn overrides declared in a prompt's frontmatter (`model`,
+/// `reasoning-effort`, `sandbox`, `approval` and `allowed-tools`). They only
+/// apply to the turn started by the prompt; later turns use the session
+/// defaults again.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, TS)]
+pub struct PromptTurnOverrides {
+    pub model: Option<String>,
+    pub effort: Option<ReasoningEffort>,
+    pub sandbox: Option<SandboxMode>,
+    pub approval: Option<AskForApproval>,
+    /// Names (or `*` globs) of the tools offered to the model for the turn.
+    pub allowed_tools: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
@@ -24,8 +48,13 @@ pub struct TemplateArg {
     pub default_value: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+/// How a prompt body is rendered with its arguments, chosen with the
+/// `template:` frontmatter key.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
 pub enum TemplateSyntax {
+    /// `{0}`, `{1}`, ... placeholders.
     Simple,
-    Askama,
+    /// Jinja templates, plus `$ARGUMENTS` and `$1`, `$2`, ... placeholders.
+    #[serde(alias = "Askama")]
+    Jinja,
 }
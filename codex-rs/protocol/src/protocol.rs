@@ -13,6 +13,7 @@ use std::time::Duration;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use crate::custom_prompts::CustomPrompt;
+use crate::custom_prompts::PromptTurnOverrides;
 use crate::mcp_protocol::ConversationId;
 use crate::message_history::HistoryEntry;
 use crate::models::ContentItem;
@@ -21,6 +22,7 @@ use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
 use crate::plan_tool::UpdatePlanArgs;
 use mcp_types::CallToolResult;
+use mcp_types::GetPromptResult;
 use mcp_types::Tool as McpTool;
 use serde::Deserialize;
 use serde::Serialize;
@@ -35,6 +37,10 @@ pub const USER_INSTRUCTIONS_OPEN_TAG: &str = "<user_instructions>";
 pub const USER_INSTRUCTIONS_CLOSE_TAG: &str = "</user_instructions>";
 pub const ENVIRONMENT_CONTEXT_OPEN_TAG: &str = "<environment_context>";
 pub const ENVIRONMENT_CONTEXT_CLOSE_TAG: &str = "</environment_context>";
+/// Hook context blocks carry a `source` attribute, so only the tag name is
+/// matched when opening.
+pub const HOOK_CONTEXT_OPEN_TAG: &str = "<hook_context";
+pub const HOOK_CONTEXT_CLOSE_TAG: &str = "</hook_context>";
 pub const USER_MESSAGE_BEGIN: &str = "## My request for Codex:";
 
 /// Submission Queue Entry - requests from user
@@ -90,6 +96,23 @@ pub enum Op {
         summary: ReasoningSummaryConfig,
         // The JSON schema to use for the final assistant message
         final_output_json_schema: Option<Value>,
+
+        /// Restrict the tools offered to the model to these names (`*` globs
+        /// allowed). `None` offers every configured tool.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        allowed_tools: Option<Vec<String>>,
+    },
+
+    /// Like [`Op::UserInput`], but starts a task with the overrides from a
+    /// custom prompt's frontmatter. They only apply to that task, and the
+    /// sandbox and approval overrides can only tighten the session's
+    /// policies. When a task is already running the input is queued for it
+    /// and the overrides are ignored.
+    PromptTurn {
+        /// User input items, see `InputItem`
+        items: Vec<InputItem>,
+
+        overrides: PromptTurnOverrides,
     },
 
     /// Override parts of the persistent turn context for subsequent turns.
@@ -166,6 +189,33 @@ pub enum Op {
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
+    /// Request the list of background processes the agent started with
+    /// `bg_start`. Reply is delivered via
+    /// `EventMsg::ListBackgroundProcessesResponse`.
+    ListBackgroundProcesses,
+
+    /// Stop a background process the agent started with `bg_start`.
+    StopBackgroundProcess {
+        /// Id of the process, as listed in `ListBackgroundProcessesResponse`.
+        id: u32,
+    },
+
+    /// Fetch the messages of a prompt offered by an MCP server.
+    /// Reply is delivered via `EventMsg::GetMcpPromptResponse`.
+    GetMcpPrompt {
+        /// Name of the MCP server that provides the prompt.
+        server: String,
+        /// Name of the prompt on that server.
+        prompt: String,
+        /// Values for the prompt's arguments.
+        arguments: HashMap<String, String>,
+    },
+
+    /// Allow commands or edits matching a permission rule such as
+    /// `Bash(cargo test:*)` for the rest of the session, and save the rule to
+    /// the permissions of the current project in `config.toml`.
+    AddPermissionRule { rule: String },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -215,9 +265,16 @@ pub enum SandboxPolicy {
     #[serde(rename = "danger-full-access")]
     DangerFullAccess,
 
-    /// Read-only access to the entire file-system.
+    /// Read-only access to the entire file-system, except for `read_deny`.
     #[serde(rename = "read-only")]
-    ReadOnly,
+    ReadOnly {
+        /// Files and folders that cannot be read from within the sandbox.
+        /// `~/` is expanded to the home directory and relative paths are
+        /// resolved against the working directory. Defaults to
+        /// [`default_read_deny`].
+        #[serde(default = "default_read_deny")]
+        read_deny: Vec<PathBuf>,
+    },
 
     /// Same as `ReadOnly` but additionally grants write access to the current
     /// working directory ("workspace").
@@ -233,6 +290,13 @@ pub enum SandboxPolicy {
         #[serde(default)]
         network_access: bool,
 
+        /// Domains that can be reached through the local network proxy when
+        /// `network_access` is `false`. `*.example.com` also matches the
+        /// subdomains of `example.com`. Empty by default, which blocks all
+        /// outbound network access.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        network_allowlist: Vec<String>,
+
         /// When set to `true`, will NOT include the per-user `TMPDIR`
         /// environment variable among the default writable roots. Defaults to
         /// `false`.
@@ -243,9 +307,40 @@ pub enum SandboxPolicy {
         /// writable roots on UNIX. Defaults to `false`.
         #[serde(default)]
         exclude_slash_tmp: bool,
+
+        /// Files and folders that cannot be read from within the sandbox, as
+        /// for `ReadOnly`.
+        #[serde(default = "default_read_deny")]
+        read_deny: Vec<PathBuf>,
     },
 }
 
+/// Secrets hidden from sandboxed commands unless the configuration says
+/// otherwise: SSH, GPG and cloud credentials, and the Codex login in its
+/// default location `~/.codex`. See [`default_read_deny_with_codex_home`].
+pub fn default_read_deny() -> Vec<PathBuf> {
+    default_read_deny_with_codex_home(Path::new("~/.codex"))
+}
+
+/// [`default_read_deny`] with the Codex login read from `codex_home`, for
+/// when `CODEX_HOME` is known.
+pub fn default_read_deny_with_codex_home(codex_home: &Path) -> Vec<PathBuf> {
+    [
+        "~/.ssh",
+        "~/.gnupg",
+        "~/.aws",
+        "~/.azure",
+        "~/.config/gcloud",
+        "~/.kube",
+        "~/.docker/config.json",
+        "~/.netrc",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .chain(std::iter::once(codex_home.join("auth.json")))
+    .collect()
+}
+
 /// A writable root path accompanied by a list of subpaths that should remain
 /// read‑only even when the root is writable. This is primarily used to ensure
 /// top‑level VCS metadata directories (e.g. `.git`) under a writable root are
@@ -288,7 +383,9 @@ impl FromStr for SandboxPolicy {
 impl SandboxPolicy {
     /// Returns a policy with read-only disk access and no network.
     pub fn new_read_only_policy() -> Self {
-        SandboxPolicy::ReadOnly
+        SandboxPolicy::ReadOnly {
+            read_deny: default_read_deny(),
+        }
     }
 
     /// Returns a policy that can read the entire disk, but can only write to
@@ -298,20 +395,22 @@ impl SandboxPolicy {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            read_deny: default_read_deny(),
         }
     }
 
-    /// Always returns `true`; restricting read access is not supported.
+    /// Returns `true` unless some paths are hidden with `read_deny`.
     pub fn has_full_disk_read_access(&self) -> bool {
-        true
+        self.read_deny().is_empty()
     }
 
     pub fn has_full_disk_write_access(&self) -> bool {
         match self {
             SandboxPolicy::DangerFullAccess => true,
-            SandboxPolicy::ReadOnly => false,
+            SandboxPolicy::ReadOnly { .. } => false,
             SandboxPolicy::WorkspaceWrite { .. } => false,
         }
     }
@@ -319,23 +418,71 @@ impl SandboxPolicy {
     pub fn has_full_network_access(&self) -> bool {
         match self {
             SandboxPolicy::DangerFullAccess => true,
-            SandboxPolicy::ReadOnly => false,
+            SandboxPolicy::ReadOnly { .. } => false,
             SandboxPolicy::WorkspaceWrite { network_access, .. } => *network_access,
         }
     }
 
+    /// Returns the domains reachable through the network proxy. Empty unless
+    /// network access is restricted to an allowlist.
+    pub fn network_allowlist(&self) -> &[String] {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                network_access: false,
+                network_allowlist,
+                ..
+            } => network_allowlist,
+            _ => &[],
+        }
+    }
+
+    /// Returns the `read_deny` entries as configured.
+    pub fn read_deny(&self) -> &[PathBuf] {
+        match self {
+            SandboxPolicy::DangerFullAccess => &[],
+            SandboxPolicy::ReadOnly { read_deny } => read_deny,
+            SandboxPolicy::WorkspaceWrite { read_deny, .. } => read_deny,
+        }
+    }
+
+    /// Replaces the `read_deny` entries. Has no effect on `DangerFullAccess`,
+    /// which cannot hide anything.
+    pub fn set_read_deny(&mut self, paths: Vec<PathBuf>) {
+        match self {
+            SandboxPolicy::DangerFullAccess => {}
+            SandboxPolicy::ReadOnly { read_deny } => *read_deny = paths,
+            SandboxPolicy::WorkspaceWrite { read_deny, .. } => *read_deny = paths,
+        }
+    }
+
+    /// Returns the absolute paths that cannot be read, with `~/` expanded to
+    /// the home directory and relative paths resolved against `cwd`. Paths
+    /// under `~/` are dropped when the home directory is unknown.
+    pub fn get_read_deny_paths_with_cwd(&self, cwd: &Path) -> Vec<PathBuf> {
+        let home = dirs::home_dir();
+        self.read_deny()
+            .iter()
+            .filter_map(|path| match path.strip_prefix("~") {
+                Ok(rest) => home.as_ref().map(|home| home.join(rest)),
+                Err(_) => Some(cwd.join(path)),
+            })
+            .collect()
+    }
+
     /// Returns the list of writable roots (tailored to the current working
     /// directory) together with subpaths that should remain read‑only under
     /// each writable root.
     pub fn get_writable_roots_with_cwd(&self, cwd: &Path) -> Vec<WritableRoot> {
         match self {
             SandboxPolicy::DangerFullAccess => Vec::new(),
-            SandboxPolicy::ReadOnly => Vec::new(),
+            SandboxPolicy::ReadOnly { .. } => Vec::new(),
             SandboxPolicy::WorkspaceWrite {
                 writable_roots,
                 exclude_tmpdir_env_var,
                 exclude_slash_tmp,
                 network_access: _,
+                network_allowlist: _,
+                read_deny: _,
             } => {
                 // Start from explicitly configured writable roots.
                 let mut roots: Vec<PathBuf> = writable_roots.clone();
@@ -469,6 +616,19 @@ pub enum EventMsg {
 
     WebSearchEnd(WebSearchEndEvent),
 
+    /// Notification that a configured hook is about to run.
+    HookBegin(HookBeginEvent),
+
+    /// Notification that a hook has finished.
+    HookEnd(HookEndEvent),
+
+    /// Notification that the agent handed a task to a subagent. Events for
+    /// the subagent's tool calls follow until the matching `SubagentEnd`.
+    SubagentBegin(SubagentBeginEvent),
+
+    /// Notification that a subagent has finished its task.
+    SubagentEnd(SubagentEndEvent),
+
     /// Notification that the server is about to execute a command.
     ExecCommandBegin(ExecCommandBeginEvent),
 
@@ -505,6 +665,12 @@ pub enum EventMsg {
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// List of background processes started by the agent.
+    ListBackgroundProcessesResponse(ListBackgroundProcessesResponseEvent),
+
+    /// Messages of an MCP prompt requested with `Op::GetMcpPrompt`.
+    GetMcpPromptResponse(GetMcpPromptResponseEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
     TurnAborted(TurnAbortedEvent),
@@ -729,6 +895,8 @@ pub enum InputMessageKind {
     UserInstructions,
     /// XML-wrapped environment context (<environment_context>...)
     EnvironmentContext,
+    /// XML-wrapped context contributed by hooks (<hook_context ...>...)
+    HookContext,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -757,6 +925,10 @@ where
             && ends_with_ignore_ascii_case(trimmed, USER_INSTRUCTIONS_CLOSE_TAG)
         {
             InputMessageKind::UserInstructions
+        } else if starts_with_ignore_ascii_case(trimmed, HOOK_CONTEXT_OPEN_TAG)
+            && ends_with_ignore_ascii_case(trimmed, HOOK_CONTEXT_CLOSE_TAG)
+        {
+            InputMessageKind::HookContext
         } else {
             InputMessageKind::Plain
         }
@@ -859,6 +1031,58 @@ pub struct WebSearchEndEvent {
     pub query: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct HookBeginEvent {
+    /// Identifier so this can be paired with the HookEnd event.
+    pub call_id: String,
+    /// Name of the hook in `config.toml`.
+    pub name: String,
+    /// Event the hook runs for, e.g. `pre_tool_use`.
+    pub event: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct HookEndEvent {
+    /// Identifier for the corresponding HookBegin that finished.
+    pub call_id: String,
+    pub name: String,
+    pub event: String,
+    #[ts(type = "string")]
+    pub duration: Duration,
+    /// `None` when the hook could not be started or was killed.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Truncated stdout of the hook. Empty when the hook asked for its
+    /// output to be suppressed.
+    pub stdout: String,
+    /// Truncated stderr of the hook, or the reason it could not be started.
+    pub stderr: String,
+    /// Set when the hook blocked the action it was consulted about.
+    pub blocked_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubagentBeginEvent {
+    /// Identifier of the `task` tool call that started the subagent.
+    pub call_id: String,
+    /// Name of the subagent, i.e. its file name in `.codex/agents`.
+    pub agent: String,
+    /// Task the subagent was given.
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubagentEndEvent {
+    /// Identifier for the corresponding SubagentBegin that finished.
+    pub call_id: String,
+    pub agent: String,
+    /// Final message of the subagent, returned to the agent as the result of
+    /// the `task` call.
+    pub last_agent_message: Option<String>,
+    /// Set when the subagent stopped because of an error.
+    pub error: Option<String>,
+}
+
 /// Response payload for `Op::GetHistory` containing the current session's
 /// in-memory transcript.
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -942,6 +1166,17 @@ pub enum RolloutItem {
     Compacted(CompactedItem),
     TurnContext(TurnContextItem),
     EventMsg(EventMsg),
+    /// Conversation item of a subagent thread, kept apart from the main
+    /// conversation history.
+    SubagentItem(SubagentRolloutItem),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+pub struct SubagentRolloutItem {
+    /// Identifier of the `task` tool call that started the subagent.
+    pub call_id: String,
+    pub agent: String,
+    pub item: ResponseItem,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
@@ -1173,12 +1408,43 @@ pub struct McpListToolsResponseEvent {
     pub tools: std::collections::HashMap<String, McpTool>,
 }
 
+/// Response payload for `Op::GetMcpPrompt`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct GetMcpPromptResponseEvent {
+    pub server: String,
+    pub prompt: String,
+    /// Result of the `prompts/get` request: either the prompt's messages or
+    /// an error message.
+    pub result: Result<GetPromptResult, String>,
+}
+
 /// Response payload for `Op::ListCustomPrompts`.
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ListCustomPromptsResponseEvent {
     pub custom_prompts: Vec<CustomPrompt>,
 }
 
+/// Response payload for `Op::ListBackgroundProcesses`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct ListBackgroundProcessesResponseEvent {
+    pub processes: Vec<BackgroundProcessInfo>,
+}
+
+/// A process the agent started with `bg_start`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct BackgroundProcessInfo {
+    pub id: u32,
+    /// The command, for display.
+    pub command: String,
+    pub cwd: PathBuf,
+    /// Exit code once the process has exited; `None` while it is running.
+    pub exit_code: Option<i32>,
+    /// Seconds since the process was started.
+    pub running_for_secs: u64,
+    /// Bytes of output the process has written.
+    pub output_bytes: u64,
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
 pub struct SessionConfiguredEvent {
     /// Name left as session_id instead of conversation_id for backwards compatibility.
@@ -1322,4 +1588,31 @@ mod tests {
         assert_eq!(deserialized, event);
         Ok(())
     }
+
+    #[test]
+    fn sandbox_policy_read_deny_defaults_and_resolution() -> Result<()> {
+        let policy = SandboxPolicy::from_str(r#"{"mode":"read-only"}"#)?;
+        assert_eq!(policy, SandboxPolicy::new_read_only_policy());
+        assert!(!policy.has_full_disk_read_access());
+
+        let policy = SandboxPolicy::from_str(
+            r#"{"mode":"workspace-write","read_deny":["~/.secrets",".env","/etc/shadow"]}"#,
+        )?;
+        let cwd = Path::new("/work");
+        let mut expected = Vec::new();
+        if let Some(home) = dirs::home_dir() {
+            expected.push(home.join(".secrets"));
+        }
+        expected.push(PathBuf::from("/work/.env"));
+        expected.push(PathBuf::from("/etc/shadow"));
+        assert_eq!(policy.get_read_deny_paths_with_cwd(cwd), expected);
+
+        // An explicitly empty list survives a round trip instead of falling
+        // back to the defaults.
+        let policy = SandboxPolicy::ReadOnly { read_deny: vec![] };
+        let round_trip = SandboxPolicy::from_str(&serde_json::to_string(&policy)?)?;
+        assert_eq!(round_trip, policy);
+        assert!(round_trip.has_full_disk_read_access());
+        Ok(())
+    }
 }
@@ -282,6 +282,10 @@ impl App {
                 return Ok(false);
             }
             AppEvent::CodexOp(op) => self.chat_widget.submit_op(op),
+            AppEvent::PromptIncludesExpanded { message, result } => {
+                self.chat_widget
+                    .on_prompt_includes_expanded(message, result);
+            }
             AppEvent::DiffResult(text) => {
                 // Clear the in-progress state in the bottom pane
                 self.chat_widget.on_diff_complete();
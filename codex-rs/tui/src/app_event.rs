@@ -4,6 +4,7 @@ use codex_core::protocol::ConversationPathResponseEvent;
 use codex_core::protocol::Event;
 use codex_file_search::FileMatch;
 
+use crate::chatwidget::UserMessage;
 use crate::history_cell::HistoryCell;
 
 use codex_core::protocol::AskForApproval;
@@ -41,6 +42,13 @@ pub(crate) enum AppEvent {
     /// Result of computing a `/diff` command.
     DiffResult(String),
 
+    /// Result of expanding the `!`cmd`` and `@file` inclusions of a custom
+    /// prompt before it is submitted.
+    PromptIncludesExpanded {
+        message: UserMessage,
+        result: Result<String, String>,
+    },
+
     InsertHistoryCell(Box<dyn HistoryCell>),
 
     StartCommitAnimation,
@@ -1,3 +1,6 @@
+use codex_core::custom_prompts::has_prompt_includes;
+use codex_core::mcp_prompts::mcp_prompt_name;
+use codex_core::protocol::Op;
 use codex_core::protocol::TokenUsageInfo;
 use codex_protocol::num_format::format_si_suffix;
 use crossterm::event::KeyCode;
@@ -29,7 +32,10 @@ use super::paste_burst::CharDecision;
 use super::paste_burst::PasteBurst;
 use crate::bottom_pane::paste_burst::FlushResult;
 use crate::slash_command::SlashCommand;
+use codex_core::template_processor::bind_prompt_arguments;
+use codex_core::template_processor::expand_prompt;
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::PromptTurnOverrides;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
@@ -87,6 +93,13 @@ pub(crate) struct ChatComposer {
     // When true, disables paste-burst logic and inserts characters immediately.
     disable_paste_burst: bool,
     custom_prompts: Vec<CustomPrompt>,
+    // Error from expanding a custom prompt, shown in the footer until the next edit.
+    prompt_error: Option<String>,
+    // Turn overrides of the custom prompt that was just submitted.
+    submission_turn_overrides: Option<PromptTurnOverrides>,
+    // Whether the custom prompt that was just submitted has `!`cmd`` or
+    // `@file` inclusions left to expand.
+    submission_has_includes: bool,
 }
 
 /// Popup state – at most one can be visible at any time.
@@ -130,6 +143,9 @@ impl ChatComposer {
             paste_burst: PasteBurst::default(),
             disable_paste_burst: false,
             custom_prompts: Vec::new(),
+            prompt_error: None,
+            submission_turn_overrides: None,
+            submission_has_includes: false,
         };
         // Apply configuration via the setter to keep side-effects centralized.
         this.set_disable_paste_burst(disable_paste_burst);
@@ -201,6 +217,7 @@ impl ChatComposer {
     }
 
     pub fn handle_paste(&mut self, pasted: String) -> bool {
+        self.prompt_error = None;
         let char_count = pasted.chars().count();
         if char_count > LARGE_PASTE_CHAR_THRESHOLD {
             let placeholder = format!("[Pasted Content {char_count} chars]");
@@ -284,6 +301,17 @@ impl ChatComposer {
         images.into_iter().map(|img| img.path).collect()
     }
 
+    /// Turn overrides declared by the custom prompt behind the last submission.
+    pub(crate) fn take_recent_submission_turn_overrides(&mut self) -> Option<PromptTurnOverrides> {
+        self.submission_turn_overrides.take()
+    }
+
+    /// Whether the last submission came from a custom prompt whose body still
+    /// has shell or file inclusions to expand.
+    pub(crate) fn take_recent_submission_has_includes(&mut self) -> bool {
+        std::mem::take(&mut self.submission_has_includes)
+    }
+
     pub(crate) fn flush_paste_burst_if_due(&mut self) -> bool {
         self.handle_paste_burst_flush(Instant::now())
     }
@@ -326,6 +354,7 @@ impl ChatComposer {
 
     /// Handle a key event coming from the main UI.
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
+        self.prompt_error = None;
         let result = match &mut self.active_popup {
             ActivePopup::Command(_) => self.handle_key_event_with_slash_popup(key_event),
             ActivePopup::File(_) => self.handle_key_event_with_file_popup(key_event),
@@ -417,16 +446,16 @@ impl ChatComposer {
             } => {
                 if let Some(sel) = popup.selected_item() {
                     // Clear textarea so no residual text remains.
+                    let original_text = self.textarea.text().to_string();
                     self.textarea.set_text("");
                     // Capture any needed data from popup before clearing it.
-                    let (prompt_content, prompt_data, current_args) = match sel {
+                    let (prompt_data, current_args) = match sel {
                         CommandItem::UserPrompt(idx) => {
-                            let content = popup.prompt_content(idx).map(|s| s.to_string());
                             let prompt = popup.prompts().get(idx).cloned();
                             let args = popup.current_arguments().to_vec();
-                            (content, prompt, args)
+                            (prompt, args)
                         }
-                        _ => (None, None, Vec::new()),
+                        _ => (None, Vec::new()),
                     };
                     // Hide popup since an action has been dispatched.
                     self.active_popup = ActivePopup::None;
@@ -436,23 +465,32 @@ impl ChatComposer {
                             return (InputResult::Command(cmd), true);
                         }
                         CommandItem::UserPrompt(_) => {
-                            if let Some(contents) = prompt_content {
-                                let processed_content = if !current_args.is_empty() {
-                                    if let Some(prompt) = prompt_data {
-                                        self.process_custom_prompt_with_args(
-                                            &contents,
-                                            &prompt,
-                                            &current_args,
-                                        )
-                                    } else {
-                                        contents
-                                    }
-                                } else {
-                                    contents
-                                };
-                                return (InputResult::Submitted(processed_content), true);
+                            let Some(prompt) = prompt_data else {
+                                return (InputResult::None, true);
+                            };
+                            if let Some(server) = prompt.mcp_server.clone() {
+                                return self.request_mcp_prompt(
+                                    server,
+                                    &prompt,
+                                    &current_args,
+                                    &original_text,
+                                );
+                            }
+                            match expand_prompt(&prompt, &current_args) {
+                                Ok(processed_content) => {
+                                    self.submission_turn_overrides = prompt.turn_overrides;
+                                    self.submission_has_includes =
+                                        has_prompt_includes(&processed_content);
+                                    return (InputResult::Submitted(processed_content), true);
+                                }
+                                Err(err) => {
+                                    // Keep the command in the composer so it can be fixed.
+                                    self.textarea.set_text(&original_text);
+                                    self.textarea.set_cursor(original_text.len());
+                                    self.prompt_error = Some(format!("/{}: {err}", prompt.name));
+                                    return (InputResult::None, true);
+                                }
                             }
-                            return (InputResult::None, true);
                         }
                     }
                 }
@@ -1161,6 +1199,10 @@ impl ChatComposer {
     /// textarea. This must be called after every modification that can change
     /// the text so the popup is shown/updated/hidden as appropriate.
     fn sync_command_popup(&mut self) {
+        // Leave the footer visible while a prompt expansion error is shown.
+        if self.prompt_error.is_some() {
+            return;
+        }
         let first_line = self.textarea.text().lines().next().unwrap_or("");
         let input_starts_with_slash = first_line.starts_with('/');
         match &mut self.active_popup {
@@ -1246,34 +1288,31 @@ impl ChatComposer {
         self.esc_backtrack_hint = show;
     }
 
-    fn process_custom_prompt_with_args(
-        &self,
-        content: &str,
+    /// Ask the MCP server that provides `prompt` for its messages. The reply
+    /// arrives as a `GetMcpPromptResponse` event and is submitted from there.
+    fn request_mcp_prompt(
+        &mut self,
+        server: String,
         prompt: &CustomPrompt,
         args: &[String],
-    ) -> String {
-        if args.is_empty() {
-            return content.to_string();
-        }
-
-        // Build argument header
-        let mut result = String::new();
-        for (i, arg) in args.iter().enumerate() {
-            result.push_str(&format!("argument_{}: {}\n", i + 1, arg));
-        }
-
-        // Add metadata from frontmatter
-        if let Some(hint) = &prompt.argument_hint {
-            result.push_str(&format!("argument-hint: {hint}\n"));
-        }
-        if let Some(desc) = &prompt.description {
-            result.push_str(&format!("description: {desc}\n"));
+        original_text: &str,
+    ) -> (InputResult, bool) {
+        match bind_prompt_arguments(prompt, args) {
+            Ok(arguments) => {
+                self.app_event_tx.send(AppEvent::CodexOp(Op::GetMcpPrompt {
+                    server,
+                    prompt: mcp_prompt_name(prompt).to_string(),
+                    arguments,
+                }));
+            }
+            Err(err) => {
+                // Keep the command in the composer so it can be fixed.
+                self.textarea.set_text(original_text);
+                self.textarea.set_cursor(original_text.len());
+                self.prompt_error = Some(format!("/{}: {err}", prompt.name));
+            }
         }
-
-        result.push('\n');
-        result.push_str(content);
-
-        result
+        (InputResult::None, true)
     }
 }
 
@@ -1310,7 +1349,12 @@ impl WidgetRef for ChatComposer {
                 } else {
                     popup_rect
                 };
-                let mut hint: Vec<Span<'static>> = if self.ctrl_c_quit_hint {
+                let mut hint: Vec<Span<'static>> = if let Some(error) = &self.prompt_error {
+                    vec![
+                        " ".into(),
+                        Span::styled(error.clone(), Style::default().fg(Color::Red)),
+                    ]
+                } else if self.ctrl_c_quit_hint {
                     let ctrl_c_followup = if self.is_task_running {
                         " to interrupt"
                     } else {
@@ -1340,7 +1384,8 @@ impl WidgetRef for ChatComposer {
                     ]
                 };
 
-                if !self.ctrl_c_quit_hint && self.esc_backtrack_hint {
+                if self.prompt_error.is_none() && !self.ctrl_c_quit_hint && self.esc_backtrack_hint
+                {
                     hint.push("   ".into());
                     hint.push(key_hint::plain("Esc"));
                     hint.push(" edit prev".into());
@@ -1413,6 +1458,8 @@ impl WidgetRef for ChatComposer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_protocol::custom_prompts::TemplateArg;
+    use codex_protocol::custom_prompts::TemplateSyntax;
     use image::ImageBuffer;
     use image::Rgba;
     use pretty_assertions::assert_eq;
@@ -2384,6 +2431,8 @@ mod tests {
             description: None,
             template_args: None,
             template_syntax: None,
+            turn_overrides: None,
+            mcp_server: None,
         }]);
 
         type_chars_humanlike(
@@ -2397,6 +2446,113 @@ mod tests {
         assert_eq!(InputResult::Submitted(prompt_text.to_string()), result);
     }
 
+    #[test]
+    fn custom_prompt_template_renders_arguments() {
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+        composer.set_custom_prompts(vec![CustomPrompt {
+            name: "audit".to_string(),
+            path: "/tmp/audit.md".to_string().into(),
+            content: "Review {{ file }}{% if focus %} for {{ focus }}{% endif %}".to_string(),
+            category: None,
+            argument_hint: None,
+            description: None,
+            template_args: Some(vec![TemplateArg {
+                name: "file".to_string(),
+                description: None,
+                required: true,
+                default_value: None,
+            }]),
+            template_syntax: Some(TemplateSyntax::Jinja),
+            turn_overrides: None,
+            mcp_server: None,
+        }]);
+
+        composer.handle_paste("/audit main.rs focus=tests".to_string());
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(
+            InputResult::Submitted("Review main.rs for tests".to_string()),
+            result
+        );
+
+        // Without the required argument the prompt is not sent and the
+        // command stays in the composer with an error in the footer.
+        composer.handle_paste("/audit".to_string());
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(InputResult::None, result);
+        assert_eq!(composer.textarea.text(), "/audit");
+        assert_eq!(
+            composer.prompt_error.as_deref(),
+            Some("/audit: missing required argument `file`")
+        );
+        assert!(!composer.popup_active());
+
+        type_chars_humanlike(&mut composer, &[' ']);
+        assert!(composer.prompt_error.is_none());
+        assert!(composer.popup_active());
+    }
+
+    #[test]
+    fn mcp_prompt_requests_messages_from_server() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+        composer.set_custom_prompts(vec![CustomPrompt {
+            name: "github:review".to_string(),
+            path: PathBuf::new(),
+            content: String::new(),
+            category: Some("github".to_string()),
+            argument_hint: Some("<pr>".to_string()),
+            description: None,
+            template_args: Some(vec![TemplateArg {
+                name: "pr".to_string(),
+                description: None,
+                required: true,
+                default_value: None,
+            }]),
+            template_syntax: None,
+            turn_overrides: None,
+            mcp_server: Some("github".to_string()),
+        }]);
+
+        composer.handle_paste("/github:review 12".to_string());
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(InputResult::None, result);
+        assert!(composer.textarea.is_empty());
+
+        match rx.try_recv() {
+            Ok(AppEvent::CodexOp(Op::GetMcpPrompt {
+                server,
+                prompt,
+                arguments,
+            })) => {
+                assert_eq!(server, "github");
+                assert_eq!(prompt, "review");
+                assert_eq!(
+                    arguments,
+                    HashMap::from([("pr".to_string(), "12".to_string())])
+                );
+            }
+            other => panic!("expected GetMcpPrompt op, got {other:?}"),
+        }
+    }
+
     #[test]
     fn burst_paste_fast_small_buffers_and_flushes_on_stop() {
         use crossterm::event::KeyCode;
@@ -59,16 +59,10 @@ impl CommandPopup {
         self.prompts.get(idx).map(|p| p.name.as_str())
     }
 
-    pub(crate) fn prompt_content(&self, idx: usize) -> Option<&str> {
-        self.prompts.get(idx).map(|p| p.content.as_str())
-    }
-
-    #[allow(dead_code)]
     pub(crate) fn current_arguments(&self) -> &[String] {
         &self.command_args
     }
 
-    #[allow(dead_code)]
     pub(crate) fn prompts(&self) -> &[CustomPrompt] {
         &self.prompts
     }
@@ -298,6 +292,8 @@ mod tests {
                 description: None,
                 template_args: None,
                 template_syntax: None,
+                turn_overrides: None,
+                mcp_server: None,
             },
             CustomPrompt {
                 name: "bar".to_string(),
@@ -308,6 +304,8 @@ mod tests {
                 description: None,
                 template_args: None,
                 template_syntax: None,
+                turn_overrides: None,
+                mcp_server: None,
             },
         ];
         let popup = CommandPopup::new(prompts);
@@ -335,6 +333,8 @@ mod tests {
             description: None,
             template_args: None,
             template_syntax: None,
+            turn_overrides: None,
+            mcp_server: None,
         }]);
         let items = popup.filtered_items();
         let has_collision_prompt = items.into_iter().any(|it| match it {
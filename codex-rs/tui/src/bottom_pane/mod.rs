@@ -40,6 +40,7 @@ pub(crate) enum CancellationEvent {
 pub(crate) use chat_composer::ChatComposer;
 pub(crate) use chat_composer::InputResult;
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::PromptTurnOverrides;
 
 use crate::status_indicator_widget::StatusIndicatorWidget;
 use approval_modal_view::ApprovalModalView;
@@ -478,6 +479,14 @@ impl BottomPane {
     pub(crate) fn take_recent_submission_images(&mut self) -> Vec<PathBuf> {
         self.composer.take_recent_submission_images()
     }
+
+    pub(crate) fn take_recent_submission_turn_overrides(&mut self) -> Option<PromptTurnOverrides> {
+        self.composer.take_recent_submission_turn_overrides()
+    }
+
+    pub(crate) fn take_recent_submission_has_includes(&mut self) -> bool {
+        self.composer.take_recent_submission_has_includes()
+    }
 }
 
 impl WidgetRef for &BottomPane {
@@ -5,8 +5,10 @@ use std::sync::Arc;
 
 use codex_core::config::Config;
 use codex_core::config_types::Notifications;
+use codex_core::custom_prompts::expand_prompt_includes;
 use codex_core::git_info::current_branch_name;
 use codex_core::git_info::local_git_branches;
+use codex_core::mcp_prompts::mcp_prompt_messages_to_text;
 use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningDeltaEvent;
@@ -22,8 +24,11 @@ use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::ExitedReviewModeEvent;
+use codex_core::protocol::GetMcpPromptResponseEvent;
+use codex_core::protocol::HookEndEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::InputMessageKind;
+use codex_core::protocol::ListBackgroundProcessesResponseEvent;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
@@ -33,6 +38,8 @@ use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::StreamErrorEvent;
+use codex_core::protocol::SubagentBeginEvent;
+use codex_core::protocol::SubagentEndEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol::TokenUsageInfo;
@@ -41,6 +48,7 @@ use codex_core::protocol::TurnDiffEvent;
 use codex_core::protocol::UserMessageEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
+use codex_protocol::custom_prompts::PromptTurnOverrides;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::parse_command::ParsedCommand;
 use crossterm::event::KeyCode;
@@ -52,6 +60,8 @@ use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
 use ratatui::widgets::Widget;
 use ratatui::widgets::WidgetRef;
 use tokio::sync::mpsc::UnboundedSender;
@@ -76,6 +86,7 @@ use crate::history_cell::AgentMessageCell;
 use crate::history_cell::CommandOutput;
 use crate::history_cell::ExecCell;
 use crate::history_cell::HistoryCell;
+use crate::history_cell::NestedHistoryCell;
 use crate::history_cell::PatchEventType;
 use crate::history_cell::RateLimitSnapshotDisplay;
 use crate::markdown::append_markdown;
@@ -226,11 +237,18 @@ pub(crate) struct ChatWidget {
     // List of ghost commits corresponding to each turn.
     ghost_snapshots: Vec<GhostCommit>,
     ghost_snapshots_disabled: bool,
+    // Number of subagents currently running; their cells are nested under
+    // the `task` header.
+    subagent_depth: usize,
 }
 
-struct UserMessage {
+#[derive(Debug)]
+pub(crate) struct UserMessage {
     text: String,
     image_paths: Vec<PathBuf>,
+    // Overrides from the custom prompt that produced this message, applied to
+    // its turn only.
+    turn_overrides: Option<PromptTurnOverrides>,
 }
 
 impl From<String> for UserMessage {
@@ -238,22 +256,62 @@ impl From<String> for UserMessage {
         Self {
             text,
             image_paths: Vec::new(),
+            turn_overrides: None,
         }
     }
 }
 
+/// One-line summary of the settings a prompt overrides for its turn.
+fn describe_turn_overrides(overrides: &PromptTurnOverrides) -> Option<String> {
+    let PromptTurnOverrides {
+        model,
+        effort,
+        sandbox,
+        approval,
+        allowed_tools,
+    } = overrides;
+    let mut parts = Vec::new();
+    if let Some(model) = model {
+        parts.push(format!("model {model}"));
+    }
+    if let Some(effort) = effort {
+        parts.push(format!("reasoning effort {effort}"));
+    }
+    if let Some(sandbox) = sandbox {
+        parts.push(format!("sandbox {sandbox}"));
+    }
+    if let Some(approval) = approval {
+        parts.push(format!("approval {approval}"));
+    }
+    if let Some(tools) = allowed_tools {
+        parts.push(format!("tools {}", tools.join(", ")));
+    }
+    (!parts.is_empty()).then(|| format!("This turn uses {}", parts.join(", ")))
+}
+
 fn create_initial_user_message(text: String, image_paths: Vec<PathBuf>) -> Option<UserMessage> {
     if text.is_empty() && image_paths.is_empty() {
         None
     } else {
-        Some(UserMessage { text, image_paths })
+        Some(UserMessage {
+            text,
+            image_paths,
+            turn_overrides: None,
+        })
     }
 }
 
 impl ChatWidget {
+    fn history_sink(&self) -> AppEventHistorySink {
+        AppEventHistorySink {
+            tx: self.app_event_tx.clone(),
+            nested: self.subagent_depth > 0,
+        }
+    }
+
     fn flush_answer_stream_with_separator(&mut self) {
         if let Some(mut controller) = self.stream_controller.take() {
-            let sink = AppEventHistorySink(self.app_event_tx.clone());
+            let sink = self.history_sink();
             controller.finalize(&sink);
         }
     }
@@ -349,7 +407,7 @@ impl ChatWidget {
         // If a stream is currently active, finalize only that stream to flush any tail
         // without emitting stray headers for other streams.
         if let Some(mut controller) = self.stream_controller.take() {
-            let sink = AppEventHistorySink(self.app_event_tx.clone());
+            let sink = self.history_sink();
             controller.finalize(&sink);
         }
         // Mark task stopped and request redraw now that all content is in history.
@@ -524,6 +582,26 @@ impl ChatWidget {
         )));
     }
 
+    fn on_hook_end(&mut self, ev: HookEndEvent) {
+        self.flush_answer_stream_with_separator();
+        self.add_to_history(history_cell::new_hook_end(ev));
+    }
+
+    fn on_subagent_begin(&mut self, ev: SubagentBeginEvent) {
+        self.flush_answer_stream_with_separator();
+        self.add_to_history(history_cell::new_subagent_begin(ev));
+        self.subagent_depth += 1;
+    }
+
+    fn on_subagent_end(&mut self, ev: SubagentEndEvent) {
+        // The subagent's last message and command still belong to it.
+        self.flush_answer_stream_with_separator();
+        self.flush_interrupt_queue();
+        self.flush_active_exec_cell();
+        self.subagent_depth = self.subagent_depth.saturating_sub(1);
+        self.add_to_history(history_cell::new_subagent_end(ev));
+    }
+
     fn on_get_history_entry_response(
         &mut self,
         event: codex_core::protocol::GetHistoryEntryResponseEvent,
@@ -558,7 +636,7 @@ impl ChatWidget {
     /// animating the output.
     pub(crate) fn on_commit_tick(&mut self) {
         if let Some(controller) = self.stream_controller.as_mut() {
-            let sink = AppEventHistorySink(self.app_event_tx.clone());
+            let sink = self.history_sink();
             let finished = controller.on_commit_tick(&sink);
             if finished {
                 self.handle_stream_finished();
@@ -606,7 +684,7 @@ impl ChatWidget {
             self.stream_controller = Some(StreamController::new(self.config.clone()));
         }
         if let Some(controller) = self.stream_controller.as_mut() {
-            let sink = AppEventHistorySink(self.app_event_tx.clone());
+            let sink = self.history_sink();
             controller.push_and_maybe_commit(&delta, &sink);
         }
         self.request_redraw();
@@ -757,10 +835,9 @@ impl ChatWidget {
         let bottom_min = self.bottom_pane.desired_height(area.width).min(area.height);
         let remaining = area.height.saturating_sub(bottom_min);
 
-        let active_desired = self
-            .active_exec_cell
-            .as_ref()
-            .map_or(0, |c| c.desired_height(area.width) + 1);
+        let active_desired = self.active_exec_cell.as_ref().map_or(0, |c| {
+            c.desired_height(self.active_cell_width(area.width)) + 1
+        });
         let active_height = active_desired.min(remaining);
         // Note: no header area; remaining is not used beyond computing active height.
 
@@ -828,6 +905,7 @@ impl ChatWidget {
             is_review_mode: false,
             ghost_snapshots: Vec::new(),
             ghost_snapshots_disabled: true,
+            subagent_depth: 0,
         }
     }
 
@@ -889,6 +967,7 @@ impl ChatWidget {
             is_review_mode: false,
             ghost_snapshots: Vec::new(),
             ghost_snapshots_disabled: true,
+            subagent_depth: 0,
         }
     }
 
@@ -897,7 +976,17 @@ impl ChatWidget {
             + self
                 .active_exec_cell
                 .as_ref()
-                .map_or(0, |c| c.desired_height(width) + 1)
+                .map_or(0, |c| c.desired_height(self.active_cell_width(width)) + 1)
+    }
+
+    /// Width left for the running command once a subagent's nesting prefix
+    /// is drawn in front of it.
+    fn active_cell_width(&self, width: u16) -> u16 {
+        if self.subagent_depth > 0 {
+            width.saturating_sub(NestedHistoryCell::PREFIX_WIDTH)
+        } else {
+            width
+        }
     }
 
     pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) {
@@ -949,12 +1038,14 @@ impl ChatWidget {
                         let user_message = UserMessage {
                             text,
                             image_paths: self.bottom_pane.take_recent_submission_images(),
+                            turn_overrides: self
+                                .bottom_pane
+                                .take_recent_submission_turn_overrides(),
                         };
-                        if self.bottom_pane.is_task_running() {
-                            self.queued_user_messages.push_back(user_message);
-                            self.refresh_queued_user_messages();
+                        if self.bottom_pane.take_recent_submission_has_includes() {
+                            self.expand_prompt_includes(user_message);
                         } else {
-                            self.submit_user_message(user_message);
+                            self.queue_or_submit_user_message(user_message);
                         }
                     }
                     InputResult::Command(cmd) => {
@@ -1050,6 +1141,9 @@ impl ChatWidget {
             SlashCommand::Mcp => {
                 self.add_mcp_output();
             }
+            SlashCommand::Ps => {
+                self.submit_op(Op::ListBackgroundProcesses);
+            }
             #[cfg(debug_assertions)]
             SlashCommand::TestApproval => {
                 use codex_core::protocol::EventMsg;
@@ -1115,11 +1209,19 @@ impl ChatWidget {
 
     fn flush_active_exec_cell(&mut self) {
         if let Some(active) = self.active_exec_cell.take() {
-            self.app_event_tx
-                .send(AppEvent::InsertHistoryCell(Box::new(active)));
+            self.insert_history_cell(Box::new(active));
         }
     }
 
+    fn insert_history_cell(&mut self, cell: Box<dyn HistoryCell>) {
+        let cell: Box<dyn HistoryCell> = if self.subagent_depth > 0 {
+            Box::new(NestedHistoryCell::new(cell))
+        } else {
+            cell
+        };
+        self.app_event_tx.send(AppEvent::InsertHistoryCell(cell));
+    }
+
     fn add_to_history(&mut self, cell: impl HistoryCell + 'static) {
         self.add_boxed_history(Box::new(cell));
     }
@@ -1129,11 +1231,56 @@ impl ChatWidget {
             // Only break exec grouping if the cell renders visible lines.
             self.flush_active_exec_cell();
         }
-        self.app_event_tx.send(AppEvent::InsertHistoryCell(cell));
+        self.insert_history_cell(cell);
+    }
+
+    fn queue_or_submit_user_message(&mut self, user_message: UserMessage) {
+        // If a task is running, queue the user input to be sent after the turn completes.
+        if self.bottom_pane.is_task_running() {
+            self.queued_user_messages.push_back(user_message);
+            self.refresh_queued_user_messages();
+        } else {
+            self.submit_user_message(user_message);
+        }
+    }
+
+    /// Resolve the `!`cmd`` and `@file` inclusions of a custom prompt in the
+    /// background, under the session's sandbox and approval policy. The
+    /// prompt's own overrides are not used: it may come from the repository.
+    fn expand_prompt_includes(&mut self, message: UserMessage) {
+        let config = self.config.clone();
+        let tx = self.app_event_tx.clone();
+        tokio::spawn(async move {
+            let result = expand_prompt_includes(&message.text, &config).await;
+            tx.send(AppEvent::PromptIncludesExpanded { message, result });
+        });
+    }
+
+    pub(crate) fn on_prompt_includes_expanded(
+        &mut self,
+        mut message: UserMessage,
+        result: Result<String, String>,
+    ) {
+        match result {
+            Ok(text) => {
+                message.text = text;
+                self.queue_or_submit_user_message(message);
+            }
+            Err(err) => {
+                self.add_to_history(history_cell::new_error_event(format!(
+                    "Failed to expand prompt: {err}"
+                )));
+                self.request_redraw();
+            }
+        }
     }
 
     fn submit_user_message(&mut self, user_message: UserMessage) {
-        let UserMessage { text, image_paths } = user_message;
+        let UserMessage {
+            text,
+            image_paths,
+            turn_overrides,
+        } = user_message;
         if text.is_empty() && image_paths.is_empty() {
             return;
         }
@@ -1150,11 +1297,15 @@ impl ChatWidget {
             items.push(InputItem::LocalImage { path });
         }
 
-        self.codex_op_tx
-            .send(Op::UserInput { items })
-            .unwrap_or_else(|e| {
-                tracing::error!("failed to send message: {e}");
-            });
+        // Core applies a prompt's overrides to the task it starts only.
+        let overrides_note = turn_overrides.as_ref().and_then(describe_turn_overrides);
+        let op = match turn_overrides {
+            Some(overrides) => Op::PromptTurn { items, overrides },
+            None => Op::UserInput { items },
+        };
+        self.codex_op_tx.send(op).unwrap_or_else(|e| {
+            tracing::error!("failed to send message: {e}");
+        });
 
         // Persist the text to cross-session message history.
         if !text.is_empty() {
@@ -1169,6 +1320,9 @@ impl ChatWidget {
         if !text.is_empty() {
             self.add_to_history(history_cell::new_user_prompt(text));
         }
+        if let Some(note) = overrides_note {
+            self.add_to_history(history_cell::new_info_event(note, None));
+        }
     }
 
     fn capture_ghost_snapshot(&mut self) {
@@ -1310,9 +1464,15 @@ impl ChatWidget {
             EventMsg::McpToolCallEnd(ev) => self.on_mcp_tool_call_end(ev),
             EventMsg::WebSearchBegin(ev) => self.on_web_search_begin(ev),
             EventMsg::WebSearchEnd(ev) => self.on_web_search_end(ev),
+            EventMsg::HookBegin(_) => {}
+            EventMsg::HookEnd(ev) => self.on_hook_end(ev),
+            EventMsg::SubagentBegin(ev) => self.on_subagent_begin(ev),
+            EventMsg::SubagentEnd(ev) => self.on_subagent_end(ev),
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
+            EventMsg::ListBackgroundProcessesResponse(ev) => self.on_list_background_processes(ev),
+            EventMsg::GetMcpPromptResponse(ev) => self.on_get_mcp_prompt(ev),
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
@@ -1387,7 +1547,8 @@ impl ChatWidget {
     fn on_user_message_event(&mut self, event: UserMessageEvent) {
         match event.kind {
             Some(InputMessageKind::EnvironmentContext)
-            | Some(InputMessageKind::UserInstructions) => {
+            | Some(InputMessageKind::UserInstructions)
+            | Some(InputMessageKind::HookContext) => {
                 // Skip XML‑wrapped context blocks in the transcript.
             }
             Some(InputMessageKind::Plain) | None => {
@@ -1542,10 +1703,14 @@ impl ChatWidget {
         let mut items: Vec<SelectionItem> = Vec::new();
         let presets: Vec<ApprovalPreset> = builtin_approval_presets();
         for preset in presets.into_iter() {
-            let is_current =
-                current_approval == preset.approval && current_sandbox == preset.sandbox;
+            // Presets keep the configured read-deny list. Full access has
+            // none, so switching away from it keeps the preset's defaults.
+            let mut sandbox = preset.sandbox.clone();
+            if current_sandbox != SandboxPolicy::DangerFullAccess {
+                sandbox.set_read_deny(current_sandbox.read_deny().to_vec());
+            }
+            let is_current = current_approval == preset.approval && current_sandbox == sandbox;
             let approval = preset.approval;
-            let sandbox = preset.sandbox.clone();
             let name = preset.label.to_string();
             let description = Some(preset.description.to_string());
             let actions: Vec<SelectionAction> = vec![Box::new(move |tx| {
@@ -1677,6 +1842,48 @@ impl ChatWidget {
         self.add_to_history(history_cell::new_mcp_tools_output(&self.config, ev.tools));
     }
 
+    /// Show the background processes started by the agent; selecting one
+    /// stops it.
+    fn on_list_background_processes(&mut self, ev: ListBackgroundProcessesResponseEvent) {
+        if ev.processes.is_empty() {
+            self.add_info_message("No background processes".to_string(), None);
+            return;
+        }
+
+        let mut items: Vec<SelectionItem> = Vec::new();
+        for process in ev.processes {
+            let id = process.id;
+            let status = match process.exit_code {
+                Some(code) => format!("exited with code {code}"),
+                None => format!("running for {}s", process.running_for_secs),
+            };
+            let description = format!(
+                "{status}, {} bytes of output, in {}",
+                process.output_bytes,
+                process.cwd.display()
+            );
+            let actions: Vec<SelectionAction> = vec![Box::new(move |tx| {
+                tx.send(AppEvent::CodexOp(Op::StopBackgroundProcess { id }));
+            })];
+            items.push(SelectionItem {
+                name: format!("#{id} {}", process.command),
+                description: Some(description),
+                is_current: false,
+                actions,
+                dismiss_on_select: true,
+                search_value: None,
+            });
+        }
+
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: "Background processes".to_string(),
+            subtitle: Some("Select a process to stop it".to_string()),
+            footer_hint: Some(STANDARD_POPUP_HINT_LINE.to_string()),
+            items,
+            ..Default::default()
+        });
+    }
+
     fn on_list_custom_prompts(&mut self, ev: ListCustomPromptsResponseEvent) {
         let len = ev.custom_prompts.len();
         debug!("received {len} custom prompts");
@@ -1684,6 +1891,23 @@ impl ChatWidget {
         self.bottom_pane.set_custom_prompts(ev.custom_prompts);
     }
 
+    fn on_get_mcp_prompt(&mut self, ev: GetMcpPromptResponseEvent) {
+        match ev.result {
+            Ok(result) => {
+                let text = mcp_prompt_messages_to_text(&result);
+                if text.is_empty() {
+                    self.add_error_message(format!(
+                        "/{}:{} returned no messages",
+                        ev.server, ev.prompt
+                    ));
+                } else {
+                    self.queue_or_submit_user_message(text.into());
+                }
+            }
+            Err(err) => self.add_error_message(format!("/{}:{}: {err}", ev.server, ev.prompt)),
+        }
+    }
+
     pub(crate) fn open_review_popup(&mut self) {
         let mut items: Vec<SelectionItem> = Vec::new();
 
@@ -1902,6 +2126,22 @@ impl WidgetRef for &ChatWidget {
             let mut active_cell_area = active_cell_area;
             active_cell_area.y = active_cell_area.y.saturating_add(1);
             active_cell_area.height -= 1;
+            // A running subagent's command is nested like its finished cells.
+            let width = self.active_cell_width(active_cell_area.width);
+            let prefix_width = active_cell_area.width - width;
+            if prefix_width > 0 {
+                for y in active_cell_area.top()..active_cell_area.bottom() {
+                    buf.set_stringn(
+                        active_cell_area.x,
+                        y,
+                        NestedHistoryCell::PREFIX,
+                        prefix_width.into(),
+                        Style::default().dim(),
+                    );
+                }
+                active_cell_area.x += prefix_width;
+                active_cell_area.width = width;
+            }
             cell.render_ref(active_cell_area, buf);
         }
     }
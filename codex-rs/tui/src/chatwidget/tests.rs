@@ -21,6 +21,7 @@ use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::ExitedReviewModeEvent;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::HookEndEvent;
 use codex_core::protocol::InputMessageKind;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
@@ -31,8 +32,11 @@ use codex_core::protocol::ReviewLineRange;
 use codex_core::protocol::ReviewOutputEvent;
 use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::StreamErrorEvent;
+use codex_core::protocol::SubagentBeginEvent;
+use codex_core::protocol::SubagentEndEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TaskStartedEvent;
+use codex_protocol::config_types::SandboxMode;
 use codex_protocol::mcp_protocol::ConversationId;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -337,6 +341,7 @@ fn make_chatwidget_manual() -> (
         is_review_mode: false,
         ghost_snapshots: Vec::new(),
         ghost_snapshots_disabled: false,
+        subagent_depth: 0,
     };
     (widget, rx, op_rx)
 }
@@ -626,6 +631,90 @@ fn alt_up_edits_most_recent_queued_message() {
     );
 }
 
+#[test]
+fn expanded_prompt_includes_are_submitted_or_reported() {
+    let (mut chat, mut rx, mut op_rx) = make_chatwidget_manual();
+    chat.ghost_snapshots_disabled = true;
+
+    chat.on_prompt_includes_expanded(
+        UserMessage::from("Diff: !`git diff`".to_string()),
+        Err("`git diff` exited with code 1: boom".to_string()),
+    );
+    assert!(op_rx.try_recv().is_err(), "nothing should be sent on error");
+    let blob = drain_insert_history(&mut rx)
+        .iter()
+        .map(|lines| lines_to_single_string(lines))
+        .collect::<String>();
+    assert!(
+        blob.contains("Failed to expand prompt: `git diff` exited with code 1: boom"),
+        "missing error: {blob:?}"
+    );
+
+    chat.on_prompt_includes_expanded(
+        UserMessage::from("Diff: !`git diff`".to_string()),
+        Ok("Diff: +added line".to_string()),
+    );
+    match op_rx.try_recv() {
+        Ok(Op::UserInput { items }) => assert_eq!(
+            items,
+            vec![InputItem::Text {
+                text: "Diff: +added line".to_string()
+            }]
+        ),
+        other => panic!("expected UserInput, got {other:?}"),
+    }
+}
+
+#[test]
+fn prompt_turn_overrides_apply_to_one_turn_only() {
+    let (mut chat, mut rx, mut op_rx) = make_chatwidget_manual();
+    chat.ghost_snapshots_disabled = true;
+
+    let mut next_turn_op = |chat: &mut ChatWidget, message: UserMessage| {
+        chat.submit_user_message(message);
+        std::iter::from_fn(|| op_rx.try_recv().ok())
+            .find(|op| !matches!(op, Op::AddToHistory { .. }))
+            .expect("expected a turn op")
+    };
+
+    let op = next_turn_op(
+        &mut chat,
+        UserMessage {
+            text: "review this".to_string(),
+            image_paths: Vec::new(),
+            turn_overrides: Some(PromptTurnOverrides {
+                model: Some("cheap-model".to_string()),
+                sandbox: Some(SandboxMode::ReadOnly),
+                allowed_tools: Some(vec!["shell".to_string()]),
+                ..Default::default()
+            }),
+        },
+    );
+    match op {
+        Op::PromptTurn { overrides, .. } => {
+            assert_eq!(overrides.model.as_deref(), Some("cheap-model"));
+            assert_eq!(overrides.sandbox, Some(SandboxMode::ReadOnly));
+            assert_eq!(overrides.allowed_tools, Some(vec!["shell".to_string()]));
+        }
+        other => panic!("expected PromptTurn, got {other:?}"),
+    }
+    let blob = drain_insert_history(&mut rx)
+        .iter()
+        .map(|lines| lines_to_single_string(lines))
+        .collect::<String>();
+    assert!(
+        blob.contains("This turn uses model cheap-model, sandbox read-only, tools shell"),
+        "missing overrides note: {blob:?}"
+    );
+
+    // Core drops the overrides when that task ends, so the next message is
+    // plain input.
+    assert!(matches!(
+        next_turn_op(&mut chat, UserMessage::from("follow up".to_string())),
+        Op::UserInput { .. }
+    ));
+}
+
 #[test]
 fn exec_history_cell_shows_working_then_completed() {
     let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
@@ -1854,6 +1943,89 @@ fn stream_error_is_rendered_to_history() {
     assert!(blob.contains("idle timeout waiting for SSE"));
 }
 
+#[test]
+fn hook_end_is_rendered_to_history() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+    chat.handle_codex_event(Event {
+        id: "sub-1".into(),
+        msg: EventMsg::HookEnd(HookEndEvent {
+            call_id: "hook-1".into(),
+            name: "guard".into(),
+            event: "pre_tool_use".into(),
+            duration: std::time::Duration::from_millis(120),
+            exit_code: Some(2),
+            timed_out: false,
+            stdout: String::new(),
+            stderr: "never run cargo publish\n".into(),
+            blocked_reason: Some("never run cargo publish".into()),
+        }),
+    });
+
+    let cells = drain_insert_history(&mut rx);
+    assert_eq!(cells.len(), 1, "expected a history cell for HookEnd");
+    let blob = lines_to_single_string(&cells[0]);
+    assert!(
+        blob.starts_with("hook pre_tool_use guard blocked, duration: 120ms\n"),
+        "unexpected hook cell: {blob:?}"
+    );
+    assert!(blob.contains("└ never run cargo publish"));
+}
+
+#[test]
+fn subagent_cells_are_nested_under_task_header() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+    chat.handle_codex_event(Event {
+        id: "sub-1".into(),
+        msg: EventMsg::SubagentBegin(SubagentBeginEvent {
+            call_id: "task-1".into(),
+            agent: "reviewer".into(),
+            prompt: "Review the diff".into(),
+        }),
+    });
+    begin_exec(&mut chat, "c1", "echo hi");
+    end_exec(&mut chat, "c1", "hi\n", "", 0);
+    chat.handle_codex_event(Event {
+        id: "sub-1".into(),
+        msg: EventMsg::AgentMessage(AgentMessageEvent {
+            message: "Checking the diff.".into(),
+        }),
+    });
+    chat.handle_codex_event(Event {
+        id: "sub-1".into(),
+        msg: EventMsg::SubagentEnd(SubagentEndEvent {
+            call_id: "task-1".into(),
+            agent: "reviewer".into(),
+            last_agent_message: Some("Looks good.".into()),
+            error: None,
+        }),
+    });
+    begin_exec(&mut chat, "c2", "echo after");
+    end_exec(&mut chat, "c2", "after\n", "", 0);
+    chat.flush_active_exec_cell();
+
+    // Drop the blank separator lines between cells.
+    let cells: Vec<String> = drain_insert_history(&mut rx)
+        .iter()
+        .map(|lines| lines_to_single_string(lines).trim_start().to_string())
+        .collect();
+    assert_eq!(cells.len(), 5, "unexpected cells: {cells:?}");
+    assert_eq!(cells[0], "task reviewer\n  └ Review the diff\n");
+    for cell in &cells[1..3] {
+        assert!(
+            cell.lines().all(|line| line.trim_start().starts_with("│")),
+            "subagent output should be nested: {cell:?}"
+        );
+    }
+    assert!(cells[1].contains("echo hi"));
+    assert!(cells[2].contains("Checking the diff."));
+    assert_eq!(cells[3], "task reviewer finished\n  └ Looks good.\n");
+    assert!(
+        !cells[4].contains('│'),
+        "later commands are not nested: {:?}",
+        cells[4]
+    );
+}
+
 #[test]
 fn multiple_agent_messages_in_single_turn_emit_multiple_headers() {
     let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
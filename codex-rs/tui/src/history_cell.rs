@@ -26,11 +26,14 @@ use codex_core::plan_tool::StepStatus;
 use codex_core::plan_tool::UpdatePlanArgs;
 use codex_core::project_doc::discover_project_doc_paths;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::HookEndEvent;
 use codex_core::protocol::McpInvocation;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::RateLimitWindow;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::SessionConfiguredEvent;
+use codex_core::protocol::SubagentBeginEvent;
+use codex_core::protocol::SubagentEndEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -963,6 +966,134 @@ pub(crate) fn new_web_search_call(query: String) -> PlainHistoryCell {
     PlainHistoryCell { lines }
 }
 
+/// Compact one-line summary of a finished hook run. The reason is shown
+/// below it when the hook blocked an action, and stderr when it failed.
+pub(crate) fn new_hook_end(ev: HookEndEvent) -> PlainHistoryCell {
+    let HookEndEvent {
+        name,
+        event,
+        duration,
+        exit_code,
+        timed_out,
+        stderr,
+        blocked_reason,
+        ..
+    } = ev;
+    let status = match (&blocked_reason, timed_out, exit_code) {
+        (Some(_), _, _) => "blocked".red(),
+        (None, true, _) => "timed out".red(),
+        (None, false, Some(0)) => "ok".green(),
+        (None, false, Some(code)) => format!("exit {code}").red(),
+        (None, false, None) => "failed".red(),
+    };
+    let succeeded = blocked_reason.is_none() && !timed_out && exit_code == Some(0);
+    let mut lines: Vec<Line<'static>> = vec![Line::from(vec![
+        "hook".magenta(),
+        " ".into(),
+        format!("{event} {name}").bold(),
+        " ".into(),
+        status,
+        format!(", duration: {}", format_duration(duration)).dim(),
+    ])];
+    if let Some(reason) = blocked_reason {
+        lines.push(vec!["  └ ".dim(), reason.into()].into());
+    } else if !succeeded {
+        lines.extend(
+            stderr
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .take(TOOL_CALL_MAX_LINES)
+                .map(|line| vec!["  ".into(), line.to_string().dim()].into()),
+        );
+    }
+    PlainHistoryCell { lines }
+}
+
+/// Header shown when the agent hands a task to a subagent. The subagent's
+/// cells follow it, nested with [`NestedHistoryCell`].
+pub(crate) fn new_subagent_begin(ev: SubagentBeginEvent) -> PlainHistoryCell {
+    let SubagentBeginEvent { agent, prompt, .. } = ev;
+    let mut lines: Vec<Line<'static>> =
+        vec![Line::from(vec!["task".magenta(), " ".into(), agent.bold()])];
+    lines.extend(prefix_lines(
+        prompt
+            .lines()
+            .take(TOOL_CALL_MAX_LINES)
+            .map(|line| Line::from(line.to_string().dim()))
+            .collect(),
+        "  └ ".dim(),
+        "    ".into(),
+    ));
+    PlainHistoryCell { lines }
+}
+
+/// Closes a subagent run with the start of its final message, or its error.
+pub(crate) fn new_subagent_end(ev: SubagentEndEvent) -> PlainHistoryCell {
+    let SubagentEndEvent {
+        agent,
+        last_agent_message,
+        error,
+        ..
+    } = ev;
+    let (status, body) = match error {
+        Some(error) => ("failed".red(), error),
+        None => ("finished".green(), last_agent_message.unwrap_or_default()),
+    };
+    let mut lines: Vec<Line<'static>> = vec![Line::from(vec![
+        "task".magenta(),
+        " ".into(),
+        agent.bold(),
+        " ".into(),
+        status,
+    ])];
+    lines.extend(prefix_lines(
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(TOOL_CALL_MAX_LINES)
+            .map(|line| Line::from(line.to_string().dim()))
+            .collect(),
+        "  └ ".dim(),
+        "    ".into(),
+    ));
+    PlainHistoryCell { lines }
+}
+
+/// Wraps the cells of a running subagent so they render indented under its
+/// `task` header.
+#[derive(Debug)]
+pub(crate) struct NestedHistoryCell {
+    inner: Box<dyn HistoryCell>,
+}
+
+impl NestedHistoryCell {
+    pub(crate) const PREFIX: &'static str = "  │ ";
+    /// Display width of [`Self::PREFIX`].
+    pub(crate) const PREFIX_WIDTH: u16 = 4;
+
+    pub(crate) fn new(inner: Box<dyn HistoryCell>) -> Self {
+        Self { inner }
+    }
+
+    fn nest(lines: Vec<Line<'static>>) -> Vec<Line<'static>> {
+        prefix_lines(lines, Self::PREFIX.dim(), Self::PREFIX.dim())
+    }
+}
+
+impl HistoryCell for NestedHistoryCell {
+    fn display_lines(&self, width: u16) -> Vec<Line<'static>> {
+        let inner_width = width.saturating_sub(Self::PREFIX_WIDTH);
+        Self::nest(self.inner.display_lines(inner_width))
+    }
+
+    fn transcript_lines(&self) -> Vec<Line<'static>> {
+        Self::nest(self.inner.transcript_lines())
+    }
+
+    fn is_stream_continuation(&self) -> bool {
+        self.inner.is_stream_continuation()
+    }
+}
+
 /// If the first content is an image, return a new cell with the image.
 /// TODO(rgwood-dd): Handle images properly even if they're not the first result.
 fn try_new_completed_mcp_tool_call_with_image_output(
@@ -1161,20 +1292,24 @@ pub(crate) fn new_status_output(
     // Sandbox (simplified name only)
     let sandbox_name = match &config.sandbox_policy {
         SandboxPolicy::DangerFullAccess => "danger-full-access",
-        SandboxPolicy::ReadOnly => "read-only",
+        SandboxPolicy::ReadOnly { .. } => "read-only",
         SandboxPolicy::WorkspaceWrite { .. } => "workspace-write",
     };
     lines.push(vec!["  • Sandbox: ".into(), sandbox_name.into()].into());
 
-    // AGENTS.md files discovered via core's project_doc logic
+    // Project docs (AGENTS.md, CLAUDE.md, ...) discovered via core's project_doc logic
     let agents_list = {
         match discover_project_doc_paths(config) {
             Ok(paths) => {
                 let mut rels: Vec<String> = Vec::new();
                 for p in paths {
+                    let file_name = p
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
                     let display = if let Some(parent) = p.parent() {
                         if parent == config.cwd {
-                            "AGENTS.md".to_string()
+                            file_name
                         } else {
                             let mut cur = config.cwd.as_path();
                             let mut ups = 0usize;
@@ -1189,7 +1324,7 @@ pub(crate) fn new_status_output(
                             }
                             if reached {
                                 let up = format!("..{}", std::path::MAIN_SEPARATOR);
-                                format!("{}AGENTS.md", up.repeat(ups))
+                                format!("{}{file_name}", up.repeat(ups))
                             } else if let Ok(stripped) = p.strip_prefix(&config.cwd) {
                                 stripped.display().to_string()
                             } else {
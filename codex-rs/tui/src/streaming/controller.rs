@@ -12,20 +12,30 @@ pub(crate) trait HistorySink {
     fn stop_commit_animation(&self);
 }
 
-/// Concrete sink backed by `AppEventSender`.
-pub(crate) struct AppEventHistorySink(pub(crate) crate::app_event_sender::AppEventSender);
+/// Concrete sink backed by `AppEventSender`. When `nested` is set, cells are
+/// indented under the `task` header of the running subagent.
+pub(crate) struct AppEventHistorySink {
+    pub(crate) tx: crate::app_event_sender::AppEventSender,
+    pub(crate) nested: bool,
+}
 
 impl HistorySink for AppEventHistorySink {
     fn insert_history_cell(&self, cell: Box<dyn crate::history_cell::HistoryCell>) {
-        self.0
+        let cell: Box<dyn HistoryCell> = if self.nested {
+            Box::new(history_cell::NestedHistoryCell::new(cell))
+        } else {
+            cell
+        };
+        self.tx
             .send(crate::app_event::AppEvent::InsertHistoryCell(cell))
     }
     fn start_commit_animation(&self) {
-        self.0
+        self.tx
             .send(crate::app_event::AppEvent::StartCommitAnimation)
     }
     fn stop_commit_animation(&self) {
-        self.0.send(crate::app_event::AppEvent::StopCommitAnimation)
+        self.tx
+            .send(crate::app_event::AppEvent::StopCommitAnimation)
     }
 }
 
@@ -9,6 +9,7 @@
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
+use codex_core::permissions::command_prefix_rule;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use crossterm::event::KeyCode;
@@ -49,11 +50,14 @@ pub(crate) enum ApprovalRequest {
 /// Options displayed in the *select* mode.
 ///
 /// The `key` is matched case-insensitively.
+#[derive(Clone)]
 struct SelectOption {
     label: Line<'static>,
     description: &'static str,
     key: KeyCode,
     decision: ReviewDecision,
+    /// Also allow the command's prefix rule in this project.
+    allow_prefix: bool,
 }
 
 static COMMAND_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
@@ -63,22 +67,43 @@ static COMMAND_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
             description: "Approve and run the command",
             key: KeyCode::Char('y'),
             decision: ReviewDecision::Approved,
+            allow_prefix: false,
         },
         SelectOption {
             label: Line::from(vec!["A".underlined(), "lways".into()]),
             description: "Approve the command for the remainder of this session",
             key: KeyCode::Char('a'),
             decision: ReviewDecision::ApprovedForSession,
+            allow_prefix: false,
         },
         SelectOption {
             label: Line::from(vec!["N".underlined(), "o, provide feedback".into()]),
             description: "Do not run the command; provide feedback",
             key: KeyCode::Char('n'),
             decision: ReviewDecision::Abort,
+            allow_prefix: false,
         },
     ]
 });
 
+/// Command options plus "always allow this prefix in this project", offered
+/// when the command has a prefix that can be turned into a rule.
+static COMMAND_WITH_PREFIX_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
+    let mut options = COMMAND_SELECT_OPTIONS.clone();
+    options.insert(
+        2,
+        SelectOption {
+            // Rendered with the rule appended, see `option_label`.
+            label: Line::from(vec!["P".underlined(), "roject".into()]),
+            description: "Approve and always allow commands with this prefix in this project",
+            key: KeyCode::Char('p'),
+            decision: ReviewDecision::ApprovedForSession,
+            allow_prefix: true,
+        },
+    );
+    options
+});
+
 static PATCH_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     vec![
         SelectOption {
@@ -86,12 +111,14 @@ static PATCH_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
             description: "Approve and apply the changes",
             key: KeyCode::Char('y'),
             decision: ReviewDecision::Approved,
+            allow_prefix: false,
         },
         SelectOption {
             label: Line::from(vec!["N".underlined(), "o, provide feedback".into()]),
             description: "Do not apply the changes; provide feedback",
             key: KeyCode::Char('n'),
             decision: ReviewDecision::Abort,
+            allow_prefix: false,
         },
     ]
 });
@@ -103,6 +130,12 @@ pub(crate) struct UserApprovalWidget {
     confirmation_prompt: Paragraph<'static>,
     select_options: &'static Vec<SelectOption>,
 
+    /// `Bash(<prefix>:*)` rule offered for an exec request, if any.
+    prefix_rule: Option<String>,
+
+    /// Rule the user chose to allow, shown in the history cell.
+    allowed_rule: Option<String>,
+
     /// Currently selected index in *select* mode.
     selected_option: usize,
 
@@ -144,11 +177,21 @@ impl UserApprovalWidget {
             }
         };
 
+        let prefix_rule = match &approval_request {
+            ApprovalRequest::Exec { command, .. } => {
+                command_prefix_rule(command).map(|rule| rule.to_string())
+            }
+            ApprovalRequest::ApplyPatch { .. } => None,
+        };
+
         Self {
-            select_options: match &approval_request {
-                ApprovalRequest::Exec { .. } => &COMMAND_SELECT_OPTIONS,
-                ApprovalRequest::ApplyPatch { .. } => &PATCH_SELECT_OPTIONS,
+            select_options: match (&approval_request, &prefix_rule) {
+                (ApprovalRequest::Exec { .. }, Some(_)) => &COMMAND_WITH_PREFIX_SELECT_OPTIONS,
+                (ApprovalRequest::Exec { .. }, None) => &COMMAND_SELECT_OPTIONS,
+                (ApprovalRequest::ApplyPatch { .. }, _) => &PATCH_SELECT_OPTIONS,
             },
+            prefix_rule,
+            allowed_rule: None,
             approval_request,
             app_event_tx,
             confirmation_prompt,
@@ -190,6 +233,7 @@ impl UserApprovalWidget {
     }
 
     fn handle_select_key(&mut self, key_event: KeyEvent) {
+        let select_options = self.select_options;
         match key_event.code {
             KeyCode::Left => {
                 self.selected_option = (self.selected_option + self.select_options.len() - 1)
@@ -199,25 +243,38 @@ impl UserApprovalWidget {
                 self.selected_option = (self.selected_option + 1) % self.select_options.len();
             }
             KeyCode::Enter => {
-                let opt = &self.select_options[self.selected_option];
-                self.send_decision(opt.decision);
+                self.send_select_option(&select_options[self.selected_option]);
             }
             KeyCode::Esc => {
                 self.send_decision(ReviewDecision::Abort);
             }
             other => {
                 let normalized = Self::normalize_keycode(other);
-                if let Some(opt) = self
-                    .select_options
+                if let Some(opt) = select_options
                     .iter()
                     .find(|opt| Self::normalize_keycode(opt.key) == normalized)
                 {
-                    self.send_decision(opt.decision);
+                    self.send_select_option(opt);
                 }
             }
         }
     }
 
+    fn send_select_option(&mut self, option: &SelectOption) {
+        if option.allow_prefix
+            && let Some(rule) = self.prefix_rule.clone()
+        {
+            // Sent before the decision so the rule is in place for the
+            // commands that follow.
+            self.app_event_tx
+                .send(AppEvent::CodexOp(Op::AddPermissionRule {
+                    rule: rule.clone(),
+                }));
+            self.allowed_rule = Some(rule);
+        }
+        self.send_decision(option.decision);
+    }
+
     fn send_decision(&mut self, decision: ReviewDecision) {
         self.send_decision_with_feedback(decision, String::new())
     }
@@ -248,16 +305,30 @@ impl UserApprovalWidget {
                             " this time".bold(),
                         ]);
                     }
-                    ReviewDecision::ApprovedForSession => {
-                        result_spans.extend(vec![
-                            "✔ ".fg(Color::Green),
-                            "You ".into(),
-                            "approved".bold(),
-                            " codex to run ".into(),
-                            snippet.dim(),
-                            " every time this session".bold(),
-                        ]);
-                    }
+                    ReviewDecision::ApprovedForSession => match &self.allowed_rule {
+                        Some(rule) => {
+                            result_spans.extend(vec![
+                                "✔ ".fg(Color::Green),
+                                "You ".into(),
+                                "approved".bold(),
+                                " codex to run ".into(),
+                                snippet.dim(),
+                                " and always allow ".into(),
+                                rule.clone().bold(),
+                                " in this project".into(),
+                            ]);
+                        }
+                        None => {
+                            result_spans.extend(vec![
+                                "✔ ".fg(Color::Green),
+                                "You ".into(),
+                                "approved".bold(),
+                                " codex to run ".into(),
+                                snippet.dim(),
+                                " every time this session".bold(),
+                            ]);
+                        }
+                    },
                     ReviewDecision::Denied => {
                         result_spans.extend(vec![
                             "✗ ".fg(Color::Red),
@@ -317,6 +388,18 @@ impl UserApprovalWidget {
         self.done
     }
 
+    /// The option's label; the project option names the rule it adds.
+    fn option_label(&self, option: &SelectOption) -> Line<'static> {
+        match (&self.prefix_rule, option.allow_prefix) {
+            (Some(rule), true) => {
+                let mut label = option.label.clone();
+                label.push_span(format!(": {rule}"));
+                label
+            }
+            _ => option.label.clone(),
+        }
+    }
+
     pub(crate) fn desired_height(&self, width: u16) -> u16 {
         // Reserve space for:
         // - 1 title line ("Allow command?" or "Apply changes?")
@@ -344,7 +427,9 @@ impl WidgetRef for &UserApprovalWidget {
                 } else {
                     Style::new().add_modifier(Modifier::DIM)
                 };
-                opt.label.clone().alignment(Alignment::Center).style(style)
+                self.option_label(opt)
+                    .alignment(Alignment::Center)
+                    .style(style)
             })
             .collect();
 
@@ -373,7 +458,14 @@ impl WidgetRef for &UserApprovalWidget {
             line.render(*area, buf);
         }
 
-        Line::from(self.select_options[self.selected_option].description)
+        let selected = &self.select_options[self.selected_option];
+        let description = match (&self.prefix_rule, selected.allow_prefix) {
+            (Some(rule), true) => {
+                format!("Approve and skip the prompt for commands matching {rule} in this project")
+            }
+            _ => selected.description.to_string(),
+        };
+        Line::from(description)
             .style(Style::new().italic().add_modifier(Modifier::DIM))
             .render(description_area.inner(Margin::new(1, 0)), buf);
 
@@ -421,6 +513,44 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn project_option_adds_prefix_rule_before_approving() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let req = ApprovalRequest::Exec {
+            id: "3".to_string(),
+            command: vec![
+                "bash".to_string(),
+                "-lc".to_string(),
+                "cargo test -p codex-core".to_string(),
+            ],
+            reason: None,
+        };
+        let mut widget = UserApprovalWidget::new(req, tx);
+        assert_eq!(
+            widget.option_label(&widget.select_options[2]).to_string(),
+            "Project: Bash(cargo test:*)"
+        );
+        widget.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE));
+        assert!(widget.is_complete());
+        let ops: Vec<Op> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|ev| match ev {
+                AppEvent::CodexOp(op) => Some(op),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(
+            ops.as_slice(),
+            [
+                Op::AddPermissionRule { rule },
+                Op::ExecApproval {
+                    decision: ReviewDecision::ApprovedForSession,
+                    ..
+                },
+            ] if rule == "Bash(cargo test:*)"
+        ));
+    }
+
     #[test]
     fn uppercase_shortcut_is_accepted() {
         let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();